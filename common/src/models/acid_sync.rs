@@ -1,8 +1,17 @@
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::collections::HashMap;
 
+/// Sessions running a statement on every table.
+type RunningStatements = Mutex<HashMap<String, Vec<u64>>>;
+
 #[derive(Default)]
-pub struct AcidSync(pub Arc<Mutex<HashMap<String, Arc<RwLock<()>>>>>);
+pub struct AcidSync(
+    pub Arc<Mutex<HashMap<String, Arc<RwLock<()>>>>>,
+    /// Tables changed by an open transaction, together with the id of its session.
+    pub Arc<Mutex<HashMap<String, u64>>>,
+    /// Statements running on the tables, signalled whenever a statement ends.
+    pub Arc<(RunningStatements, Condvar)>,
+);
 
 impl AcidSync {
     pub fn get_rw_lock(&self, table_name: String) -> Arc<RwLock<()>> {
@@ -13,11 +22,96 @@ impl AcidSync {
                 .or_insert_with(|| Arc::new(RwLock::new(())))
         )
     }
+
+    /// Reserve the tables for the transaction of the session until `release_tables`.
+    /// Nothing is reserved when any of the tables belongs to another transaction,
+    /// its name is returned instead. The statements of other sessions running
+    /// on the tables are waited for.
+    pub fn claim_tables(&self, session_id: u64, table_names: &[String]) -> Result<(), String> {
+        let (statements, statement_ended) = &*self.2;
+        loop {
+            let mut claims = self.1.lock().unwrap();
+            Self::find_foreign_claim(&claims, session_id, table_names)?;
+            let running_statements = statements.lock().unwrap();
+            let is_used = table_names.iter().any(|table_name| {
+                claims.get(table_name) != Some(&session_id)
+                    && running_statements
+                        .get(table_name)
+                        .is_some_and(|sessions| sessions.iter().any(|id| *id != session_id))
+            });
+            if !is_used {
+                for table_name in table_names {
+                    claims.insert(table_name.clone(), session_id);
+                }
+                return Ok(());
+            }
+            drop(claims);
+            drop(statement_ended.wait(running_statements).unwrap());
+        }
+    }
+
+    /// Check that none of the tables belongs to a transaction of another session
+    /// and keep them from being claimed by other sessions until `end_statement`.
+    pub fn begin_statement(&self, session_id: u64, table_names: &[String]) -> Result<(), String> {
+        let claims = self.1.lock().unwrap();
+        Self::find_foreign_claim(&claims, session_id, table_names)?;
+        let mut statements = self.2.0.lock().unwrap();
+        for table_name in table_names {
+            statements.entry(table_name.clone()).or_default().push(session_id);
+        }
+        Ok(())
+    }
+
+    pub fn end_statement(&self, session_id: u64, table_names: &[String]) {
+        let (statements, statement_ended) = &*self.2;
+        let mut statements = statements.lock().unwrap();
+        for table_name in table_names {
+            if let Some(sessions) = statements.get_mut(table_name) {
+                if let Some(position) = sessions.iter().position(|id| *id == session_id) {
+                    sessions.swap_remove(position);
+                }
+                if sessions.is_empty() {
+                    statements.remove(table_name);
+                }
+            }
+        }
+        statement_ended.notify_all();
+    }
+
+    /// Check that none of the tables belongs to a transaction of another session.
+    pub fn check_tables(&self, session_id: Option<u64>, table_names: &[String]) -> Result<(), String> {
+        let claims = self.1.lock().unwrap();
+        match session_id {
+            Some(session_id) => Self::find_foreign_claim(&claims, session_id, table_names),
+            None => match table_names.iter().find(|name| claims.contains_key(*name)) {
+                Some(table_name) => Err(table_name.clone()),
+                None => Ok(()),
+            },
+        }
+    }
+
+    pub fn release_tables(&self, session_id: u64) {
+        let mut claims = self.1.lock().unwrap();
+        claims.retain(|_, claimed_by| *claimed_by != session_id);
+    }
+
+    fn find_foreign_claim(
+        claims: &HashMap<String, u64>,
+        session_id: u64,
+        table_names: &[String],
+    ) -> Result<(), String> {
+        for table_name in table_names {
+            match claims.get(table_name) {
+                Some(claimed_by) if *claimed_by != session_id => return Err(table_name.clone()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Clone for AcidSync {
     fn clone(&self) -> AcidSync {
-        AcidSync(Arc::clone(&self.0))
+        AcidSync(Arc::clone(&self.0), Arc::clone(&self.1), Arc::clone(&self.2))
     }
   }
-
@@ -0,0 +1,127 @@
+//! DATE is the number of days since 1970-01-01, TIMESTAMP the number of microseconds
+//! since 1970-01-01 00:00:00, both without a time zone. The years are 1 to 9999.
+
+pub const MICROS_PER_DAY: i64 = 24 * 60 * 60 * MICROS_PER_SECOND;
+const MICROS_PER_SECOND: i64 = 1_000_000;
+/// 0001-01-01
+const MIN_DATE: i32 = -719_162;
+/// 9999-12-31
+const MAX_DATE: i32 = 2_932_896;
+
+pub fn is_valid_date(days: i64) -> bool {
+    (MIN_DATE as i64..=MAX_DATE as i64).contains(&days)
+}
+
+pub fn is_valid_timestamp(micros: i64) -> bool {
+    is_valid_date(micros.div_euclid(MICROS_PER_DAY))
+}
+
+/// Parse the date written as `YYYY-MM-DD`.
+pub fn date_from_string(text: &str) -> Option<i32> {
+    let mut parts = text.splitn(3, '-');
+    let year = parse_number(parts.next()?, 4)?;
+    let month = parse_number(parts.next()?, 2)?;
+    let day = parse_number(parts.next()?, 2)?;
+    if year == 0 || !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Parse the timestamp written as `YYYY-MM-DD HH:MM:SS.ffffff`, the fraction of the second
+/// is optional, the time too. `T` can separate the date from the time.
+pub fn timestamp_from_string(text: &str) -> Option<i64> {
+    let (date, time) = match text.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+    let days = date_from_string(date)? as i64;
+    let Some(time) = time else {
+        return Some(days * MICROS_PER_DAY);
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut parts = time.splitn(3, ':');
+    let hours = parse_number(parts.next()?, 2)?;
+    let minutes = parse_number(parts.next()?, 2)?;
+    let seconds = parse_number(parts.next()?, 2)?;
+    if hours > 23 || minutes > 59 || seconds > 59 || fraction.len() > 6 {
+        return None;
+    }
+    let micros = match fraction {
+        "" => 0,
+        fraction => parse_number(fraction, fraction.len())? * 10_u32.pow(6 - fraction.len() as u32),
+    };
+    let seconds = ((hours * 60 + minutes) * 60 + seconds) as i64;
+    Some(days * MICROS_PER_DAY + seconds * MICROS_PER_SECOND + micros as i64)
+}
+
+pub fn date_string(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The fraction of the second is written only when there's any, without the trailing zeros.
+pub fn timestamp_string(micros: i64) -> String {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    let time = micros.rem_euclid(MICROS_PER_DAY);
+    let seconds = time / MICROS_PER_SECOND;
+    let mut text = format!(
+        "{} {:02}:{:02}:{:02}",
+        date_string(days as i32),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    let fraction = time % MICROS_PER_SECOND;
+    if fraction != 0 {
+        text.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+    }
+    text
+}
+
+/// The number of exactly `length` digits.
+fn parse_number(text: &str, length: usize) -> Option<u32> {
+    match text.len() == length && text.chars().all(|c| c.is_ascii_digit()) {
+        true => text.parse().ok(),
+        false => None,
+    }
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of the date of the proleptic Gregorian calendar.
+fn days_from_civil(year: u32, month: u32, day: u32) -> i32 {
+    // the years start in March, so the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year } as i32;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year as i32;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of the days since 1970-01-01.
+fn civil_from_days(days: i32) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
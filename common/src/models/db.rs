@@ -1,28 +1,93 @@
-use std::{fmt, hash::Hash, hash::Hasher};
+use std::{cmp::Ordering, fmt, hash::Hash, hash::Hasher};
 
 use serde::{Deserialize, Serialize};
 
+use super::{
+    datetime::{date_string, timestamp_string, MICROS_PER_DAY},
+    decimal::Decimal,
+};
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize)]
 pub enum DataType {
     INT,
+    /// VARCHAR of at most `size` characters.
     STRING { size: i32 },
     BOOLEAN,
     FLOAT,
+    /// String of any length.
+    TEXT,
+    /// Bytes of any length.
+    BLOB,
+    BIGINT,
+    /// Exact number of at most `precision` digits, `scale` of them after the decimal point.
+    DECIMAL { precision: u8, scale: u8 },
+    DATE,
+    TIMESTAMP,
+    UUID,
 }
 
 impl DataType {
-    fn is_string(&self) -> bool {
-        match &self {
-            #[allow(unused_variables)]
-            DataType::STRING { size } => true,
-            _ => false,
+    /// The data type of the name given by the query parser, VARCHAR without the length holds 256 characters
+    /// and DECIMAL without the precision has 10 digits.
+    pub fn from_name(name: &str) -> Option<DataType> {
+        if let Some(size) = name
+            .strip_prefix("varchar(")
+            .and_then(|size| size.strip_suffix(')'))
+            .and_then(|size| size.parse().ok())
+        {
+            return Some(DataType::STRING { size });
+        }
+        if let Some((precision, scale)) = name
+            .strip_prefix("decimal(")
+            .and_then(|decimal| decimal.strip_suffix(')'))
+            .and_then(|decimal| decimal.split_once(','))
+        {
+            return Some(DataType::DECIMAL {
+                precision: precision.parse().ok()?,
+                scale: scale.parse().ok()?,
+            });
         }
+        let data_type = match name {
+            "varchar" => DataType::STRING { size: 256 },
+            "int" => DataType::INT,
+            "boolean" => DataType::BOOLEAN,
+            "float" => DataType::FLOAT,
+            "text" => DataType::TEXT,
+            "blob" => DataType::BLOB,
+            "bigint" => DataType::BIGINT,
+            "decimal" => DataType::DECIMAL {
+                precision: 10,
+                scale: 0,
+            },
+            "date" => DataType::DATE,
+            "timestamp" => DataType::TIMESTAMP,
+            "uuid" => DataType::UUID,
+            _ => return None,
+        };
+        Some(data_type)
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, DataType::STRING { .. } | DataType::TEXT)
     }
 }
 
+/// The name of the data type as it's written in the queries.
 impl fmt::Display for DataType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        match self {
+            DataType::INT => write!(f, "INT"),
+            DataType::STRING { size } => write!(f, "VARCHAR({})", size),
+            DataType::BOOLEAN => write!(f, "BOOLEAN"),
+            DataType::FLOAT => write!(f, "FLOAT"),
+            DataType::TEXT => write!(f, "TEXT"),
+            DataType::BLOB => write!(f, "BLOB"),
+            DataType::BIGINT => write!(f, "BIGINT"),
+            DataType::DECIMAL { precision, scale } => write!(f, "DECIMAL({},{})", precision, scale),
+            DataType::DATE => write!(f, "DATE"),
+            DataType::TIMESTAMP => write!(f, "TIMESTAMP"),
+            DataType::UUID => write!(f, "UUID"),
+        }
     }
 }
 
@@ -31,7 +96,18 @@ impl fmt::Display for DataType {
 pub struct Column {
     pub name: String,
     pub data_type: DataType,
-    pub is_indexed: bool,
+    #[serde(skip)]
+    pub constraints: ColumnConstraints,
+}
+
+/// Constraints of the column from CREATE TABLE, the expressions are kept as the query text.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct ColumnConstraints {
+    pub not_null: bool,
+    /// The value of the column omitted by INSERT, NULL without it.
+    pub default: Option<String>,
+    /// Condition of the row, which is satisfied unless it's false.
+    pub check: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -41,6 +117,14 @@ pub enum Data {
     NULL,
     BOOLEAN(bool),
     FLOAT(f64),
+    BLOB(Vec<u8>),
+    BIGINT(i64),
+    DECIMAL(Decimal),
+    /// Days since 1970-01-01.
+    DATE(i32),
+    /// Microseconds since 1970-01-01 00:00:00.
+    TIMESTAMP(i64),
+    UUID(u128),
 }
 
 impl Data {
@@ -51,6 +135,12 @@ impl Data {
             Data::NULL => true,
             Data::BOOLEAN(_) => data_type.eq(&DataType::BOOLEAN),
             Data::FLOAT(_) => data_type.eq(&DataType::FLOAT),
+            Data::BLOB(_) => data_type.eq(&DataType::BLOB),
+            Data::BIGINT(_) => data_type.eq(&DataType::BIGINT),
+            Data::DECIMAL(_) => matches!(data_type, DataType::DECIMAL { .. }),
+            Data::DATE(_) => data_type.eq(&DataType::DATE),
+            Data::TIMESTAMP(_) => data_type.eq(&DataType::TIMESTAMP),
+            Data::UUID(_) => data_type.eq(&DataType::UUID),
         }
     }
 
@@ -61,6 +151,76 @@ impl Data {
             Data::NULL => String::from("UNKNOWN since value was null"),
             Data::BOOLEAN(_) => String::from("BOOLEAN"),
             Data::FLOAT(_) => String::from("FLOAT"),
+            Data::BLOB(_) => String::from("BLOB"),
+            Data::BIGINT(_) => String::from("BIGINT"),
+            Data::DECIMAL(_) => String::from("DECIMAL"),
+            Data::DATE(_) => String::from("DATE"),
+            Data::TIMESTAMP(_) => String::from("TIMESTAMP"),
+            Data::UUID(_) => String::from("UUID"),
+        }
+    }
+
+    /// Total ordering over all values. The numbers are compared by their numeric value,
+    /// DATE is compared with TIMESTAMP as its midnight. Values of different types are ordered
+    /// BOOLEAN < numbers < STRING < BLOB < DATE/TIMESTAMP < UUID < NULL.
+    pub fn total_cmp(&self, other: &Data) -> Ordering {
+        match (self, other) {
+            (Data::FLOAT(a), Data::FLOAT(b)) => a.total_cmp(b),
+            (Data::FLOAT(a), _) if other.is_number() => a.total_cmp(&other.to_f64().unwrap()),
+            (_, Data::FLOAT(b)) if self.is_number() => self.to_f64().unwrap().total_cmp(b),
+            (Data::STRING(a), Data::STRING(b)) => a.cmp(b),
+            (Data::BOOLEAN(a), Data::BOOLEAN(b)) => a.cmp(b),
+            (Data::BLOB(a), Data::BLOB(b)) => a.cmp(b),
+            (Data::UUID(a), Data::UUID(b)) => a.cmp(b),
+            _ => match (self.to_exact_number(), other.to_exact_number()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => match (self.to_timestamp(), other.to_timestamp()) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    _ => self.type_rank().cmp(&other.type_rank()),
+                },
+            },
+        }
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            Data::BOOLEAN(_) => 0,
+            Data::INT(_) | Data::FLOAT(_) | Data::BIGINT(_) | Data::DECIMAL(_) => 1,
+            Data::STRING(_) => 2,
+            Data::BLOB(_) => 3,
+            Data::DATE(_) | Data::TIMESTAMP(_) => 4,
+            Data::UUID(_) => 5,
+            Data::NULL => 6,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        self.type_rank() == 1
+    }
+
+    /// The value of INT, BIGINT and DECIMAL.
+    fn to_exact_number(&self) -> Option<Decimal> {
+        match self {
+            Data::INT(integer) => Some(Decimal::from_int(*integer as i64)),
+            Data::BIGINT(integer) => Some(Decimal::from_int(*integer)),
+            Data::DECIMAL(decimal) => Some(*decimal),
+            _ => None,
+        }
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        match self {
+            Data::FLOAT(float) => Some(*float),
+            _ => self.to_exact_number().map(Decimal::to_f64),
+        }
+    }
+
+    /// The microseconds of TIMESTAMP and of the midnight of DATE.
+    fn to_timestamp(&self) -> Option<i64> {
+        match self {
+            Data::DATE(days) => Some(*days as i64 * MICROS_PER_DAY),
+            Data::TIMESTAMP(micros) => Some(*micros),
+            _ => None,
         }
     }
 }
@@ -72,6 +232,16 @@ impl Hash for Data {
             Data::STRING(string) => string.hash(state),
             Data::NULL => state.write_u8(0),
             Data::BOOLEAN(bool) => bool.hash(state),
+            Data::BLOB(bytes) => bytes.hash(state),
+            // the integers equal to INT have its hash, so they are found in the same index
+            Data::BIGINT(val) => hash_integer(*val, state),
+            Data::DECIMAL(decimal) => match decimal.to_integer() {
+                Some(integer) => hash_integer(integer, state),
+                None => decimal.hash(state),
+            },
+            Data::DATE(days) => days.hash(state),
+            Data::TIMESTAMP(micros) => micros.hash(state),
+            Data::UUID(uuid) => uuid.hash(state),
             Data::FLOAT(val) => {
                 let integer_part = *val as i64;
                 let fractional_part = get_frac(*val);
@@ -82,6 +252,13 @@ impl Hash for Data {
     }
 }
 
+fn hash_integer<H: Hasher>(integer: i64, state: &mut H) {
+    match i32::try_from(integer) {
+        Ok(integer) => integer.hash(state),
+        Err(_) => integer.hash(state),
+    }
+}
+
 impl PartialEq for Data {
     fn eq(&self, other: &Self) -> bool {
 
@@ -90,8 +267,16 @@ impl PartialEq for Data {
             (Data::STRING(a), Data::STRING(b)) => a == b,
             (Data::BOOLEAN(a), Data::BOOLEAN(b)) => a == b,
             (Data::FLOAT(a), Data::FLOAT(b)) => a == b,
+            (Data::BLOB(a), Data::BLOB(b)) => a == b,
+            (Data::DATE(a), Data::DATE(b)) => a == b,
+            (Data::TIMESTAMP(a), Data::TIMESTAMP(b)) => a == b,
+            (Data::UUID(a), Data::UUID(b)) => a == b,
             (Data::NULL, Data::NULL) => true,
-            _ => false,
+            // INT, BIGINT and DECIMAL of the same value
+            _ => matches!(
+                (self.to_exact_number(), other.to_exact_number()),
+                (Some(a), Some(b)) if a == b
+            ),
         }
     }
 }
@@ -114,7 +299,7 @@ fn get_frac(f: f64) -> u64 {
     f.round() as u64
 }
 
-#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Row {
     pub values: Vec<Data>,
@@ -130,6 +315,13 @@ impl serde::ser::Serialize for DataType {
             DataType::STRING { size: _ } => serializer.serialize_str("STRING"),
             DataType::BOOLEAN => serializer.serialize_str("BOOLEAN"),
             DataType::FLOAT => serializer.serialize_str("FLOAT"),
+            DataType::TEXT => serializer.serialize_str("TEXT"),
+            DataType::BLOB => serializer.serialize_str("BLOB"),
+            DataType::BIGINT => serializer.serialize_str("BIGINT"),
+            DataType::DECIMAL { .. } => serializer.serialize_str("DECIMAL"),
+            DataType::DATE => serializer.serialize_str("DATE"),
+            DataType::TIMESTAMP => serializer.serialize_str("TIMESTAMP"),
+            DataType::UUID => serializer.serialize_str("UUID"),
         }
     }
 }
@@ -147,6 +339,54 @@ impl serde::ser::Serialize for Data {
                 serializer.serialize_newtype_variant("Data", 3, "BOOLEAN", &val.to_string())
             }
             Data::FLOAT(val) => serializer.serialize_newtype_variant("Data", 4, "FLOAT", &val),
+            Data::BLOB(val) => {
+                serializer.serialize_newtype_variant("Data", 5, "BLOB", &hex_string(val))
+            }
+            Data::BIGINT(val) => serializer.serialize_newtype_variant("Data", 6, "BIGINT", &val),
+            // the exact value, which a JSON number wouldn't keep
+            Data::DECIMAL(val) => {
+                serializer.serialize_newtype_variant("Data", 7, "DECIMAL", &val.to_string())
+            }
+            Data::DATE(val) => {
+                serializer.serialize_newtype_variant("Data", 8, "DATE", &date_string(*val))
+            }
+            Data::TIMESTAMP(val) => {
+                serializer.serialize_newtype_variant("Data", 9, "TIMESTAMP", &timestamp_string(*val))
+            }
+            Data::UUID(val) => {
+                serializer.serialize_newtype_variant("Data", 10, "UUID", &uuid_string(*val))
+            }
         }
     }
 }
+
+/// The bytes written as `\x` followed by two hex digits per byte.
+pub fn hex_string(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("\\x{}", digits)
+}
+
+/// The UUID written as 32 lowercase hex digits in the groups of 8-4-4-4-12.
+pub fn uuid_string(uuid: u128) -> String {
+    let digits = format!("{:032x}", uuid);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &digits[..8],
+        &digits[8..12],
+        &digits[12..16],
+        &digits[16..20],
+        &digits[20..]
+    )
+}
+
+/// Parse the UUID of 32 hex digits, either in the groups of 8-4-4-4-12 or without the hyphens.
+pub fn uuid_from_string(text: &str) -> Option<u128> {
+    let groups: Vec<&str> = text.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+    if lengths != [32] && lengths != [8, 4, 4, 4, 12]
+        || !groups.iter().all(|group| group.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        return None;
+    }
+    u128::from_str_radix(&groups.concat(), 16).ok()
+}
@@ -0,0 +1,203 @@
+use std::{cmp::Ordering, fmt, hash::Hash, hash::Hasher};
+
+use serde::Deserialize;
+
+/// Most digits of DECIMAL, its values are stored in 8 bytes.
+pub const MAX_PRECISION: u8 = 18;
+/// Digits after the decimal point of a quotient, unless the operands have more.
+const DIVISION_SCALE: u32 = 6;
+
+/// Exact number `mantissa / 10^scale`. The numbers equal regardless of their scale,
+/// `1.50` is equal to `1.5` and it has the same hash.
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub struct Decimal {
+    pub mantissa: i128,
+    pub scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: u32) -> Decimal {
+        Decimal { mantissa, scale }
+    }
+
+    pub fn from_int(integer: i64) -> Decimal {
+        Decimal::new(integer as i128, 0)
+    }
+
+    /// The decimal of the shortest text of the float, None for infinity and NaN.
+    pub fn from_f64(float: f64) -> Option<Decimal> {
+        match float.is_finite() {
+            true => Decimal::parse(&float.to_string()),
+            false => None,
+        }
+    }
+
+    /// Parse the number written like `-12.340`, the digits after the point give the scale.
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty() && fraction.is_empty()
+            || !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let mut mantissa: i128 = 0;
+        for digit in integer.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa.checked_mul(10)?.checked_add((digit - b'0') as i128)?;
+        }
+        let mantissa = if negative { -mantissa } else { mantissa };
+        Some(Decimal::new(mantissa, fraction.len() as u32))
+    }
+
+    /// The number with the scale, the dropped digits are rounded half away from zero.
+    /// None when the number doesn't fit the mantissa.
+    pub fn rescale(self, scale: u32) -> Option<Decimal> {
+        let mantissa = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.mantissa,
+            Ordering::Greater => self.mantissa.checked_mul(pow10(scale - self.scale)?)?,
+            Ordering::Less => divide_rounded(self.mantissa, pow10(self.scale - scale)?),
+        };
+        Some(Decimal::new(mantissa, scale))
+    }
+
+    /// Count of the digits of the mantissa, the digits after the point included.
+    pub fn precision(&self) -> u32 {
+        match self.mantissa.unsigned_abs() {
+            0 => 1,
+            mantissa => mantissa.ilog10() + 1,
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.mantissa as f64 / 10_f64.powi(self.scale as i32)
+    }
+
+    /// The integer part of the number, None when it doesn't fit i64.
+    pub fn trunc(self) -> Option<i64> {
+        let integer = self.mantissa / pow10(self.scale)?;
+        i64::try_from(integer).ok()
+    }
+
+    /// The number when it's an integer fitting i64.
+    pub fn to_integer(self) -> Option<i64> {
+        let decimal = self.normalized();
+        match decimal.scale {
+            0 => i64::try_from(decimal.mantissa).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = align(self, other)?;
+        Some(Decimal::new(a.checked_add(b)?, scale))
+    }
+
+    pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = align(self, other)?;
+        Some(Decimal::new(a.checked_sub(b)?, scale))
+    }
+
+    pub fn checked_mul(self, other: Decimal) -> Option<Decimal> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        Some(Decimal::new(mantissa, self.scale + other.scale).normalized())
+    }
+
+    /// The quotient has the scale of the operands, at least 6 digits after the point.
+    /// None for the division by zero.
+    pub fn checked_div(self, other: Decimal) -> Option<Decimal> {
+        if other.mantissa == 0 {
+            return None;
+        }
+        let scale = self.scale.max(other.scale).max(DIVISION_SCALE);
+        let dividend = self.mantissa.checked_mul(pow10(scale + other.scale - self.scale)?)?;
+        Some(Decimal::new(divide_rounded(dividend, other.mantissa), scale))
+    }
+
+    /// None for the division by zero.
+    pub fn checked_rem(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = align(self, other)?;
+        Some(Decimal::new(a.checked_rem(b)?, scale))
+    }
+
+    /// The same number without the trailing zeros after the point.
+    fn normalized(self) -> Decimal {
+        let mut decimal = self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal = Decimal::new(decimal.mantissa / 10, decimal.scale - 1);
+        }
+        decimal
+    }
+}
+
+fn pow10(exponent: u32) -> Option<i128> {
+    10_i128.checked_pow(exponent)
+}
+
+/// The quotient rounded half away from zero.
+fn divide_rounded(dividend: i128, divisor: i128) -> i128 {
+    let quotient = dividend / divisor;
+    let remainder = dividend % divisor;
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        match (dividend < 0) == (divisor < 0) {
+            true => quotient + 1,
+            false => quotient - 1,
+        }
+    } else {
+        quotient
+    }
+}
+
+/// The mantissas of both numbers with the greater of their scales.
+fn align(a: Decimal, b: Decimal) -> Option<(i128, i128, u32)> {
+    let scale = a.scale.max(b.scale);
+    Some((a.rescale(scale)?.mantissa, b.rescale(scale)?.mantissa, scale))
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match align(*self, *other) {
+            Some((a, b, _)) => a.cmp(&b),
+            // the mantissas of so different scales don't overlap
+            None => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let decimal = self.normalized();
+        decimal.mantissa.hash(state);
+        decimal.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, integer, fraction)
+    }
+}
@@ -1,3 +1,5 @@
 pub mod webserver_models;
 pub mod db;
-pub mod acid_sync;
\ No newline at end of file
+pub mod acid_sync;
+pub mod datetime;
+pub mod decimal;
\ No newline at end of file
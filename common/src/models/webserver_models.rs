@@ -28,7 +28,10 @@ pub struct QueryResponseData {
     pub status: QueryStatus,
     pub data: Option<TableData>,
     pub message: Option<String>,
-    pub duration: String
+    pub duration: String,
+    /// Id to send with the following queries to continue the session, e.g. its transaction.
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 
@@ -36,4 +39,6 @@ pub struct QueryResponseData {
 #[serde(rename_all = "camelCase")]
 pub struct QueryRequestData {
     pub query: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
\ No newline at end of file
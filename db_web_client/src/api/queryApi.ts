@@ -1,7 +1,11 @@
 import { IDBResponse } from "../types";
 import { axiosInstance } from "./base";
 
+// the session keeps the transaction started by BEGIN until COMMIT or ROLLBACK
+let sessionId: string | undefined;
+
 export async function dbQuery(query: string): Promise<IDBResponse> {
-    const response = await axiosInstance.post<IDBResponse>('/query', { query });
+    const response = await axiosInstance.post<IDBResponse>('/query', { query, sessionId });
+    sessionId = response.data.sessionId;
     return response.data;
-}
\ No newline at end of file
+}
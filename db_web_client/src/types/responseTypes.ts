@@ -10,4 +10,5 @@ export interface IDBResponse {
     message?: string;
     data?: IDBTable;
     duration: string;
+    sessionId?: string;
 }
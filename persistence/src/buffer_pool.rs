@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::table::page::Page;
+
+/// Pages of the rows files shared by all queries, at most 4 MiB are kept.
+static BUFFER_POOL: Mutex<BufferPool> = Mutex::new(BufferPool::new(4 * 1024 * 1024));
+
+/// The cached page, or the page loaded by `load` which is cached from now on.
+/// The pool isn't locked while the page is loaded.
+pub(crate) fn read_page(
+    file_name: &str,
+    page_number: u64,
+    load: impl FnOnce() -> io::Result<Page>,
+) -> io::Result<Arc<Page>> {
+    if let Some(page) = BUFFER_POOL.lock().unwrap().get(file_name, page_number) {
+        return Ok(page);
+    }
+    let page = Arc::new(load()?);
+    BUFFER_POOL
+        .lock()
+        .unwrap()
+        .insert(file_name, page_number, Arc::clone(&page));
+    Ok(page)
+}
+
+/// Forget the pages which were rewritten on the disk.
+pub(crate) fn evict_pages(file_name: &str, page_numbers: &[u64]) {
+    let mut buffer_pool = BUFFER_POOL.lock().unwrap();
+    for page_number in page_numbers {
+        buffer_pool.evict(file_name, *page_number);
+    }
+}
+
+/// Forget all pages of the file, e.g. when the file was replaced or removed.
+pub(crate) fn evict_file(file_name: &str) {
+    BUFFER_POOL.lock().unwrap().evict_file(file_name);
+}
+
+/// Least recently used pages are evicted once the size of the pages exceeds the capacity.
+struct BufferPool {
+    pages: BTreeMap<(String, u64), (Arc<Page>, u64)>,
+    /// Keys of the pages by the time of their last use.
+    last_uses: BTreeMap<u64, (String, u64)>,
+    time: u64,
+    size: usize,
+    capacity: usize,
+}
+
+impl BufferPool {
+    const fn new(capacity: usize) -> Self {
+        BufferPool {
+            pages: BTreeMap::new(),
+            last_uses: BTreeMap::new(),
+            time: 0,
+            size: 0,
+            capacity,
+        }
+    }
+
+    fn get(&mut self, file_name: &str, page_number: u64) -> Option<Arc<Page>> {
+        let key = (file_name.to_string(), page_number);
+        let (page, last_use) = self.pages.get_mut(&key)?;
+        self.last_uses.remove(last_use);
+        self.time += 1;
+        *last_use = self.time;
+        self.last_uses.insert(self.time, key);
+        Some(Arc::clone(page))
+    }
+
+    fn insert(&mut self, file_name: &str, page_number: u64, page: Arc<Page>) {
+        self.evict(file_name, page_number);
+        self.time += 1;
+        self.size += page.bytes().len();
+        let key = (file_name.to_string(), page_number);
+        self.last_uses.insert(self.time, key.clone());
+        self.pages.insert(key, (page, self.time));
+
+        while self.size > self.capacity {
+            let Some((_time, (file_name, page_number))) = self.last_uses.pop_first() else {
+                break;
+            };
+            self.evict(&file_name, page_number);
+        }
+    }
+
+    fn evict(&mut self, file_name: &str, page_number: u64) {
+        if let Some((page, last_use)) = self.pages.remove(&(file_name.to_string(), page_number)) {
+            self.last_uses.remove(&last_use);
+            self.size -= page.bytes().len();
+        }
+    }
+
+    fn evict_file(&mut self, file_name: &str) {
+        let page_numbers: Vec<u64> = self
+            .pages
+            .range((file_name.to_string(), 0)..=(file_name.to_string(), u64::MAX))
+            .map(|((_file_name, page_number), _page)| *page_number)
+            .collect();
+        for page_number in page_numbers {
+            self.evict(file_name, page_number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::page::PAGE_SIZE;
+
+    fn page(byte: u8) -> Arc<Page> {
+        let mut page = Page::new(PAGE_SIZE);
+        page.push(&[byte]);
+        Arc::new(page)
+    }
+
+    #[test]
+    fn least_recently_used_page_evicted() {
+        let mut buffer_pool = BufferPool::new(2 * PAGE_SIZE);
+        buffer_pool.insert("file", 0, page(0));
+        buffer_pool.insert("file", 1, page(1));
+        assert_eq!(buffer_pool.get("file", 0), Some(page(0)));
+        buffer_pool.insert("other_file", 0, page(2));
+
+        assert_eq!(buffer_pool.get("file", 1), None);
+        assert_eq!(buffer_pool.get("file", 0), Some(page(0)));
+        assert_eq!(buffer_pool.get("other_file", 0), Some(page(2)));
+        assert_eq!(buffer_pool.size, 2 * PAGE_SIZE);
+    }
+
+    #[test]
+    fn file_evicted() {
+        let mut buffer_pool = BufferPool::new(10 * PAGE_SIZE);
+        buffer_pool.insert("file", 0, page(0));
+        buffer_pool.insert("file", 3, page(3));
+        buffer_pool.insert("file_rows", 0, page(1));
+        buffer_pool.evict_file("file");
+
+        assert_eq!(buffer_pool.get("file", 0), None);
+        assert_eq!(buffer_pool.get("file", 3), None);
+        assert_eq!(buffer_pool.get("file_rows", 0), Some(page(1)));
+        assert_eq!(buffer_pool.size, PAGE_SIZE);
+        assert_eq!(buffer_pool.last_uses.len(), 1);
+    }
+}
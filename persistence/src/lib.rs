@@ -1 +1,3 @@
+mod buffer_pool;
 pub mod table;
+pub mod wal;
@@ -0,0 +1,155 @@
+use std::fs::{copy, remove_file, rename};
+use std::path::Path;
+
+use crate::buffer_pool;
+use crate::table::{errors::PersistenceErrors, table::Table};
+
+/// Copies of all files of the table, taken before the table is changed by a transaction.
+/// A table which doesn't exist has no files, restoring its backup removes the table.
+#[derive(Debug)]
+pub struct TableBackup {
+    pub(crate) table_name: String,
+    pub(crate) file_names: Vec<String>,
+}
+
+impl TableBackup {
+    pub fn create(table_name: &str) -> Result<TableBackup, PersistenceErrors> {
+        let file_names = match Table::load(table_name.to_string()) {
+            Ok(table) => table.file_names(),
+            Err(_) => Vec::new(),
+        };
+        for (copied, file_name) in file_names.iter().enumerate() {
+            if let Err(error) = copy(file_name, backup_file_name(file_name)) {
+                // the copies made so far would be left behind by the failed backup
+                for file_name in &file_names[..copied] {
+                    let _ = remove_file(backup_file_name(file_name));
+                }
+                return Err(PersistenceErrors::TableBackup(error));
+            }
+        }
+        Ok(TableBackup {
+            table_name: table_name.to_string(),
+            file_names,
+        })
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Replace the current files of the table by the copies. The restore can be repeated
+    /// after a crash, the files without a copy are removed before any copy is put back.
+    pub fn restore(self) -> Result<(), PersistenceErrors> {
+        if let Ok(table) = Table::load(self.table_name.clone()) {
+            for file_name in table.file_names() {
+                if !self.file_names.contains(&file_name) && Path::new(&file_name).exists() {
+                    remove_file(&file_name).map_err(PersistenceErrors::TableBackup)?;
+                }
+                buffer_pool::evict_file(&file_name);
+            }
+        }
+        for file_name in &self.file_names {
+            let backup_file_name = backup_file_name(file_name);
+            // the copy was put back already by the interrupted restore
+            if Path::new(&backup_file_name).exists() {
+                rename(backup_file_name, file_name).map_err(PersistenceErrors::TableBackup)?;
+            }
+            buffer_pool::evict_file(file_name);
+        }
+        Ok(())
+    }
+
+    /// Remove the copies, the current files of the table are kept.
+    pub fn discard(self) -> Result<(), PersistenceErrors> {
+        for file_name in &self.file_names {
+            remove_file(backup_file_name(file_name)).map_err(PersistenceErrors::TableBackup)?;
+        }
+        Ok(())
+    }
+}
+
+/// The identifiers can't contain `#`, so the copy can't be taken for a file of another table.
+fn backup_file_name(file_name: &str) -> String {
+    file_name.to_string() + "#backup"
+}
+
+#[cfg(test)]
+mod tests {
+    use common::models::db::Row;
+
+    use super::*;
+    use crate::table::table::tests::{insert_data, insert_row};
+    use crate::table::table::{IndexKind, TableIndex};
+    use crate::table::table_iterator::RowsIterator;
+
+    #[test]
+    fn backup_restore() {
+        let (mut table, row) = insert_data("Table16", false);
+        let backup = TableBackup::create(&table.name).unwrap();
+        insert_row(&table, String::from("This will disappear."), 2);
+        table
+            .add_index(TableIndex {
+                name: String::from("Id"),
+                columns: vec![String::from("Id")],
+                kind: IndexKind::Hash,
+                constraint: None,
+            })
+            .unwrap();
+        backup.restore().unwrap();
+
+        let loaded_table = Table::load(table.name.clone()).unwrap();
+        let rows: Vec<Row> = RowsIterator::from_table(&loaded_table).unwrap().map(Result::unwrap).collect();
+        let index_exists = Path::new("Table16#Id_index").exists();
+        let backup_exists = Path::new("Table16#backup").exists();
+        assert!(loaded_table.drop().is_ok());
+        assert_eq!(rows, vec![row]);
+        assert!(loaded_table.indexes.is_empty());
+        assert!(!index_exists);
+        assert!(!backup_exists);
+    }
+
+    #[test]
+    fn backup_restore_created_table() {
+        let backup = TableBackup::create("Table17").unwrap();
+        let (table, _row) = insert_data("Table17", true);
+        backup.restore().unwrap();
+
+        assert!(Table::load(table.name.clone()).is_err());
+        assert!(table
+            .file_names()
+            .iter()
+            .all(|file_name| !Path::new(file_name).exists()));
+    }
+
+    #[test]
+    fn failed_backup_removes_copies() {
+        let (table, _row) = insert_data("Table31", true);
+        remove_file("Table31#Id_index").unwrap();
+        let backup = TableBackup::create(&table.name);
+        let backups_exist = table
+            .file_names()
+            .iter()
+            .any(|file_name| Path::new(&backup_file_name(file_name)).exists());
+        assert!(table.drop().is_ok());
+        assert!(backup.is_err());
+        assert!(!backups_exist);
+    }
+
+    #[test]
+    fn backup_discard() {
+        let (table, _row) = insert_data("Table18", true);
+        let backup = TableBackup::create(&table.name).unwrap();
+        let row = insert_row(&table, String::from("This will stay."), 2);
+        backup.discard().unwrap();
+
+        let rows: Vec<Row> = RowsIterator::from_table(&table).unwrap().map(Result::unwrap).collect();
+        let backups_exist = table
+            .file_names()
+            .iter()
+            .any(|file_name| Path::new(&backup_file_name(file_name)).exists());
+        assert!(table.drop().is_ok());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], row);
+        assert!(!backups_exist);
+    }
+}
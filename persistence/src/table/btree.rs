@@ -0,0 +1,899 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Bound;
+
+use common::models::db::{Column, Data, DataType};
+
+use crate::table::errors::PersistenceErrors;
+use crate::table::page::PAGE_SIZE;
+use crate::wal;
+
+use super::{
+    column::PersistenceColumn,
+    row::{self, PersistenceData},
+};
+
+/// The first node of the file holds the format magic and version, the root and the node count.
+const FILE_HEADER: [u8; 8] = [b'B', b'T', b'I', b'X', 0, 0, 0, 1];
+/// Kind of the node, number of the keys, next leaf or the first child.
+const NODE_HEADER_SIZE: usize = 16;
+const LEAF: u8 = 0;
+const INTERNAL: u8 = 1;
+const NO_NODE: u64 = u64::MAX;
+
+/// Values of the indexed columns together with the number of their row. The entries are
+/// ordered by the values first, so the entries are unique even when the values aren't.
+type Entry = (Vec<Data>, u64);
+
+#[derive(PartialEq, Debug)]
+enum Node {
+    /// The entries in order, `next` is the leaf with the following entries.
+    Leaf { entries: Vec<Entry>, next: u64 },
+    /// The child `i + 1` holds the entries from `keys[i]` on.
+    Internal {
+        keys: Vec<Entry>,
+        children: Vec<u64>,
+    },
+}
+
+/// B+tree of the values of the columns stored in nodes of a fixed size, nodes are read
+/// only along the path from the root and the leaves are chained for range scans.
+/// The values are compared column by column, NULL values are ordered after all other values.
+pub struct BTreeIndex {
+    file: File,
+    columns: Vec<Column>,
+    node_size: usize,
+    root: u64,
+    node_count: u64,
+}
+
+impl BTreeIndex {
+    /// Write the tree of the entries, the leaves are filled one after another.
+    pub(crate) fn build(
+        file_name: &str,
+        columns: &[Column],
+        mut entries: Vec<Entry>,
+    ) -> Result<(), PersistenceErrors> {
+        entries.sort_by(compare_entries);
+        let node_size = node_size(columns);
+        let leaf_capacity = leaf_capacity(columns, node_size);
+        let internal_capacity = internal_capacity(columns, node_size);
+
+        let mut nodes = vec![];
+        let chunks: Vec<&[Entry]> = match entries.is_empty() {
+            true => vec![&[]],
+            false => entries.chunks(leaf_capacity).collect(),
+        };
+        // the first key and the number of every node of the current level
+        let mut level: Vec<(Option<Entry>, u64)> = vec![];
+        for (chunk_index, chunk) in chunks.iter().enumerate() {
+            let node_number = nodes.len() as u64 + 1;
+            let next = match chunk_index + 1 < chunks.len() {
+                true => node_number + 1,
+                false => NO_NODE,
+            };
+            level.push((chunk.first().cloned(), node_number));
+            nodes.push(Node::Leaf {
+                entries: chunk.to_vec(),
+                next,
+            });
+        }
+        while level.len() > 1 {
+            let mut upper_level = vec![];
+            for children in level.chunks(internal_capacity + 1) {
+                let keys = children[1..]
+                    .iter()
+                    .map(|(key, _node_number)| key.clone().expect("Only the single leaf is empty."))
+                    .collect();
+                nodes.push(Node::Internal {
+                    keys,
+                    children: children
+                        .iter()
+                        .map(|(_key, node_number)| *node_number)
+                        .collect(),
+                });
+                upper_level.push((children[0].0.clone(), nodes.len() as u64));
+            }
+            level = upper_level;
+        }
+
+        let mut bytes = header_bytes(nodes.len() as u64, nodes.len() as u64 + 1, node_size);
+        for node in &nodes {
+            bytes.extend(node_to_bytes(node, columns, node_size));
+        }
+        wal::write_file(file_name, &bytes).map_err(PersistenceErrors::IndexRefresh)
+    }
+
+    pub(crate) fn open(
+        file_name: &str,
+        columns: &[Column],
+    ) -> Result<BTreeIndex, PersistenceErrors> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(file_name)
+            .map_err(PersistenceErrors::IndexLoading)?;
+        let mut header = [0; 24];
+        file.read_exact(&mut header)
+            .map_err(PersistenceErrors::IndexLoading)?;
+        if header[..8] != FILE_HEADER {
+            return Err(PersistenceErrors::IndexLoading(
+                io::ErrorKind::InvalidData.into(),
+            ));
+        }
+        Ok(BTreeIndex {
+            file,
+            columns: columns.to_vec(),
+            node_size: node_size(columns),
+            root: u64_from_bytes(&header[8..16]),
+            node_count: u64_from_bytes(&header[16..24]),
+        })
+    }
+
+    /// Entries with the values within the bounds, in the order of the values. The bounds
+    /// may hold the values of only the leading columns, they are compared with those only.
+    pub fn range(
+        &mut self,
+        lower: Bound<&[Data]>,
+        upper: Bound<&[Data]>,
+    ) -> Result<Vec<Entry>, PersistenceErrors> {
+        let mut node_number = self.root;
+        let mut node = self.read_node(node_number)?;
+        while let Node::Internal { keys, children } = node {
+            let child = keys
+                .iter()
+                .take_while(|(key, _row_number)| match lower {
+                    Bound::Included(lower) => compare_prefix(key, lower) == Ordering::Less,
+                    Bound::Excluded(lower) => compare_prefix(key, lower) != Ordering::Greater,
+                    Bound::Unbounded => false,
+                })
+                .count();
+            node_number = children[child];
+            node = self.read_node(node_number)?;
+        }
+
+        let mut result = vec![];
+        loop {
+            let Node::Leaf { entries, next } = node else {
+                return Err(PersistenceErrors::IndexLoading(
+                    io::ErrorKind::InvalidData.into(),
+                ));
+            };
+            for (data, row_number) in entries {
+                let is_above_lower = match lower {
+                    Bound::Included(lower) => compare_prefix(&data, lower) != Ordering::Less,
+                    Bound::Excluded(lower) => compare_prefix(&data, lower) == Ordering::Greater,
+                    Bound::Unbounded => true,
+                };
+                let is_below_upper = match upper {
+                    Bound::Included(upper) => compare_prefix(&data, upper) != Ordering::Greater,
+                    Bound::Excluded(upper) => compare_prefix(&data, upper) == Ordering::Less,
+                    Bound::Unbounded => true,
+                };
+                if !is_below_upper {
+                    return Ok(result);
+                }
+                if is_above_lower {
+                    result.push((data, row_number));
+                }
+            }
+            if next == NO_NODE {
+                return Ok(result);
+            }
+            node = self.read_node(next)?;
+        }
+    }
+
+    /// All the entries in the order of the values.
+    pub fn entries(&mut self) -> Result<Vec<Entry>, PersistenceErrors> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Walk the leaves of the tree from either end, a leaf is read only when its entries
+    /// are reached. The entries of equal values are in the order of their rows either way.
+    pub fn into_ordered_entries(self, descending: bool) -> OrderedEntries {
+        OrderedEntries {
+            path: vec![(vec![self.root], 0)],
+            index: self,
+            descending,
+            leaf: VecDeque::new(),
+            equal_entries: vec![],
+            following_entry: None,
+        }
+    }
+
+    /// Add the entry to its leaf, the full nodes on the path are split.
+    pub(crate) fn insert(&mut self, entry: Entry) -> Result<(), PersistenceErrors> {
+        // the nodes from the root to the leaf with the positions of their children
+        let mut path = vec![];
+        let mut node_number = self.root;
+        let mut node = self.read_node(node_number)?;
+        while let Node::Internal { keys, children } = &node {
+            let child = keys
+                .iter()
+                .take_while(|key| compare_entries(key, &entry) != Ordering::Greater)
+                .count();
+            let child_number = children[child];
+            path.push((node_number, node, child));
+            node_number = child_number;
+            node = self.read_node(node_number)?;
+        }
+
+        let Node::Leaf { mut entries, next } = node else {
+            return Err(PersistenceErrors::IndexRefresh(
+                io::ErrorKind::InvalidData.into(),
+            ));
+        };
+        let position =
+            entries.partition_point(|other| compare_entries(other, &entry) == Ordering::Less);
+        entries.insert(position, entry);
+        if entries.len() <= leaf_capacity(&self.columns, self.node_size) {
+            return self.write_node(node_number, &Node::Leaf { entries, next });
+        }
+        let right_entries = entries.split_off(entries.len() / 2);
+        let right_number = self.allocate_node();
+        let mut separator = right_entries[0].clone();
+        self.write_node(
+            right_number,
+            &Node::Leaf {
+                entries: right_entries,
+                next,
+            },
+        )?;
+        self.write_node(
+            node_number,
+            &Node::Leaf {
+                entries,
+                next: right_number,
+            },
+        )?;
+
+        // the new node is added to the parent, which may be split as well
+        let mut right_number = right_number;
+        while let Some((parent_number, parent, child)) = path.pop() {
+            let Node::Internal {
+                mut keys,
+                mut children,
+            } = parent
+            else {
+                return Err(PersistenceErrors::IndexRefresh(
+                    io::ErrorKind::InvalidData.into(),
+                ));
+            };
+            keys.insert(child, separator);
+            children.insert(child + 1, right_number);
+            if keys.len() <= internal_capacity(&self.columns, self.node_size) {
+                self.write_node(parent_number, &Node::Internal { keys, children })?;
+                return self.write_header();
+            }
+            let middle = keys.len() / 2;
+            let right_keys = keys.split_off(middle + 1);
+            separator = keys.pop().expect("The middle key was kept.");
+            let right_children = children.split_off(middle + 1);
+            right_number = self.allocate_node();
+            self.write_node(
+                right_number,
+                &Node::Internal {
+                    keys: right_keys,
+                    children: right_children,
+                },
+            )?;
+            self.write_node(parent_number, &Node::Internal { keys, children })?;
+        }
+
+        // the root was split
+        let old_root = self.root;
+        self.root = self.allocate_node();
+        self.write_node(
+            self.root,
+            &Node::Internal {
+                keys: vec![separator],
+                children: vec![old_root, right_number],
+            },
+        )?;
+        self.write_header()
+    }
+
+    /// Remove the entry from its leaf. The nodes aren't merged, a leaf may be left empty.
+    pub(crate) fn remove(&mut self, entry: &Entry) -> Result<(), PersistenceErrors> {
+        let mut node_number = self.root;
+        let mut node = self.read_node(node_number)?;
+        while let Node::Internal { keys, children } = node {
+            let child = keys
+                .iter()
+                .take_while(|key| compare_entries(key, entry) != Ordering::Greater)
+                .count();
+            node_number = children[child];
+            node = self.read_node(node_number)?;
+        }
+
+        let Node::Leaf { mut entries, next } = node else {
+            return Err(PersistenceErrors::IndexRefresh(
+                io::ErrorKind::InvalidData.into(),
+            ));
+        };
+        match entries.binary_search_by(|other| compare_entries(other, entry)) {
+            Ok(position) => {
+                entries.remove(position);
+                self.write_node(node_number, &Node::Leaf { entries, next })
+            }
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Remove the entries of the deleted rows, the rows behind them move forward by the number
+    /// of the deleted rows before them. The row numbers have to be sorted. The keys of the
+    /// internal nodes move the same way, so they still separate the entries of the children.
+    pub(crate) fn remove_rows(&mut self, row_numbers: &[u64]) -> Result<(), PersistenceErrors> {
+        for node_number in 1..self.node_count {
+            let node = self.read_node(node_number)?;
+            let shifted_node = match &node {
+                Node::Leaf { entries, next } => Node::Leaf {
+                    entries: entries
+                        .iter()
+                        .filter(|(_key, row_number)| row_numbers.binary_search(row_number).is_err())
+                        .map(|(key, row_number)| {
+                            (key.clone(), shifted_row(row_numbers, *row_number))
+                        })
+                        .collect(),
+                    next: *next,
+                },
+                Node::Internal { keys, children } => Node::Internal {
+                    keys: keys
+                        .iter()
+                        .map(|(key, row_number)| {
+                            (key.clone(), shifted_row(row_numbers, *row_number))
+                        })
+                        .collect(),
+                    children: children.clone(),
+                },
+            };
+            if shifted_node != node {
+                self.write_node(node_number, &shifted_node)?;
+            }
+        }
+        self.sync()
+    }
+
+    pub(crate) fn sync(&self) -> Result<(), PersistenceErrors> {
+        wal::sync_file(&self.file).map_err(PersistenceErrors::IndexRefresh)
+    }
+
+    fn allocate_node(&mut self) -> u64 {
+        self.node_count += 1;
+        self.node_count - 1
+    }
+
+    fn read_node(&mut self, node_number: u64) -> Result<Node, PersistenceErrors> {
+        let mut bytes = vec![0; self.node_size];
+        self.file
+            .seek(SeekFrom::Start(node_number * self.node_size as u64))
+            .and_then(|_| self.file.read_exact(&mut bytes))
+            .map_err(PersistenceErrors::IndexLoading)?;
+        Ok(node_from_bytes(&bytes, &self.columns))
+    }
+
+    fn write_node(&mut self, node_number: u64, node: &Node) -> Result<(), PersistenceErrors> {
+        let bytes = node_to_bytes(node, &self.columns, self.node_size);
+        self.write_at(node_number * self.node_size as u64, &bytes)
+    }
+
+    fn write_header(&mut self) -> Result<(), PersistenceErrors> {
+        let bytes = header_bytes(self.root, self.node_count, self.node_size);
+        self.write_at(0, &bytes)
+    }
+
+    fn write_at(&mut self, position: u64, bytes: &[u8]) -> Result<(), PersistenceErrors> {
+        self.file
+            .seek(SeekFrom::Start(position))
+            .and_then(|_| self.file.write_all(bytes))
+            .map_err(PersistenceErrors::IndexRefresh)
+    }
+}
+
+/// The entries of the index in the order of the values, ascending or descending.
+pub struct OrderedEntries {
+    index: BTreeIndex,
+    descending: bool,
+    /// The children of the internal nodes from the root to the current leaf, with the
+    /// number of the children already walked.
+    path: Vec<(Vec<u64>, usize)>,
+    /// The entries of the current leaf which weren't walked yet.
+    leaf: VecDeque<Entry>,
+    /// Descending, the entries of the same values are returned from the last one,
+    /// so they are in the order of their rows.
+    equal_entries: Vec<Entry>,
+    /// The entry read after the entries of the same values.
+    following_entry: Option<Entry>,
+}
+
+impl OrderedEntries {
+    /// The next entry in the order of the walk, the leaves are read as they are reached.
+    fn walk(&mut self) -> Result<Option<Entry>, PersistenceErrors> {
+        loop {
+            let entry = match self.descending {
+                true => self.leaf.pop_back(),
+                false => self.leaf.pop_front(),
+            };
+            if entry.is_some() {
+                return Ok(entry);
+            }
+            let Some((children, walked)) = self.path.last_mut() else {
+                return Ok(None);
+            };
+            if *walked == children.len() {
+                self.path.pop();
+                continue;
+            }
+            let child = match self.descending {
+                true => children[children.len() - 1 - *walked],
+                false => children[*walked],
+            };
+            *walked += 1;
+            match self.index.read_node(child)? {
+                Node::Leaf { entries, .. } => self.leaf = entries.into(),
+                Node::Internal { children, .. } => self.path.push((children, 0)),
+            }
+        }
+    }
+
+    fn next_entry(&mut self) -> Result<Option<Entry>, PersistenceErrors> {
+        if !self.descending {
+            return self.walk();
+        }
+        if let Some(entry) = self.equal_entries.pop() {
+            return Ok(Some(entry));
+        }
+        let first = match self.following_entry.take() {
+            Some(entry) => entry,
+            None => match self.walk()? {
+                Some(entry) => entry,
+                None => return Ok(None),
+            },
+        };
+        self.equal_entries.push(first);
+        loop {
+            match self.walk()? {
+                Some(entry)
+                    if compare_keys(&entry.0, &self.equal_entries[0].0) == Ordering::Equal =>
+                {
+                    self.equal_entries.push(entry)
+                }
+                entry => {
+                    self.following_entry = entry;
+                    return Ok(self.equal_entries.pop());
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for OrderedEntries {
+    type Item = Result<Entry, PersistenceErrors>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+/// Number of the row once the deleted rows before it are removed. The row numbers have to be sorted.
+fn shifted_row(row_numbers: &[u64], row_number: u64) -> u64 {
+    row_number - row_numbers.partition_point(|deleted| *deleted < row_number) as u64
+}
+
+fn compare_entries((key_a, row_a): &Entry, (key_b, row_b): &Entry) -> Ordering {
+    compare_keys(key_a, key_b).then(row_a.cmp(row_b))
+}
+
+fn compare_keys(key_a: &[Data], key_b: &[Data]) -> Ordering {
+    key_a
+        .iter()
+        .zip(key_b)
+        .map(|(data_a, data_b)| data_a.total_cmp(data_b))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(key_a.len().cmp(&key_b.len()))
+}
+
+/// Compare the leading values of the key with the values of the bound.
+fn compare_prefix(key: &[Data], bound: &[Data]) -> Ordering {
+    compare_keys(&key[..bound.len().min(key.len())], bound)
+}
+
+/// The NULL flag and the value of every column, the row number.
+fn entry_size(columns: &[Column]) -> usize {
+    columns
+        .iter()
+        .map(|column| 1 + value_size(column))
+        .sum::<usize>()
+        + 8
+}
+
+/// The values are kept whole within the entries, a character of VARCHAR takes
+/// at most 4 bytes. The types without a limit can't be ordered by the tree.
+fn value_size(column: &Column) -> usize {
+    match column.data_type {
+        DataType::STRING { size } => 4 * size as usize,
+        _ => column.size(),
+    }
+}
+
+pub(crate) fn is_orderable(column: &Column) -> bool {
+    !matches!(column.data_type, DataType::TEXT | DataType::BLOB)
+}
+
+/// The smallest multiple of `PAGE_SIZE` holding at least 3 keys of an internal node.
+fn node_size(columns: &[Column]) -> usize {
+    let needed_size = NODE_HEADER_SIZE + 3 * (entry_size(columns) + 8);
+    needed_size.div_ceil(PAGE_SIZE) * PAGE_SIZE
+}
+
+fn leaf_capacity(columns: &[Column], node_size: usize) -> usize {
+    (node_size - NODE_HEADER_SIZE) / entry_size(columns)
+}
+
+/// Keys of an internal node, every key is followed by the number of its child.
+fn internal_capacity(columns: &[Column], node_size: usize) -> usize {
+    (node_size - NODE_HEADER_SIZE) / (entry_size(columns) + 8)
+}
+
+fn header_bytes(root: u64, node_count: u64, node_size: usize) -> Vec<u8> {
+    let mut bytes = FILE_HEADER.to_vec();
+    bytes.extend(root.to_be_bytes());
+    bytes.extend(node_count.to_be_bytes());
+    bytes.resize(node_size, 0);
+    bytes
+}
+
+fn node_to_bytes(node: &Node, columns: &[Column], node_size: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(node_size);
+    match node {
+        Node::Leaf { entries, next } => {
+            bytes.extend([LEAF, 0, 0, 0]);
+            bytes.extend((entries.len() as u32).to_be_bytes());
+            bytes.extend(next.to_be_bytes());
+            for entry in entries {
+                bytes.extend(entry_to_bytes(entry, columns));
+            }
+        }
+        Node::Internal { keys, children } => {
+            bytes.extend([INTERNAL, 0, 0, 0]);
+            bytes.extend((keys.len() as u32).to_be_bytes());
+            bytes.extend(children[0].to_be_bytes());
+            for (key, child) in keys.iter().zip(&children[1..]) {
+                bytes.extend(entry_to_bytes(key, columns));
+                bytes.extend(child.to_be_bytes());
+            }
+        }
+    }
+    bytes.resize(node_size, 0);
+    bytes
+}
+
+fn node_from_bytes(bytes: &[u8], columns: &[Column]) -> Node {
+    let count = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    let first = u64_from_bytes(&bytes[8..16]);
+    let entry_size = entry_size(columns);
+    match bytes[0] {
+        LEAF => Node::Leaf {
+            entries: (0..count)
+                .map(|index| {
+                    let position = NODE_HEADER_SIZE + index * entry_size;
+                    entry_from_bytes(&bytes[position..position + entry_size], columns)
+                })
+                .collect(),
+            next: first,
+        },
+        _ => {
+            let mut keys = vec![];
+            let mut children = vec![first];
+            for index in 0..count {
+                let position = NODE_HEADER_SIZE + index * (entry_size + 8);
+                keys.push(entry_from_bytes(
+                    &bytes[position..position + entry_size],
+                    columns,
+                ));
+                children.push(u64_from_bytes(
+                    &bytes[position + entry_size..position + entry_size + 8],
+                ));
+            }
+            Node::Internal { keys, children }
+        }
+    }
+}
+
+fn entry_to_bytes((key, row_number): &Entry, columns: &[Column]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (data, column) in key.iter().zip(columns) {
+        let value_end = bytes.len() + 1 + value_size(column);
+        bytes.push((*data == Data::NULL) as u8);
+        if *data != Data::NULL {
+            bytes.extend(row::value_to_bytes(data, column, value_size(column)));
+        }
+        bytes.resize(value_end, 0);
+    }
+    bytes.extend(row_number.to_be_bytes());
+    bytes
+}
+
+fn entry_from_bytes(bytes: &[u8], columns: &[Column]) -> Entry {
+    let mut key = vec![];
+    let mut position = 0;
+    for column in columns {
+        let value_end = position + 1 + value_size(column);
+        key.push(match bytes[position] {
+            1 => Data::NULL,
+            _ => Data::from_bytes(bytes[position + 1..value_end].to_vec(), column),
+        });
+        position = value_end;
+    }
+    (key, u64_from_bytes(&bytes[position..position + 8]))
+}
+
+fn u64_from_bytes(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes[..8].try_into().expect("Eight bytes were given."))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_file;
+
+    use common::models::db::{ColumnConstraints, DataType};
+
+    use super::*;
+
+    fn int_column() -> Column {
+        Column {
+            data_type: DataType::INT,
+            name: String::from("id"),
+            constraints: ColumnConstraints::default(),
+        }
+    }
+
+    fn values(entries: &[Entry]) -> Vec<Vec<Data>> {
+        entries
+            .iter()
+            .map(|(key, _row_number)| key.clone())
+            .collect()
+    }
+
+    #[test]
+    fn btree_range() {
+        let columns = [int_column()];
+        let file_name = "btree1";
+        // enough entries for three levels of nodes
+        let entries: Vec<Entry> = (0..100_000)
+            .map(|row_number| {
+                (
+                    vec![Data::INT((row_number * 7919 % 100_000) as i32 / 2)],
+                    row_number,
+                )
+            })
+            .chain([(vec![Data::NULL], 100_000)])
+            .collect();
+        BTreeIndex::build(file_name, &columns, entries).unwrap();
+        let mut index = BTreeIndex::open(file_name, &columns).unwrap();
+        let between = index
+            .range(
+                Bound::Included(&[Data::INT(10)]),
+                Bound::Excluded(&[Data::INT(12)]),
+            )
+            .unwrap();
+        let above = index
+            .range(Bound::Excluded(&[Data::INT(49_998)]), Bound::Unbounded)
+            .unwrap();
+        let all = index.entries().unwrap();
+        remove_file(file_name).unwrap();
+
+        assert_eq!(
+            values(&between),
+            [10, 10, 11, 11].map(|value| vec![Data::INT(value)])
+        );
+        assert!(between
+            .windows(2)
+            .all(|pair| compare_entries(&pair[0], &pair[1]) == Ordering::Less));
+        assert_eq!(
+            values(&above),
+            vec![
+                vec![Data::INT(49_999)],
+                vec![Data::INT(49_999)],
+                vec![Data::NULL]
+            ]
+        );
+        assert_eq!(all.len(), 100_001);
+        assert!(all
+            .windows(2)
+            .all(|pair| compare_entries(&pair[0], &pair[1]) == Ordering::Less));
+    }
+
+    #[test]
+    fn btree_insert_splits_nodes() {
+        let columns = [Column {
+            data_type: DataType::STRING { size: 1000 },
+            name: String::from("name"),
+            constraints: ColumnConstraints::default(),
+        }];
+        let file_name = "btree2";
+        BTreeIndex::build(file_name, &columns, vec![]).unwrap();
+        let mut index = BTreeIndex::open(file_name, &columns).unwrap();
+        for row_number in 0..200 {
+            let data = Data::STRING(format!("{:03}", (row_number * 37) % 200));
+            index.insert((vec![data], row_number)).unwrap();
+        }
+        let mut index = BTreeIndex::open(file_name, &columns).unwrap();
+        let node_count = index.node_count;
+        let prefixed = index
+            .range(
+                Bound::Included(&[Data::STRING(String::from("05"))]),
+                Bound::Excluded(&[Data::STRING(String::from("06"))]),
+            )
+            .unwrap();
+        let all = index.entries().unwrap();
+        remove_file(file_name).unwrap();
+
+        assert!(node_count > 50);
+        let expected: Vec<Vec<Data>> = (50..60)
+            .map(|value| vec![Data::STRING(format!("{:03}", value))])
+            .collect();
+        assert_eq!(values(&prefixed), expected);
+        assert_eq!(all.len(), 200);
+        assert!(all
+            .windows(2)
+            .all(|pair| compare_entries(&pair[0], &pair[1]) == Ordering::Less));
+    }
+
+    #[test]
+    fn btree_of_multiple_columns() {
+        let columns = [int_column(), int_column()];
+        let file_name = "btree3";
+        let entries: Vec<Entry> = (0..1000)
+            .map(|row_number| {
+                (
+                    vec![
+                        Data::INT(row_number as i32 % 10),
+                        Data::INT(row_number as i32 / 10),
+                    ],
+                    row_number,
+                )
+            })
+            .collect();
+        BTreeIndex::build(file_name, &columns, entries).unwrap();
+        let mut index = BTreeIndex::open(file_name, &columns).unwrap();
+        index
+            .insert((vec![Data::INT(3), Data::NULL], 1000))
+            .unwrap();
+        let leading = index
+            .range(
+                Bound::Included(&[Data::INT(3)]),
+                Bound::Included(&[Data::INT(3)]),
+            )
+            .unwrap();
+        let both = index
+            .range(
+                Bound::Included(&[Data::INT(3), Data::INT(98)]),
+                Bound::Unbounded,
+            )
+            .unwrap();
+        remove_file(file_name).unwrap();
+
+        assert_eq!(leading.len(), 101);
+        assert!(leading
+            .windows(2)
+            .all(|pair| compare_entries(&pair[0], &pair[1]) == Ordering::Less));
+        assert_eq!(
+            leading.last(),
+            Some(&(vec![Data::INT(3), Data::NULL], 1000))
+        );
+        assert_eq!(
+            values(&both)[..2],
+            [[3, 98], [3, 99]].map(|key| key.map(Data::INT).to_vec())
+        );
+        assert_eq!(both[2].0, vec![Data::INT(3), Data::NULL]);
+        assert_eq!(both.len(), 3 + 6 * 100);
+    }
+
+    #[test]
+    fn btree_entries_walked_from_both_ends() {
+        let columns = [int_column()];
+        let file_name = "btree6";
+        let entries: Vec<Entry> = (0..1000)
+            .map(|row_number| (vec![Data::INT(row_number as i32 / 3)], row_number))
+            .collect();
+        BTreeIndex::build(file_name, &columns, entries.clone()).unwrap();
+        let ascending: Vec<Entry> = BTreeIndex::open(file_name, &columns)
+            .unwrap()
+            .into_ordered_entries(false)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let first_descending: Vec<Entry> = BTreeIndex::open(file_name, &columns)
+            .unwrap()
+            .into_ordered_entries(true)
+            .take(4)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        remove_file(file_name).unwrap();
+
+        assert_eq!(ascending, entries);
+        let expected = [(333, 999), (332, 996), (332, 997), (332, 998)];
+        assert_eq!(
+            first_descending,
+            expected.map(|(value, row_number)| (vec![Data::INT(value)], row_number))
+        );
+    }
+
+    #[test]
+    fn btree_entries_removed() {
+        let columns = [int_column()];
+        let file_name = "btree4";
+        let entries: Vec<Entry> = (0..1000)
+            .map(|row_number| (vec![Data::INT(row_number as i32 / 2)], row_number))
+            .collect();
+        BTreeIndex::build(file_name, &columns, entries).unwrap();
+        let mut index = BTreeIndex::open(file_name, &columns).unwrap();
+        for row_number in 0..900 {
+            index
+                .remove(&(vec![Data::INT(row_number as i32 / 2)], row_number))
+                .unwrap();
+        }
+        index.remove(&(vec![Data::INT(460)], 0)).unwrap();
+        index.insert((vec![Data::INT(0)], 0)).unwrap();
+        let all = index.entries().unwrap();
+        let between = index
+            .range(
+                Bound::Included(&[Data::INT(460)]),
+                Bound::Excluded(&[Data::INT(462)]),
+            )
+            .unwrap();
+        remove_file(file_name).unwrap();
+
+        assert_eq!(all.len(), 101);
+        assert_eq!(all[0], (vec![Data::INT(0)], 0));
+        let expected = [(460, 920), (460, 921), (461, 922), (461, 923)];
+        assert_eq!(
+            between,
+            expected.map(|(value, row_number)| (vec![Data::INT(value)], row_number))
+        );
+    }
+
+    #[test]
+    fn btree_rows_removed() {
+        let columns = [int_column()];
+        let file_name = "btree5";
+        let entries: Vec<Entry> = (0..1000)
+            .map(|row_number| (vec![Data::INT(row_number as i32 / 4)], row_number))
+            .collect();
+        BTreeIndex::build(file_name, &columns, entries).unwrap();
+        let mut index = BTreeIndex::open(file_name, &columns).unwrap();
+        let deleted: Vec<u64> = (0..1000).filter(|row_number| row_number % 3 == 0).collect();
+        index.remove_rows(&deleted).unwrap();
+        index.insert((vec![Data::INT(100)], 1000)).unwrap();
+        let all = index.entries().unwrap();
+        let between = index
+            .range(
+                Bound::Included(&[Data::INT(100)]),
+                Bound::Excluded(&[Data::INT(101)]),
+            )
+            .unwrap();
+        remove_file(file_name).unwrap();
+
+        let expected: Vec<Entry> = (0..1000u64)
+            .filter(|row_number| row_number % 3 != 0)
+            .enumerate()
+            .map(|(position, row_number)| (vec![Data::INT(row_number as i32 / 4)], position as u64))
+            .collect();
+        assert_eq!(all.len(), expected.len() + 1);
+        assert_eq!(
+            all.iter()
+                .filter(|entry| entry.1 != 1000)
+                .cloned()
+                .collect::<Vec<_>>(),
+            expected
+        );
+        let expected = [(100, 266), (100, 267), (100, 268), (100, 1000)];
+        assert_eq!(
+            between,
+            expected.map(|(value, row_number)| (vec![Data::INT(value)], row_number))
+        );
+    }
+}
@@ -1,4 +1,12 @@
-use common::models::db::{Column, DataType};
+use common::models::db::{Column, ColumnConstraints, DataType};
+
+const NOT_NULL_FLAG: u8 = 2;
+/// The expression of the default value follows the flags.
+const DEFAULT_FLAG: u8 = 4;
+/// The expression of the check follows the flags, after the default value.
+const CHECK_FLAG: u8 = 8;
+/// Size of the slot of a string or a blob in the row, see `row::value_to_slot`.
+pub(crate) const VALUE_SLOT_SIZE: usize = 16;
 
 pub trait PersistenceColumn {
     fn to_bytes(&self) -> Vec<u8>;
@@ -8,13 +16,31 @@ pub trait PersistenceColumn {
 
 impl PersistenceColumn for Column {
     fn to_bytes(&self) -> Vec<u8> {
-        return [
+        let constraints = &self.constraints;
+        // the lowest bit is the former index flag, the indexes are stored after the columns now
+        let mut flags = 0;
+        let mut expressions = vec![];
+        if constraints.not_null {
+            flags |= NOT_NULL_FLAG;
+        }
+        for (flag, expression) in [
+            (DEFAULT_FLAG, &constraints.default),
+            (CHECK_FLAG, &constraints.check),
+        ] {
+            if let Some(expression) = expression {
+                flags |= flag;
+                expressions.extend((expression.len() as u32).to_be_bytes());
+                expressions.extend(expression.as_bytes());
+            }
+        }
+        [
             (self.name.len() as u32).to_be_bytes().to_vec(),
             self.name.as_bytes().to_vec(),
             self.data_type.to_bytes().to_vec(),
-            vec![self.is_indexed as u8],
+            vec![flags],
+            expressions,
         ]
-        .concat();
+        .concat()
     }
 
     fn from_bytes(bytes: Vec<u8>) -> Column {
@@ -28,26 +54,71 @@ impl PersistenceColumn for Column {
         };
         let data_type_beginning = 4usize + name_size as usize;
 
-        let is_indexed_beginning = data_type_beginning + 8;
         let data_type =
-            DataType::from_bytes(bytes[data_type_beginning..is_indexed_beginning].to_owned());
-        let is_indexed = (bytes[is_indexed_beginning] & 1) == 1;
+            DataType::from_bytes(bytes[data_type_beginning..data_type_beginning + 8].to_owned());
+
+        let flags = bytes[data_type_beginning + 8];
+        let mut cursor = data_type_beginning + 9;
+        let mut read_expression = |flag: u8| {
+            if flags & flag == 0 {
+                return None;
+            }
+            let size = get_size(&bytes[cursor..]);
+            let expression = String::from_utf8_lossy(&bytes[cursor + 4..cursor + 4 + size]).to_string();
+            cursor += 4 + size;
+            Some(expression)
+        };
+        let default = read_expression(DEFAULT_FLAG);
+        let check = read_expression(CHECK_FLAG);
 
         Column {
             name,
             data_type,
-            is_indexed,
+            constraints: ColumnConstraints {
+                not_null: flags & NOT_NULL_FLAG != 0,
+                default,
+                check,
+            },
         }
     }
 
     fn size(&self) -> usize {
         match self.data_type {
             DataType::INT => 8,
-            DataType::STRING { size } => size as usize,
+            DataType::STRING { .. } | DataType::TEXT | DataType::BLOB => VALUE_SLOT_SIZE,
             DataType::BOOLEAN => 8,
             DataType::FLOAT => 8,
+            DataType::BIGINT | DataType::DECIMAL { .. } | DataType::DATE | DataType::TIMESTAMP => 8,
+            DataType::UUID => 16,
+        }
+    }
+}
+
+/// The values of the type are stored in the slot of the column when they fit it,
+/// otherwise in the values file of the table.
+pub(crate) fn has_variable_size(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::STRING { .. } | DataType::TEXT | DataType::BLOB)
+}
+
+/// Position of the flags of the column serialized at the beginning of the bytes.
+pub(crate) fn flags_position(bytes: &[u8]) -> usize {
+    4 + get_size(bytes) + 8
+}
+
+/// Length of the column serialized at the beginning of the bytes.
+pub(crate) fn bytes_size(bytes: &[u8]) -> usize {
+    let flags_position = flags_position(bytes);
+    let mut size = flags_position + 1;
+    for flag in [DEFAULT_FLAG, CHECK_FLAG] {
+        if bytes[flags_position] & flag != 0 {
+            size += 4 + get_size(&bytes[size..]);
         }
     }
+    size
+}
+
+fn get_size(bytes: &[u8]) -> usize {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
 }
 
 pub trait PersistenceDataType {
@@ -62,6 +133,13 @@ impl PersistenceDataType for DataType {
             DataType::STRING { size } => [[1, 0, 0, 0], size.to_be_bytes()].concat(),
             DataType::BOOLEAN => [2, 0, 0, 0, 0, 0, 0, 0].to_vec(),
             DataType::FLOAT => [3, 0, 0, 0, 0, 0, 0, 0].to_vec(),
+            DataType::TEXT => [4, 0, 0, 0, 0, 0, 0, 0].to_vec(),
+            DataType::BLOB => [5, 0, 0, 0, 0, 0, 0, 0].to_vec(),
+            DataType::BIGINT => [6, 0, 0, 0, 0, 0, 0, 0].to_vec(),
+            DataType::DECIMAL { precision, scale } => [7, 0, precision, scale, 0, 0, 0, 0].to_vec(),
+            DataType::DATE => [8, 0, 0, 0, 0, 0, 0, 0].to_vec(),
+            DataType::TIMESTAMP => [9, 0, 0, 0, 0, 0, 0, 0].to_vec(),
+            DataType::UUID => [10, 0, 0, 0, 0, 0, 0, 0].to_vec(),
         }
     }
 
@@ -73,6 +151,16 @@ impl PersistenceDataType for DataType {
             },
             2  => DataType::BOOLEAN,
             3  => DataType::FLOAT,
+            4 => DataType::TEXT,
+            5 => DataType::BLOB,
+            6 => DataType::BIGINT,
+            7 => DataType::DECIMAL {
+                precision: bytes[2],
+                scale: bytes[3],
+            },
+            8 => DataType::DATE,
+            9 => DataType::TIMESTAMP,
+            10 => DataType::UUID,
             _ => {
                 panic!("Unknown DataType")
             }
@@ -86,17 +174,55 @@ mod tests {
 
     #[test]
     fn column_to_and_from_bytes() {
-        test_column_to_and_from_bytes(String::from("Name"), DataType::STRING { size: 255 }, true);
-        test_column_to_and_from_bytes(String::from("Rust is just so cool"), DataType::INT, false);
-        test_column_to_and_from_bytes(String::from("Rust is just so cool"), DataType::BOOLEAN, false);
-        test_column_to_and_from_bytes(String::from("Rust is just so cool"), DataType::FLOAT, false);
+        test_column_to_and_from_bytes(String::from("Name"), DataType::STRING { size: 255 });
+        test_column_to_and_from_bytes(String::from("Rust is just so cool"), DataType::INT);
+        test_column_to_and_from_bytes(String::from("Rust is just so cool"), DataType::BOOLEAN);
+        test_column_to_and_from_bytes(String::from("Rust is just so cool"), DataType::FLOAT);
+        test_column_to_and_from_bytes(String::from("Text"), DataType::TEXT);
+        test_column_to_and_from_bytes(String::from("Blob"), DataType::BLOB);
+        test_column_to_and_from_bytes(String::from("Big"), DataType::BIGINT);
+        test_column_to_and_from_bytes(
+            String::from("Price"),
+            DataType::DECIMAL {
+                precision: 10,
+                scale: 2,
+            },
+        );
+        test_column_to_and_from_bytes(String::from("Day"), DataType::DATE);
+        test_column_to_and_from_bytes(String::from("Time"), DataType::TIMESTAMP);
+        test_column_to_and_from_bytes(String::from("Id"), DataType::UUID);
+    }
+
+    #[test]
+    fn column_constraints_to_and_from_bytes() {
+        let column = Column {
+            name: String::from("Id"),
+            data_type: DataType::INT,
+            constraints: ColumnConstraints {
+                not_null: true,
+                default: Some(String::from("1 + 2")),
+                check: Some(String::from("Id > 0")),
+            },
+        };
+        let bytes = [column.to_bytes(), vec![7, 7]].concat();
+        assert_eq!(bytes_size(&bytes), bytes.len() - 2);
+        assert_eq!(Column::from_bytes(bytes), column);
+
+        let column = Column {
+            constraints: ColumnConstraints {
+                default: None,
+                ..column.constraints
+            },
+            ..column
+        };
+        assert_eq!(Column::from_bytes(column.to_bytes()), column);
     }
 
-    fn test_column_to_and_from_bytes(name: String, data_type: DataType, is_indexed: bool) {
+    fn test_column_to_and_from_bytes(name: String, data_type: DataType) {
         let column = Column {
             name,
             data_type,
-            is_indexed,
+            constraints: ColumnConstraints::default(),
         };
         let column_from_bytes = Column::from_bytes(column.to_bytes());
         assert_eq!(column_from_bytes, column);
@@ -21,4 +21,14 @@ pub enum PersistenceErrors {
     IndexCreating(),
     #[error("Row wasn't deleted properly.")]
     RowDeletion(#[source] io::Error),
+    #[error("Row wasn't updated properly.")]
+    RowUpdate(#[source] io::Error),
+    #[error("Table wasn't altered properly.")]
+    TableAlter(#[source] io::Error),
+    #[error("Table backup wasn't created or restored properly.")]
+    TableBackup(#[source] io::Error),
+    #[error("Write-ahead log wasn't written or read properly.")]
+    Wal(#[source] io::Error),
+    #[error("Table {0} has a change which failed and couldn't be finished yet.")]
+    UnfinishedChange(String),
 }
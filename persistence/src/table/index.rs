@@ -1,27 +1,39 @@
 use common::models::db::{Data, Column};
 
 use crate::table::errors::PersistenceErrors;
-use std::{fs::write, collections::HashMap};
+use crate::wal;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
 
-use super::{column::PersistenceColumn, row::PersistenceData};
+use super::row::{self, PersistenceData};
+
+/// Row number of the entries removed within the index file, they are skipped when
+/// the index is loaded and dropped when the file is written again. The file is written
+/// again once most of its entries are removed.
+const REMOVED_ROW: u64 = u64::MAX;
 
 #[derive(PartialEq, Debug)]
 pub struct IndexRow {
     pub hash: u64,
-    pub values: Vec<(Data, u64)>,
+    /// Values of the indexed columns together with the number of their row.
+    pub values: Vec<(Vec<Data>, u64)>,
 }
 
 impl IndexRow {
-    pub(crate) fn to_bytes(&self, column: &Column) -> Vec<u8> {
+    pub(crate) fn to_bytes(&self, columns: &[Column]) -> Vec<u8> {
         let mut length: u64 = 0;
         let mut bytes = vec![self.hash.to_be_bytes().to_vec()];
-        for (data, row_number) in &self.values {
-            let column_size = column.size();
-            bytes.push(data.to_bytes(column_size, &column.data_type));
+        for (key, row_number) in &self.values {
+            let key_bytes = row::key_to_bytes(key, columns);
+            length += key_bytes.len() as u64;
+            bytes.push(key_bytes);
             bytes.push(row_number.to_be_bytes().to_vec());
-            length += column_size as u64 + 8;
+            length += 8;
         }
-        vec![length.to_be_bytes().to_vec(), bytes.concat()].concat()
+        [length.to_be_bytes().to_vec(), bytes.concat()].concat()
     }
 
     pub(crate) fn parse_u64(bytes: &[u8], cursor: usize) -> u64 {
@@ -38,85 +50,252 @@ impl IndexRow {
         ])
     }
 
-    pub(crate) fn from_bytes(bytes: Vec<u8>, column: &Column) -> IndexRow {
+    pub(crate) fn from_bytes(bytes: Vec<u8>, columns: &[Column]) -> IndexRow {
         let mut values_length = Self::parse_u64(&bytes, 0);
         let mut cursor: usize = 8;
         let hash = Self::parse_u64(&bytes, cursor);
         cursor += 8;
-        let column_size = column.size();
-        let mut values: Vec<(Data, u64)> = vec![];
+        let mut values: Vec<(Vec<Data>, u64)> = vec![];
         while values_length != 0 {
-            let data_bytes = bytes[cursor..cursor + column_size].to_owned();
-            let data = Data::from_bytes(data_bytes, column);
-            cursor += column_size;
+            let (key, key_size) = row::key_from_bytes(&bytes[cursor..], columns);
+            cursor += key_size;
+            values_length -= key_size as u64;
             let row_number = Self::parse_u64(&bytes, cursor);
-            values.push((data, row_number));
+            values.push((key, row_number));
             cursor += 8;
-            values_length -= column_size as u64 + 8;
+            values_length -= 8;
         }
         IndexRow { hash, values }
     }
 }
 
+/// Hash of the values of the indexed columns. A single value is hashed on its own,
+/// so the files of the indexes of a single column keep their hashes.
+pub fn key_hash(key: &[Data]) -> u64 {
+    match key {
+        [data] => data.calculate_hash(),
+        _ => {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            hasher.finish()
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub struct Index {
     pub rows: HashMap<u64, IndexRow>,
 }
 
 impl Index {
-    fn to_bytes(&self, column: &Column) -> Vec<u8> {
+    fn to_bytes(&self, columns: &[Column]) -> Vec<u8> {
         let mut bytes = vec![];
         for row in &self.rows {
-            bytes.push(row.1.to_bytes(column));
+            bytes.push(row.1.to_bytes(columns));
         }
         bytes.concat()
     }
 
-    fn from_bytes(bytes: Vec<u8>, column: &Column) -> Self {
-        let mut rows = HashMap::new();
+    fn from_bytes(bytes: Vec<u8>, columns: &[Column]) -> Self {
+        let mut rows: HashMap<u64, IndexRow> = HashMap::new();
         let mut cursor: usize = 0;
         while cursor < bytes.len() {
             let length = IndexRow::parse_u64(&bytes, cursor) as usize + 16;
             let bytes = bytes[cursor..cursor + length].to_owned();
-            let index_row = IndexRow::from_bytes(bytes, column);
-            rows.insert(index_row.hash, index_row);
+            let mut index_row = IndexRow::from_bytes(bytes, columns);
+            index_row.values.retain(|(_key, row_number)| *row_number != REMOVED_ROW);
             cursor += length;
+            if index_row.values.is_empty() {
+                continue;
+            }
+            // the rows appended to the file are merged with the bucket of the same hash
+            match rows.entry(index_row.hash) {
+                Entry::Occupied(mut entry) => entry.get_mut().values.extend(index_row.values),
+                Entry::Vacant(entry) => {
+                    entry.insert(index_row);
+                }
+            }
         }
         Index { rows }
     }
 
+    /// Rows with exactly the given values of the indexed columns.
+    pub fn get_rows(&self, key: &[Data]) -> Vec<u64> {
+        match self.rows.get(&key_hash(key)) {
+            Some(index_row) => index_row
+                .values
+                .iter()
+                .filter(|(values, _row_number)| values.as_slice() == key)
+                .map(|(_values, row_number)| *row_number)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Add the values of the row to the bucket of their hash.
+    pub(crate) fn insert(&mut self, key: Vec<Data>, row_number: u64) {
+        let hash = key_hash(&key);
+        self.rows
+            .entry(hash)
+            .or_insert_with(|| IndexRow { hash, values: vec![] })
+            .values
+            .push((key, row_number));
+    }
+
+    /// Append the buckets to the index file, so the index isn't rewritten
+    /// when only a few rows are added.
+    pub(crate) fn append_to_file(&self, file_name: String, columns: &[Column]) -> Result<(), PersistenceErrors> {
+        wal::append_file(&file_name, &self.to_bytes(columns)).map_err(PersistenceErrors::IndexRefresh)
+    }
+
+    /// Mark the entries of the rows as removed within the index file, the entries are given
+    /// by the hash of their values and their row number. Only the buckets of the hashes are written.
+    pub(crate) fn remove_entries_from_file(
+        file_name: &str,
+        columns: &[Column],
+        entries: &[(u64, u64)],
+    ) -> Result<(), PersistenceErrors> {
+        let mut reader = BufReader::new(File::open(file_name).map_err(PersistenceErrors::IndexRefresh)?);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(file_name)
+            .map_err(PersistenceErrors::IndexRefresh)?;
+        let file_size = file.metadata().map_err(PersistenceErrors::IndexRefresh)?.len();
+        let (mut entries_count, mut removed_count) = (0, 0);
+        let mut position = 0;
+        while position < file_size {
+            let mut header = [0; 16];
+            reader.read_exact(&mut header).map_err(PersistenceErrors::IndexRefresh)?;
+            let length = IndexRow::parse_u64(&header, 0);
+            let hash = IndexRow::parse_u64(&header, 8);
+            let values_position = position + 16;
+            position = values_position + length;
+            let mut bytes = vec![0; length as usize];
+            reader.read_exact(&mut bytes).map_err(PersistenceErrors::IndexRefresh)?;
+            let is_searched = entries.iter().any(|(removed_hash, _row_number)| *removed_hash == hash);
+            let mut cursor = 0;
+            while cursor < bytes.len() {
+                cursor += row::key_from_bytes(&bytes[cursor..], columns).1;
+                let row_number = IndexRow::parse_u64(&bytes, cursor);
+                entries_count += 1;
+                if row_number == REMOVED_ROW {
+                    removed_count += 1;
+                } else if is_searched && entries.contains(&(hash, row_number)) {
+                    file.seek(SeekFrom::Start(values_position + cursor as u64))
+                        .and_then(|_| file.write_all(&REMOVED_ROW.to_be_bytes()))
+                        .map_err(PersistenceErrors::IndexRefresh)?;
+                    removed_count += 1;
+                }
+                cursor += 8;
+            }
+        }
+        wal::sync_file(&file).map_err(PersistenceErrors::IndexRefresh)?;
+        Self::compact_file(file_name, columns, entries_count, removed_count)
+    }
+
+    /// Remove the entries of the deleted rows within the index file, the rows behind them
+    /// move forward by the number of the deleted rows before them. The row numbers have to be
+    /// sorted. Only the buckets with changed entries are written back.
+    pub(crate) fn remove_rows_from_file(
+        file_name: &str,
+        columns: &[Column],
+        row_numbers: &[u64],
+    ) -> Result<(), PersistenceErrors> {
+        let mut reader = BufReader::new(File::open(file_name).map_err(PersistenceErrors::IndexRefresh)?);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(file_name)
+            .map_err(PersistenceErrors::IndexRefresh)?;
+        let file_size = file.metadata().map_err(PersistenceErrors::IndexRefresh)?.len();
+        let (mut entries_count, mut removed_count) = (0, 0);
+        let mut position = 0;
+        while position < file_size {
+            let mut header = [0; 16];
+            reader.read_exact(&mut header).map_err(PersistenceErrors::IndexRefresh)?;
+            let length = IndexRow::parse_u64(&header, 0);
+            let values_position = position + 16;
+            position = values_position + length;
+            let mut bytes = vec![0; length as usize];
+            reader.read_exact(&mut bytes).map_err(PersistenceErrors::IndexRefresh)?;
+            let mut is_changed = false;
+            let mut cursor = 0;
+            while cursor < bytes.len() {
+                cursor += row::key_from_bytes(&bytes[cursor..], columns).1;
+                let row_number = IndexRow::parse_u64(&bytes, cursor);
+                let deleted_before = row_numbers.partition_point(|deleted| *deleted < row_number);
+                let shifted_row = match row_number {
+                    REMOVED_ROW => REMOVED_ROW,
+                    _ if row_numbers.get(deleted_before) == Some(&row_number) => REMOVED_ROW,
+                    _ => row_number - deleted_before as u64,
+                };
+                entries_count += 1;
+                if shifted_row == REMOVED_ROW {
+                    removed_count += 1;
+                }
+                if shifted_row != row_number {
+                    bytes[cursor..cursor + 8].copy_from_slice(&shifted_row.to_be_bytes());
+                    is_changed = true;
+                }
+                cursor += 8;
+            }
+            if is_changed {
+                file.seek(SeekFrom::Start(values_position))
+                    .and_then(|_| file.write_all(&bytes))
+                    .map_err(PersistenceErrors::IndexRefresh)?;
+            }
+        }
+        wal::sync_file(&file).map_err(PersistenceErrors::IndexRefresh)?;
+        Self::compact_file(file_name, columns, entries_count, removed_count)
+    }
+
+    /// Write the index file again without the removed entries once they are the majority
+    /// of its entries, so the file doesn't keep growing by the updates and deletes.
+    fn compact_file(
+        file_name: &str,
+        columns: &[Column],
+        entries_count: usize,
+        removed_count: usize,
+    ) -> Result<(), PersistenceErrors> {
+        if removed_count * 2 <= entries_count {
+            return Ok(());
+        }
+        let index = Index::load(file_name.to_string(), columns)?;
+        wal::replace_file(file_name, |file| file.write_all(&index.to_bytes(columns)))
+            .map_err(PersistenceErrors::IndexRefresh)
+    }
+
     pub(crate) fn write_index_to_file(
         &self,
         file_name: String,
-        column: &Column,
+        columns: &[Column],
     ) -> Result<(), PersistenceErrors> {
-        write(file_name, self.to_bytes(column)).map_err(PersistenceErrors::IndexRefresh)?;
+        wal::write_file(&file_name, &self.to_bytes(columns)).map_err(PersistenceErrors::IndexRefresh)?;
         Ok(())
     }
 
-    pub(crate) fn load(file_name: String, column: &Column) -> Result<Self, PersistenceErrors> {
+    pub(crate) fn load(file_name: String, columns: &[Column]) -> Result<Self, PersistenceErrors> {
         let bytes = std::fs::read(file_name).map_err(PersistenceErrors::IndexLoading)?;
-        Result::Ok(Index::from_bytes(bytes, column))
+        Result::Ok(Index::from_bytes(bytes, columns))
     }
 }
 
 #[cfg(test)]
 pub mod tests {
     use std::fs::remove_file;
-    use common::models::db::DataType;
+    use common::models::db::{ColumnConstraints, DataType};
 
     use super::*;
 
     #[test]
     fn index_row_int_to_and_from_bytes() {
-        let int_column = Column {
+        let int_column = [Column {
             data_type: DataType::INT,
-            is_indexed: true,
             name: String::from("id"),
-        };
-        let value1 = (Data::INT(1), 10);
-        let value2 = (Data::INT(8), 5);
-        let value3 = (Data::INT(5), 16);
+            constraints: ColumnConstraints::default(),
+        }];
+        let value1 = (vec![Data::INT(1)], 10);
+        let value2 = (vec![Data::INT(8)], 5);
+        let value3 = (vec![Data::INT(5)], 16);
         let index_row = IndexRow {
             hash: 123,
             values: vec![value1, value2, value3],
@@ -129,14 +308,14 @@ pub mod tests {
 
     #[test]
     fn index_row_string_to_and_from_bytes() {
-        let string_column = Column {
+        let string_column = [Column {
             data_type: DataType::STRING { size: 255 },
-            is_indexed: true,
             name: String::from("name"),
-        };
-        let value1 = (Data::STRING(String::from("Rust")), 10);
-        let value2 = (Data::STRING(String::from("is")), 5);
-        let value3 = (Data::STRING(String::from("great")), 16);
+            constraints: ColumnConstraints::default(),
+        }];
+        let value1 = (vec![Data::STRING(String::from("Rust"))], 10);
+        let value2 = (vec![Data::STRING(String::from("is"))], 5);
+        let value3 = (vec![Data::STRING(String::from("great"))], 16);
         let index_row = IndexRow {
             hash: 123,
             values: vec![value1, value2, value3],
@@ -149,30 +328,30 @@ pub mod tests {
 
     #[test]
     fn index_to_and_from_bytes() {
-        let string_column = Column {
+        let string_column = [Column {
             data_type: DataType::STRING { size: 255 },
-            is_indexed: true,
             name: String::from("name"),
-        };
+            constraints: ColumnConstraints::default(),
+        }];
         let index_row1 = IndexRow {
             hash: 123,
             values: vec![
-                (Data::STRING(String::from("Rust")), 10),
-                (Data::STRING(String::from("is")), 5),
+                (vec![Data::STRING(String::from("Rust"))], 10),
+                (vec![Data::STRING(String::from("is"))], 5),
             ],
         };
         let index_row2 = IndexRow {
             hash: 566,
             values: vec![
-                (Data::STRING(String::from("just")), 1),
-                (Data::STRING(String::from("so")), 12),
+                (vec![Data::STRING(String::from("just"))], 1),
+                (vec![Data::STRING(String::from("so"))], 12),
             ],
         };
         let index_row3 = IndexRow {
             hash: 99,
             values: vec![
-                (Data::STRING(String::from("great")), 99),
-                (Data::STRING(String::from("man")), 69),
+                (vec![Data::STRING(String::from("great"))], 99),
+                (vec![Data::STRING(String::from("man"))], 69),
             ],
         };
         let index = Index {
@@ -188,32 +367,164 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn index_of_multiple_columns() {
+        let mut index = Index { rows: HashMap::new() };
+        index.insert(vec![Data::INT(1), Data::STRING(String::from("a"))], 0);
+        index.insert(vec![Data::INT(1), Data::STRING(String::from("b"))], 1);
+        index.insert(vec![Data::INT(1), Data::STRING(String::from("a"))], 2);
+        let columns = [
+            Column {
+                data_type: DataType::INT,
+                name: String::from("id"),
+                constraints: ColumnConstraints::default(),
+            },
+            Column {
+                data_type: DataType::STRING { size: 10 },
+                name: String::from("name"),
+                constraints: ColumnConstraints::default(),
+            },
+        ];
+        let index = Index::from_bytes(index.to_bytes(&columns), &columns);
+
+        assert_eq!(index.get_rows(&[Data::INT(1), Data::STRING(String::from("a"))]), vec![0, 2]);
+        assert_eq!(index.get_rows(&[Data::INT(1), Data::STRING(String::from("b"))]), vec![1]);
+        assert!(index.get_rows(&[Data::INT(2), Data::STRING(String::from("a"))]).is_empty());
+        assert_eq!(key_hash(&[Data::INT(1)]), Data::INT(1).calculate_hash());
+    }
+
+    #[test]
+    fn index_appended_to_file() {
+        let int_column = [Column {
+            data_type: DataType::INT,
+            name: String::from("id"),
+            constraints: ColumnConstraints::default(),
+        }];
+        let mut index = Index { rows: HashMap::new() };
+        index.insert(vec![Data::INT(1)], 0);
+        let mut appended_index = Index { rows: HashMap::new() };
+        appended_index.insert(vec![Data::INT(1)], 1);
+        appended_index.insert(vec![Data::INT(2)], 2);
+        let file_name = String::from("index2");
+        index.write_index_to_file(file_name.clone(), &int_column).unwrap();
+        appended_index.append_to_file(file_name.clone(), &int_column).unwrap();
+        let loaded_index = Index::load(file_name.clone(), &int_column).unwrap();
+        remove_file(file_name).unwrap();
+
+        index.insert(vec![Data::INT(1)], 1);
+        index.insert(vec![Data::INT(2)], 2);
+        assert_eq!(loaded_index, index);
+    }
+
+    #[test]
+    fn index_entries_removed_from_file() {
+        let int_column = [Column {
+            data_type: DataType::INT,
+            name: String::from("id"),
+            constraints: ColumnConstraints::default(),
+        }];
+        let mut index = Index { rows: HashMap::new() };
+        index.insert(vec![Data::INT(1)], 0);
+        index.insert(vec![Data::INT(2)], 1);
+        index.insert(vec![Data::INT(1)], 2);
+        let file_name = "index3";
+        index.write_index_to_file(file_name.to_string(), &int_column).unwrap();
+        let removed = [(Data::INT(1).calculate_hash(), 2), (Data::INT(2).calculate_hash(), 1)];
+        Index::remove_entries_from_file(file_name, &int_column, &removed).unwrap();
+        let loaded_index = Index::load(file_name.to_string(), &int_column).unwrap();
+        remove_file(file_name).unwrap();
+
+        assert_eq!(loaded_index.get_rows(&[Data::INT(1)]), vec![0]);
+        assert!(!loaded_index.rows.contains_key(&Data::INT(2).calculate_hash()));
+    }
+
+    #[test]
+    fn index_rows_removed_and_moved_in_file() {
+        let int_column = [Column {
+            data_type: DataType::INT,
+            name: String::from("id"),
+            constraints: ColumnConstraints::default(),
+        }];
+        let mut index = Index { rows: HashMap::new() };
+        index.insert(vec![Data::INT(1)], 0);
+        index.insert(vec![Data::INT(2)], 1);
+        index.insert(vec![Data::INT(1)], 2);
+        index.insert(vec![Data::INT(3)], 3);
+        index.insert(vec![Data::INT(1)], 4);
+        index.insert(vec![Data::INT(4)], 5);
+        index.insert(vec![Data::INT(4)], 6);
+        let file_name = "index4";
+        index.write_index_to_file(file_name.to_string(), &int_column).unwrap();
+        Index::remove_entries_from_file(file_name, &int_column, &[(Data::INT(1).calculate_hash(), 0)]).unwrap();
+        let file_size = std::fs::metadata(file_name).unwrap().len();
+        Index::remove_rows_from_file(file_name, &int_column, &[1, 3]).unwrap();
+        let loaded_index = Index::load(file_name.to_string(), &int_column).unwrap();
+        let rewritten_size = std::fs::metadata(file_name).unwrap().len();
+        remove_file(file_name).unwrap();
+
+        assert_eq!(rewritten_size, file_size);
+        let ones = &loaded_index.rows.get(&Data::INT(1).calculate_hash()).unwrap().values;
+        assert_eq!(ones, &vec![(vec![Data::INT(1)], 1), (vec![Data::INT(1)], 2)]);
+        assert!(!loaded_index.rows.contains_key(&Data::INT(2).calculate_hash()));
+        assert!(!loaded_index.rows.contains_key(&Data::INT(3).calculate_hash()));
+        assert_eq!(loaded_index.get_rows(&[Data::INT(4)]), vec![3, 4]);
+    }
+
+    #[test]
+    fn index_file_compacted() {
+        let int_column = [Column {
+            data_type: DataType::INT,
+            name: String::from("id"),
+            constraints: ColumnConstraints::default(),
+        }];
+        let mut index = Index { rows: HashMap::new() };
+        for row_number in 0..4 {
+            index.insert(vec![Data::INT(row_number as i32)], row_number);
+        }
+        let file_name = "index5";
+        index.write_index_to_file(file_name.to_string(), &int_column).unwrap();
+        let file_size = std::fs::metadata(file_name).unwrap().len();
+        // half of the entries stay in the file
+        Index::remove_entries_from_file(file_name, &int_column, &[(Data::INT(0).calculate_hash(), 0)]).unwrap();
+        Index::remove_rows_from_file(file_name, &int_column, &[1]).unwrap();
+        let patched_size = std::fs::metadata(file_name).unwrap().len();
+        Index::remove_rows_from_file(file_name, &int_column, &[1]).unwrap();
+        let loaded_index = Index::load(file_name.to_string(), &int_column).unwrap();
+        let compacted_size = std::fs::metadata(file_name).unwrap().len();
+        remove_file(file_name).unwrap();
+
+        assert_eq!(patched_size, file_size);
+        assert_eq!(compacted_size, file_size / 4);
+        assert_eq!(loaded_index.rows.len(), 1);
+        assert_eq!(loaded_index.get_rows(&[Data::INT(3)]), vec![1]);
+    }
+
     #[test]
     fn index_creation_and_loading() {
-        let string_column = Column {
+        let string_column = [Column {
             data_type: DataType::STRING { size: 255 },
-            is_indexed: true,
             name: String::from("name"),
-        };
+            constraints: ColumnConstraints::default(),
+        }];
         let index_row1 = IndexRow {
             hash: 123,
             values: vec![
-                (Data::STRING(String::from("Rust")), 10),
-                (Data::STRING(String::from("is")), 5),
+                (vec![Data::STRING(String::from("Rust"))], 10),
+                (vec![Data::STRING(String::from("is"))], 5),
             ],
         };
         let index_row2 = IndexRow {
             hash: 566,
             values: vec![
-                (Data::STRING(String::from("just")), 1),
-                (Data::STRING(String::from("so")), 12),
+                (vec![Data::STRING(String::from("just"))], 1),
+                (vec![Data::STRING(String::from("so"))], 12),
             ],
         };
         let index_row3 = IndexRow {
             hash: 99,
             values: vec![
-                (Data::STRING(String::from("great")), 99),
-                (Data::STRING(String::from("man")), 69),
+                (vec![Data::STRING(String::from("great"))], 99),
+                (vec![Data::STRING(String::from("man"))], 69),
             ],
         };
         let index = Index {
@@ -1,6 +1,11 @@
+pub mod backup;
+mod btree;
 pub mod column;
 pub mod errors;
 mod index;
+pub(crate) mod page;
 pub mod row;
+#[allow(clippy::module_inception)]
 pub mod table;
 pub mod table_iterator;
+pub mod values;
@@ -0,0 +1,236 @@
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Size of the pages, unless a single row needs a bigger one.
+pub(crate) const PAGE_SIZE: usize = 4096;
+/// The rows file starts with the format magic and version, the pages follow.
+/// The rows of the third version keep the strings and blobs in slots of a fixed size,
+/// the longer ones are in the values file of the table.
+pub(crate) const ROWS_FILE_HEADER: [u8; 8] = [b'R', b'W', b'P', b'G', 0, 0, 0, 3];
+/// Number of the slots, beginning of the records.
+const PAGE_HEADER_SIZE: usize = 8;
+/// Offset and length of the record.
+const SLOT_SIZE: usize = 8;
+
+/// Slotted page of the rows file. The slot directory grows from the page header
+/// towards the end of the page and the records are stored from the end of the page
+/// backwards, the space between them is free. The records are in the order of the slots.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub(crate) struct Page {
+    bytes: Vec<u8>,
+}
+
+impl Page {
+    pub(crate) fn new(size: usize) -> Page {
+        let mut page = Page {
+            bytes: vec![0; size],
+        };
+        page.set_u32(4, size);
+        page
+    }
+
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Page {
+        Page { bytes }
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub(crate) fn slot_count(&self) -> usize {
+        self.get_u32(0)
+    }
+
+    fn records_start(&self) -> usize {
+        self.get_u32(4)
+    }
+
+    pub(crate) fn free_space(&self) -> usize {
+        self.records_start() - PAGE_HEADER_SIZE - self.slot_count() * SLOT_SIZE
+    }
+
+    pub(crate) fn record(&self, slot: usize) -> Option<&[u8]> {
+        if slot >= self.slot_count() {
+            return None;
+        }
+        let (offset, length) = self.get_slot(slot);
+        self.bytes.get(offset..offset + length)
+    }
+
+    /// Add the record behind the last one, false when it doesn't fit the free space.
+    pub(crate) fn push(&mut self, record: &[u8]) -> bool {
+        if self.free_space() < record.len() + SLOT_SIZE {
+            return false;
+        }
+        let slot = self.slot_count();
+        let offset = self.records_start() - record.len();
+        self.bytes[offset..offset + record.len()].copy_from_slice(record);
+        self.set_u32(slot_position(slot), offset);
+        self.set_u32(slot_position(slot) + 4, record.len());
+        self.set_u32(0, slot + 1);
+        self.set_u32(4, offset);
+        true
+    }
+
+    /// Overwrite the record in place, false when there's no record of the same length.
+    pub(crate) fn set_record(&mut self, slot: usize, record: &[u8]) -> bool {
+        if slot >= self.slot_count() {
+            return false;
+        }
+        let (offset, length) = self.get_slot(slot);
+        if length != record.len() {
+            return false;
+        }
+        self.bytes[offset..offset + length].copy_from_slice(record);
+        true
+    }
+
+    /// Keep only the first `slot_count` records, the space of the others becomes free.
+    pub(crate) fn truncate(&mut self, slot_count: usize) {
+        if slot_count >= self.slot_count() {
+            return;
+        }
+        let records_start = match slot_count {
+            0 => self.bytes.len(),
+            _ => self.get_slot(slot_count - 1).0,
+        };
+        self.set_u32(0, slot_count);
+        self.set_u32(4, records_start);
+    }
+
+    fn get_slot(&self, slot: usize) -> (usize, usize) {
+        let position = slot_position(slot);
+        (self.get_u32(position), self.get_u32(position + 4))
+    }
+
+    fn get_u32(&self, position: usize) -> usize {
+        let bytes = [
+            self.bytes[position],
+            self.bytes[position + 1],
+            self.bytes[position + 2],
+            self.bytes[position + 3],
+        ];
+        u32::from_be_bytes(bytes) as usize
+    }
+
+    fn set_u32(&mut self, position: usize, value: usize) {
+        self.bytes[position..position + 4].copy_from_slice(&(value as u32).to_be_bytes());
+    }
+}
+
+fn slot_position(slot: usize) -> usize {
+    PAGE_HEADER_SIZE + slot * SLOT_SIZE
+}
+
+/// The smallest multiple of `PAGE_SIZE` holding at least one row.
+pub(crate) fn page_size(row_size: usize) -> usize {
+    let needed_size = PAGE_HEADER_SIZE + SLOT_SIZE + row_size;
+    needed_size.div_ceil(PAGE_SIZE) * PAGE_SIZE
+}
+
+/// The pages are filled one after another, so all pages but the last one hold
+/// this number of rows and the row number determines the page and the slot.
+pub(crate) fn rows_per_page(row_size: usize) -> usize {
+    (page_size(row_size) - PAGE_HEADER_SIZE) / (row_size + SLOT_SIZE)
+}
+
+pub(crate) fn page_position(page_number: u64, page_size: usize) -> u64 {
+    ROWS_FILE_HEADER.len() as u64 + page_number * page_size as u64
+}
+
+/// Packs the records into pages, a full page is written before the next one is started.
+pub(crate) struct PageWriter<'a> {
+    file: &'a mut File,
+    page_number: u64,
+    page: Page,
+    written_pages: Vec<u64>,
+}
+
+impl<'a> PageWriter<'a> {
+    /// Continue with the page of the given number, the pages before it are kept.
+    pub(crate) fn new(file: &'a mut File, page_number: u64, page: Page) -> Self {
+        PageWriter {
+            file,
+            page_number,
+            page,
+            written_pages: vec![],
+        }
+    }
+
+    pub(crate) fn push(&mut self, record: &[u8]) -> io::Result<()> {
+        if self.page.push(record) {
+            return Ok(());
+        }
+        self.write_page()?;
+        let page_size = self.page.bytes.len();
+        self.page = Page::new(page_size);
+        self.page_number += 1;
+        self.page.push(record);
+        Ok(())
+    }
+
+    /// Write the last page, returns the numbers of all written pages.
+    pub(crate) fn finish(mut self) -> io::Result<Vec<u64>> {
+        if self.page.slot_count() > 0 {
+            self.write_page()?;
+        }
+        Ok(self.written_pages)
+    }
+
+    fn write_page(&mut self) -> io::Result<()> {
+        let page_size = self.page.bytes.len();
+        self.file
+            .seek(SeekFrom::Start(page_position(self.page_number, page_size)))?;
+        self.file.write_all(&self.page.bytes)?;
+        self.written_pages.push(self.page_number);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_records() {
+        let mut page = Page::new(PAGE_SIZE);
+        assert_eq!(page.free_space(), PAGE_SIZE - PAGE_HEADER_SIZE);
+        assert!(page.push(&[1, 2, 3]));
+        assert!(page.push(&[4, 5, 6]));
+        assert!(page.set_record(0, &[7, 8, 9]));
+        assert!(!page.set_record(1, &[1]));
+        assert!(!page.set_record(2, &[1, 2, 3]));
+
+        let page = Page::from_bytes(page.bytes().to_vec());
+        assert_eq!(page.slot_count(), 2);
+        assert_eq!(page.record(0), Some(&[7, 8, 9][..]));
+        assert_eq!(page.record(1), Some(&[4, 5, 6][..]));
+        assert_eq!(page.record(2), None);
+        assert_eq!(page.free_space(), PAGE_SIZE - PAGE_HEADER_SIZE - 2 * (SLOT_SIZE + 3));
+    }
+
+    #[test]
+    fn page_full_and_truncated() {
+        let row_size = 100;
+        let mut page = Page::new(page_size(row_size));
+        for _ in 0..rows_per_page(row_size) {
+            assert!(page.push(&[1; 100]));
+        }
+        assert!(!page.push(&[1; 100]));
+
+        page.truncate(1);
+        assert_eq!(page.slot_count(), 1);
+        assert!(page.push(&[2; 100]));
+        assert_eq!(page.record(1), Some(&[2; 100][..]));
+        page.truncate(0);
+        assert_eq!(page.slot_count(), 0);
+        assert_eq!(page.free_space(), Page::new(page_size(row_size)).free_space());
+    }
+
+    #[test]
+    fn page_size_of_big_rows() {
+        assert_eq!(page_size(8), PAGE_SIZE);
+        assert_eq!(page_size(PAGE_SIZE), 2 * PAGE_SIZE);
+        assert_eq!(rows_per_page(PAGE_SIZE), 1);
+    }
+}
@@ -1,53 +1,217 @@
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
+    io,
 };
 
-use common::models::db::{Column, Data, DataType, Row};
+use common::models::{
+    db::{Column, Data, DataType, Row},
+    decimal::Decimal,
+};
+
+use super::column::{self, PersistenceColumn};
+use super::values::{Values, ValuesWriter};
 
-use super::column::PersistenceColumn;
+/// The values of a variable size up to this length are kept in their slot,
+/// which starts with the length of the value.
+const INLINE_VALUE_SIZE: usize = column::VALUE_SLOT_SIZE - 4;
 
 pub trait PersistenceRow {
-    fn from_bytes(bytes: Vec<u8>, columns: &Vec<Column>) -> Row;
-    fn to_bytes(&self, columns: &[Column]) -> Vec<u8>;
+    fn from_bytes(bytes: Vec<u8>, columns: &[Column], values: &mut Values) -> io::Result<Row>;
+    /// Decode only the columns selected by the projection, the others are NULL.
+    fn from_bytes_projected(
+        bytes: &[u8],
+        columns: &[Column],
+        projection: Option<&[bool]>,
+        values: &mut Values,
+    ) -> io::Result<Row>;
+    /// The values which don't fit their slots are collected by `values`.
+    fn to_bytes(&self, columns: &[Column], values: &mut Values) -> Vec<u8>;
+}
+
+/// The row starts with the bitmap of its NULL values, one bit for every column
+/// from the lowest bit of the first byte. The value of NULL is all zeros.
+pub(crate) fn null_bitmap_size(columns: &[Column]) -> usize {
+    columns.len().div_ceil(8)
+}
+
+/// Size of the row of the columns, the null bitmap included.
+pub(crate) fn row_size(columns: &[Column]) -> usize {
+    null_bitmap_size(columns) + columns.iter().map(|column| column.size()).sum::<usize>()
+}
+
+/// Size of the row written before the pages, the strings took their whole size.
+pub(crate) fn legacy_row_size(columns: &[Column]) -> usize {
+    columns.iter().map(legacy_value_size).sum::<usize>()
+}
+
+fn legacy_value_size(column: &Column) -> usize {
+    match column.data_type {
+        DataType::STRING { size } => size as usize,
+        _ => column.size(),
+    }
+}
+
+/// The row written before the pages. NULL was a value of the type, the NULL of a string
+/// was written shorter than the string and the rest of such row is NULL.
+pub(crate) fn legacy_row_from_bytes(bytes: &[u8], columns: &[Column]) -> Row {
+    let mut values = vec![];
+    let mut byte_counter = 0;
+    for column in columns {
+        let data_size = legacy_value_size(column);
+        let value = match bytes.get(byte_counter..byte_counter + data_size) {
+            Some(bytes) if is_legacy_null(bytes, &column.data_type) => Data::NULL,
+            Some(bytes) => Data::from_bytes(bytes.to_vec(), column),
+            None => Data::NULL,
+        };
+        values.push(value);
+        byte_counter += data_size;
+    }
+    Row { values }
+}
+
+fn is_legacy_null(bytes: &[u8], data_type: &DataType) -> bool {
+    match data_type {
+        DataType::INT => bytes == [1, 0, 0, 0, 0, 0, 0, 0],
+        _ => bytes == [0, 0, 0, 0, 0, 0, 0, 0],
+    }
+}
+
+fn is_null(bytes: &[u8], index: usize) -> bool {
+    bytes[index / 8] & (1 << (index % 8)) != 0
+}
+
+/// The key of an index written like a row of the indexed columns, the values which
+/// don't fit their slots follow it, so the key doesn't need the values file.
+pub(crate) fn key_to_bytes(key: &[Data], columns: &[Column]) -> Vec<u8> {
+    let mut values = Values::default();
+    let row = Row { values: key.to_vec() }.to_bytes(columns, &mut values);
+    let appended = values.into_appended().bytes;
+    [row, (appended.len() as u64).to_be_bytes().to_vec(), appended].concat()
+}
+
+/// The key written at the beginning of the bytes and the size of its bytes.
+pub(crate) fn key_from_bytes(bytes: &[u8], columns: &[Column]) -> (Vec<Data>, usize) {
+    let row_size = row_size(columns);
+    let appended_size = u64::from_be_bytes(
+        bytes[row_size..row_size + 8].try_into().expect("Eight bytes were given."),
+    ) as usize;
+    let mut values = Values::default();
+    values.push(&bytes[row_size + 8..row_size + 8 + appended_size]);
+    let key = Row::from_bytes_projected(&bytes[..row_size], columns, None, &mut values)
+        .expect("The values follow the key.")
+        .values;
+    (key, row_size + 8 + appended_size)
 }
 
 impl PersistenceRow for Row {
-    fn from_bytes(bytes: Vec<u8>, columns: &Vec<Column>) -> Row {
-        let mut values = vec![];
-        let mut byte_counter = 0;
-        for column in columns {
-            let data_size = match column.data_type {
-                DataType::STRING { size } => {
-                    size as usize
+    fn from_bytes(bytes: Vec<u8>, columns: &[Column], values: &mut Values) -> io::Result<Row> {
+        Self::from_bytes_projected(&bytes, columns, None, values)
+    }
+
+    fn from_bytes_projected(
+        bytes: &[u8],
+        columns: &[Column],
+        projection: Option<&[bool]>,
+        values: &mut Values,
+    ) -> io::Result<Row> {
+        let mut row_values = vec![];
+        let mut byte_counter = null_bitmap_size(columns);
+        for (index, column) in columns.iter().enumerate() {
+            let data_size = column.size();
+            let is_projected = projection.is_none_or(|projection| projection[index]);
+            let slot = &bytes[byte_counter..byte_counter + data_size];
+            row_values.push(match is_projected && !is_null(bytes, index) {
+                true if column::has_variable_size(&column.data_type) => {
+                    Data::from_bytes(value_from_slot(slot, values)?, column)
                 }
-                _ => 8usize
-            };
-            values.push(Data::from_bytes(
-                bytes[byte_counter..byte_counter + data_size].to_owned(),
-                column
-            ));
+                true => Data::from_bytes(slot.to_owned(), column),
+                false => Data::NULL,
+            });
             byte_counter += data_size;
         }
-        Row { values }
+        Ok(Row { values: row_values })
     }
 
-    fn to_bytes(&self, columns: &[Column]) -> Vec<u8> {
+    fn to_bytes(&self, columns: &[Column], values: &mut Values) -> Vec<u8> {
+        let mut null_bitmap = vec![0; null_bitmap_size(columns)];
         let mut byte_vectors: Vec<Vec<u8>> = vec![];
         for (index, column) in columns.iter().enumerate() {
-            byte_vectors.push(
-                self.values
-                    .get(index)
-                    .unwrap()
-                    .to_bytes(column.size(), &column.data_type),
-            );
+            let data = self.values.get(index).unwrap();
+            byte_vectors.push(match data {
+                Data::NULL => {
+                    null_bitmap[index / 8] |= 1 << (index % 8);
+                    data.to_bytes(column.size())
+                }
+                Data::STRING(string) => value_to_slot(string.as_bytes(), values),
+                Data::BLOB(bytes) => value_to_slot(bytes, values),
+                _ => value_to_bytes(data, column, column.size()),
+            });
+        }
+        [null_bitmap, byte_vectors.concat()].concat()
+    }
+}
+
+/// The slot of the value of a variable size, the length of the value is followed
+/// either by the value or by its position in the values file.
+fn value_to_slot(value: &[u8], values: &mut Values) -> Vec<u8> {
+    let mut slot = (value.len() as u32).to_be_bytes().to_vec();
+    match value.len() <= INLINE_VALUE_SIZE {
+        true => slot.extend(value),
+        false => slot.extend(values.push(value).to_be_bytes()),
+    }
+    slot.resize(column::VALUE_SLOT_SIZE, 0);
+    slot
+}
+
+/// The row with its values which don't fit the slots moved to the new values file.
+pub(crate) fn move_values(
+    bytes: &[u8],
+    columns: &[Column],
+    values: &mut Values,
+    values_writer: &mut ValuesWriter,
+) -> io::Result<Vec<u8>> {
+    let mut bytes = bytes.to_vec();
+    let mut byte_counter = null_bitmap_size(columns);
+    for (index, column) in columns.iter().enumerate() {
+        let data_size = column.size();
+        if column::has_variable_size(&column.data_type) && !is_null(&bytes, index) {
+            let slot = &mut bytes[byte_counter..byte_counter + data_size];
+            let length = u32::from_be_bytes([slot[0], slot[1], slot[2], slot[3]]) as usize;
+            if length > INLINE_VALUE_SIZE {
+                let value = value_from_slot(slot, values)?;
+                slot[4..12].copy_from_slice(&values_writer.push(&value)?.to_be_bytes());
+            }
+        }
+        byte_counter += data_size;
+    }
+    Ok(bytes)
+}
+
+fn value_from_slot(slot: &[u8], values: &mut Values) -> io::Result<Vec<u8>> {
+    let length = u32::from_be_bytes([slot[0], slot[1], slot[2], slot[3]]) as usize;
+    match length <= INLINE_VALUE_SIZE {
+        true => Ok(slot[4..4 + length].to_vec()),
+        false => {
+            let position = u64::from_be_bytes(slot[4..12].try_into().expect("Eight bytes were given."));
+            values.read(position, length)
+        }
+    }
+}
+
+/// The value of the column padded to `size` bytes, DECIMAL is written with the scale of its column.
+pub(crate) fn value_to_bytes(data: &Data, column: &Column, size: usize) -> Vec<u8> {
+    match (data, column.data_type) {
+        (Data::DECIMAL(decimal), DataType::DECIMAL { scale, .. }) => {
+            let decimal = decimal.rescale(scale as u32).expect("The value fits its column.");
+            Data::DECIMAL(decimal).to_bytes(size)
         }
-        byte_vectors.concat()
+        _ => data.to_bytes(size),
     }
 }
 
 pub trait PersistenceData {
-    fn to_bytes(&self, max_size: usize, data_type: &DataType) -> Vec<u8>;
+    fn to_bytes(&self, max_size: usize) -> Vec<u8>;
     fn int_from_bytes(bytes: Vec<u8>) -> Data;
     fn string_from_bytes(bytes: Vec<u8>) -> Data;
     fn boolean_from_bytes(bytes: Vec<u8>) -> Data;
@@ -57,32 +221,29 @@ pub trait PersistenceData {
 }
 
 impl PersistenceData for Data {
-    fn to_bytes(&self, max_size: usize, data_type: &DataType) -> Vec<u8> {
-        return match &self {
+    /// The value padded to `max_size` bytes, the strings and blobs have to fit it.
+    /// DECIMAL is written as its mantissa, it has the scale of its column.
+    fn to_bytes(&self, max_size: usize) -> Vec<u8> {
+        match &self {
             Data::INT(integer) => [0_i32.to_be_bytes(), integer.to_be_bytes()].concat(),
-            Data::STRING(string) => {
-                let mut string_bytes = string.as_bytes().to_vec();
-                if string_bytes.len() > max_size {
-                    panic!();
-                }
-                while string_bytes.len() < max_size {
-                    string_bytes.push(0);
-                }
-
-                string_bytes
-            }
-            Data::NULL => match data_type {
-                DataType::INT => [1, 0, 0, 0, 0, 0, 0, 0].to_vec(),
-                DataType::STRING { size: _ } => [0, 0, 0, 0, 0, 0, 0, 0].to_vec(),
-                DataType::BOOLEAN => [0, 0, 0, 0, 0, 0, 0, 0].to_vec(),
-                DataType::FLOAT => [0, 0, 0, 0, 0, 0, 0, 0].to_vec(),
-            },
+            Data::STRING(string) => pad_bytes(string.as_bytes(), max_size),
+            Data::BLOB(bytes) => pad_bytes(bytes, max_size),
+            // the value is marked as NULL by the null bitmap of the row
+            Data::NULL => vec![0; max_size],
             Data::BOOLEAN(bool) => {
                 let bool_representation: u8 = if *bool { 1 } else { 0 };
                 [1, 1, bool_representation, 0, 0, 0, 0, 0].to_vec()
             }
             Data::FLOAT(float) => [float.to_be_bytes()].concat(),
-        };
+            Data::BIGINT(integer) => integer.to_be_bytes().to_vec(),
+            Data::DECIMAL(decimal) => i64::try_from(decimal.mantissa)
+                .expect("The value fits its column.")
+                .to_be_bytes()
+                .to_vec(),
+            Data::DATE(days) => [0_i32.to_be_bytes(), days.to_be_bytes()].concat(),
+            Data::TIMESTAMP(micros) => micros.to_be_bytes().to_vec(),
+            Data::UUID(uuid) => uuid.to_be_bytes().to_vec(),
+        }
     }
 
     fn int_from_bytes(bytes: Vec<u8>) -> Data {
@@ -114,34 +275,21 @@ impl PersistenceData for Data {
 
     fn from_bytes(bytes: Vec<u8>, column: &Column) -> Self {
         match column.data_type {
-            DataType::INT => {
-                let null = [1, 0, 0, 0, 0, 0, 0, 0];
-                if bytes.eq(&null) {
-                    return Data::NULL;
-                }
-                Self::int_from_bytes(bytes)
-            }
-            DataType::STRING { size: _size } => {
-                let null = [0, 0, 0, 0, 0, 0, 0, 0];
-                if bytes.eq(&null) {
-                    return Data::NULL;
-                }
-                Self::string_from_bytes(bytes)
-            }
-            DataType::BOOLEAN => {
-                let null = [0, 0, 0, 0, 0, 0, 0, 0];
-                if bytes.eq(&null) {
-                    return Data::NULL;
-                }
-                Self::boolean_from_bytes(bytes)
-            }
-            DataType::FLOAT => {
-                let null = [0, 0, 0, 0, 0, 0, 0, 0];
-                if bytes.eq(&null) {
-                    return Data::NULL;
-                }
-                Self::float_from_bytes(bytes)
+            DataType::INT => Self::int_from_bytes(bytes),
+            DataType::STRING { size: _size } => Self::string_from_bytes(bytes),
+            DataType::BOOLEAN => Self::boolean_from_bytes(bytes),
+            DataType::FLOAT => Self::float_from_bytes(bytes),
+            DataType::TEXT => Self::string_from_bytes(bytes),
+            DataType::BLOB => Data::BLOB(bytes),
+            DataType::BIGINT => Data::BIGINT(i64_from_bytes(&bytes)),
+            DataType::DECIMAL { scale, .. } => {
+                Data::DECIMAL(Decimal::new(i64_from_bytes(&bytes) as i128, scale as u32))
             }
+            DataType::DATE => Data::DATE(i32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]])),
+            DataType::TIMESTAMP => Data::TIMESTAMP(i64_from_bytes(&bytes)),
+            DataType::UUID => Data::UUID(u128::from_be_bytes(
+                bytes[..16].try_into().expect("The slot of UUID has 16 bytes."),
+            )),
         }
     }
 
@@ -152,8 +300,23 @@ impl PersistenceData for Data {
     }
 }
 
+fn i64_from_bytes(bytes: &[u8]) -> i64 {
+    i64::from_be_bytes(bytes[..8].try_into().expect("The slot has 8 bytes."))
+}
+
+fn pad_bytes(bytes: &[u8], size: usize) -> Vec<u8> {
+    if bytes.len() > size {
+        panic!("The value of {} bytes doesn't fit {} bytes.", bytes.len(), size);
+    }
+    let mut padded_bytes = bytes.to_vec();
+    padded_bytes.resize(size, 0);
+    padded_bytes
+}
+
 #[cfg(test)]
 mod tests {
+    use common::models::db::ColumnConstraints;
+
     use crate::table::column::PersistenceDataType;
 
     use super::*;
@@ -161,8 +324,7 @@ mod tests {
     #[test]
     fn one_into_bytes() {
         let one = Data::INT(1);
-        let data_type = DataType::INT;
-        assert_eq!(one.to_bytes(255, &data_type), [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(one.to_bytes(255), [0, 0, 0, 0, 0, 0, 0, 1]);
     }
 
     #[test]
@@ -174,11 +336,7 @@ mod tests {
     #[test]
     fn big_int_into_bytes() {
         let big_int = Data::INT(78456845);
-        let data_type = DataType::INT;
-        assert_eq!(
-            big_int.to_bytes(255, &data_type),
-            [0, 0, 0, 0, 4, 173, 40, 13]
-        );
+        assert_eq!(big_int.to_bytes(255), [0, 0, 0, 0, 4, 173, 40, 13]);
     }
 
     #[test]
@@ -193,8 +351,7 @@ mod tests {
         let expected_result = [
             72, 101, 108, 108, 111, 32, 119, 111, 114, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
         ];
-        let data_type = DataType::INT;
-        assert_eq!(hello_world.to_bytes(20, &data_type), expected_result);
+        assert_eq!(hello_world.to_bytes(20), expected_result);
     }
 
     #[test]
@@ -207,37 +364,192 @@ mod tests {
     }
 
     #[test]
-    fn int_null_from_bytes() {
+    fn int_null_from_legacy_bytes() {
         let bytes = [1, 0, 0, 0, 0, 0, 0, 0];
         let column = Column {
             data_type: DataType::INT,
-            is_indexed: false,
             name: String::from("abc"),
+            constraints: ColumnConstraints::default(),
         };
-        let null = Data::from_bytes(bytes.to_vec(), &column);
-        assert_eq!(null, Data::NULL)
+        let row = legacy_row_from_bytes(&bytes, &[column]);
+        assert_eq!(row.values, [Data::NULL])
     }
 
     #[test]
-    fn string_null_from_bytes() {
-        let bytes = [0, 0, 0, 0, 0, 0, 0, 0];
-        let column = Column {
+    fn string_null_from_legacy_bytes() {
+        let string_column = Column {
             data_type: DataType::STRING { size: 256 },
-            is_indexed: false,
             name: String::from("abc"),
+            constraints: ColumnConstraints::default(),
+        };
+        let int_column = Column {
+            data_type: DataType::INT,
+            name: String::from("id"),
+            constraints: ColumnConstraints::default(),
         };
-        let null = Data::from_bytes(bytes.to_vec(), &column);
-        assert_eq!(null, Data::NULL)
+        let columns = [string_column, int_column];
+        let bytes = [vec![0; 256], vec![0, 0, 0, 0, 0, 0, 0, 1]].concat();
+        let row = legacy_row_from_bytes(&bytes, &columns);
+        assert_eq!(row.values, [Data::STRING(String::new()), Data::INT(1)]);
+        // the NULL of the string was shorter than the string
+        let bytes = [[0, 0, 0, 0, 0, 0, 0, 0], [0, 0, 0, 0, 0, 0, 0, 1]].concat();
+        let row = legacy_row_from_bytes(&bytes, &columns);
+        assert_eq!(row.values, [Data::NULL, Data::NULL])
     }
 
     #[test]
     fn null_to_bytes() {
-        let bytes = [0, 0, 0, 0, 0, 0, 0, 0].to_vec();
-        let data_type = DataType::STRING { size: 256 };
-        assert_eq!(bytes, Data::NULL.to_bytes(256, &data_type));
-        let bytes = [1, 0, 0, 0, 0, 0, 0, 0].to_vec();
-        let data_type = DataType::INT;
-        assert_eq!(bytes, Data::NULL.to_bytes(256, &data_type));
+        assert_eq!(vec![0; 256], Data::NULL.to_bytes(256));
+        assert_eq!(vec![0; 8], Data::NULL.to_bytes(8));
+    }
+
+    #[test]
+    fn variable_size_values_round_trip() {
+        let columns: Vec<Column> = [DataType::STRING { size: 100 }, DataType::TEXT, DataType::BLOB]
+            .into_iter()
+            .enumerate()
+            .map(|(index, data_type)| Column {
+                name: index.to_string(),
+                data_type,
+                constraints: ColumnConstraints::default(),
+            })
+            .collect();
+        assert_eq!(row_size(&columns), 49);
+        let short_row = Row {
+            values: vec![
+                Data::STRING(String::from("twelve bytes")),
+                Data::STRING(String::new()),
+                Data::BLOB(vec![0, 1, 0]),
+            ],
+        };
+        let long_row = Row {
+            values: vec![
+                Data::STRING(String::from("thirteen byte")),
+                Data::STRING("text ".repeat(1000)),
+                Data::BLOB(vec![0; 100]),
+            ],
+        };
+
+        let mut values = Values::default();
+        let short_bytes = short_row.to_bytes(&columns, &mut values);
+        assert_eq!(values.push(&[]), 0);
+        let long_bytes = long_row.to_bytes(&columns, &mut values);
+        assert_eq!(values.push(&[]), 13 + 5000 + 100);
+        assert_eq!(Row::from_bytes(short_bytes, &columns, &mut values).unwrap(), short_row);
+        assert_eq!(Row::from_bytes(long_bytes, &columns, &mut values).unwrap(), long_row);
+
+        let key_bytes = [key_to_bytes(&long_row.values, &columns), vec![7]].concat();
+        assert_eq!(
+            key_from_bytes(&key_bytes, &columns),
+            (long_row.values, key_bytes.len() - 1)
+        );
+    }
+
+    #[test]
+    fn scalar_values_round_trip() {
+        let columns: Vec<Column> = [
+            DataType::BIGINT,
+            DataType::DECIMAL {
+                precision: 18,
+                scale: 2,
+            },
+            DataType::DATE,
+            DataType::TIMESTAMP,
+            DataType::UUID,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, data_type)| Column {
+            name: index.to_string(),
+            data_type,
+            constraints: ColumnConstraints::default(),
+        })
+        .collect();
+        assert_eq!(row_size(&columns), 1 + 4 * 8 + 16);
+        let row = Row {
+            values: vec![
+                Data::BIGINT(-(1 << 40)),
+                Data::DECIMAL(Decimal::new(-999_999_999_999_999_999, 2)),
+                Data::DATE(-719_162),
+                Data::TIMESTAMP(1_700_000_000_123_456),
+                Data::UUID(u128::MAX - 1),
+            ],
+        };
+        let mut values = Values::default();
+        let bytes = row.to_bytes(&columns, &mut values);
+        assert_eq!(Row::from_bytes(bytes, &columns, &mut values).unwrap(), row);
+
+        // the decimal gets the scale of its column
+        let row = Row {
+            values: vec![
+                Data::BIGINT(0),
+                Data::DECIMAL(Decimal::new(12345, 3)),
+                Data::NULL,
+                Data::NULL,
+                Data::NULL,
+            ],
+        };
+        let bytes = row.to_bytes(&columns, &mut values);
+        let Data::DECIMAL(decimal) = Row::from_bytes(bytes, &columns, &mut values).unwrap().values[1] else {
+            panic!()
+        };
+        assert_eq!((decimal.mantissa, decimal.scale), (1235, 2));
+    }
+
+    #[test]
+    fn null_and_zero_values_round_trip() {
+        let columns: Vec<Column> = [
+            DataType::INT,
+            DataType::STRING { size: 8 },
+            DataType::BOOLEAN,
+            DataType::FLOAT,
+            DataType::STRING { size: 100 },
+            DataType::INT,
+            DataType::FLOAT,
+            DataType::BOOLEAN,
+            DataType::INT,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, data_type)| Column {
+            name: index.to_string(),
+            data_type,
+            constraints: ColumnConstraints::default(),
+        })
+        .collect();
+        assert_eq!(null_bitmap_size(&columns), 2);
+        let rows = [
+            vec![
+                Data::INT(0),
+                Data::STRING(String::new()),
+                Data::BOOLEAN(false),
+                Data::FLOAT(0.0),
+                Data::STRING(String::new()),
+                Data::INT(1),
+                Data::FLOAT(-0.5),
+                Data::BOOLEAN(true),
+                Data::INT(0),
+            ],
+            vec![Data::NULL; 9],
+            vec![
+                Data::NULL,
+                Data::STRING(String::from("12345678")),
+                Data::NULL,
+                Data::FLOAT(0.0),
+                Data::NULL,
+                Data::INT(0),
+                Data::NULL,
+                Data::BOOLEAN(false),
+                Data::NULL,
+            ],
+        ];
+        for values in rows {
+            let row = Row { values };
+            let mut values = Values::default();
+            let bytes = row.to_bytes(&columns, &mut values);
+            assert_eq!(bytes.len(), row_size(&columns));
+            assert_eq!(Row::from_bytes(bytes, &columns, &mut values).unwrap(), row);
+        }
     }
 
     #[test]
@@ -302,22 +614,22 @@ mod tests {
         let column_name = Column {
             name: String::from("Name"),
             data_type: string_data_type,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let column_id = Column {
             name: String::from("Id"),
             data_type: DataType::INT,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let bool_column = Column {
             name: String::from("Bool"),
             data_type: DataType::BOOLEAN,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let float_column = Column {
             name: String::from("Float"),
             data_type: DataType::FLOAT,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
 
         let columns = vec![column_name, column_id, bool_column, float_column];
@@ -330,9 +642,10 @@ mod tests {
         let row = Row {
             values: vec![string, int, bool_data, float_data],
         };
-        let bytes = row.to_bytes(&columns);
-        let loaded_row = Row::from_bytes(bytes, &columns);
-        match loaded_row.values.get(0).unwrap() {
+        let mut values = Values::default();
+        let bytes = row.to_bytes(&columns, &mut values);
+        let loaded_row = Row::from_bytes(bytes, &columns, &mut values).unwrap();
+        match loaded_row.values.first().unwrap() {
             Data::STRING(value) => {
                 assert_eq!(&string_value, value);
             }
@@ -350,7 +663,7 @@ mod tests {
         }
         match loaded_row.values.get(2).unwrap() {
             Data::BOOLEAN(value) => {
-                assert_eq!(true, *value);
+                assert!(*value);
             }
             _ => panic!(),
         }
@@ -1,154 +1,476 @@
-use common::models::db::{Column, Row};
+use common::models::db::{Column, Data, Row};
 
-use crate::table::index::{Index, IndexRow};
+use crate::buffer_pool;
+use crate::table::btree::{self, BTreeIndex};
+use crate::table::index::{key_hash, Index};
+use crate::table::page::{self, Page, PageWriter, ROWS_FILE_HEADER};
+use crate::table::values::{AppendedValues, Values, ValuesWriter};
 use crate::table::{errors::PersistenceErrors,table_iterator};
-use std::collections::HashMap;
-use std::fs::{remove_file, write, File, OpenOptions};
-use std::io::{Read, Seek, SeekFrom, Write};
+use crate::wal::{self, Change};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{remove_file, rename, File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
 use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-use super::column::PersistenceColumn;
-use super::row::{PersistenceData, PersistenceRow};
+/// Tables stored in the format before the pages are migrated one at a time.
+static MIGRATION: Mutex<()> = Mutex::new(());
+/// The indexes of the table follow its columns in the header after this mark,
+/// as no column name is that long.
+const INDEXES_MARK: u32 = u32::MAX - 1;
 
-#[derive(Eq, PartialEq, Debug)]
+use super::column::{self, PersistenceColumn};
+use super::row::{self, PersistenceRow};
+
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
+    pub indexes: Vec<TableIndex>,
+}
+
+/// Index of the values of one or more columns, the name is unique within the table.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct TableIndex {
+    pub name: String,
+    /// Names of the indexed columns, the values are hashed or ordered in this order.
+    pub columns: Vec<String>,
+    pub kind: IndexKind,
+    /// The constraint enforced through the index, it's created with the table.
+    pub constraint: Option<IndexConstraint>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IndexKind {
+    /// Finds the rows with the given values of all the columns.
+    Hash,
+    /// Keeps the rows in the order of the values, finds ranges of the values
+    /// and the rows with the given values of the leading columns.
+    BTree,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IndexConstraint {
+    /// The values are unique and not NULL.
+    PrimaryKey,
+    /// The values are unique unless one of them is NULL.
+    Unique,
 }
 
 impl Table {
-    pub fn add_index(&mut self, column_index: usize) -> Result<(), PersistenceErrors> {
-        let column = self.columns.get(column_index);
-        if column.is_none() {
+    /// Add the index, which replaces the former index of the same name.
+    /// The B+tree index can't have a TEXT or BLOB column.
+    pub fn add_index(&mut self, index: TableIndex) -> Result<(), PersistenceErrors> {
+        let has_columns = index.columns.iter().all(|name| {
+            self.columns.iter().any(|column| {
+                column.name == *name && (index.kind == IndexKind::Hash || btree::is_orderable(column))
+            })
+        });
+        if index.columns.is_empty() || !has_columns {
             return Err(PersistenceErrors::IndexCreating());
         }
-        let column = column.unwrap();
-        let file_name = self.get_index_file_name(column);
-        let table_iterator: Vec<(usize, Row)> = table_iterator::RowsIterator::from_table(self)?
-            .enumerate()
+        let name = index.name.clone();
+        self.indexes.retain(|other| other.name != name);
+        self.indexes.push(index);
+        wal::execute(Change::SetIndex {
+            header: self.to_bytes(),
+            name,
+        })
+    }
+
+    /// Remove the index, false when the table doesn't have it.
+    pub fn remove_index(&mut self, name: &str) -> Result<bool, PersistenceErrors> {
+        if !self.indexes.iter().any(|index| index.name == name) {
+            return Ok(false);
+        }
+        self.indexes.retain(|index| index.name != name);
+        wal::execute(Change::SetIndex {
+            header: self.to_bytes(),
+            name: name.to_string(),
+        })?;
+        Ok(true)
+    }
+
+    /// Build or remove the index, depending on the header.
+    pub(crate) fn write_index_change(&self, name: &str) -> Result<(), PersistenceErrors> {
+        // the index may have replaced an index of the other kind
+        for kind in [IndexKind::Hash, IndexKind::BTree] {
+            remove_file_if_exists(&self.get_index_file_name(name, kind)).map_err(PersistenceErrors::TableDrop)?;
+        }
+        let indexes: Vec<TableIndex> = self.indexes.iter().filter(|index| index.name == name).cloned().collect();
+        self.build_indexes(&indexes)?;
+        self.write_table_header()
+    }
+
+    pub fn get_index(&self, name: &str) -> Result<Index, PersistenceErrors> {
+        let index = self.find_index(name, IndexKind::Hash)?;
+        let columns = self.get_columns(&self.get_indexed_positions(index)?);
+        Index::load(self.get_index_file_name(name, IndexKind::Hash), &columns)
+    }
+
+    pub fn get_btree_index(&self, name: &str) -> Result<BTreeIndex, PersistenceErrors> {
+        let index = self.find_index(name, IndexKind::BTree)?;
+        let columns = self.get_columns(&self.get_indexed_positions(index)?);
+        BTreeIndex::open(&self.get_index_file_name(name, IndexKind::BTree), &columns)
+    }
+
+    fn find_index(&self, name: &str, kind: IndexKind) -> Result<&TableIndex, PersistenceErrors> {
+        self.indexes
+            .iter()
+            .find(|index| index.name == name && index.kind == kind)
+            .ok_or(PersistenceErrors::IndexLoading(io::ErrorKind::NotFound.into()))
+    }
+
+    /// Build the indexes from the rows of the table, the rows are read only once.
+    fn build_indexes(&self, indexes: &[TableIndex]) -> Result<(), PersistenceErrors> {
+        if indexes.is_empty() {
+            return Ok(());
+        }
+        let mut positions = vec![];
+        for index in indexes {
+            positions.push(self.get_indexed_positions(index)?);
+        }
+        let projection = (0..self.columns.len())
+            .map(|column_index| positions.iter().any(|positions| positions.contains(&column_index)))
             .collect();
-        let column = self.columns.get_mut(column_index).expect("We already checked it is not none");
-        column.is_indexed = true;
-        let mut index_row_map = HashMap::new();
-        for (row_number, row) in table_iterator.iter() {
-            let option = row.values.get(column_index);
-            let data = option.unwrap();
-            index_row_map
-                .entry(data.calculate_hash())
-                .or_insert(vec![])
-                .push((data.clone(), *row_number as u64));
-        }
-        let mut index = Index { rows: HashMap::new() };
-        for (hash, values) in &mut index_row_map {
-            index.rows.insert(*hash, IndexRow {
-                hash: *hash,
-                values: mem::take(values),
-            });
-        }
-        index.write_index_to_file(file_name, column)?;
-        self.write_table_header()?;
+        let mut entries: Vec<Vec<(Vec<Data>, u64)>> = vec![vec![]; indexes.len()];
+        let table_iterator = table_iterator::RowsIterator::from_table(self)?.with_projection(projection);
+        for (row_number, row) in table_iterator.enumerate() {
+            let row = row?;
+            for (positions, entries) in positions.iter().zip(&mut entries) {
+                entries.push((index_key(&row, positions), row_number as u64));
+            }
+        }
+
+        for ((index, positions), entries) in indexes.iter().zip(&positions).zip(entries) {
+            let file_name = self.get_index_file_name(&index.name, index.kind);
+            let columns = self.get_columns(positions);
+            match index.kind {
+                IndexKind::Hash => {
+                    let mut hash_index = Index { rows: HashMap::new() };
+                    for (key, row_number) in entries {
+                        hash_index.insert(key, row_number);
+                    }
+                    hash_index.write_index_to_file(file_name, &columns)?;
+                }
+                IndexKind::BTree => BTreeIndex::build(&file_name, &columns, entries)?,
+            }
+        }
         Ok(())
     }
 
-    pub fn remove_index(&mut self, column_index: usize) -> Result<(), PersistenceErrors> {
-        let column = self.columns.get_mut(column_index);
-        if column.is_none() {
-            return Err(PersistenceErrors::IndexCreating());
+    /// Positions of the indexed columns in the order of the index.
+    fn get_indexed_positions(&self, index: &TableIndex) -> Result<Vec<usize>, PersistenceErrors> {
+        index
+            .columns
+            .iter()
+            .map(|name| {
+                self.columns
+                    .iter()
+                    .position(|column| column.name == *name)
+                    .ok_or(PersistenceErrors::IndexCreating())
+            })
+            .collect()
+    }
+
+    fn get_columns(&self, positions: &[usize]) -> Vec<Column> {
+        positions.iter().map(|position| self.columns[*position].clone()).collect()
+    }
+
+    /// The `#` keeps the names apart, as identifiers can't contain it.
+    fn get_index_file_name(&self, name: &str, kind: IndexKind) -> String {
+        match kind {
+            IndexKind::Hash => self.name.clone() + "#" + name + "_index",
+            IndexKind::BTree => self.name.clone() + "#" + name + "_btree",
         }
-        let column = column.expect("We already checked it is not none");
-        column.is_indexed = false;
-        let column = self.columns.get(column_index).expect("We already checked it is not none");
-        self.write_table_header()?;
-        remove_file(self.get_index_file_name(column))
-            .map_err(PersistenceErrors::TableDrop)?;
-        Ok(())
+    }
+
+    /// Columns read to maintain the indexes.
+    fn get_indexed_columns(&self) -> Vec<bool> {
+        self.columns
+            .iter()
+            .map(|column| self.indexes.iter().any(|index| index.columns.contains(&column.name)))
+            .collect()
     }
 
     pub fn seek_row(&self, row_number: u64) -> Result<Row, PersistenceErrors> {
-        let mut rows_file =
-            File::open(self.table_rows_name()).map_err(PersistenceErrors::RowSeeking)?;
-        let row_size = self.get_row_size();
-        rows_file
-            .seek(SeekFrom::Start(row_number * (row_size as u64)))
-            .map_err(PersistenceErrors::RowSeeking)?;
-        let mut bytes: Vec<u8> = vec![0; row_size];
-        let mut rows_file = rows_file.take(row_size as u64);
-        rows_file
-            .read_exact(&mut bytes)
+        let rows_per_page = page::rows_per_page(self.get_row_size()) as u64;
+        let page = self
+            .read_page(row_number / rows_per_page)
             .map_err(PersistenceErrors::RowSeeking)?;
-        Ok(Row::from_bytes(bytes, &self.columns))
+        match page.record((row_number % rows_per_page) as usize) {
+            Some(bytes) => {
+                let mut values = self.open_values().map_err(PersistenceErrors::RowSeeking)?;
+                Row::from_bytes(bytes.to_vec(), &self.columns, &mut values)
+                    .map_err(PersistenceErrors::RowSeeking)
+            }
+            None => Err(PersistenceErrors::RowSeeking(io::ErrorKind::UnexpectedEof.into())),
+        }
+    }
+
+    /// The page of the rows file, served from the buffer pool when possible.
+    pub(crate) fn read_page(&self, page_number: u64) -> io::Result<Arc<Page>> {
+        let file_name = self.table_rows_name();
+        buffer_pool::read_page(&file_name, page_number, || {
+            let page_size = self.get_page_size();
+            let mut rows_file = File::open(&file_name)?;
+            rows_file.seek(SeekFrom::Start(page::page_position(page_number, page_size)))?;
+            let mut bytes = vec![0; page_size];
+            rows_file.read_exact(&mut bytes)?;
+            Ok(Page::from_bytes(bytes))
+        })
+    }
+
+    pub(crate) fn get_page_size(&self) -> usize {
+        page::page_size(self.get_row_size())
+    }
+
+    pub(crate) fn get_page_count(&self) -> io::Result<u64> {
+        let file_size = std::fs::metadata(self.table_rows_name())?.len();
+        Ok(file_size.saturating_sub(ROWS_FILE_HEADER.len() as u64) / self.get_page_size() as u64)
     }
 
     pub fn get_row_size(&self) -> usize {
-        let mut row_size = 0;
-        for column in &self.columns {
-            row_size += column.size();
-        }
-        row_size
+        row::row_size(&self.columns)
     }
 
     pub fn create(&self) -> Result<(), PersistenceErrors> {
+        wal::execute(Change::CreateTable {
+            header: self.to_bytes(),
+        })
+    }
+
+    pub(crate) fn create_files(&self) -> Result<(), PersistenceErrors> {
         self.write_table_header()?;
-        write(self.table_rows_name(), []).map_err(PersistenceErrors::TableCreation)?;
-        for column in &self.columns {
-            if column.is_indexed {
-                write(self.get_index_file_name(column), [])
-                    .map_err(PersistenceErrors::TableCreation)?;
-            }
-        }
+        wal::write_file(&self.table_rows_name(), &ROWS_FILE_HEADER)
+            .map_err(PersistenceErrors::TableCreation)?;
+        wal::write_file(&self.table_values_name(), &[]).map_err(PersistenceErrors::TableCreation)?;
+        buffer_pool::evict_file(&self.table_rows_name());
+        self.build_indexes(&self.indexes)?;
         Result::Ok(())
     }
 
     fn write_table_header(&self) -> Result<(), PersistenceErrors> {
-        write(&self.name, self.to_bytes()).map_err(PersistenceErrors::TableCreation)?;
+        wal::write_file(&self.name, &self.to_bytes()).map_err(PersistenceErrors::TableCreation)?;
         Ok(())
     }
 
+    /// The header, rows, values and index files of the table.
+    pub(crate) fn file_names(&self) -> Vec<String> {
+        let mut file_names = vec![self.name.clone(), self.table_rows_name(), self.table_values_name()];
+        for index in &self.indexes {
+            file_names.push(self.get_index_file_name(&index.name, index.kind));
+        }
+        file_names
+    }
+
     pub(crate) fn table_rows_name(&self) -> String {
         self.name.clone() + "_rows"
     }
 
+    pub(crate) fn table_values_name(&self) -> String {
+        self.name.clone() + "_values"
+    }
+
+    /// The values of the rows, which don't fit the rows.
+    pub(crate) fn open_values(&self) -> io::Result<Values> {
+        Values::open(&self.table_values_name())
+    }
+
     pub fn drop(&self) -> Result<(), PersistenceErrors> {
-        remove_file(&self.name).map_err(PersistenceErrors::TableDrop)?;
-        remove_file(self.table_rows_name()).map_err(PersistenceErrors::TableDrop)?;
-        for column in &self.columns {
-            if column.is_indexed {
-                remove_file(self.get_index_file_name(column))
-                    .map_err(PersistenceErrors::TableDrop)?;
-            }
+        if !Path::new(&self.name).exists() {
+            return Err(PersistenceErrors::TableDrop(io::ErrorKind::NotFound.into()));
         }
+        wal::execute(Change::DropTable {
+            header: self.to_bytes(),
+        })
+    }
+
+    /// Remove the files which still exist, the header goes last.
+    pub(crate) fn remove_files(&self) -> Result<(), PersistenceErrors> {
+        for file_name in self.file_names().iter().rev() {
+            remove_file_if_exists(file_name).map_err(PersistenceErrors::TableDrop)?;
+        }
+        buffer_pool::evict_file(&self.table_rows_name());
         Result::Ok(())
     }
 
     pub fn insert_row(&self, row: &Row) -> Result<(), PersistenceErrors> {
+        self.insert_rows(std::slice::from_ref(row))
+    }
+
+    /// Append all the rows in one write, the indexes are updated only once.
+    pub fn insert_rows(&self, rows: &[Row]) -> Result<(), PersistenceErrors> {
+        let mut values = self.open_values().map_err(PersistenceErrors::Insert)?;
+        let bytes: Vec<u8> = rows
+            .iter()
+            .flat_map(|row| row.to_bytes(&self.columns, &mut values))
+            .collect();
+        wal::execute(Change::Insert {
+            header: self.to_bytes(),
+            first_row: self.get_rows_count().map_err(PersistenceErrors::Insert)?,
+            rows: bytes,
+            values: Some(values.into_appended()),
+        })
+    }
+
+    /// Write the rows after the first `first_row` rows, anything behind them is cut off.
+    /// The rows are added to the last page while they fit, new pages follow.
+    /// Their values are written before them, so the rows never refer to a missing value.
+    pub(crate) fn write_rows_from(
+        &self,
+        first_row: u64,
+        bytes: &[u8],
+        values: Option<&AppendedValues>,
+    ) -> Result<(), PersistenceErrors> {
+        self.write_values(values).map_err(PersistenceErrors::Insert)?;
+        let rows_count = self.get_rows_count().map_err(PersistenceErrors::Insert)?;
+        let row_size = self.get_row_size();
+        let rows_per_page = page::rows_per_page(row_size) as u64;
+        let page_number = first_row / rows_per_page;
+        let page = match first_row % rows_per_page {
+            0 => Page::new(self.get_page_size()),
+            slot_count => {
+                let mut page = (*self.read_page(page_number).map_err(PersistenceErrors::Insert)?).clone();
+                page.truncate(slot_count as usize);
+                page
+            }
+        };
+
         let mut rows_file = OpenOptions::new()
-            .append(true)
+            .write(true)
             .open(self.table_rows_name())
             .map_err(PersistenceErrors::Insert)?;
-        rows_file
-            .write_all(&row.to_bytes(&self.columns))
-            .map_err(PersistenceErrors::Insert)?;
-        self.generate_indexes()?;
+        let page_count = self.get_page_count().map_err(PersistenceErrors::Insert)?;
+        if page_count > page_number {
+            rows_file
+                .set_len(page::page_position(page_number, self.get_page_size()))
+                .map_err(PersistenceErrors::Insert)?;
+            let removed_pages: Vec<u64> = (page_number..page_count).collect();
+            buffer_pool::evict_pages(&self.table_rows_name(), &removed_pages);
+        }
+
+        let mut writer = PageWriter::new(&mut rows_file, page_number, page);
+        for row in bytes.chunks(row_size) {
+            writer.push(row).map_err(PersistenceErrors::Insert)?;
+        }
+        let written_pages = writer.finish().map_err(PersistenceErrors::Insert)?;
+        buffer_pool::evict_pages(&self.table_rows_name(), &written_pages);
+        wal::sync_file(&rows_file).map_err(PersistenceErrors::Insert)?;
+        if rows_count == first_row {
+            self.append_to_indexes(first_row, bytes)?;
+        } else {
+            // the insert is repeated after a crash, the rows may be in the indexes already
+            self.generate_indexes()?;
+        }
         Result::Ok(())
     }
 
+    /// Add the rows appended from `first_row` to the indexes, the rest of the table isn't read.
+    fn append_to_indexes(&self, first_row: u64, bytes: &[u8]) -> Result<(), PersistenceErrors> {
+        if self.indexes.is_empty() {
+            return Ok(());
+        }
+        let mut hash_indexes = vec![];
+        let mut btree_indexes = vec![];
+        for index in &self.indexes {
+            let positions = self.get_indexed_positions(index)?;
+            match index.kind {
+                IndexKind::Hash => hash_indexes.push((positions, index, Index { rows: HashMap::new() })),
+                IndexKind::BTree => btree_indexes.push((positions, self.get_btree_index(&index.name)?)),
+            }
+        }
+        let projection = self.get_indexed_columns();
+        let mut values = self.open_values().map_err(PersistenceErrors::IndexRefresh)?;
+        for (row_number, bytes) in (first_row..).zip(bytes.chunks(self.get_row_size())) {
+            let row = Row::from_bytes_projected(bytes, &self.columns, Some(&projection), &mut values)
+                .map_err(PersistenceErrors::IndexRefresh)?;
+            for (positions, _index, hash_index) in &mut hash_indexes {
+                hash_index.insert(index_key(&row, positions), row_number);
+            }
+            for (positions, btree_index) in &mut btree_indexes {
+                btree_index.insert((index_key(&row, positions), row_number))?;
+            }
+        }
+        for (positions, index, hash_index) in hash_indexes {
+            let file_name = self.get_index_file_name(&index.name, index.kind);
+            hash_index.append_to_file(file_name, &self.get_columns(&positions))?;
+        }
+        for (_positions, btree_index) in btree_indexes {
+            btree_index.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Remove the deleted rows from the indexes and move the rows behind them.
+    fn remove_from_indexes(&self, row_numbers: &[u64]) -> Result<(), PersistenceErrors> {
+        for index in &self.indexes {
+            let file_name = self.get_index_file_name(&index.name, index.kind);
+            let columns = self.get_columns(&self.get_indexed_positions(index)?);
+            match index.kind {
+                IndexKind::Hash => Index::remove_rows_from_file(&file_name, &columns, row_numbers)?,
+                IndexKind::BTree => BTreeIndex::open(&file_name, &columns)?.remove_rows(row_numbers)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_rows_count(&self) -> io::Result<u64> {
+        let page_count = self.get_page_count()?;
+        if page_count == 0 {
+            return Ok(0);
+        }
+        let rows_per_page = page::rows_per_page(self.get_row_size()) as u64;
+        let last_page = self.read_page(page_count - 1)?;
+        Ok((page_count - 1) * rows_per_page + last_page.slot_count() as u64)
+    }
+
     pub(crate) fn to_bytes(&self) -> Vec<u8> {
         let mut columns_bytes: Vec<Vec<u8>> = vec![];
         for column in &self.columns {
             columns_bytes.push(column.to_bytes());
         }
 
+        let mut indexes_bytes = vec![];
+        if !self.indexes.is_empty() {
+            indexes_bytes.extend(INDEXES_MARK.to_be_bytes());
+            indexes_bytes.extend((self.indexes.len() as u32).to_be_bytes());
+            for index in &self.indexes {
+                put_name(&mut indexes_bytes, &index.name);
+                // the constraint is kept in the upper bits of the kind
+                let kind = match index.kind {
+                    IndexKind::Hash => 0,
+                    IndexKind::BTree => 1,
+                };
+                let constraint = match index.constraint {
+                    None => 0,
+                    Some(IndexConstraint::PrimaryKey) => 2,
+                    Some(IndexConstraint::Unique) => 4,
+                };
+                indexes_bytes.push(kind | constraint);
+                indexes_bytes.extend((index.columns.len() as u32).to_be_bytes());
+                for column in &index.columns {
+                    put_name(&mut indexes_bytes, column);
+                }
+            }
+        }
+
         [
             (self.name.len() as u32).to_be_bytes().to_vec(),
             self.name.as_bytes().to_vec(),
             columns_bytes.concat(),
+            indexes_bytes,
         ]
         .concat()
     }
 
     pub fn load(name: String) -> Result<Table, PersistenceErrors> {
         let bytes = std::fs::read(name).map_err(PersistenceErrors::TableLoading)?;
-        Result::Ok(Table::from_bytes(bytes))
+        let table = Table::from_bytes(bytes);
+        table.migrate_rows_file()?;
+        Result::Ok(table)
     }
 
     pub(crate) fn from_bytes(bytes: Vec<u8>) -> Table {
@@ -160,93 +482,481 @@ impl Table {
             }
         };
         let mut columns: Vec<Column> = vec![];
-        let mut column_begging: usize = 4usize + name_size as usize;
+        let mut indexes = vec![];
+        let mut column_begging: usize = 4usize + name_size;
         while column_begging < bytes.len() {
-            let column_size = get_size(&bytes[column_begging..column_begging + 4usize]) + 13usize;
-            columns.push(Column::from_bytes(
-                bytes[column_begging..column_begging + column_size].to_vec(),
-            ));
+            if get_size(&bytes[column_begging..]) as u32 == INDEXES_MARK {
+                indexes.extend(indexes_from_bytes(&bytes[column_begging + 4..]));
+                break;
+            }
+            let column_size = column::bytes_size(&bytes[column_begging..]);
+            let column = Column::from_bytes(bytes[column_begging..column_begging + column_size].to_vec());
+            // the hash index of the column used to be marked by the lowest bit of its flags
+            if bytes[column_begging + column::flags_position(&bytes[column_begging..])] & 1 == 1 {
+                indexes.push(TableIndex {
+                    name: column.name.clone(),
+                    columns: vec![column.name.clone()],
+                    kind: IndexKind::Hash,
+                    constraint: None,
+                });
+            }
+            columns.push(column);
             column_begging += column_size;
         }
 
-        Table { name, columns }
+        Table { name, columns, indexes }
     }
 
     pub fn generate_indexes(&self) -> Result<(), PersistenceErrors> {
-        let mut indexed_columns = vec![];
-        for (index, column) in self.columns.iter().enumerate() {
-            if column.is_indexed {
-                indexed_columns.push((index, column, HashMap::new()));
+        self.build_indexes(&self.indexes)
+    }
+
+    pub fn delete_rows(&self, row_numbers: Vec<u64>) -> Result<(), PersistenceErrors> {
+        wal::execute(Change::Delete {
+            header: self.to_bytes(),
+            rows_count: self.get_rows_count().map_err(PersistenceErrors::RowDeletion)?,
+            row_numbers,
+        })
+    }
+
+    /// Remove the rows unless the table doesn't have `rows_count` rows anymore,
+    /// i.e. they were removed already. The remaining rows are packed into a new rows file
+    /// page by page, which replaces the old one at once. Their values which don't fit them
+    /// are written to a new values file, which replaces the old one after the rows file,
+    /// so the values of the removed and updated rows don't stay in it.
+    pub(crate) fn remove_rows(&self, rows_count: u64, row_numbers: &[u64]) -> Result<(), PersistenceErrors> {
+        let compacted_values_name = self.table_values_name() + "#compacted";
+        if self.get_rows_count().map_err(PersistenceErrors::RowDeletion)? != rows_count {
+            // the rows refer to the new values file already
+            if Path::new(&compacted_values_name).exists() {
+                rename(&compacted_values_name, self.table_values_name()).map_err(PersistenceErrors::RowDeletion)?;
             }
+            return self.generate_indexes();
         }
-        let table_iterator: Vec<(usize, Row)> = table_iterator::RowsIterator::from_table(self)?
-            .enumerate()
+        let mut row_numbers: Vec<u64> = row_numbers.to_vec();
+        row_numbers.sort_unstable();
+        row_numbers.dedup();
+        let page_count = self.get_page_count().map_err(PersistenceErrors::RowDeletion)?;
+        let mut values = self.open_values().map_err(PersistenceErrors::RowDeletion)?;
+        let mut values_writer =
+            ValuesWriter::create(&compacted_values_name).map_err(PersistenceErrors::RowDeletion)?;
+        wal::replace_file(&self.table_rows_name(), |rows_file| {
+            rows_file.write_all(&ROWS_FILE_HEADER)?;
+            let mut writer = PageWriter::new(rows_file, 0, Page::new(self.get_page_size()));
+            let mut row_number = 0;
+            for page_number in 0..page_count {
+                let page = self.read_page(page_number)?;
+                for slot in 0..page.slot_count() {
+                    if row_numbers.binary_search(&row_number).is_err() {
+                        let record = page.record(slot).expect("The slot is in the page.");
+                        writer.push(&row::move_values(record, &self.columns, &mut values, &mut values_writer)?)?;
+                    }
+                    row_number += 1;
+                }
+            }
+            values_writer.finish()?;
+            writer.finish().map(|_written_pages| ())
+        })
+        .map_err(PersistenceErrors::RowDeletion)?;
+        buffer_pool::evict_file(&self.table_rows_name());
+        rename(&compacted_values_name, self.table_values_name()).map_err(PersistenceErrors::RowDeletion)?;
+        self.remove_from_indexes(&row_numbers)?;
+        Ok(())
+    }
+
+    /// Overwrite rows in place, every row keeps its row number.
+    /// The values replaced by the update stay in the values file until rows are removed
+    /// or the rows are rewritten.
+    pub fn update_rows(&self, rows: &[(u64, Row)]) -> Result<(), PersistenceErrors> {
+        let mut values = self.open_values().map_err(PersistenceErrors::RowUpdate)?;
+        let rows = rows
+            .iter()
+            .map(|(row_number, row)| (*row_number, row.to_bytes(&self.columns, &mut values)))
             .collect();
-        for (row_number, row) in table_iterator.iter() {
-            for (column_number, _column, index_rows_map) in &mut indexed_columns {
-                let option = row.values.get(*column_number);
-                let data = option.unwrap();
-                index_rows_map
-                    .entry(data.calculate_hash())
-                    .or_insert(vec![])
-                    .push((data.clone(), *row_number as u64));
+        wal::execute(Change::Update {
+            header: self.to_bytes(),
+            rows,
+            values: Some(values.into_appended()),
+        })
+    }
+
+    pub(crate) fn write_rows_in_place(
+        &self,
+        rows: &[(u64, Vec<u8>)],
+        values: Option<&AppendedValues>,
+    ) -> Result<(), PersistenceErrors> {
+        self.write_values(values).map_err(PersistenceErrors::RowUpdate)?;
+        let projection = self.get_indexed_columns();
+        let mut values = self.open_values().map_err(PersistenceErrors::RowUpdate)?;
+        let rows_per_page = page::rows_per_page(self.get_row_size()) as u64;
+        let mut pages: BTreeMap<u64, Page> = BTreeMap::new();
+        // the indexed values of the rows before and after the update
+        let mut indexed_rows = vec![];
+        for (row_number, row) in rows {
+            let page_number = row_number / rows_per_page;
+            let page = match pages.entry(page_number) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let page = self.read_page(page_number).map_err(PersistenceErrors::RowUpdate)?;
+                    entry.insert((*page).clone())
+                }
+            };
+            let slot = (row_number % rows_per_page) as usize;
+            if !self.indexes.is_empty() {
+                let old_row = page
+                    .record(slot)
+                    .ok_or(PersistenceErrors::RowUpdate(io::ErrorKind::InvalidInput.into()))?;
+                let old_row = Row::from_bytes_projected(old_row, &self.columns, Some(&projection), &mut values)
+                    .map_err(PersistenceErrors::RowUpdate)?;
+                let new_row = Row::from_bytes_projected(row, &self.columns, Some(&projection), &mut values)
+                    .map_err(PersistenceErrors::RowUpdate)?;
+                indexed_rows.push((*row_number, old_row, new_row));
+            }
+            if !page.set_record(slot, row) {
+                return Err(PersistenceErrors::RowUpdate(io::ErrorKind::InvalidInput.into()));
             }
         }
-        for (_column_number, column, index_rows_map) in &mut indexed_columns {
-            let mut index = Index { rows: HashMap::new() };
-            for (hash, values) in index_rows_map {
-                index.rows.insert(*hash, IndexRow {
-                    hash: *hash,
-                    values: mem::take(values),
-                });
+
+        let mut rows_file = OpenOptions::new()
+            .write(true)
+            .open(self.table_rows_name())
+            .map_err(PersistenceErrors::RowUpdate)?;
+        let page_size = self.get_page_size();
+        for (page_number, page) in &pages {
+            rows_file
+                .seek(SeekFrom::Start(page::page_position(*page_number, page_size)))
+                .map_err(PersistenceErrors::RowUpdate)?;
+            rows_file
+                .write_all(page.bytes())
+                .map_err(PersistenceErrors::RowUpdate)?;
+        }
+        let page_numbers: Vec<u64> = pages.into_keys().collect();
+        buffer_pool::evict_pages(&self.table_rows_name(), &page_numbers);
+        wal::sync_file(&rows_file).map_err(PersistenceErrors::RowUpdate)?;
+        self.update_indexes(&indexed_rows)
+    }
+
+    /// Replace the entries of the updated rows whose indexed values changed,
+    /// the other entries aren't touched.
+    fn update_indexes(&self, rows: &[(u64, Row, Row)]) -> Result<(), PersistenceErrors> {
+        for index in &self.indexes {
+            let positions = self.get_indexed_positions(index)?;
+            let changed_keys: Vec<(u64, Vec<Data>, Vec<Data>)> = rows
+                .iter()
+                .map(|(row_number, old_row, row)| {
+                    (*row_number, index_key(old_row, &positions), index_key(row, &positions))
+                })
+                .filter(|(_row_number, old_key, key)| old_key != key)
+                .collect();
+            if changed_keys.is_empty() {
+                continue;
+            }
+            let file_name = self.get_index_file_name(&index.name, index.kind);
+            let columns = self.get_columns(&positions);
+            match index.kind {
+                IndexKind::Hash => {
+                    let removed: Vec<(u64, u64)> = changed_keys
+                        .iter()
+                        .map(|(row_number, old_key, _key)| (key_hash(old_key), *row_number))
+                        .collect();
+                    Index::remove_entries_from_file(&file_name, &columns, &removed)?;
+                    let mut hash_index = Index { rows: HashMap::new() };
+                    for (row_number, _old_key, key) in changed_keys {
+                        hash_index.insert(key, row_number);
+                    }
+                    hash_index.append_to_file(file_name, &columns)?;
+                }
+                IndexKind::BTree => {
+                    let mut btree_index = BTreeIndex::open(&file_name, &columns)?;
+                    for (row_number, old_key, key) in changed_keys {
+                        btree_index.remove(&(old_key, row_number))?;
+                        btree_index.insert((key, row_number))?;
+                    }
+                    btree_index.sync()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Append a new column (without index), all existing rows get the `default` value.
+    pub fn add_column(&mut self, column: Column, default: &Data) -> Result<(), PersistenceErrors> {
+        let old_table = self.clone();
+        self.columns.push(column);
+        let rows = table_iterator::RowsIterator::from_table(&old_table)?.map(|row| {
+            let mut row = row?;
+            row.values.push(default.clone());
+            Ok(row)
+        });
+        self.write_rows(&old_table, rows)
+    }
+
+    /// Remove the column from every row, the indexes of the column are dropped as well.
+    /// The indexes of other columns stay valid, as the row numbers don't change.
+    pub fn drop_column(&mut self, column_index: usize) -> Result<(), PersistenceErrors> {
+        let old_table = self.clone();
+        let column = self.columns.remove(column_index);
+        self.indexes.retain(|index| !index.columns.contains(&column.name));
+        let rows = table_iterator::RowsIterator::from_table(&old_table)?.map(|row| {
+            let mut row = row?;
+            row.values.remove(column_index);
+            Ok(row)
+        });
+        self.write_rows(&old_table, rows)
+    }
+
+    pub fn rename_column(&mut self, column_index: usize, new_name: String) -> Result<(), PersistenceErrors> {
+        let old_table = self.clone();
+        let old_name = mem::replace(&mut self.columns[column_index].name, new_name.clone());
+        for index in &mut self.indexes {
+            // the index created by the name of its column keeps being named by it,
+            // its file is moved as well
+            if index.name == old_name && index.columns == [old_name.clone()] {
+                index.name = new_name.clone();
+            }
+            for column in index.columns.iter_mut().filter(|column| **column == old_name) {
+                *column = new_name.clone();
             }
-            index.write_index_to_file(self.get_index_file_name(column), column)?;
         }
+        wal::execute(Change::Rename {
+            old_header: old_table.to_bytes(),
+            header: self.to_bytes(),
+        })
+    }
+
+    /// Move the header, rows and index files of the table under the new name.
+    pub fn rename(&mut self, new_name: String) -> Result<(), PersistenceErrors> {
+        let old_table = self.clone();
+        self.name = new_name;
+        wal::execute(Change::Rename {
+            old_header: old_table.to_bytes(),
+            header: self.to_bytes(),
+        })
+    }
+
+    /// Move the files of the old table (with the same columns) to the current names.
+    pub(crate) fn move_files(&self, old_table: &Table) -> Result<(), PersistenceErrors> {
+        let old_file_names = old_table.file_names();
+        for (old_file_name, file_name) in old_file_names.iter().zip(self.file_names()).skip(1) {
+            if *old_file_name != file_name && Path::new(old_file_name).exists() {
+                rename(old_file_name, file_name).map_err(PersistenceErrors::TableAlter)?;
+            }
+        }
+        buffer_pool::evict_file(&old_table.table_rows_name());
+        buffer_pool::evict_file(&self.table_rows_name());
+        self.write_table_header()?;
+        if old_table.name != self.name {
+            remove_file_if_exists(&old_table.name).map_err(PersistenceErrors::TableAlter)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the whole rows file, the rows are written in the current layout.
+    /// The values file is written again with only the values of the rows.
+    /// The indexes of the kept columns stay valid, as the row numbers don't change.
+    fn write_rows(
+        &self,
+        old_table: &Table,
+        rows: impl Iterator<Item = Result<Row, PersistenceErrors>>,
+    ) -> Result<(), PersistenceErrors> {
+        self.write_rewritten_files(rows, PersistenceErrors::TableAlter)?;
+        wal::execute(Change::Rewrite {
+            old_header: old_table.to_bytes(),
+            header: self.to_bytes(),
+        })
+    }
 
+    pub(crate) fn rewrite_rows(&self, old_table: &Table) -> Result<(), PersistenceErrors> {
+        self.move_rewritten_files().map_err(PersistenceErrors::TableAlter)?;
+        self.write_table_header()?;
+        let file_names = self.file_names();
+        for old_file_name in old_table.file_names() {
+            if !file_names.contains(&old_file_name) {
+                remove_file_if_exists(&old_file_name).map_err(PersistenceErrors::TableAlter)?;
+            }
+        }
         Ok(())
     }
 
-    fn get_index_file_name(&self, column: &Column) -> String {
-        self.name.clone() + column.name.clone().as_str() + "_index"
+    /// The changes logged before the values file was introduced have no values.
+    fn write_values(&self, values: Option<&AppendedValues>) -> io::Result<()> {
+        match values {
+            Some(values) => values.write(&self.table_values_name()),
+            None => Ok(()),
+        }
     }
 
-    pub(crate) fn read_table_rows_bytes(&self) -> Result<Vec<u8>, PersistenceErrors> {
-        std::fs::read(self.table_rows_name()).map_err(PersistenceErrors::TableLoading)
+    /// Write the rows packed into the pages page by page next to the rows file and the values
+    /// which don't fit them next to the values file, they replace the files by `move_rewritten_files`.
+    fn write_rewritten_files(
+        &self,
+        rows: impl Iterator<Item = Result<Row, PersistenceErrors>>,
+        error: fn(io::Error) -> PersistenceErrors,
+    ) -> Result<(), PersistenceErrors> {
+        let result = self.write_rewritten_rows(rows, error);
+        if result.is_err() {
+            // nothing refers to the files written partially
+            for file_name in [self.table_values_name(), self.table_rows_name()] {
+                let _ = remove_file(rewritten_file_name(&file_name));
+            }
+        }
+        result
     }
 
-    pub fn delete_rows(&self, row_numbers: Vec<u64>) -> Result<(), PersistenceErrors> {
-        let rows_bytes = self.read_table_rows_bytes()?;
-        let row_size = self.get_row_size();
-        let row_count = rows_bytes.len() / row_size;
-        let mut new_rows_bytes = vec![];
-        let mut rows_bytes_iterator = rows_bytes.iter();
-        for row_number in 0..row_count {
-            if row_numbers.contains(&(row_number as u64)) {
-                for _ in 0..row_size {
-                    rows_bytes_iterator
-                        .next()
-                        .expect("Element you are try to delete doesn't exist.");
-                }
-            } else {
-                for _ in 0..row_size {
-                    new_rows_bytes.push(
-                        *rows_bytes_iterator
-                            .next()
-                            .expect("There is different number of rows then you thought."),
-                    );
-                }
+    fn write_rewritten_rows(
+        &self,
+        rows: impl Iterator<Item = Result<Row, PersistenceErrors>>,
+        error: fn(io::Error) -> PersistenceErrors,
+    ) -> Result<(), PersistenceErrors> {
+        let values_file_name = rewritten_file_name(&self.table_values_name());
+        let mut values_writer = ValuesWriter::create(&values_file_name).map_err(error)?;
+        let mut rows_file = File::create(rewritten_file_name(&self.table_rows_name())).map_err(error)?;
+        rows_file.write_all(&ROWS_FILE_HEADER).map_err(error)?;
+        let mut writer = PageWriter::new(&mut rows_file, 0, Page::new(self.get_page_size()));
+        for row in rows {
+            let mut values = Values::default();
+            let bytes = row?.to_bytes(&self.columns, &mut values);
+            let bytes = row::move_values(&bytes, &self.columns, &mut values, &mut values_writer).map_err(error)?;
+            writer.push(&bytes).map_err(error)?;
+        }
+        writer.finish().map_err(error)?;
+        values_writer.finish().map_err(error)?;
+        wal::sync_file(&rows_file).map_err(error)
+    }
+
+    /// Replace the values file and then the rows file by the rewritten files,
+    /// the files replaced already before a crash are kept.
+    fn move_rewritten_files(&self) -> io::Result<()> {
+        for file_name in [self.table_values_name(), self.table_rows_name()] {
+            let rewritten_file_name = rewritten_file_name(&file_name);
+            if Path::new(&rewritten_file_name).exists() {
+                rename(rewritten_file_name, file_name)?;
             }
         }
-        write(self.table_rows_name(), new_rows_bytes).map_err(PersistenceErrors::RowDeletion)?;
+        buffer_pool::evict_file(&self.table_rows_name());
+        Ok(())
+    }
+
+    /// The rows file written before the pages were introduced contains just the rows
+    /// one after another, the rows of the first version of the pages are without the null bitmap
+    /// and the strings of the first two versions take their whole size within the rows.
+    /// All are converted to the current pages the first time the table is loaded,
+    /// the indexes are built again as their keys are written like the rows.
+    fn migrate_rows_file(&self) -> Result<(), PersistenceErrors> {
+        if !self.has_old_rows_file()? {
+            return Ok(());
+        }
+        let _x = MIGRATION.lock().unwrap();
+        if !self.has_old_rows_file()? {
+            return Ok(());
+        }
+
+        let rows_file = File::open(self.table_rows_name()).map_err(PersistenceErrors::TableLoading)?;
+        let file_size = rows_file.metadata().map_err(PersistenceErrors::TableLoading)?.len();
+        let mut reader = BufReader::new(rows_file);
+        let row_size = row::legacy_row_size(&self.columns);
+        let records = (0..file_size / row_size as u64).map_while(move |_row_number| {
+            let mut row = vec![0; row_size];
+            reader.read_exact(&mut row).ok().map(|_| row)
+        });
+        let rows = records.map(|record| Ok(row::legacy_row_from_bytes(&record, &self.columns)));
+        self.write_rewritten_files(rows, PersistenceErrors::TableLoading)?;
+        self.move_rewritten_files().map_err(PersistenceErrors::TableLoading)?;
         self.generate_indexes()?;
+        // the hash indexes of the columns were named without a separator
+        for index in &self.indexes {
+            remove_file_if_exists(&(self.name.clone() + &index.name + "_index"))
+                .map_err(PersistenceErrors::TableLoading)?;
+        }
         Ok(())
     }
 
-    pub fn get_index(&self, column: &Column) -> Result<Index, PersistenceErrors> {
-        let string = self.get_index_file_name(column);
-        Index::load(string, &column)
+    fn has_old_rows_file(&self) -> Result<bool, PersistenceErrors> {
+        let Ok(mut rows_file) = File::open(self.table_rows_name()) else {
+            return Ok(false);
+        };
+        let mut file_header = vec![];
+        (&mut rows_file)
+            .take(ROWS_FILE_HEADER.len() as u64)
+            .read_to_end(&mut file_header)
+            .map_err(PersistenceErrors::TableLoading)?;
+        Ok(file_header != ROWS_FILE_HEADER)
+    }
+
+}
+
+/// The file written next to the file of the table to replace it.
+fn rewritten_file_name(file_name: &str) -> String {
+    file_name.to_string() + "#rewritten"
+}
+
+fn remove_file_if_exists(file_name: &str) -> io::Result<()> {
+    match remove_file(file_name) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
     }
+}
 
+fn put_name(bytes: &mut Vec<u8>, name: &str) {
+    bytes.extend((name.len() as u32).to_be_bytes());
+    bytes.extend(name.as_bytes());
+}
+
+/// Reads the names one after another.
+struct NameReader<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+}
+
+impl NameReader<'_> {
+    fn size(&mut self) -> usize {
+        let size = get_size(&self.bytes[self.cursor..]);
+        self.cursor += 4;
+        size
+    }
+
+    fn name(&mut self) -> String {
+        let name_size = self.size();
+        let name = String::from_utf8_lossy(&self.bytes[self.cursor..self.cursor + name_size]).to_string();
+        self.cursor += name_size;
+        name
+    }
+
+    fn kind(&mut self) -> (IndexKind, Option<IndexConstraint>) {
+        self.cursor += 1;
+        let byte = self.bytes[self.cursor - 1];
+        let kind = match byte & 1 {
+            0 => IndexKind::Hash,
+            _ => IndexKind::BTree,
+        };
+        let constraint = match byte >> 1 {
+            1 => Some(IndexConstraint::PrimaryKey),
+            2 => Some(IndexConstraint::Unique),
+            _ => None,
+        };
+        (kind, constraint)
+    }
+}
+
+fn indexes_from_bytes(bytes: &[u8]) -> Vec<TableIndex> {
+    let mut reader = NameReader { bytes, cursor: 0 };
+    (0..reader.size())
+        .map(|_index| {
+            let name = reader.name();
+            let (kind, constraint) = reader.kind();
+            let columns = (0..reader.size()).map(|_column| reader.name()).collect();
+            TableIndex {
+                name,
+                columns,
+                kind,
+                constraint,
+            }
+        })
+        .collect()
+}
+
+fn index_key(row: &Row, positions: &[usize]) -> Vec<Data> {
+    positions.iter().map(|position| row.values[*position].clone()).collect()
 }
 
 fn get_size(bytes: &[u8]) -> usize {
@@ -255,10 +965,12 @@ fn get_size(bytes: &[u8]) -> usize {
 
 #[cfg(test)]
 pub mod tests {
-    use common::models::db::{DataType, Data};
+    use common::models::db::{ColumnConstraints, DataType, Data};
 
     use super::*;
-    use crate::table::index;
+    use crate::table::index::IndexRow;
+    use crate::table::row::PersistenceData;
+    use std::ops::Bound;
     use std::path::Path;
 
     #[test]
@@ -267,20 +979,28 @@ pub mod tests {
         let column_name = Column {
             name: String::from("Name"),
             data_type: string_data_type,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let column_id = Column {
             name: String::from("Id"),
             data_type: DataType::INT,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
+        };
+        let index = TableIndex {
+            name: String::from("name_and_id"),
+            columns: vec![String::from("Name"), String::from("Id")],
+            kind: IndexKind::BTree,
+            constraint: Some(IndexConstraint::Unique),
         };
         let table = Table {
             name: String::from("Table"),
             columns: vec![column_name, column_id],
+            indexes: vec![id_index(), index],
         };
         let table_from_bytes = Table::from_bytes(table.to_bytes());
 
         assert_eq!(table.name, table_from_bytes.name);
+        assert_eq!(table.indexes, table_from_bytes.indexes);
         assert_eq!(table_from_bytes.columns.len(), 2usize);
         let column_name_from_bytes = &table_from_bytes.columns[0];
         assert_eq!(column_name_from_bytes.name, String::from("Name"));
@@ -314,7 +1034,7 @@ pub mod tests {
         let table_rows_path = Path::new(&table_rows_path);
         assert!(table_rows_path.exists());
         assert_eq!(std::fs::read(table_path).unwrap(), table.to_bytes());
-        assert!(std::fs::read(table_rows_path).unwrap().is_empty());
+        assert_eq!(std::fs::read(table_rows_path).unwrap(), ROWS_FILE_HEADER);
         assert!(table.drop().is_ok())
     }
 
@@ -323,18 +1043,67 @@ pub mod tests {
         let column_name = Column {
             name: String::from("Name"),
             data_type: string_data_type,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let column_id = Column {
             name: String::from("Id"),
             data_type: DataType::INT,
-            is_indexed: indexed,
+            constraints: ColumnConstraints::default(),
         };
-        let table = Table {
+        Table {
             name: String::from(name),
             columns: vec![column_name, column_id],
-        };
-        table
+            indexes: match indexed {
+                true => vec![id_index()],
+                false => vec![],
+            },
+        }
+    }
+
+    fn id_index() -> TableIndex {
+        TableIndex {
+            name: String::from("Id"),
+            columns: vec![String::from("Id")],
+            kind: IndexKind::Hash,
+            constraint: None,
+        }
+    }
+
+    #[test]
+    fn index_file_names_kept_apart() {
+        let table = |name: &str| Table { name: String::from(name), columns: vec![], indexes: vec![] };
+
+        assert_ne!(
+            table("ab").get_index_file_name("x", IndexKind::Hash),
+            table("a").get_index_file_name("bx", IndexKind::Hash)
+        );
+    }
+
+    #[test]
+    fn old_table_header() {
+        let columns = vec![
+            Column {
+                name: String::from("Name"),
+                data_type: DataType::STRING { size: 255 },
+                constraints: ColumnConstraints::default(),
+            },
+            Column {
+                name: String::from("Id"),
+                data_type: DataType::INT,
+                constraints: ColumnConstraints::default(),
+            },
+        ];
+        let mut bytes = [(5u32).to_be_bytes().to_vec(), b"Table".to_vec()].concat();
+        bytes.extend(columns[0].to_bytes());
+        let mut id_bytes = columns[1].to_bytes();
+        // the flag of the hash index of the column
+        *id_bytes.last_mut().unwrap() = 1;
+        bytes.extend(id_bytes);
+        let table = Table::from_bytes(bytes);
+
+        assert_eq!(table.columns, columns);
+        assert_eq!(table.indexes, vec![id_index()]);
+        assert_eq!(Table::from_bytes(table.to_bytes()), table);
     }
 
     #[test]
@@ -346,30 +1115,25 @@ pub mod tests {
             1,
         );
         insert_row(&table, String::from("I am sure about it."), 10);
-        let column_id = Column {
-            name: String::from("Id"),
-            data_type: DataType::INT,
-            is_indexed: true,
-        };
-        let string = table.get_index_file_name(&column_id);
+        let string = table.get_index_file_name("Id", IndexKind::Hash);
         let table_rows_path = Path::new(&string);
         assert!(table_rows_path.exists());
-        let loaded_index = index::Index::load(string, &column_id).unwrap();
+        let loaded_index = table.get_index("Id").unwrap();
         assert!(table.drop().is_ok());
         let int_data = Data::INT(8);
         let int_data2 = Data::INT(10);
         let int_data3 = Data::INT(1);
         let index_row1 = IndexRow {
             hash: int_data.calculate_hash(),
-            values: vec![(int_data, 0)],
+            values: vec![(vec![int_data], 0)],
         };
         let index_row2 = IndexRow {
             hash: int_data2.calculate_hash(),
-            values: vec![(int_data2, 2)],
+            values: vec![(vec![int_data2], 2)],
         };
         let index_row3 = IndexRow {
             hash: int_data3.calculate_hash(),
-            values: vec![(int_data3, 1)],
+            values: vec![(vec![int_data3], 1)],
         };
 
         assert!(matches!(loaded_index.rows.get(&index_row1.hash), Some(row) if *row == index_row1));
@@ -386,18 +1150,13 @@ pub mod tests {
             1,
         );
         insert_row(&table, String::from("I am sure about it."), 10);
-        let index_was_created_successfully = table.add_index(1).is_ok();
-        let column_id = Column {
-            name: String::from("Id"),
-            data_type: DataType::INT,
-            is_indexed: true,
-        };
+        let index_was_created_successfully = table.add_index(id_index()).is_ok();
         let loaded_table = Table::load(String::from("Table9")).unwrap();
         assert_eq!(loaded_table, table);
-        let string = table.get_index_file_name(&column_id);
+        let string = table.get_index_file_name("Id", IndexKind::Hash);
         let table_rows_path = Path::new(&string);
         assert!(table_rows_path.exists());
-        let loaded_index = index::Index::load(string, &column_id).unwrap();
+        let loaded_index = table.get_index("Id").unwrap();
         assert!(table.drop().is_ok());
         assert!(index_was_created_successfully);
         let int_data = Data::INT(8);
@@ -405,15 +1164,15 @@ pub mod tests {
         let int_data3 = Data::INT(1);
         let index_row1 = IndexRow {
             hash: int_data.calculate_hash(),
-            values: vec![(int_data, 0)],
+            values: vec![(vec![int_data], 0)],
         };
         let index_row2 = IndexRow {
             hash: int_data2.calculate_hash(),
-            values: vec![(int_data2, 2)],
+            values: vec![(vec![int_data2], 2)],
         };
         let index_row3 = IndexRow {
             hash: int_data3.calculate_hash(),
-            values: vec![(int_data3, 1)],
+            values: vec![(vec![int_data3], 1)],
         };
 
         assert!(matches!(loaded_index.rows.get(&index_row1.hash), Some(row) if *row == index_row1));
@@ -429,10 +1188,14 @@ pub mod tests {
             String::from("We will surely finish this project."),
             1,
         );
-        assert!(table.remove_index(1).is_ok());
+        assert!(table.remove_index("Id").unwrap());
+        assert!(!table.remove_index("Id").unwrap());
         let loaded_table = Table::load(String::from("Table10")).unwrap();
-        assert_eq!(table, loaded_table);
+        let index_exists = Path::new("Table10#Id_index").exists();
         assert!(table.drop().is_ok());
+        assert_eq!(table, loaded_table);
+        assert!(loaded_table.indexes.is_empty());
+        assert!(!index_exists);
     }
 
     #[test]
@@ -450,11 +1213,97 @@ pub mod tests {
         let (table, row) = insert_data("Table1", false);
         let table_rows_path = table.table_rows_name();
         let table_rows_path = Path::new(&table_rows_path);
+        let bytes = std::fs::read(table_rows_path).unwrap();
+        assert_eq!(bytes[..ROWS_FILE_HEADER.len()], ROWS_FILE_HEADER);
+        let page = Page::from_bytes(bytes[ROWS_FILE_HEADER.len()..].to_vec());
+        assert_eq!(page.slot_count(), 1);
+        assert_eq!(page.record(0), Some(&row.to_bytes(&table.columns, &mut Values::default())[..]));
+        assert_eq!(table.seek_row(0).unwrap(), row);
+        assert!(table.drop().is_ok())
+    }
+
+    #[test]
+    fn table_insert_rows() {
+        let (table, row) = insert_data("Table12", true);
+        let rows = vec![
+            Row {
+                values: vec![Data::STRING(String::from("First of many.")), Data::INT(2)],
+            },
+            Row {
+                values: vec![Data::STRING(String::from("Second of many.")), Data::INT(8)],
+            },
+        ];
+        table.insert_rows(&rows).unwrap();
+        let loaded_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let loaded_index = table.get_index("Id").unwrap();
+        assert!(table.drop().is_ok());
+        assert_eq!(loaded_rows, vec![row, rows[0].clone(), rows[1].clone()]);
+        let index_row = loaded_index.rows.get(&Data::INT(8).calculate_hash()).unwrap();
+        assert_eq!(index_row.values, vec![(vec![Data::INT(8)], 0), (vec![Data::INT(8)], 2)]);
+    }
+
+    #[test]
+    fn table_add_and_drop_column() {
+        let (mut table, row) = insert_data("Table13", true);
+        let column = Column {
+            name: String::from("Active"),
+            data_type: DataType::BOOLEAN,
+            constraints: ColumnConstraints::default(),
+        };
+        table.add_column(column.clone(), &Data::BOOLEAN(true)).unwrap();
+        let loaded_table = Table::load(table.name.clone()).unwrap();
+        let added_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        table.drop_column(0).unwrap();
+        let dropped_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let loaded_index = table.get_index("Id").unwrap();
+        assert!(table.drop().is_ok());
+
+        assert_eq!(loaded_table.columns[2], column);
+        let mut expected_values = row.values.clone();
+        expected_values.push(Data::BOOLEAN(true));
+        assert_eq!(added_rows, vec![Row { values: expected_values }]);
+        assert_eq!(table.columns.len(), 2);
         assert_eq!(
-            std::fs::read(table_rows_path).unwrap(),
-            row.to_bytes(&table.columns)
+            dropped_rows,
+            vec![Row {
+                values: vec![Data::INT(8), Data::BOOLEAN(true)]
+            }]
         );
-        assert!(table.drop().is_ok())
+        assert!(loaded_index.rows.contains_key(&Data::INT(8).calculate_hash()));
+    }
+
+    #[test]
+    fn table_rename() {
+        let (mut table, row) = insert_data("Table14", true);
+        table.rename_column(1, String::from("Number")).unwrap();
+        table.rename(String::from("Table15")).unwrap();
+
+        let loaded_table = Table::load(String::from("Table15")).unwrap();
+        let rows: Vec<Row> = table_iterator::RowsIterator::from_table(&loaded_table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let index_exists = Path::new("Table15#Number_index").exists();
+        let old_files_exist = ["Table14", "Table14_rows", "Table14#Id_index", "Table15#Id_index"]
+            .iter()
+            .any(|file_name| Path::new(file_name).exists());
+        assert!(table.drop().is_ok());
+
+        assert_eq!(loaded_table, table);
+        assert_eq!(loaded_table.columns[1].name, "Number");
+        assert_eq!(rows, vec![row]);
+        assert!(index_exists);
+        assert!(!old_files_exist);
     }
 
     #[test]
@@ -486,13 +1335,376 @@ pub mod tests {
         table.delete_rows(vec![1, 2]).unwrap();
         let rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
             .unwrap()
+            .map(Result::unwrap)
             .collect();
         assert!(table.drop().is_ok());
         assert_eq!(rows.len(), 2);
-        assert_eq!(rows.get(0).unwrap(), &row);
+        assert_eq!(rows.first().unwrap(), &row);
         assert_eq!(rows.get(1).unwrap(), &row3);
     }
 
+    #[test]
+    fn indexes_maintained_on_insert_and_delete() {
+        let (table, _row) = insert_data("Table24", true);
+        insert_row(&table, String::from("Deleted."), 1);
+        insert_row(&table, String::from("Moved forward."), 8);
+        insert_row(&table, String::from("Deleted too."), 3);
+        insert_row(&table, String::from("Moved forward too."), 4);
+        let appended_index = table.get_index("Id").unwrap();
+        table.delete_rows(vec![3, 1]).unwrap();
+        let index = table.get_index("Id").unwrap();
+        table.generate_indexes().unwrap();
+        let generated_index = table.get_index("Id").unwrap();
+        assert!(table.drop().is_ok());
+
+        assert_eq!(appended_index.get_rows(&[Data::INT(8)]), vec![0, 2]);
+        assert_eq!(index.get_rows(&[Data::INT(8)]), vec![0, 1]);
+        assert!(!index.rows.contains_key(&Data::INT(1).calculate_hash()));
+        assert!(!index.rows.contains_key(&Data::INT(3).calculate_hash()));
+        assert_eq!(index, generated_index);
+    }
+
+    #[test]
+    fn rewritten_rows_moved_after_crash() {
+        let (old_table, row) = insert_data("Table37", false);
+        let mut table = old_table.clone();
+        table.columns.push(Column {
+            name: String::from("Note"),
+            data_type: DataType::TEXT,
+            constraints: ColumnConstraints::default(),
+        });
+        let note = Data::STRING("note ".repeat(10));
+        let rows = table_iterator::RowsIterator::from_table(&old_table).unwrap().map(|row| {
+            let mut row = row?;
+            row.values.push(note.clone());
+            Ok(row)
+        });
+        table.write_rewritten_files(rows, PersistenceErrors::TableAlter).unwrap();
+        // the crash came after the values file was moved, before the rows file
+        rename(rewritten_file_name(&table.table_values_name()), table.table_values_name()).unwrap();
+        table.rewrite_rows(&old_table).unwrap();
+        table.rewrite_rows(&old_table).unwrap();
+        let loaded_table = Table::load(table.name.clone()).unwrap();
+        let loaded_row = loaded_table.seek_row(0).unwrap();
+        let rewritten_file_exists = Path::new(&rewritten_file_name(&table.table_rows_name())).exists();
+        assert!(table.drop().is_ok());
+        assert_eq!(loaded_table, table);
+        assert_eq!(loaded_row.values, [row.values, vec![note]].concat());
+        assert!(!rewritten_file_exists);
+    }
+
+    #[test]
+    fn btree_index_kept_by_alter() {
+        let (mut table, _row) = insert_data("Table25", false);
+        insert_row(&table, String::from("Second."), 1);
+        table
+            .add_index(TableIndex {
+                name: String::from("Id"),
+                columns: vec![String::from("Id")],
+                kind: IndexKind::BTree,
+                constraint: None,
+            })
+            .unwrap();
+        let loaded_table = Table::load(table.name.clone()).unwrap();
+        table.rename_column(1, String::from("Number")).unwrap();
+        let renamed_file_exists = Path::new("Table25#Number_btree").exists();
+        let entries = table.get_btree_index("Number").unwrap().entries().unwrap();
+        table.drop_column(1).unwrap();
+        let dropped_file_exists = Path::new("Table25#Number_btree").exists();
+        let indexes = table.indexes.clone();
+        assert!(table.drop().is_ok());
+
+        let index = TableIndex {
+            name: String::from("Id"),
+            columns: vec![String::from("Id")],
+            kind: IndexKind::BTree,
+            constraint: None,
+        };
+        assert_eq!(loaded_table.indexes, vec![index]);
+        assert!(!Path::new("Table25#Id_btree").exists());
+        assert!(renamed_file_exists);
+        assert_eq!(entries, vec![(vec![Data::INT(1)], 1), (vec![Data::INT(8)], 0)]);
+        assert!(!dropped_file_exists);
+        assert!(indexes.is_empty());
+    }
+
+    #[test]
+    fn index_of_multiple_columns() {
+        let (mut table, _row) = insert_data("Table26", false);
+        for kind in [IndexKind::Hash, IndexKind::BTree] {
+            let index = TableIndex {
+                name: format!("{:?}", kind),
+                columns: vec![String::from("Id"), String::from("Name")],
+                kind,
+                constraint: None,
+            };
+            table.add_index(index).unwrap();
+        }
+        insert_row(&table, String::from("Deleted."), 8);
+        insert_row(&table, String::from("Best SQL Server"), 8);
+        table.delete_rows(vec![1]).unwrap();
+        let key = [Data::INT(8), Data::STRING(String::from("Best SQL Server"))];
+        let hash_rows = table.get_index("Hash").unwrap().get_rows(&key);
+        let prefixed = table
+            .get_btree_index("BTree")
+            .unwrap()
+            .range(Bound::Included(&key[..1]), Bound::Included(&key[..1]))
+            .unwrap();
+        let wrong_kind = table.get_index("BTree").is_err();
+        let missing_column = TableIndex {
+            name: String::from("missing"),
+            columns: vec![String::from("Id"), String::from("Missing")],
+            kind: IndexKind::Hash,
+            constraint: None,
+        };
+        let missing_column_added = table.add_index(missing_column).is_ok();
+        assert!(table.drop().is_ok());
+
+        assert_eq!(hash_rows, vec![0, 1]);
+        assert_eq!(prefixed, vec![(key.to_vec(), 0), (key.to_vec(), 1)]);
+        assert!(wrong_kind);
+        assert!(!missing_column_added);
+    }
+
+    #[test]
+    fn update_row() {
+        let (mut table, row) = insert_data("Table11", true);
+        insert_row(&table, String::from("This will be updated."), 1);
+        let row2 = insert_row(&table, String::from("This will stay."), 10);
+        table
+            .add_index(TableIndex {
+                name: String::from("Name"),
+                columns: vec![String::from("Name")],
+                kind: IndexKind::BTree,
+                constraint: None,
+            })
+            .unwrap();
+        let updated_row = Row {
+            values: vec![Data::STRING(String::from("Updated.")), Data::INT(5)],
+        };
+        table.update_rows(&[(1, updated_row.clone()), (2, row2.clone())]).unwrap();
+        let rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let loaded_index = table.get_index("Id").unwrap();
+        let names = table.get_btree_index("Name").unwrap().entries().unwrap();
+        assert!(table.drop().is_ok());
+        assert_eq!(rows, vec![row.clone(), updated_row.clone(), row2.clone()]);
+        assert_eq!(
+            names,
+            vec![
+                (vec![row.values[0].clone()], 0),
+                (vec![row2.values[0].clone()], 2),
+                (vec![updated_row.values[0].clone()], 1)
+            ]
+        );
+        let updated_data = Data::INT(5);
+        let index_row = IndexRow {
+            hash: updated_data.calculate_hash(),
+            values: vec![(vec![updated_data], 1)],
+        };
+        assert!(matches!(loaded_index.rows.get(&index_row.hash), Some(row) if *row == index_row));
+        assert!(!loaded_index.rows.contains_key(&Data::INT(1).calculate_hash()));
+    }
+
+    #[test]
+    fn rows_over_multiple_pages() {
+        let table = create_table("Table21", false);
+        table.create().unwrap();
+        let rows_per_page = page::rows_per_page(table.get_row_size()) as i32;
+        let rows: Vec<Row> = (0..3 * rows_per_page)
+            .map(|int| Row {
+                values: vec![Data::STRING(int.to_string()), Data::INT(int)],
+            })
+            .collect();
+        table.insert_rows(&rows).unwrap();
+        let page_count = table.get_page_count().unwrap();
+        let last_row = table.seek_row(3 * rows_per_page as u64 - 1).unwrap();
+        table.delete_rows(vec![0, rows_per_page as u64]).unwrap();
+        let loaded_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let rows_count = table.get_rows_count().unwrap();
+        assert!(table.drop().is_ok());
+
+        assert_eq!(page_count, 3);
+        assert_eq!(last_row, rows[rows.len() - 1]);
+        assert_eq!(rows_count, 3 * rows_per_page as u64 - 2);
+        let expected_rows: Vec<Row> = rows
+            .into_iter()
+            .enumerate()
+            .filter(|(row_number, _row)| *row_number != 0 && *row_number != rows_per_page as usize)
+            .map(|(_row_number, row)| row)
+            .collect();
+        assert_eq!(loaded_rows, expected_rows);
+    }
+
+    #[test]
+    fn old_rows_file_migrated() {
+        let table = create_table("Table22", false);
+        table.create().unwrap();
+        let rows = vec![
+            Row { values: vec![Data::STRING(String::from("first")), Data::INT(1)] },
+            Row { values: vec![Data::STRING(String::from("second")), Data::INT(2)] },
+        ];
+        // the rows without the null bitmap
+        let bytes: Vec<u8> = rows.iter().flat_map(legacy_row_bytes).collect();
+        std::fs::write(table.table_rows_name(), bytes).unwrap();
+
+        let table = Table::load(table.name).unwrap();
+        let file_header = std::fs::read(table.table_rows_name()).unwrap()[..ROWS_FILE_HEADER.len()].to_vec();
+        let loaded_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert!(table.drop().is_ok());
+        assert_eq!(file_header, ROWS_FILE_HEADER);
+        assert_eq!(loaded_rows, rows);
+    }
+
+    #[test]
+    fn rows_without_null_bitmap_migrated() {
+        let table = create_table("Table27", true);
+        table.create().unwrap();
+        let name = Data::STRING(String::from("first")).to_bytes(255);
+        // the NULL of the integer was a value
+        let bytes = [name.clone(), Data::INT(1).to_bytes(8), name, vec![1, 0, 0, 0, 0, 0, 0, 0]].concat();
+        std::fs::write(table.table_rows_name(), bytes).unwrap();
+        std::fs::write("Table27Id_index", []).unwrap();
+
+        let table = Table::load(table.name).unwrap();
+        let file_header = std::fs::read(table.table_rows_name()).unwrap()[..ROWS_FILE_HEADER.len()].to_vec();
+        let loaded_rows: Vec<Vec<Data>> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(|row| row.unwrap().values)
+            .collect();
+        let index = table.get_index("Id").unwrap();
+        let old_index_exists = Path::new("Table27Id_index").exists();
+        assert!(table.drop().is_ok());
+        assert_eq!(file_header, ROWS_FILE_HEADER);
+        assert!(!old_index_exists);
+        assert_eq!(
+            loaded_rows,
+            vec![
+                vec![Data::STRING(String::from("first")), Data::INT(1)],
+                vec![Data::STRING(String::from("first")), Data::NULL],
+            ]
+        );
+        assert_eq!(index.get_rows(&[Data::INT(1)]), vec![0]);
+        assert_eq!(index.get_rows(&[Data::NULL]), vec![1]);
+    }
+
+    #[test]
+    fn rows_with_whole_strings_migrated() {
+        let table = create_table("Table29", true);
+        table.create().unwrap();
+        let rows = vec![
+            Row { values: vec![Data::STRING(String::from("The string of the first row.")), Data::INT(1)] },
+            Row { values: vec![Data::STRING(String::from("second")), Data::INT(2)] },
+        ];
+        let bytes: Vec<u8> = rows.iter().flat_map(legacy_row_bytes).collect();
+        std::fs::write(table.table_rows_name(), bytes).unwrap();
+        // the tables had no values file
+        std::fs::remove_file(table.table_values_name()).unwrap();
+
+        let table = Table::load(table.name).unwrap();
+        let file_header = std::fs::read(table.table_rows_name()).unwrap()[..ROWS_FILE_HEADER.len()].to_vec();
+        let values_size = std::fs::metadata(table.table_values_name()).unwrap().len();
+        let loaded_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let index = table.get_index("Id").unwrap();
+        assert!(table.drop().is_ok());
+        assert_eq!(file_header, ROWS_FILE_HEADER);
+        assert_eq!(values_size, 28);
+        assert_eq!(loaded_rows, rows);
+        assert_eq!(index.get_rows(&[Data::INT(2)]), vec![1]);
+    }
+
+    #[test]
+    fn long_values_stored_out_of_rows() {
+        let mut table = create_table("Table30", false);
+        table.columns.push(Column {
+            name: String::from("Data"),
+            data_type: DataType::BLOB,
+            constraints: ColumnConstraints::default(),
+        });
+        table.create().unwrap();
+        let long_string = "long ".repeat(50);
+        let rows = vec![
+            Row { values: vec![Data::STRING(long_string.clone()), Data::INT(1), Data::BLOB(vec![1; 300])] },
+            Row { values: vec![Data::STRING(String::from("short")), Data::INT(2), Data::BLOB(vec![2])] },
+        ];
+        table.insert_rows(&rows).unwrap();
+        assert_eq!(std::fs::metadata(table.table_values_name()).unwrap().len(), 550);
+        assert_eq!(table.get_row_size(), 1 + 16 + 8 + 16);
+        assert_eq!(table.seek_row(0).unwrap(), rows[0]);
+
+        let updated_row = Row { values: vec![Data::STRING(long_string.clone()), Data::INT(1), Data::BLOB(vec![])] };
+        table.update_rows(&[(0, updated_row.clone())]).unwrap();
+        assert_eq!(table.seek_row(0).unwrap(), updated_row);
+        assert_eq!(table.seek_row(1).unwrap(), rows[1]);
+        // the replaced values stay until the rows are rewritten
+        assert_eq!(std::fs::metadata(table.table_values_name()).unwrap().len(), 800);
+        table.drop_column(2).unwrap();
+        let values_size = std::fs::metadata(table.table_values_name()).unwrap().len();
+        let loaded_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert!(table.drop().is_ok());
+        assert_eq!(values_size, 250);
+        assert_eq!(
+            loaded_rows,
+            vec![
+                Row { values: vec![Data::STRING(long_string), Data::INT(1)] },
+                Row { values: vec![Data::STRING(String::from("short")), Data::INT(2)] },
+            ]
+        );
+    }
+
+    #[test]
+    fn values_of_removed_rows_reclaimed() {
+        let (table, _row) = insert_data("Table36", false);
+        let long_string = "long ".repeat(50);
+        let row = insert_row(&table, long_string.clone(), 2);
+        let updated_row = Row { values: vec![Data::STRING("updated ".repeat(20)), Data::INT(3)] };
+        insert_row(&table, String::from("short"), 3);
+        table.update_rows(&[(2, updated_row.clone())]).unwrap();
+        assert_eq!(std::fs::metadata(table.table_values_name()).unwrap().len(), 15 + 250 + 160);
+
+        table.delete_rows(vec![0]).unwrap();
+        let values_size = std::fs::metadata(table.table_values_name()).unwrap().len();
+        let loaded_rows: Vec<Row> = table_iterator::RowsIterator::from_table(&table)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        // the crash came after the rows file was replaced, before the values file
+        let compacted_values_name = table.table_values_name() + "#compacted";
+        std::fs::copy(table.table_values_name(), &compacted_values_name).unwrap();
+        std::fs::write(table.table_values_name(), "old values").unwrap();
+        table.remove_rows(3, &[0]).unwrap();
+        let replayed_row = table.seek_row(1).unwrap();
+        let compacted_file_exists = Path::new(&compacted_values_name).exists();
+        assert!(table.drop().is_ok());
+        assert_eq!(values_size, 250 + 160);
+        assert_eq!(loaded_rows, vec![row, updated_row.clone()]);
+        assert_eq!(replayed_row, updated_row);
+        assert!(!compacted_file_exists);
+    }
+
+    /// The row as written before the pages, the strings took their whole size.
+    fn legacy_row_bytes(row: &Row) -> Vec<u8> {
+        let name = match &row.values[0] {
+            Data::NULL => vec![0; 255],
+            name => name.to_bytes(255),
+        };
+        [name, row.values[1].to_bytes(8)].concat()
+    }
+
     pub fn insert_data(name: &str, indexed: bool) -> (Table, Row) {
         let table = create_table(name, indexed);
         table.create().unwrap();
@@ -1,45 +1,105 @@
+use std::sync::Arc;
+
 use common::models::db::Row;
 
+use crate::table::page::Page;
 use crate::table::table::Table;
 use crate::table::{errors::PersistenceErrors};
+use crate::table::values::Values;
 
 use super::row::PersistenceRow;
 
+/// Reads the rows page by page through the buffer pool, so only the current page
+/// is held and the scan can be stopped without reading the whole table.
 pub struct RowsIterator {
-    rows: Vec<Row>,
+    table: Table,
+    /// Columns which are decoded, the others are NULL in the returned rows.
+    projection: Option<Vec<bool>>,
+    /// The values which don't fit the rows.
+    values: Values,
+    page_count: u64,
+    page_number: u64,
+    page: Option<Arc<Page>>,
+    slot: usize,
+    remaining_rows: u64,
 }
 
 impl RowsIterator {
     pub fn from_table(table: &Table) -> Result<RowsIterator, PersistenceErrors> {
-        let bytes = table.read_table_rows_bytes()?;
-        let mut index = 0;
-        let row_size = table.get_row_size();
-        let mut rows = vec![];
-        while index < bytes.len() {
-            rows.push(Row::from_bytes(
-                bytes[index..index + row_size].to_vec(),
-                &table.columns,
-            ));
-            index += row_size;
-        }
-        Ok(RowsIterator { rows })
+        Ok(RowsIterator {
+            table: table.clone(),
+            projection: None,
+            values: table.open_values().map_err(PersistenceErrors::TableLoading)?,
+            page_count: table.get_page_count().map_err(PersistenceErrors::TableLoading)?,
+            page_number: 0,
+            page: None,
+            slot: 0,
+            remaining_rows: table.get_rows_count().map_err(PersistenceErrors::TableLoading)?,
+        })
+    }
+
+    /// Decode only the columns marked in the projection, one flag for every column of the table.
+    pub fn with_projection(mut self, projection: Vec<bool>) -> RowsIterator {
+        self.projection = Some(projection);
+        self
+    }
+
+    fn stop(&mut self) {
+        self.page = None;
+        self.page_number = self.page_count;
+        self.remaining_rows = 0;
     }
 }
 
 impl Iterator for RowsIterator {
-    type Item = Row;
+    type Item = Result<Row, PersistenceErrors>;
 
+    /// A read error is returned once and ends the iteration, the rows after it aren't read.
     fn next(&mut self) -> Option<Self::Item> {
-        if self.rows.get(0).is_some() {
-            return Some(self.rows.remove(0));
+        loop {
+            let page = match &self.page {
+                Some(page) => page,
+                None if self.page_number < self.page_count => {
+                    self.slot = 0;
+                    match self.table.read_page(self.page_number) {
+                        Ok(page) => self.page.insert(page),
+                        Err(error) => {
+                            self.stop();
+                            return Some(Err(PersistenceErrors::TableLoading(error)));
+                        }
+                    }
+                }
+                None => return None,
+            };
+            if let Some(bytes) = page.record(self.slot) {
+                self.slot += 1;
+                self.remaining_rows = self.remaining_rows.saturating_sub(1);
+                let row = Row::from_bytes_projected(
+                    bytes,
+                    &self.table.columns,
+                    self.projection.as_deref(),
+                    &mut self.values,
+                );
+                if row.is_err() {
+                    self.stop();
+                }
+                return Some(row.map_err(PersistenceErrors::TableLoading));
+            }
+            self.page = None;
+            self.page_number += 1;
         }
-        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining_rows = self.remaining_rows as usize;
+        (remaining_rows, Some(remaining_rows))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::models::db::Data;
     use crate::table::table::tests::{insert_data, insert_row};
 
     #[test]
@@ -54,10 +114,47 @@ mod tests {
         let rows_iterator = RowsIterator::from_table(&table);
         assert!(rows_iterator.is_ok());
         let mut rows_iterator = rows_iterator.unwrap();
-        assert_eq!(rows_iterator.next().unwrap(), row);
-        assert_eq!(rows_iterator.next().unwrap(), row1);
-        assert_eq!(rows_iterator.next().unwrap(), row2);
+        assert_eq!(rows_iterator.next().unwrap().unwrap(), row);
+        assert_eq!(rows_iterator.next().unwrap().unwrap(), row1);
+        assert_eq!(rows_iterator.next().unwrap().unwrap(), row2);
         assert!(rows_iterator.next().is_none());
         assert!(table.drop().is_ok())
     }
+
+    #[test]
+    fn table_iterator_projection() {
+        let (table, _row) = insert_data("Table23", false);
+        insert_row(&table, String::from("Not decoded."), 1);
+        let mut rows_iterator = RowsIterator::from_table(&table)
+            .unwrap()
+            .with_projection(vec![false, true]);
+        assert_eq!(rows_iterator.size_hint(), (2, Some(2)));
+        let row = rows_iterator.next().unwrap().unwrap();
+        assert_eq!(rows_iterator.size_hint(), (1, Some(1)));
+        let row1 = rows_iterator.next().unwrap().unwrap();
+        assert_eq!(rows_iterator.size_hint(), (0, Some(0)));
+        assert!(rows_iterator.next().is_none());
+        assert!(table.drop().is_ok());
+
+        assert_eq!(row.values, vec![Data::NULL, Data::INT(8)]);
+        assert_eq!(row1.values, vec![Data::NULL, Data::INT(1)]);
+    }
+
+    #[test]
+    fn table_iterator_read_error() {
+        let (table, _row) = insert_data("Table34", false);
+        insert_row(&table, String::from("Not read."), 1);
+        // the string of the first row doesn't fit its slot and it's lost
+        std::fs::write(table.table_values_name(), []).unwrap();
+        let mut rows_iterator = RowsIterator::from_table(&table).unwrap();
+        assert!(matches!(rows_iterator.next(), Some(Err(PersistenceErrors::TableLoading(_)))));
+        assert!(rows_iterator.next().is_none());
+
+        let mut altered_table = table.clone();
+        let column = altered_table.columns[1].clone();
+        assert!(altered_table.add_column(column, &Data::NULL).is_err());
+        let rows_count = table.get_rows_count().unwrap();
+        assert!(table.drop().is_ok());
+        assert_eq!(rows_count, 2);
+    }
 }
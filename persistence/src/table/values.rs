@@ -0,0 +1,119 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+
+use crate::wal;
+
+/// The values of a variable size which don't fit the slots of their columns. They are kept
+/// in the values file of the table one after another, the slot holds their position.
+/// The values of the rows about to be written are collected here and written to the file
+/// by the change of the rows, they follow the values already in the file.
+#[derive(Debug, Default)]
+pub struct Values {
+    file_name: Option<String>,
+    file: Option<File>,
+    /// Length of the file, the position of the first collected value.
+    position: u64,
+    appended: Vec<u8>,
+}
+
+impl Values {
+    /// The values of the file, the file of a table created before the values file
+    /// was introduced doesn't exist and it's empty.
+    pub(crate) fn open(file_name: &str) -> io::Result<Values> {
+        let position = match std::fs::metadata(file_name) {
+            Ok(metadata) => metadata.len(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => 0,
+            Err(error) => return Err(error),
+        };
+        Ok(Values {
+            file_name: Some(file_name.to_string()),
+            position,
+            ..Values::default()
+        })
+    }
+
+    /// Collect the value, returns its position in the file.
+    pub(crate) fn push(&mut self, value: &[u8]) -> u64 {
+        let position = self.position + self.appended.len() as u64;
+        self.appended.extend(value);
+        position
+    }
+
+    pub(crate) fn read(&mut self, position: u64, length: usize) -> io::Result<Vec<u8>> {
+        if position >= self.position {
+            let start = (position - self.position) as usize;
+            return self
+                .appended
+                .get(start..start + length)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| io::ErrorKind::UnexpectedEof.into());
+        }
+        let file = match (&mut self.file, &self.file_name) {
+            (Some(file), _) => file,
+            (None, Some(file_name)) => self.file.insert(File::open(file_name)?),
+            (None, None) => return Err(io::ErrorKind::NotFound.into()),
+        };
+        let mut value = vec![0; length];
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(&mut value)?;
+        Ok(value)
+    }
+
+    /// The collected values, which are written to the file by the change of their rows.
+    pub(crate) fn into_appended(self) -> AppendedValues {
+        AppendedValues {
+            position: self.position,
+            bytes: self.appended,
+        }
+    }
+}
+
+/// Values written to the values file from the position on, anything behind them is cut off.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct AppendedValues {
+    pub(crate) position: u64,
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl AppendedValues {
+    pub(crate) fn write(&self, file_name: &str) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(file_name)?;
+        file.set_len(self.position)?;
+        file.seek(SeekFrom::Start(self.position))?;
+        file.write_all(&self.bytes)?;
+        wal::sync_file(&file)
+    }
+}
+
+/// New values file written one value after another, e.g. with only the values of the rows
+/// kept by a delete. It replaces the values file once the rows referring to it are written.
+pub(crate) struct ValuesWriter {
+    writer: BufWriter<File>,
+    position: u64,
+}
+
+impl ValuesWriter {
+    pub(crate) fn create(file_name: &str) -> io::Result<ValuesWriter> {
+        Ok(ValuesWriter {
+            writer: BufWriter::new(File::create(file_name)?),
+            position: 0,
+        })
+    }
+
+    /// Write the value, returns its position in the file.
+    pub(crate) fn push(&mut self, value: &[u8]) -> io::Result<u64> {
+        let position = self.position;
+        self.writer.write_all(value)?;
+        self.position += value.len() as u64;
+        Ok(position)
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        let file = self.writer.into_inner().map_err(|error| error.into_error())?;
+        wal::sync_file(&file)
+    }
+}
@@ -0,0 +1,719 @@
+use std::fs::{read, rename, File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::table::{backup::TableBackup, errors::PersistenceErrors, table::Table, values::AppendedValues};
+
+/// Write-ahead log of the table changes shared by all tables in the working directory.
+/// Every change is recorded before its files are touched and marked as finished afterwards,
+/// the log is emptied whenever no change or transaction is running.
+static LOG: Mutex<WriteAheadLog> = Mutex::new(WriteAheadLog::new("wal.log"));
+static SYNC_ALWAYS: AtomicBool = AtomicBool::new(true);
+
+const CHANGE_RECORD: u8 = 0;
+const FINISHED_RECORD: u8 = 1;
+const TRANSACTION_RECORD: u8 = 2;
+const BACKUP_RECORD: u8 = 3;
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum SyncPolicy {
+    /// The log record reaches the disk before the files are touched and the files
+    /// before the change is marked as finished, so the changes survive a power loss.
+    Always,
+    /// Flushing is left to the operating system, only a crash of the server is survived.
+    Never,
+}
+
+pub fn set_sync_policy(policy: SyncPolicy) {
+    SYNC_ALWAYS.store(policy == SyncPolicy::Always, Ordering::Relaxed);
+}
+
+/// Changes replayed and transactions rolled back by the recovery.
+#[derive(PartialEq, Debug, Default)]
+pub struct Recovery {
+    pub replayed_changes: usize,
+    /// Tables of the transactions which never finished, their backups were put back.
+    pub restored_tables: Vec<String>,
+}
+
+/// Finish the changes interrupted by a crash and roll back the transactions which never
+/// finished, has to be called before the tables are used.
+pub fn recover() -> Result<Recovery, PersistenceErrors> {
+    LOG.lock().unwrap().recover()
+}
+
+/// Log the start of a transaction, the log is kept until the transaction finishes.
+/// Returns the id of the transaction.
+pub fn begin_transaction() -> Result<u64, PersistenceErrors> {
+    LOG.lock().unwrap().append_transaction()
+}
+
+/// Log the backup taken by the transaction, it's restored by the recovery
+/// unless the transaction finishes.
+pub fn log_backup(transaction_id: u64, backup: &TableBackup) -> Result<(), PersistenceErrors> {
+    LOG.lock().unwrap().append_backup(transaction_id, backup)
+}
+
+/// Log the end of the transaction, its backups aren't restored anymore. A commit is logged
+/// before its backups are removed, a rollback after its backups were restored.
+pub fn finish_transaction(transaction_id: u64) -> Result<(), PersistenceErrors> {
+    LOG.lock().unwrap().append_finished(transaction_id)
+}
+
+/// Log the change, apply it to the files and mark it as finished. A failed change is applied
+/// once more the way the recovery would, when that fails as well it stays unfinished
+/// and the later changes of its tables fail until it's applied.
+pub(crate) fn execute(change: Change) -> Result<(), PersistenceErrors> {
+    LOG.lock().unwrap().finish_failed_changes(&change.table_names())?;
+    let id = LOG.lock().unwrap().append_change(&change)?;
+    match change.apply().or_else(|error| change.replay().map_err(|_| error)) {
+        Ok(()) => LOG.lock().unwrap().append_finished(id),
+        Err(error) => {
+            LOG.lock().unwrap().failed_changes.push((id, change));
+            Err(error)
+        }
+    }
+}
+
+/// Write the whole file, flushed to the disk according to the sync policy.
+pub(crate) fn write_file(file_name: &str, bytes: &[u8]) -> io::Result<()> {
+    let mut file = File::create(file_name)?;
+    file.write_all(bytes)?;
+    sync_file(&file)
+}
+
+/// Replace the file at once by the content written by `write`,
+/// it has either the old or the new content after a crash.
+pub(crate) fn replace_file(
+    file_name: &str,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> io::Result<()> {
+    let temporary_file_name = file_name.to_string() + ".tmp";
+    let mut file = File::create(&temporary_file_name)?;
+    write(&mut file)?;
+    sync_file(&file)?;
+    rename(temporary_file_name, file_name)
+}
+
+/// Add the bytes to the end of the file, which is created when it doesn't exist.
+pub(crate) fn append_file(file_name: &str, bytes: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(file_name)?;
+    file.write_all(bytes)?;
+    sync_file(&file)
+}
+
+pub(crate) fn sync_file(file: &File) -> io::Result<()> {
+    if SYNC_ALWAYS.load(Ordering::Relaxed) {
+        file.sync_data()?;
+    }
+    Ok(())
+}
+
+/// Logical change of a table. Applying the change brings the files to the state after
+/// the change, no matter how far the interrupted change got, so it can be replayed.
+/// The values of the rows which don't fit them are written to the values file with the rows,
+/// the changes logged before the values file was introduced end before them.
+#[derive(PartialEq, Debug)]
+pub(crate) enum Change {
+    CreateTable { header: Vec<u8> },
+    DropTable { header: Vec<u8> },
+    /// The rows written after the first `first_row` rows of the table.
+    Insert { header: Vec<u8>, first_row: u64, rows: Vec<u8>, values: Option<AppendedValues> },
+    /// Removal of the rows from the table of `rows_count` rows.
+    Delete { header: Vec<u8>, rows_count: u64, row_numbers: Vec<u64> },
+    Update { header: Vec<u8>, rows: Vec<(u64, Vec<u8>)>, values: Option<AppendedValues> },
+    /// The index of the table was added or removed.
+    SetIndex { header: Vec<u8>, name: String },
+    /// All rows were rewritten in the new layout, as a column was added or dropped.
+    /// The rows and their values are written to the files next to the files of the table
+    /// before the change is logged, the change moves them in place.
+    Rewrite { old_header: Vec<u8>, header: Vec<u8> },
+    /// The table or its column was renamed.
+    Rename { old_header: Vec<u8>, header: Vec<u8> },
+}
+
+impl Change {
+    fn apply(&self) -> Result<(), PersistenceErrors> {
+        match self {
+            Change::CreateTable { header } => Table::from_bytes(header.clone()).create_files(),
+            Change::DropTable { header } => Table::from_bytes(header.clone()).remove_files(),
+            Change::Insert { header, first_row, rows, values } => {
+                Table::from_bytes(header.clone()).write_rows_from(*first_row, rows, values.as_ref())
+            }
+            Change::Delete { header, rows_count, row_numbers } => {
+                Table::from_bytes(header.clone()).remove_rows(*rows_count, row_numbers)
+            }
+            Change::Update { header, rows, values } => {
+                Table::from_bytes(header.clone()).write_rows_in_place(rows, values.as_ref())
+            }
+            Change::SetIndex { header, name } => Table::from_bytes(header.clone()).write_index_change(name),
+            Change::Rewrite { old_header, header } => {
+                Table::from_bytes(header.clone()).rewrite_rows(&Table::from_bytes(old_header.clone()))
+            }
+            Change::Rename { old_header, header } => Table::from_bytes(header.clone())
+                .move_files(&Table::from_bytes(old_header.clone())),
+        }
+    }
+
+    /// Apply the change interrupted by a crash. The update can't tell which rows it
+    /// overwrote already, so the indexes of the table are built again.
+    fn replay(&self) -> Result<(), PersistenceErrors> {
+        self.apply()?;
+        match self {
+            Change::Update { header, .. } => Table::from_bytes(header.clone()).generate_indexes(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Tables whose files are changed.
+    fn table_names(&self) -> Vec<String> {
+        match self {
+            Change::CreateTable { header }
+            | Change::DropTable { header }
+            | Change::Insert { header, .. }
+            | Change::Delete { header, .. }
+            | Change::Update { header, .. }
+            | Change::SetIndex { header, .. } => vec![Table::from_bytes(header.clone()).name],
+            Change::Rewrite { old_header, header } | Change::Rename { old_header, header } => vec![
+                Table::from_bytes(old_header.clone()).name,
+                Table::from_bytes(header.clone()).name,
+            ],
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        match self {
+            Change::CreateTable { header } => {
+                bytes.push(0);
+                put_bytes(&mut bytes, header);
+            }
+            Change::DropTable { header } => {
+                bytes.push(1);
+                put_bytes(&mut bytes, header);
+            }
+            Change::Insert { header, first_row, rows, values } => {
+                bytes.push(2);
+                put_bytes(&mut bytes, header);
+                bytes.extend(first_row.to_be_bytes());
+                put_bytes(&mut bytes, rows);
+                put_values(&mut bytes, values);
+            }
+            Change::Delete { header, rows_count, row_numbers } => {
+                bytes.push(3);
+                put_bytes(&mut bytes, header);
+                bytes.extend(rows_count.to_be_bytes());
+                bytes.extend((row_numbers.len() as u64).to_be_bytes());
+                for row_number in row_numbers {
+                    bytes.extend(row_number.to_be_bytes());
+                }
+            }
+            Change::Update { header, rows, values } => {
+                bytes.push(4);
+                put_bytes(&mut bytes, header);
+                bytes.extend((rows.len() as u64).to_be_bytes());
+                for (row_number, row) in rows {
+                    bytes.extend(row_number.to_be_bytes());
+                    put_bytes(&mut bytes, row);
+                }
+                put_values(&mut bytes, values);
+            }
+            Change::Rewrite { old_header, header } => {
+                bytes.push(9);
+                put_bytes(&mut bytes, old_header);
+                put_bytes(&mut bytes, header);
+            }
+            Change::Rename { old_header, header } => {
+                bytes.push(7);
+                put_bytes(&mut bytes, old_header);
+                put_bytes(&mut bytes, header);
+            }
+            Change::SetIndex { header, name } => {
+                bytes.push(8);
+                put_bytes(&mut bytes, header);
+                put_bytes(&mut bytes, name.as_bytes());
+            }
+        }
+        bytes
+    }
+
+    fn from_bytes(reader: &mut Reader) -> Option<Change> {
+        let change = match reader.u8()? {
+            0 => Change::CreateTable { header: reader.bytes()? },
+            1 => Change::DropTable { header: reader.bytes()? },
+            2 => Change::Insert {
+                header: reader.bytes()?,
+                first_row: reader.u64()?,
+                rows: reader.bytes()?,
+                values: reader.values()?,
+            },
+            3 => {
+                let header = reader.bytes()?;
+                let rows_count = reader.u64()?;
+                let mut row_numbers = vec![];
+                for _ in 0..reader.u64()? {
+                    row_numbers.push(reader.u64()?);
+                }
+                Change::Delete { header, rows_count, row_numbers }
+            }
+            4 => {
+                let header = reader.bytes()?;
+                let mut rows = vec![];
+                for _ in 0..reader.u64()? {
+                    rows.push((reader.u64()?, reader.bytes()?));
+                }
+                Change::Update { header, rows, values: reader.values()? }
+            }
+            7 => Change::Rename {
+                old_header: reader.bytes()?,
+                header: reader.bytes()?,
+            },
+            8 => Change::SetIndex {
+                header: reader.bytes()?,
+                name: reader.string()?,
+            },
+            9 => Change::Rewrite {
+                old_header: reader.bytes()?,
+                header: reader.bytes()?,
+            },
+            _ => return None,
+        };
+        Some(change)
+    }
+}
+
+/// The log consists of records `length, checksum, kind, id, change`. The id of the change
+/// is the position of its record in the log, the finished record contains just the id.
+/// The transaction record holds just its id as well, the backup record the id of its
+/// transaction followed by the table and its copied files.
+struct WriteAheadLog {
+    file_name: &'static str,
+    file: Option<File>,
+    unfinished_count: usize,
+    /// Changes which failed to be applied, they are kept unfinished until they are applied.
+    failed_changes: Vec<(u64, Change)>,
+}
+
+impl WriteAheadLog {
+    const fn new(file_name: &'static str) -> Self {
+        WriteAheadLog {
+            file_name,
+            file: None,
+            unfinished_count: 0,
+            failed_changes: vec![],
+        }
+    }
+
+    /// Apply the failed changes of the tables again, the tables can't be changed until then.
+    fn finish_failed_changes(&mut self, table_names: &[String]) -> Result<(), PersistenceErrors> {
+        while let Some(position) = self
+            .failed_changes
+            .iter()
+            .position(|(_id, change)| change.table_names().iter().any(|name| table_names.contains(name)))
+        {
+            let (id, change) = &self.failed_changes[position];
+            if change.replay().is_err() {
+                return Err(PersistenceErrors::UnfinishedChange(change.table_names()[0].clone()));
+            }
+            let id = *id;
+            self.failed_changes.remove(position);
+            self.append_finished(id)?;
+        }
+        Ok(())
+    }
+
+    fn append_change(&mut self, change: &Change) -> Result<u64, PersistenceErrors> {
+        let id = self.open()?.metadata().map_err(PersistenceErrors::Wal)?.len();
+        let mut payload = vec![CHANGE_RECORD];
+        payload.extend(id.to_be_bytes());
+        payload.extend(change.to_bytes());
+        self.append(&payload)?;
+        self.unfinished_count += 1;
+        Ok(id)
+    }
+
+    fn append_transaction(&mut self) -> Result<u64, PersistenceErrors> {
+        let id = self.open()?.metadata().map_err(PersistenceErrors::Wal)?.len();
+        let mut payload = vec![TRANSACTION_RECORD];
+        payload.extend(id.to_be_bytes());
+        self.append(&payload)?;
+        self.unfinished_count += 1;
+        Ok(id)
+    }
+
+    fn append_backup(&mut self, transaction_id: u64, backup: &TableBackup) -> Result<(), PersistenceErrors> {
+        let mut payload = vec![BACKUP_RECORD];
+        payload.extend(transaction_id.to_be_bytes());
+        put_bytes(&mut payload, backup.table_name().as_bytes());
+        payload.extend((backup.file_names.len() as u64).to_be_bytes());
+        for file_name in &backup.file_names {
+            put_bytes(&mut payload, file_name.as_bytes());
+        }
+        self.append(&payload)
+    }
+
+    fn append_finished(&mut self, id: u64) -> Result<(), PersistenceErrors> {
+        self.unfinished_count -= 1;
+        if self.unfinished_count == 0 {
+            // nothing left to replay
+            return self.truncate();
+        }
+        let mut payload = vec![FINISHED_RECORD];
+        payload.extend(id.to_be_bytes());
+        self.append(&payload)
+    }
+
+    fn append(&mut self, payload: &[u8]) -> Result<(), PersistenceErrors> {
+        let mut record = vec![];
+        record.extend((payload.len() as u64).to_be_bytes());
+        record.extend(checksum(payload).to_be_bytes());
+        record.extend(payload);
+
+        let file = self.open()?;
+        file.write_all(&record).map_err(PersistenceErrors::Wal)?;
+        sync_file(file).map_err(PersistenceErrors::Wal)
+    }
+
+    fn open(&mut self) -> Result<&mut File, PersistenceErrors> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.file_name)
+                .map_err(PersistenceErrors::Wal)?;
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().expect("The log was just opened."))
+    }
+
+    fn truncate(&mut self) -> Result<(), PersistenceErrors> {
+        let file = self.open()?;
+        file.set_len(0).map_err(PersistenceErrors::Wal)?;
+        sync_file(file).map_err(PersistenceErrors::Wal)
+    }
+
+    /// Replay the changes which weren't marked as finished, then restore the backups
+    /// of the unfinished transactions, the latest first. A torn record at the end
+    /// of the log is dropped, its change hasn't touched any file yet.
+    fn recover(&mut self) -> Result<Recovery, PersistenceErrors> {
+        let bytes = match read(self.file_name) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Recovery::default()),
+            Err(error) => return Err(PersistenceErrors::Wal(error)),
+        };
+
+        let mut unfinished: Vec<(u64, Change)> = vec![];
+        let mut transactions: Vec<(u64, Vec<TableBackup>)> = vec![];
+        let mut reader = Reader::new(&bytes);
+        while let Some(payload) = reader.record() {
+            let mut payload = Reader::new(payload);
+            match (payload.u8(), payload.u64()) {
+                (Some(CHANGE_RECORD), Some(id)) => match Change::from_bytes(&mut payload) {
+                    Some(change) => unfinished.push((id, change)),
+                    None => break,
+                },
+                (Some(FINISHED_RECORD), Some(id)) => {
+                    unfinished.retain(|(other, _)| *other != id);
+                    transactions.retain(|(other, _)| *other != id);
+                }
+                (Some(TRANSACTION_RECORD), Some(id)) => transactions.push((id, vec![])),
+                (Some(BACKUP_RECORD), Some(id)) => match payload.backup() {
+                    Some(backup) => {
+                        if let Some((_id, backups)) = transactions.iter_mut().find(|(other, _)| *other == id) {
+                            backups.push(backup);
+                        }
+                    }
+                    None => break,
+                },
+                _ => break,
+            }
+        }
+
+        for (_id, change) in &unfinished {
+            change.replay()?;
+        }
+        let mut restored_tables = vec![];
+        for (_id, backups) in transactions.into_iter().rev() {
+            for backup in backups.into_iter().rev() {
+                restored_tables.push(backup.table_name().to_string());
+                backup.restore()?;
+            }
+        }
+        self.failed_changes.clear();
+        self.truncate()?;
+        Ok(Recovery {
+            replayed_changes: unfinished.len(),
+            restored_tables,
+        })
+    }
+}
+
+fn put_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    bytes.extend((value.len() as u64).to_be_bytes());
+    bytes.extend(value);
+}
+
+fn put_values(bytes: &mut Vec<u8>, values: &Option<AppendedValues>) {
+    if let Some(values) = values {
+        bytes.extend(values.position.to_be_bytes());
+        put_bytes(bytes, &values.bytes);
+    }
+}
+
+/// FNV-1a hash of the record, detects the records written only partially.
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, cursor: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        let end = self.cursor.checked_add(length)?;
+        let bytes = self.bytes.get(self.cursor..end)?;
+        self.cursor = end;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_be_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn bytes(&mut self) -> Option<Vec<u8>> {
+        let length = usize::try_from(self.u64()?).ok()?;
+        Some(self.take(length)?.to_vec())
+    }
+
+    /// The values of the change, the change without them ends before them.
+    fn values(&mut self) -> Option<Option<AppendedValues>> {
+        if self.cursor == self.bytes.len() {
+            return Some(None);
+        }
+        Some(Some(AppendedValues {
+            position: self.u64()?,
+            bytes: self.bytes()?,
+        }))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?).ok()
+    }
+
+    fn backup(&mut self) -> Option<TableBackup> {
+        let table_name = self.string()?;
+        let mut file_names = vec![];
+        for _ in 0..self.u64()? {
+            file_names.push(self.string()?);
+        }
+        Some(TableBackup { table_name, file_names })
+    }
+
+    /// Payload of the next record, None at the end of the log or for a damaged record.
+    fn record(&mut self) -> Option<&'a [u8]> {
+        let length = usize::try_from(self.u64()?).ok()?;
+        let checksum_value = self.u64()?;
+        let payload = self.take(length)?;
+        (checksum(payload) == checksum_value).then_some(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{remove_file, OpenOptions};
+    use std::io::Write;
+    use std::path::Path;
+
+    use common::models::db::{Data, Row};
+
+    use super::*;
+    use crate::table::row::PersistenceRow;
+    use crate::table::table::tests::{insert_data, insert_row};
+    use crate::table::table_iterator::RowsIterator;
+
+    #[test]
+    fn change_to_and_from_bytes() {
+        let changes = vec![
+            Change::CreateTable { header: vec![1, 2] },
+            Change::Insert {
+                header: vec![3],
+                first_row: 7,
+                rows: vec![4, 5, 6],
+                values: Some(AppendedValues {
+                    position: 9,
+                    bytes: vec![1, 2],
+                }),
+            },
+            // logged before the values file was introduced
+            Change::Insert {
+                header: vec![3],
+                first_row: 7,
+                rows: vec![4, 5, 6],
+                values: None,
+            },
+            Change::Delete {
+                header: vec![],
+                rows_count: 3,
+                row_numbers: vec![0, 2],
+            },
+            Change::Update {
+                header: vec![8],
+                rows: vec![(1, vec![9]), (2, vec![])],
+                values: Some(AppendedValues {
+                    position: 0,
+                    bytes: vec![],
+                }),
+            },
+            Change::Rewrite {
+                old_header: vec![1],
+                header: vec![2],
+            },
+            Change::Rename {
+                old_header: vec![10],
+                header: vec![11],
+            },
+            Change::SetIndex {
+                header: vec![12],
+                name: String::from("id"),
+            },
+        ];
+        for change in changes {
+            let bytes = change.to_bytes();
+            assert_eq!(Change::from_bytes(&mut Reader::new(&bytes)), Some(change));
+        }
+    }
+
+    #[test]
+    fn recover_unfinished_insert() {
+        let (table, row) = insert_data("Table19", true);
+        let new_row = Row {
+            values: vec![Data::STRING(String::from("Replayed")), Data::INT(3)],
+        };
+        let mut values = table.open_values().unwrap();
+        let mut log = WriteAheadLog::new("Table19_wal.log");
+        log.append_change(&Change::Insert {
+            header: table.to_bytes(),
+            first_row: 1,
+            rows: new_row.to_bytes(&table.columns, &mut values),
+            values: Some(values.into_appended()),
+        })
+        .unwrap();
+        // the record of the next change was torn by the crash
+        let mut file = OpenOptions::new().append(true).open(log.file_name).unwrap();
+        file.write_all(&[0, 0, 0, 0, 0, 0, 0, 50, 1, 2, 3]).unwrap();
+
+        let replayed = WriteAheadLog::new("Table19_wal.log").recover();
+        let replayed_again = WriteAheadLog::new("Table19_wal.log").recover();
+        let rows: Vec<Row> = RowsIterator::from_table(&table).unwrap().map(Result::unwrap).collect();
+        let index = table.get_index("Id").unwrap();
+        assert!(table.drop().is_ok());
+        assert!(remove_file(log.file_name).is_ok());
+        assert_eq!(replayed.unwrap().replayed_changes, 1);
+        assert_eq!(replayed_again.unwrap().replayed_changes, 0);
+        assert_eq!(rows, vec![row, new_row]);
+        assert_eq!(index.get_rows(&[Data::INT(3)]), vec![1]);
+    }
+
+    #[test]
+    fn recover_applied_delete() {
+        let (table, row) = insert_data("Table20", false);
+        insert_row(&table, String::from("Deleted before the crash."), 2);
+        let mut log = WriteAheadLog::new("Table20_wal.log");
+        let change = Change::Delete {
+            header: table.to_bytes(),
+            rows_count: 2,
+            row_numbers: vec![1],
+        };
+        let id = log.append_change(&change).unwrap();
+        change.apply().unwrap();
+        let finished_change = Change::Delete {
+            header: table.to_bytes(),
+            rows_count: 1,
+            row_numbers: vec![0],
+        };
+        let finished_id = log.append_change(&finished_change).unwrap();
+        log.append_finished(finished_id).unwrap();
+
+        let replayed = WriteAheadLog::new("Table20_wal.log").recover();
+        let rows: Vec<Row> = RowsIterator::from_table(&table).unwrap().map(Result::unwrap).collect();
+        assert!(table.drop().is_ok());
+        assert!(remove_file(log.file_name).is_ok());
+        assert_ne!(id, finished_id);
+        assert_eq!(replayed.unwrap().replayed_changes, 1);
+        assert_eq!(rows, vec![row]);
+    }
+
+    #[test]
+    fn recover_unfinished_transaction() {
+        let (table, row) = insert_data("Table32", false);
+        let mut log = WriteAheadLog::new("Table32_wal.log");
+        let id = log.append_transaction().unwrap();
+        let backup = TableBackup::create(&table.name).unwrap();
+        log.append_backup(id, &backup).unwrap();
+        insert_row(&table, String::from("Rolled back by the recovery."), 2);
+        let committed_id = log.append_transaction().unwrap();
+        log.append_backup(committed_id, &TableBackup::create("Table33").unwrap()).unwrap();
+        log.append_finished(committed_id).unwrap();
+
+        let recovery = WriteAheadLog::new("Table32_wal.log").recover();
+        let rows: Vec<Row> = RowsIterator::from_table(&table).unwrap().map(Result::unwrap).collect();
+        let backups_exist = table
+            .file_names()
+            .iter()
+            .any(|file_name| Path::new(&(file_name.clone() + "#backup")).exists());
+        assert!(table.drop().is_ok());
+        assert!(remove_file(log.file_name).is_ok());
+        assert_eq!(
+            recovery.unwrap(),
+            Recovery {
+                replayed_changes: 0,
+                restored_tables: vec![String::from("Table32")],
+            }
+        );
+        assert_eq!(rows, vec![row]);
+        assert!(!backups_exist);
+    }
+
+    #[test]
+    fn failed_change_kept_unfinished() {
+        let (table, row) = insert_data("Table35", true);
+        let index_file_name = table.file_names().into_iter().find(|name| name.ends_with("_index")).unwrap();
+        // neither the change nor its replay can write the index
+        std::fs::remove_file(&index_file_name).unwrap();
+        std::fs::create_dir(&index_file_name).unwrap();
+        let failed_row = Row {
+            values: vec![Data::STRING(String::from("Indexed later.")), Data::INT(2)],
+        };
+        let failed_insert = table.insert_row(&failed_row);
+        let blocked_insert = table.insert_row(&row);
+        let rows_count = table.get_rows_count().unwrap();
+
+        std::fs::remove_dir(&index_file_name).unwrap();
+        let last_row = insert_row(&table, String::from("Applied after the failed change."), 3);
+        let rows: Vec<Row> = RowsIterator::from_table(&table).unwrap().map(Result::unwrap).collect();
+        let index = table.get_index("Id").unwrap();
+        assert!(table.drop().is_ok());
+        assert!(failed_insert.is_err());
+        assert!(matches!(blocked_insert, Err(PersistenceErrors::UnfinishedChange(name)) if name == "Table35"));
+        assert_eq!(rows_count, 2);
+        assert_eq!(rows, vec![row, failed_row, last_row]);
+        assert_eq!(index.get_rows(&[Data::INT(2)]), vec![1]);
+        assert_eq!(index.get_rows(&[Data::INT(3)]), vec![2]);
+    }
+}
@@ -1,11 +1,9 @@
-mod parser;
-
 use std::collections::HashMap;
 
-use parser::expression_tree::parse_tree;
-use parser::expression_tree_eval::evaluate_binary_node;
-use parser::lexer::lex;
-use parser::query_parser::parse;
+use query_parser::parser::expression_tree::parse_tree;
+use query_parser::parser::expression_tree_eval::evaluate_binary_node;
+use query_parser::parser::lexer::lex;
+use query_parser::parser::query_parser::parse;
 
 fn main() {
     dbg!(parse("SELECT *, 1, id FROM my_table WHERE x = 2").unwrap());
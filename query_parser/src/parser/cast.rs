@@ -0,0 +1,383 @@
+//! Conversions of the values between the types. The assignment converts the values stored
+//! by INSERT, UPDATE and DEFAULT to the type of the column, CAST converts them explicitly.
+//! NULL is converted to any type.
+//!
+//! | from \ to                  | assignment                         | CAST only                          |
+//! |----------------------------|------------------------------------|------------------------------------|
+//! | INT                        | BIGINT, DECIMAL, FLOAT             | BOOLEAN, STRING                    |
+//! | BIGINT                     | INT (checked), DECIMAL, FLOAT      | BOOLEAN, STRING                    |
+//! | DECIMAL                    | DECIMAL (rounded), FLOAT           | INT, BIGINT (rounded), STRING      |
+//! | FLOAT                      | DECIMAL (rounded)                  | INT, BIGINT (rounded), STRING      |
+//! | BOOLEAN                    |                                    | INT, BIGINT (1, 0), STRING         |
+//! | STRING                     | DECIMAL, DATE, TIMESTAMP, UUID     | INT, BIGINT, FLOAT, BOOLEAN, BLOB  |
+//! | DATE                       | TIMESTAMP (midnight)               | STRING                             |
+//! | TIMESTAMP                  |                                    | DATE (the day), STRING             |
+//! | UUID, BLOB                 |                                    | STRING                             |
+//!
+//! The numbers are rounded half away from zero, the values out of range of the type are errors.
+//! The strings are parsed without the surrounding whitespace and the invalid ones are errors,
+//! BOOLEAN is written as `true`, `t`, `yes`, `y`, `on`, `1` or `false`, `f`, `no`, `n`, `off`, `0`.
+//! CAST to VARCHAR(n) cuts the string to n characters, the assignment leaves the length check to the column.
+//! In the comparisons, a string is converted to the DATE, TIMESTAMP, UUID or DECIMAL compared with it.
+
+use common::models::{
+    datetime::{
+        date_from_string, date_string, timestamp_from_string, timestamp_string, MICROS_PER_DAY,
+    },
+    db::{hex_string, uuid_from_string, uuid_string, DataType},
+    decimal::Decimal,
+};
+
+use super::{errors::ParseError, expression_tree_eval::NodeValue};
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Coercion {
+    /// The value stored in the column.
+    Assignment,
+    /// CAST of the query.
+    Explicit,
+}
+
+/// Convert the value to the data type, see the table of the conversions above.
+pub fn cast_value(
+    value: NodeValue,
+    data_type: &DataType,
+    coercion: Coercion,
+) -> Result<NodeValue, ParseError> {
+    let explicit = coercion == Coercion::Explicit;
+    let invalid_cast = |value: &NodeValue| {
+        ParseError::InvalidCast(value.type_name().into(), data_type.to_string())
+    };
+    let out_of_range = || ParseError::OutOfRange(data_type.to_string());
+    let invalid_syntax =
+        |string: &str| ParseError::InvalidTypedLiteral(data_type.to_string(), string.to_string());
+    if value == NodeValue::Null {
+        return Ok(NodeValue::Null);
+    }
+
+    let value = match *data_type {
+        DataType::INT => match value {
+            NodeValue::Int(_) => value,
+            NodeValue::BigInt(number) => {
+                NodeValue::Int(i32::try_from(number).map_err(|_| out_of_range())?)
+            }
+            _ if explicit => {
+                let number = integer_value(&value, data_type)?;
+                NodeValue::Int(i32::try_from(number).map_err(|_| out_of_range())?)
+            }
+            _ => return Err(invalid_cast(&value)),
+        },
+        DataType::BIGINT => match value {
+            NodeValue::BigInt(_) => value,
+            NodeValue::Int(number) => NodeValue::BigInt(number as i64),
+            _ if explicit => NodeValue::BigInt(integer_value(&value, data_type)?),
+            _ => return Err(invalid_cast(&value)),
+        },
+        DataType::DECIMAL { precision, scale } => {
+            let decimal = match &value {
+                NodeValue::Int(number) => Decimal::from_int(*number as i64),
+                NodeValue::BigInt(number) => Decimal::from_int(*number),
+                NodeValue::Decimal(decimal) => *decimal,
+                NodeValue::Float(float) => Decimal::from_f64(*float).ok_or_else(out_of_range)?,
+                NodeValue::String(string) => {
+                    Decimal::parse(string.trim()).ok_or_else(|| invalid_syntax(string))?
+                }
+                _ => return Err(invalid_cast(&value)),
+            };
+            match decimal.rescale(scale as u32) {
+                Some(decimal) if decimal.precision() <= precision as u32 => {
+                    NodeValue::Decimal(decimal)
+                }
+                _ => return Err(out_of_range()),
+            }
+        }
+        DataType::FLOAT => match value {
+            NodeValue::Float(_) => value,
+            NodeValue::Int(number) => NodeValue::Float(number as f64),
+            NodeValue::BigInt(number) => NodeValue::Float(number as f64),
+            NodeValue::Decimal(decimal) => NodeValue::Float(decimal.to_f64()),
+            NodeValue::String(string) if explicit => match string.trim().parse() {
+                Ok(float) => NodeValue::Float(float),
+                Err(_) => return Err(invalid_syntax(&string)),
+            },
+            _ => return Err(invalid_cast(&value)),
+        },
+        DataType::BOOLEAN => match value {
+            NodeValue::Bool(_) => value,
+            NodeValue::Int(number) if explicit => NodeValue::Bool(number != 0),
+            NodeValue::BigInt(number) if explicit => NodeValue::Bool(number != 0),
+            NodeValue::String(string) if explicit => match string.trim().to_lowercase().as_str() {
+                "true" | "t" | "yes" | "y" | "on" | "1" => NodeValue::Bool(true),
+                "false" | "f" | "no" | "n" | "off" | "0" => NodeValue::Bool(false),
+                _ => return Err(invalid_syntax(&string)),
+            },
+            _ => return Err(invalid_cast(&value)),
+        },
+        DataType::STRING { .. } | DataType::TEXT => {
+            let string = match value {
+                NodeValue::String(string) => string,
+                _ if explicit => value_string(&value),
+                _ => return Err(invalid_cast(&value)),
+            };
+            match *data_type {
+                DataType::STRING { size } if explicit => {
+                    NodeValue::String(string.chars().take(size as usize).collect())
+                }
+                _ => NodeValue::String(string),
+            }
+        }
+        DataType::BLOB => match value {
+            NodeValue::Blob(_) => value,
+            NodeValue::String(string) if explicit => NodeValue::Blob(string.into_bytes()),
+            _ => return Err(invalid_cast(&value)),
+        },
+        DataType::DATE => match value {
+            NodeValue::Date(_) => value,
+            NodeValue::String(string) => match date_from_string(string.trim()) {
+                Some(days) => NodeValue::Date(days),
+                None => return Err(invalid_syntax(&string)),
+            },
+            NodeValue::Timestamp(micros) if explicit => {
+                NodeValue::Date(micros.div_euclid(MICROS_PER_DAY) as i32)
+            }
+            _ => return Err(invalid_cast(&value)),
+        },
+        DataType::TIMESTAMP => match value {
+            NodeValue::Timestamp(_) => value,
+            NodeValue::Date(days) => NodeValue::Timestamp(days as i64 * MICROS_PER_DAY),
+            NodeValue::String(string) => match timestamp_from_string(string.trim()) {
+                Some(micros) => NodeValue::Timestamp(micros),
+                None => return Err(invalid_syntax(&string)),
+            },
+            _ => return Err(invalid_cast(&value)),
+        },
+        DataType::UUID => match value {
+            NodeValue::Uuid(_) => value,
+            NodeValue::String(string) => match uuid_from_string(string.trim()) {
+                Some(uuid) => NodeValue::Uuid(uuid),
+                None => return Err(invalid_syntax(&string)),
+            },
+            _ => return Err(invalid_cast(&value)),
+        },
+    };
+    Ok(value)
+}
+
+/// The string compared with a value of the data type, converted to that type. None when the strings
+/// aren't converted to the type. DECIMAL is parsed without rounding to any scale.
+pub fn compared_string(
+    string: &str,
+    data_type: &DataType,
+) -> Option<Result<NodeValue, ParseError>> {
+    match data_type {
+        DataType::DECIMAL { .. } => Some(match Decimal::parse(string.trim()) {
+            Some(decimal) => Ok(NodeValue::Decimal(decimal)),
+            None => Err(ParseError::InvalidTypedLiteral(
+                data_type.to_string(),
+                string.to_string(),
+            )),
+        }),
+        DataType::DATE | DataType::TIMESTAMP | DataType::UUID => Some(cast_value(
+            NodeValue::String(string.to_string()),
+            data_type,
+            Coercion::Assignment,
+        )),
+        _ => None,
+    }
+}
+
+/// The text of the value, as the value is written in the query, BLOB as `\x0a1b`.
+pub fn value_string(value: &NodeValue) -> String {
+    match value {
+        NodeValue::Bool(bool) => bool.to_string(),
+        NodeValue::String(string) => string.clone(),
+        NodeValue::Int(number) => number.to_string(),
+        NodeValue::Float(float) => float.to_string(),
+        NodeValue::Blob(bytes) => hex_string(bytes),
+        NodeValue::BigInt(number) => number.to_string(),
+        NodeValue::Decimal(decimal) => decimal.to_string(),
+        NodeValue::Date(days) => date_string(*days),
+        NodeValue::Timestamp(micros) => timestamp_string(*micros),
+        NodeValue::Uuid(uuid) => uuid_string(*uuid),
+        NodeValue::Null => "null".to_string(),
+    }
+}
+
+/// The integer of CAST, FLOAT and DECIMAL are rounded half away from zero.
+fn integer_value(value: &NodeValue, data_type: &DataType) -> Result<i64, ParseError> {
+    let out_of_range = || ParseError::OutOfRange(data_type.to_string());
+    match value {
+        NodeValue::Int(number) => Ok(*number as i64),
+        NodeValue::BigInt(number) => Ok(*number),
+        NodeValue::Bool(bool) => Ok(*bool as i64),
+        // i64::MAX as f64 is 2^63, which is out of range
+        NodeValue::Float(float) => match float.round() {
+            float if float >= i64::MIN as f64 && float < i64::MAX as f64 => Ok(float as i64),
+            _ => Err(out_of_range()),
+        },
+        NodeValue::Decimal(decimal) => decimal
+            .rescale(0)
+            .and_then(|decimal| i64::try_from(decimal.mantissa).ok())
+            .ok_or_else(out_of_range),
+        NodeValue::String(string) => match string.trim().parse::<i64>() {
+            Ok(number) => Ok(number),
+            // the integer too long for i64 is out of range, not invalid
+            Err(_) if Decimal::parse(string.trim()).is_some_and(|decimal| decimal.scale == 0) => {
+                Err(out_of_range())
+            }
+            Err(_) => Err(ParseError::InvalidTypedLiteral(
+                data_type.to_string(),
+                string.clone(),
+            )),
+        },
+        _ => Err(ParseError::InvalidCast(
+            value.type_name().into(),
+            data_type.to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cast(value: NodeValue, data_type: DataType) -> Result<NodeValue, ParseError> {
+        cast_value(value, &data_type, Coercion::Explicit)
+    }
+
+    #[test]
+    fn test_assignment() {
+        let assign = |value, data_type| cast_value(value, &data_type, Coercion::Assignment);
+        assert_eq!(
+            assign(NodeValue::Int(3), DataType::FLOAT).unwrap(),
+            NodeValue::Float(3.0)
+        );
+        assert_eq!(
+            assign(NodeValue::Int(3), DataType::BIGINT).unwrap(),
+            NodeValue::BigInt(3)
+        );
+        assert!(matches!(
+            assign(NodeValue::BigInt(1 << 40), DataType::INT),
+            Err(ParseError::OutOfRange(_))
+        ));
+        assert_eq!(
+            assign(
+                NodeValue::Float(1.005),
+                DataType::DECIMAL {
+                    precision: 5,
+                    scale: 2
+                }
+            )
+            .unwrap(),
+            NodeValue::Decimal(Decimal::new(101, 2))
+        );
+        assert_eq!(
+            assign(NodeValue::Null, DataType::UUID).unwrap(),
+            NodeValue::Null
+        );
+        // the conversions losing the value are explicit
+        assert!(matches!(
+            assign(NodeValue::Float(1.5), DataType::INT),
+            Err(ParseError::InvalidCast(..))
+        ));
+        assert!(matches!(
+            assign(NodeValue::String("3".into()), DataType::INT),
+            Err(ParseError::InvalidCast(..))
+        ));
+        assert!(matches!(
+            assign(NodeValue::Int(3), DataType::TEXT),
+            Err(ParseError::InvalidCast(..))
+        ));
+    }
+
+    #[test]
+    fn test_cast_numbers() {
+        assert_eq!(
+            cast(NodeValue::Float(2.5), DataType::INT).unwrap(),
+            NodeValue::Int(3)
+        );
+        assert_eq!(
+            cast(NodeValue::Float(-2.5), DataType::BIGINT).unwrap(),
+            NodeValue::BigInt(-3)
+        );
+        assert_eq!(
+            cast(NodeValue::Decimal(Decimal::new(-1250, 3)), DataType::INT).unwrap(),
+            NodeValue::Int(-1)
+        );
+        assert_eq!(
+            cast(NodeValue::String(" 42 ".into()), DataType::INT).unwrap(),
+            NodeValue::Int(42)
+        );
+        assert_eq!(
+            cast(NodeValue::String("1e3".into()), DataType::FLOAT).unwrap(),
+            NodeValue::Float(1000.0)
+        );
+        assert!(matches!(
+            cast(NodeValue::String("4.2".into()), DataType::INT),
+            Err(ParseError::InvalidTypedLiteral(..))
+        ));
+        assert!(matches!(
+            cast(NodeValue::String("3000000000".into()), DataType::INT),
+            Err(ParseError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            cast(
+                NodeValue::String("99999999999999999999".into()),
+                DataType::BIGINT
+            ),
+            Err(ParseError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            cast(NodeValue::Float(f64::NAN), DataType::INT),
+            Err(ParseError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            cast(NodeValue::Date(0), DataType::INT),
+            Err(ParseError::InvalidCast(..))
+        ));
+    }
+
+    #[test]
+    fn test_cast_bool_and_string() {
+        assert_eq!(
+            cast(NodeValue::Bool(true), DataType::INT).unwrap(),
+            NodeValue::Int(1)
+        );
+        assert_eq!(
+            cast(NodeValue::Int(-7), DataType::BOOLEAN).unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            cast(NodeValue::String("No".into()), DataType::BOOLEAN).unwrap(),
+            NodeValue::Bool(false)
+        );
+        assert!(matches!(
+            cast(NodeValue::String("maybe".into()), DataType::BOOLEAN),
+            Err(ParseError::InvalidTypedLiteral(..))
+        ));
+        assert_eq!(
+            cast(NodeValue::Bool(false), DataType::TEXT).unwrap(),
+            NodeValue::String("false".into())
+        );
+        assert_eq!(
+            cast(
+                NodeValue::Decimal(Decimal::new(1050, 2)),
+                DataType::STRING { size: 4 }
+            )
+            .unwrap(),
+            NodeValue::String("10.5".into())
+        );
+        assert_eq!(
+            cast(NodeValue::Timestamp(MICROS_PER_DAY + 1), DataType::DATE).unwrap(),
+            NodeValue::Date(1)
+        );
+        assert_eq!(
+            cast(NodeValue::Blob(vec![0x0a, 0xff]), DataType::TEXT).unwrap(),
+            NodeValue::String("\\x0aff".into())
+        );
+        assert_eq!(
+            cast(NodeValue::String("ab".into()), DataType::BLOB).unwrap(),
+            NodeValue::Blob(vec![0x61, 0x62])
+        );
+    }
+}
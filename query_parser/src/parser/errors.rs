@@ -10,6 +10,10 @@ pub enum ParseError {
     InvalidIdentifier(char, String),
     #[error("unfinished string literal {0}")]
     UnfinishedStringLiteral(String),
+    #[error("invalid blob literal x'{0}', two hex digits per byte are expected")]
+    InvalidBlobLiteral(String),
+    #[error("invalid input syntax for type {0}: '{1}'")]
+    InvalidTypedLiteral(String, String),
     #[error("unexpected query token - expected <{0}>, got {1:?}")]
     UnexpectedToken(String, LexerToken),
     #[error("unexpected query ending")]
@@ -25,6 +29,20 @@ pub enum ParseError {
     InvalidType(String, NodeValue),
     #[error("identifier {0} not found")]
     IdentifierNotFound(String),
+    #[error("unknown function {0}")]
+    UnknownFunction(String),
+    #[error("function {0} does not take {1} arguments")]
+    InvalidArgumentCount(String, usize),
+    #[error("invalid argument of function {0}: {1:?}")]
+    InvalidArgument(String, NodeValue),
+    #[error("aggregate function {0} is not allowed here")]
+    MisplacedAggregate(String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("value out of range for type {0}")]
+    OutOfRange(String),
+    #[error("cannot cast type {0} to {1}")]
+    InvalidCast(String, String),
 }
 
 pub type ParseResult<T> = Result<T, ParseError>;
@@ -1,6 +1,128 @@
-use super::{errors::ParseError, lexer::LexerToken};
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+use super::{errors::ParseError, lexer::LexerToken, query_parser::parse_data_type};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "avg" => Some(AggregateFunction::Avg),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateFunction::Count => write!(f, "count"),
+            AggregateFunction::Sum => write!(f, "sum"),
+            AggregateFunction::Avg => write!(f, "avg"),
+            AggregateFunction::Min => write!(f, "min"),
+            AggregateFunction::Max => write!(f, "max"),
+        }
+    }
+}
+
+/// The built-in scalar functions, evaluated by expression_tree_eval.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ScalarFunction {
+    Upper,
+    Lower,
+    Length,
+    Substr,
+    Trim,
+    Replace,
+    Concat,
+    Abs,
+    Round,
+    Floor,
+    Ceil,
+    Power,
+    Sqrt,
+    Coalesce,
+    NullIf,
+    IfNull,
+}
+
+impl ScalarFunction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "upper" => Some(ScalarFunction::Upper),
+            "lower" => Some(ScalarFunction::Lower),
+            "length" => Some(ScalarFunction::Length),
+            "substr" => Some(ScalarFunction::Substr),
+            "trim" => Some(ScalarFunction::Trim),
+            "replace" => Some(ScalarFunction::Replace),
+            "concat" => Some(ScalarFunction::Concat),
+            "abs" => Some(ScalarFunction::Abs),
+            "round" => Some(ScalarFunction::Round),
+            "floor" => Some(ScalarFunction::Floor),
+            "ceil" => Some(ScalarFunction::Ceil),
+            "power" => Some(ScalarFunction::Power),
+            "sqrt" => Some(ScalarFunction::Sqrt),
+            "coalesce" => Some(ScalarFunction::Coalesce),
+            "nullif" => Some(ScalarFunction::NullIf),
+            "ifnull" => Some(ScalarFunction::IfNull),
+            _ => None,
+        }
+    }
+
+    /// The least and the most count of the arguments, None when there's no limit.
+    pub fn arguments_count(&self) -> (usize, Option<usize>) {
+        match self {
+            ScalarFunction::Upper
+            | ScalarFunction::Lower
+            | ScalarFunction::Length
+            | ScalarFunction::Abs
+            | ScalarFunction::Floor
+            | ScalarFunction::Ceil
+            | ScalarFunction::Sqrt => (1, Some(1)),
+            ScalarFunction::Trim | ScalarFunction::Round => (1, Some(2)),
+            ScalarFunction::Substr => (2, Some(3)),
+            ScalarFunction::Replace => (3, Some(3)),
+            ScalarFunction::Power | ScalarFunction::NullIf | ScalarFunction::IfNull => (2, Some(2)),
+            ScalarFunction::Concat | ScalarFunction::Coalesce => (1, None),
+        }
+    }
+}
+
+impl fmt::Display for ScalarFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalarFunction::Upper => write!(f, "upper"),
+            ScalarFunction::Lower => write!(f, "lower"),
+            ScalarFunction::Length => write!(f, "length"),
+            ScalarFunction::Substr => write!(f, "substr"),
+            ScalarFunction::Trim => write!(f, "trim"),
+            ScalarFunction::Replace => write!(f, "replace"),
+            ScalarFunction::Concat => write!(f, "concat"),
+            ScalarFunction::Abs => write!(f, "abs"),
+            ScalarFunction::Round => write!(f, "round"),
+            ScalarFunction::Floor => write!(f, "floor"),
+            ScalarFunction::Ceil => write!(f, "ceil"),
+            ScalarFunction::Power => write!(f, "power"),
+            ScalarFunction::Sqrt => write!(f, "sqrt"),
+            ScalarFunction::Coalesce => write!(f, "coalesce"),
+            ScalarFunction::NullIf => write!(f, "nullif"),
+            ScalarFunction::IfNull => write!(f, "ifnull"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Node {
     Leaf(LexerToken),
     Binary {
@@ -12,6 +134,41 @@ pub enum Node {
         op: LexerToken,
         node: Box<Node>,
     },
+    /// `argument` is None for `count(*)`
+    Aggregate {
+        function: AggregateFunction,
+        argument: Option<Box<Node>>,
+    },
+    /// `node IS NULL`, the negated predicates are wrapped in the unary NOT
+    IsNull(Box<Node>),
+    /// `node IN (list)`
+    In {
+        node: Box<Node>,
+        list: Vec<Node>,
+    },
+    /// `node BETWEEN low AND high`
+    Between {
+        node: Box<Node>,
+        low: Box<Node>,
+        high: Box<Node>,
+    },
+    /// `CAST(node AS data_type)` or `node::data_type`, the data type is named as by the query parser
+    Cast {
+        node: Box<Node>,
+        data_type: String,
+    },
+    /// Call of the built-in scalar function
+    Call {
+        function: ScalarFunction,
+        arguments: Vec<Node>,
+    },
+    /// `CASE [operand] WHEN value THEN result ... [ELSE result] END`, the WHEN values are compared
+    /// with the operand, without the operand they are the conditions
+    Case {
+        operand: Option<Box<Node>>,
+        branches: Vec<(Node, Node)>,
+        else_result: Option<Box<Node>>,
+    },
 }
 
 #[allow(dead_code)]
@@ -31,25 +188,276 @@ impl Node {
         }
     }
 
+    /// Qualified identifiers (`table.column`) are collected as they are written,
+    /// use `split_qualified_identifier` to get their parts.
     pub fn collect_identifiers(&self, identifiers: &mut Vec<String>) {
         match self {
             Node::Leaf(LexerToken::Identifier(identifier)) => identifiers.push(identifier.clone()),
-            Node::Leaf(_) => {}
-            Node::Binary { left, right, .. } => {
-                left.collect_identifiers(identifiers);
-                right.collect_identifiers(identifiers);
+            _ => {
+                for child in self.children() {
+                    child.collect_identifiers(identifiers);
+                }
+            }
+        }
+    }
+
+    /// The direct sub-expressions of the node.
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Node::Leaf(_) => Vec::new(),
+            Node::Binary { left, right, .. } => vec![left, right],
+            Node::Unary { node, .. } | Node::IsNull(node) | Node::Cast { node, .. } => vec![node],
+            Node::Aggregate { argument, .. } => argument.iter().map(|node| node.as_ref()).collect(),
+            Node::In { node, list } => {
+                let mut children = vec![node.as_ref()];
+                children.extend(list);
+                children
+            }
+            Node::Between { node, low, high } => vec![node, low, high],
+            Node::Call { arguments, .. } => arguments.iter().collect(),
+            Node::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                let mut children: Vec<&Node> = operand.iter().map(|node| node.as_ref()).collect();
+                for (value, result) in branches {
+                    children.push(value);
+                    children.push(result);
+                }
+                children.extend(else_result.iter().map(|node| node.as_ref()));
+                children
+            }
+        }
+    }
+
+    /// Copy of the tree with every identifier replaced by the result of `map`.
+    pub fn try_map_identifiers<E>(
+        &self,
+        map: &mut impl FnMut(&str) -> Result<String, E>,
+    ) -> Result<Node, E> {
+        let node = match self {
+            Node::Leaf(LexerToken::Identifier(identifier)) => {
+                Node::Leaf(LexerToken::Identifier(map(identifier)?))
+            }
+            Node::Leaf(token) => Node::Leaf(token.clone()),
+            Node::Binary { left, op, right } => Node::Binary {
+                left: Box::new(left.try_map_identifiers(map)?),
+                op: op.clone(),
+                right: Box::new(right.try_map_identifiers(map)?),
+            },
+            Node::Unary { op, node } => Node::Unary {
+                op: op.clone(),
+                node: Box::new(node.try_map_identifiers(map)?),
+            },
+            Node::Aggregate { function, argument } => Node::Aggregate {
+                function: *function,
+                argument: match argument {
+                    Some(argument) => Some(Box::new(argument.try_map_identifiers(map)?)),
+                    None => None,
+                },
+            },
+            Node::IsNull(node) => Node::IsNull(Box::new(node.try_map_identifiers(map)?)),
+            Node::In { node, list } => Node::In {
+                node: Box::new(node.try_map_identifiers(map)?),
+                list: list
+                    .iter()
+                    .map(|item| item.try_map_identifiers(map))
+                    .collect::<Result<_, _>>()?,
+            },
+            Node::Between { node, low, high } => Node::Between {
+                node: Box::new(node.try_map_identifiers(map)?),
+                low: Box::new(low.try_map_identifiers(map)?),
+                high: Box::new(high.try_map_identifiers(map)?),
+            },
+            Node::Cast { node, data_type } => Node::Cast {
+                node: Box::new(node.try_map_identifiers(map)?),
+                data_type: data_type.clone(),
+            },
+            Node::Call { function, arguments } => Node::Call {
+                function: *function,
+                arguments: arguments
+                    .iter()
+                    .map(|argument| argument.try_map_identifiers(map))
+                    .collect::<Result<_, _>>()?,
+            },
+            Node::Case {
+                operand,
+                branches,
+                else_result,
+            } => Node::Case {
+                operand: match operand {
+                    Some(operand) => Some(Box::new(operand.try_map_identifiers(map)?)),
+                    None => None,
+                },
+                branches: branches
+                    .iter()
+                    .map(|(value, result)| Ok((value.try_map_identifiers(map)?, result.try_map_identifiers(map)?)))
+                    .collect::<Result<_, _>>()?,
+                else_result: match else_result {
+                    Some(else_result) => Some(Box::new(else_result.try_map_identifiers(map)?)),
+                    None => None,
+                },
+            },
+        };
+        Ok(node)
+    }
+
+    pub fn contains_aggregate(&self) -> bool {
+        match self {
+            Node::Aggregate { .. } => true,
+            _ => self.children().iter().any(|child| child.contains_aggregate()),
+        }
+    }
+
+    /// Collect the aggregate function calls, nested calls are not collected.
+    pub fn collect_aggregates<'a>(&'a self, aggregates: &mut Vec<&'a Node>) {
+        match self {
+            Node::Aggregate { .. } => aggregates.push(self),
+            _ => {
+                for child in self.children() {
+                    child.collect_aggregates(aggregates);
+                }
+            }
+        }
+    }
+}
+
+/// Split `table.column` into its qualifier and the column name.
+pub fn split_qualified_identifier(identifier: &str) -> (Option<&str>, &str) {
+    match identifier.split_once('.') {
+        Some((qualifier, column)) => (Some(qualifier), column),
+        None => (None, identifier),
+    }
+}
+
+/// Writes the expression back as query text, nested operations are enclosed in parenthesis.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_operand(f: &mut fmt::Formatter<'_>, node: &Node) -> fmt::Result {
+            match node {
+                Node::Binary { .. }
+                | Node::IsNull(_)
+                | Node::In { .. }
+                | Node::Between { .. }
+                | Node::Unary {
+                    op: LexerToken::Not,
+                    ..
+                } => write!(f, "({})", node),
+                _ => write!(f, "{}", node),
+            }
+        }
+
+        match self {
+            Node::Leaf(LexerToken::StringLiteral(string)) => write!(f, "'{}'", string),
+            Node::Leaf(token) => write!(f, "{}", token),
+            Node::Binary { left, op, right } => {
+                write_operand(f, left)?;
+                write!(f, " {} ", op)?;
+                write_operand(f, right)
+            }
+            Node::Unary { op, node } => {
+                match op {
+                    LexerToken::Not => write!(f, "not ")?,
+                    _ => write!(f, "{}", op)?,
+                }
+                write_operand(f, node)
+            }
+            Node::Aggregate { function, argument } => match argument {
+                Some(argument) => write!(f, "{}({})", function, argument),
+                None => write!(f, "{}(*)", function),
+            },
+            Node::IsNull(node) => {
+                write_operand(f, node)?;
+                write!(f, " is null")
+            }
+            Node::In { node, list } => {
+                write_operand(f, node)?;
+                let list: Vec<String> = list.iter().map(|item| item.to_string()).collect();
+                write!(f, " in ({})", list.join(", "))
+            }
+            Node::Between { node, low, high } => {
+                write_operand(f, node)?;
+                write!(f, " between ")?;
+                write_operand(f, low)?;
+                write!(f, " and ")?;
+                write_operand(f, high)
+            }
+            Node::Cast { node, data_type } => write!(f, "cast({} as {})", node, data_type),
+            Node::Call { function, arguments } => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+                write!(f, "{}({})", function, arguments.join(", "))
+            }
+            Node::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                write!(f, "case")?;
+                if let Some(operand) = operand {
+                    write!(f, " {}", operand)?;
+                }
+                for (value, result) in branches {
+                    write!(f, " when {} then {}", value, result)?;
+                }
+                if let Some(else_result) = else_result {
+                    write!(f, " else {}", else_result)?;
+                }
+                write!(f, " end")
             }
-            Node::Unary { node, .. } => node.collect_identifiers(identifiers),
         }
     }
 }
 
 pub fn parse_tree(expression: Vec<LexerToken>) -> Result<Option<Node>, ParseError> {
-    let expression = fix_operator_precedence(expression);
+    let expression = merge_cast_data_types(expression)?;
     let mut parser = ExpressionTreeParser::from(expression);
     parser.parse()
 }
 
+// Binding powers of the operators, from the loosest to the tightest. An operator takes the operand
+// on its left only from an operator of a lower power, the operand on its right is parsed with its
+// own power, so the binary operators of the same power are left-associative.
+const OR: u8 = 1;
+const XOR: u8 = 2;
+const AND: u8 = 3;
+/// prefix NOT
+const NOT: u8 = 4;
+/// IS [NOT] NULL
+const IS: u8 = 5;
+/// =, !=, <>, <, <=, >, >=
+const COMPARISON: u8 = 6;
+/// [NOT] IN, [NOT] BETWEEN, [NOT] LIKE, [NOT] ILIKE
+const PREDICATE: u8 = 7;
+/// + and -, `+` also concatenates the strings and the blobs
+const ADDITIVE: u8 = 8;
+/// *, / and %
+const MULTIPLICATIVE: u8 = 9;
+/// prefix - and !
+const UNARY: u8 = 10;
+/// `value::data_type`
+const CAST: u8 = 11;
+
+/// The binding power of the token following an operand, None when the token ends the operand.
+fn infix_binding_power(token: &LexerToken) -> Option<u8> {
+    let power = match token {
+        LexerToken::LogicalOp(op) => match op.as_str() {
+            "or" => OR,
+            "xor" => XOR,
+            _ => AND,
+        },
+        LexerToken::Is => IS,
+        LexerToken::CompareOp(_) => COMPARISON,
+        // NOT after the operand is the negation of the predicate
+        LexerToken::In | LexerToken::Between | LexerToken::Like | LexerToken::ILike | LexerToken::Not => PREDICATE,
+        LexerToken::Plus | LexerToken::Minus => ADDITIVE,
+        LexerToken::Star | LexerToken::Slash | LexerToken::Percent => MULTIPLICATIVE,
+        LexerToken::DoubleColon => CAST,
+        _ => return None,
+    };
+    Some(power)
+}
+
 struct ExpressionTreeParser {
     tokens: Vec<LexerToken>,
     index: usize,
@@ -68,10 +476,6 @@ impl ExpressionTreeParser {
         self.tokens.get(self.index)
     }
 
-    fn eof(&self) -> bool {
-        self.index >= self.tokens.len()
-    }
-
     fn expect_head(&self) -> Result<&LexerToken, ParseError> {
         self.head().ok_or(ParseError::UnexpectedQueryEnding)
     }
@@ -81,169 +485,299 @@ impl ExpressionTreeParser {
             return Ok(None);
         }
 
-        let mut node = self.parse_start(false)?;
-        while !self.eof() {
-            node = self.parse_leaf_or_binary(node)?;
+        let node = self.parse_expression(0)?;
+        if let Some(head) = self.head() {
+            return Err(ParseError::UnexpectedToken("operator".into(), head.clone()));
         }
         Ok(Some(node))
     }
 
-    fn parse_start(&mut self, parenthesised: bool) -> Result<Node, ParseError> {
+    /// Parse the operand and the operators binding tighter than `power`.
+    fn parse_expression(&mut self, power: u8) -> Result<Node, ParseError> {
+        let mut node = self.parse_prefix()?;
+        // the star can be only the argument of count(*) or the whole projection item
+        if node == Node::Leaf(LexerToken::Star) {
+            return Ok(node);
+        }
+
+        while let Some(infix_power) = self.head().and_then(infix_binding_power) {
+            if infix_power <= power {
+                break;
+            }
+            node = self.parse_infix(node, infix_power)?;
+        }
+        Ok(node)
+    }
+
+    fn parse_prefix(&mut self) -> Result<Node, ParseError> {
         let head = self.expect_head()?.clone();
-        let node = match head {
-            LexerToken::Minus | LexerToken::Not | LexerToken::ExclamationMark => {
-                self.advance();
-                let node = self.parse_unary(head)?;
-                Ok(node)
+        self.advance();
+        match head {
+            LexerToken::Minus | LexerToken::ExclamationMark => {
+                Ok(Node::new_unary(head, self.parse_expression(UNARY)?))
             }
+            LexerToken::Not => Ok(Node::new_unary(head, self.parse_expression(NOT)?)),
+            LexerToken::Identifier(ref name) => match self.head() {
+                Some(LexerToken::ParOpen) => self.parse_function(name),
+                _ => Ok(Node::Leaf(head)),
+            },
             LexerToken::Null
             | LexerToken::StringLiteral(_)
+            | LexerToken::BlobLiteral(_)
             | LexerToken::NumberLiteral(_)
+            | LexerToken::BigNumberLiteral(_)
+            | LexerToken::DecimalLiteral(_)
+            | LexerToken::DateLiteral(_)
+            | LexerToken::TimestampLiteral(_)
+            | LexerToken::UuidLiteral(_)
             | LexerToken::BoolLiteral(_)
             | LexerToken::FloatNumberLiteral(_)
-            | LexerToken::Identifier(_) => {
-                self.advance();
-                self.parse_leaf_or_binary(Node::Leaf(head))
-            }
+            | LexerToken::Star => Ok(Node::Leaf(head)),
             LexerToken::ParOpen => {
-                self.advance();
-                let par_node = self.parse_start(true)?;
-                self.parse_leaf_or_binary(par_node)
+                let node = self.parse_expression(0)?;
+                self.expect_par_close()?;
+                Ok(node)
             }
+            LexerToken::Cast => self.parse_cast(),
+            LexerToken::Case => self.parse_case(),
             _ => Err(ParseError::UnexpectedToken(
                 "identifier, literal, unary operator, (".into(),
                 head,
             )),
-        }?;
+        }
+    }
 
-        if parenthesised {
-            match self.head() {
-                Some(LexerToken::ParClose) => self.advance(),
-                _ => return Err(ParseError::UnfinishedParenthesis),
+    /// Parse the operator of the power following the left operand.
+    fn parse_infix(&mut self, left: Node, power: u8) -> Result<Node, ParseError> {
+        let head = self.expect_head()?.clone();
+        self.advance();
+        match head {
+            LexerToken::Is => {
+                let is_negated = self.head() == Some(&LexerToken::Not);
+                if is_negated {
+                    self.advance();
+                }
+                match self.head() {
+                    Some(LexerToken::Null) => self.advance(),
+                    Some(token) => {
+                        return Err(ParseError::UnexpectedToken("NULL or NOT NULL".into(), token.clone()))
+                    }
+                    None => return Err(ParseError::UnexpectedQueryEnding),
+                }
+                let node = Node::IsNull(Box::new(left));
+                match is_negated {
+                    true => Ok(Node::new_unary(LexerToken::Not, node)),
+                    false => Ok(node),
+                }
+            }
+            LexerToken::In => {
+                let list = self.parse_in_list()?;
+                Ok(Node::In {
+                    node: Box::new(left),
+                    list,
+                })
+            }
+            // the bounds bind tighter than AND, which separates them
+            LexerToken::Between => {
+                let low = self.parse_expression(PREDICATE)?;
+                match self.head() {
+                    Some(LexerToken::LogicalOp(op)) if op == "and" => self.advance(),
+                    Some(token) => {
+                        return Err(ParseError::UnexpectedToken("AND".into(), token.clone()))
+                    }
+                    None => return Err(ParseError::UnexpectedQueryEnding),
+                }
+                let high = self.parse_expression(PREDICATE)?;
+                Ok(Node::Between {
+                    node: Box::new(left),
+                    low: Box::new(low),
+                    high: Box::new(high),
+                })
+            }
+            // NOT IN, NOT BETWEEN, NOT LIKE, NOT ILIKE
+            LexerToken::Not => match self.head() {
+                Some(LexerToken::In | LexerToken::Between | LexerToken::Like | LexerToken::ILike) => {
+                    let node = self.parse_infix(left, power)?;
+                    Ok(Node::new_unary(LexerToken::Not, node))
+                }
+                Some(token) => Err(ParseError::UnexpectedToken(
+                    "IN, BETWEEN, LIKE or ILIKE".into(),
+                    token.clone(),
+                )),
+                None => Err(ParseError::UnexpectedQueryEnding),
+            },
+            LexerToken::DoubleColon => Ok(Node::Cast {
+                node: Box::new(left),
+                data_type: self.expect_data_type()?,
+            }),
+            _ => {
+                let right = self.parse_expression(power)?;
+                Ok(Node::new_binary(left, head, right))
             }
         }
+    }
 
-        Ok(node)
+    /// Parse the comma-separated values of `IN (...)`.
+    fn parse_in_list(&mut self) -> Result<Vec<Node>, ParseError> {
+        self.expect_token(LexerToken::ParOpen)?;
+        let list = self.parse_comma_separated()?;
+        self.expect_par_close()?;
+        Ok(list)
     }
 
-    fn parse_leaf_or_binary(&mut self, left: Node) -> Result<Node, ParseError> {
-        if self.eof() {
-            return Ok(left);
+    fn parse_comma_separated(&mut self) -> Result<Vec<Node>, ParseError> {
+        let mut nodes = vec![self.parse_expression(0)?];
+        while self.head() == Some(&LexerToken::Comma) {
+            self.advance();
+            nodes.push(self.parse_expression(0)?);
         }
+        Ok(nodes)
+    }
 
-        let head = self.expect_head()?.clone();
+    fn expect_token(&mut self, expected: LexerToken) -> Result<(), ParseError> {
+        let head = self.expect_head()?;
+        if *head != expected {
+            return Err(ParseError::UnexpectedToken(
+                expected.to_string(),
+                head.clone(),
+            ));
+        }
+        self.advance();
+        Ok(())
+    }
 
-        match head {
-            LexerToken::CompareOp(_)
-            | LexerToken::LogicalOp(_)
-            | LexerToken::Star
-            | LexerToken::Plus
-            | LexerToken::Minus
-            | LexerToken::Slash
-            | LexerToken::Percent => {
+    fn expect_par_close(&mut self) -> Result<(), ParseError> {
+        match self.head() {
+            Some(LexerToken::ParClose) => {
                 self.advance();
-                let right = self.parse_start(false)?;
-
-                Ok(Node::Binary {
-                    left: Box::new(left),
-                    op: head,
-                    right: Box::new(right),
-                })
+                Ok(())
             }
-            // todo: check other variants
-            _ => Ok(left),
+            _ => Err(ParseError::UnfinishedParenthesis),
         }
     }
 
-    fn parse_unary(&mut self, unary_op: LexerToken) -> Result<Node, ParseError> {
-        let node = self.parse_start(false)?;
-
-        Ok(Node::Unary {
-            op: unary_op,
+    /// Parse `(node AS data_type)` of CAST.
+    fn parse_cast(&mut self) -> Result<Node, ParseError> {
+        self.expect_token(LexerToken::ParOpen)?;
+        let node = self.parse_expression(0)?;
+        self.expect_token(LexerToken::As)?;
+        let data_type = self.expect_data_type()?;
+        self.expect_par_close()?;
+        Ok(Node::Cast {
             node: Box::new(node),
+            data_type,
         })
     }
-}
 
-// See Alternative methods in https://en.wikipedia.org/wiki/Operator-precedence_parser
-#[allow(clippy::vec_init_then_push)]
-fn fix_operator_precedence(expression: Vec<LexerToken>) -> Vec<LexerToken> {
-    if expression.len() <= 2 {
-        return expression;
+    /// Parse the rest of CASE after its keyword.
+    fn parse_case(&mut self) -> Result<Node, ParseError> {
+        let operand = match self.head() {
+            Some(LexerToken::When) => None,
+            _ => Some(Box::new(self.parse_expression(0)?)),
+        };
+        let mut branches = Vec::new();
+        while self.head() == Some(&LexerToken::When) {
+            self.advance();
+            let value = self.parse_expression(0)?;
+            self.expect_token(LexerToken::Then)?;
+            branches.push((value, self.parse_expression(0)?));
+        }
+        if branches.is_empty() {
+            return Err(ParseError::UnexpectedToken("WHEN".into(), self.expect_head()?.clone()));
+        }
+        let else_result = match self.head() {
+            Some(LexerToken::Else) => {
+                self.advance();
+                Some(Box::new(self.parse_expression(0)?))
+            }
+            _ => None,
+        };
+        self.expect_token(LexerToken::End)?;
+        Ok(Node::Case {
+            operand,
+            branches,
+            else_result,
+        })
     }
 
-    let mut result = Vec::new();
-    result.push(LexerToken::ParOpen);
-    result.push(LexerToken::ParOpen);
-    result.push(LexerToken::ParOpen);
-    result.push(LexerToken::ParOpen);
-
-    for token in expression {
-        match &token {
-            // operator_precedence: 4
-            LexerToken::LogicalOp(_) => {
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(token);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
+    /// The data type merged into one token by merge_cast_data_types.
+    fn expect_data_type(&mut self) -> Result<String, ParseError> {
+        match self.expect_head()? {
+            LexerToken::DataType(data_type) => {
+                let data_type = data_type.clone();
+                self.advance();
+                Ok(data_type)
             }
-            // operator_precedence: 3
-            LexerToken::CompareOp(_) => {
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(token);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
+            token => Err(ParseError::UnexpectedToken("data-type".into(), token.clone())),
+        }
+    }
+
+    fn parse_function(&mut self, name: &str) -> Result<Node, ParseError> {
+        if let Some(function) = AggregateFunction::from_name(name) {
+            return self.parse_aggregate(function);
+        }
+        match ScalarFunction::from_name(name) {
+            Some(function) => self.parse_call(function),
+            None => Err(ParseError::UnknownFunction(name.to_string())),
+        }
+    }
+
+    /// Parse the comma-separated arguments of the scalar function.
+    fn parse_call(&mut self, function: ScalarFunction) -> Result<Node, ParseError> {
+        self.advance();
+        let arguments = self.parse_comma_separated()?;
+        self.expect_par_close()?;
+
+        let (least, most) = function.arguments_count();
+        if arguments.len() < least || most.is_some_and(|most| arguments.len() > most) {
+            return Err(ParseError::InvalidArgumentCount(function.to_string(), arguments.len()));
+        }
+        Ok(Node::Call { function, arguments })
+    }
+
+    fn parse_aggregate(&mut self, function: AggregateFunction) -> Result<Node, ParseError> {
+        self.advance();
+        let argument = self.parse_expression(0)?;
+        self.expect_par_close()?;
+
+        let argument = match argument {
+            Node::Leaf(LexerToken::Star) if function == AggregateFunction::Count => None,
+            Node::Leaf(LexerToken::Star) => {
+                return Err(ParseError::UnexpectedToken(
+                    format!("{} argument", function),
+                    LexerToken::Star,
+                ))
             }
-            // operator_precedence: 2
-            LexerToken::Plus | LexerToken::Minus => {
-                if result.last() == Some(&LexerToken::ParOpen) {
-                    result.push(token);
-                    continue;
+            argument => Some(Box::new(argument)),
+        };
+        Ok(Node::Aggregate { function, argument })
+    }
+}
+
+/// The data type after `::` or AS of CAST is merged into one token, e.g. `decimal(10,2)`,
+/// so that its parentheses and comma aren't taken for the parts of the expression.
+fn merge_cast_data_types(expression: Vec<LexerToken>) -> Result<Vec<LexerToken>, ParseError> {
+    let mut result = Vec::new();
+    let mut tokens = expression.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let is_cast = matches!(token, LexerToken::DoubleColon | LexerToken::As);
+        result.push(token);
+        if !is_cast {
+            continue;
+        }
+        let mut data_type: Vec<LexerToken> = tokens.next().into_iter().collect();
+        if tokens.peek() == Some(&LexerToken::ParOpen) {
+            for token in tokens.by_ref() {
+                let is_end = token == LexerToken::ParClose;
+                data_type.push(token);
+                if is_end {
+                    break;
                 }
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(token);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
             }
-            // operator_precedence: 1
-            LexerToken::Star | LexerToken::Slash | LexerToken::Percent => {
-                result.push(LexerToken::ParClose);
-                result.push(token);
-                result.push(LexerToken::ParOpen);
-            }
-            LexerToken::ParOpen => {
-                result.push(token);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
-                result.push(LexerToken::ParOpen);
-            }
-            LexerToken::ParClose => {
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(LexerToken::ParClose);
-                result.push(token);
-            }
-            _ => result.push(token),
         }
+        result.push(LexerToken::DataType(parse_data_type(data_type)?));
     }
-
-    result.push(LexerToken::ParClose);
-    result.push(LexerToken::ParClose);
-    result.push(LexerToken::ParClose);
-    result.push(LexerToken::ParClose);
-
-    result
+    Ok(result)
 }
 
 #[test]
@@ -257,3 +791,36 @@ fn test_basic_stuff() {
 
     dbg!(tree);
 }
+
+#[test]
+fn test_binding_powers() {
+    let parse = |expression: &str| parse_tree(crate::parser::lexer::lex(expression).unwrap());
+    let cases = [
+        ("a or b xor c and not d", "a or (b xor (c and (not d)))"),
+        ("a and b and c", "(a and b) and c"),
+        ("not a is null", "not (a is null)"),
+        ("a + 1 = b is null", "((a + 1) = b) is null"),
+        ("a = b + c * d % e", "a = (b + ((c * d) % e))"),
+        ("a like b + 'c'", "a like (b + 'c')"),
+        ("a not in (1, 2) = b between 1 and 2", "(not (a in (1, 2))) = (b between 1 and 2)"),
+        ("-a::int * -b", "-cast(a as int) * -b"),
+        ("!a = b", "!a = b"),
+        ("count(*) + 1", "count(*) + 1"),
+    ];
+    for (expression, expected) in cases {
+        assert_eq!(parse(expression).unwrap().unwrap().to_string(), expected, "{}", expression);
+    }
+
+    // the errors reference only the tokens of the expression
+    assert!(matches!(parse("a +"), Err(ParseError::UnexpectedQueryEnding)));
+    assert!(matches!(parse("a + )"), Err(ParseError::UnexpectedToken(_, LexerToken::ParClose))));
+    assert!(matches!(parse("(a + 1"), Err(ParseError::UnfinishedParenthesis)));
+    assert!(matches!(parse("a b"), Err(ParseError::UnexpectedToken(_, LexerToken::Identifier(_)))));
+    assert!(matches!(parse("a is 1"), Err(ParseError::UnexpectedToken(_, LexerToken::NumberLiteral(1)))));
+    assert!(matches!(
+        parse("a between 1 or 2"),
+        Err(ParseError::UnexpectedToken(expected, LexerToken::LogicalOp(_))) if expected == "AND"
+    ));
+    assert!(matches!(parse("a not = 1"), Err(ParseError::UnexpectedToken(_, LexerToken::CompareOp(_)))));
+    assert!(matches!(parse("* * 2"), Err(ParseError::UnexpectedToken(_, LexerToken::Star))));
+}
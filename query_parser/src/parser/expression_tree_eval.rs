@@ -4,9 +4,36 @@ pub enum NodeValue {
     String(String),
     Int(i32),
     Float(f64),
+    Blob(Vec<u8>),
+    BigInt(i64),
+    Decimal(Decimal),
+    /// Days since 1970-01-01.
+    Date(i32),
+    /// Microseconds since 1970-01-01 00:00:00.
+    Timestamp(i64),
+    Uuid(u128),
     Null,
 }
 
+impl NodeValue {
+    /// The name of the type, as the type of the column is named.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            NodeValue::Bool(_) => "BOOLEAN",
+            NodeValue::String(_) => "STRING",
+            NodeValue::Int(_) => "INT",
+            NodeValue::Float(_) => "FLOAT",
+            NodeValue::Blob(_) => "BLOB",
+            NodeValue::BigInt(_) => "BIGINT",
+            NodeValue::Decimal(_) => "DECIMAL",
+            NodeValue::Date(_) => "DATE",
+            NodeValue::Timestamp(_) => "TIMESTAMP",
+            NodeValue::Uuid(_) => "UUID",
+            NodeValue::Null => "NULL",
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 enum NumberBinOp {
     Add,
@@ -95,6 +122,12 @@ enum StringOp {
     Concat,
     Equal,
     NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Like,
+    ILike,
 }
 
 impl TryFrom<&LexerToken> for StringOp {
@@ -103,11 +136,17 @@ impl TryFrom<&LexerToken> for StringOp {
     fn try_from(value: &LexerToken) -> Result<Self, Self::Error> {
         match &value {
             LexerToken::Plus => Ok(StringOp::Concat),
+            LexerToken::Like => Ok(StringOp::Like),
+            LexerToken::ILike => Ok(StringOp::ILike),
             LexerToken::CompareOp(op) => match op.as_str() {
                 "=" => Ok(StringOp::Equal),
                 "!=" | "<>" => Ok(StringOp::NotEqual),
+                "<" => Ok(StringOp::Less),
+                ">" => Ok(StringOp::Greater),
+                "<=" => Ok(StringOp::LessEqual),
+                ">=" => Ok(StringOp::GreaterEqual),
                 _ => Err(ParseError::InvalidOperator(
-                    "=, !=, <>".into(),
+                    "comparison".into(),
                     value.clone(),
                 )),
             },
@@ -121,8 +160,17 @@ impl TryFrom<&LexerToken> for StringOp {
 
 use std::collections::HashMap;
 
-use super::errors::ParseError;
-use crate::parser::expression_tree::Node;
+use common::models::{
+    datetime::{is_valid_date, is_valid_timestamp, MICROS_PER_DAY},
+    db::DataType,
+    decimal::Decimal,
+};
+
+use super::{
+    cast::{cast_value, compared_string, value_string, Coercion},
+    errors::ParseError,
+};
+use crate::parser::expression_tree::{Node, ScalarFunction};
 use crate::parser::lexer::LexerToken;
 
 pub fn evaluate_binary_node(
@@ -153,28 +201,319 @@ pub fn evaluate_node(
             let left_value = evaluate_node(left, identifier_map)?;
             let right_value = evaluate_node(right, identifier_map)?;
 
-            // let the left type decide, which type of operation is expected
-            match left_value {
-                NodeValue::Bool(_) => evaluate_bool_op(&left_value, &right_value, op.try_into()?),
-                NodeValue::String(_) => {
-                    evaluate_string_op(&left_value, &right_value, op.try_into()?)
+            evaluate_binary_op(&left_value, op, &right_value)
+        }
+        Node::IsNull(node) => Ok(NodeValue::Bool(
+            evaluate_node(node, identifier_map)? == NodeValue::Null,
+        )),
+        // true when any value is equal, otherwise NULL when any comparison is NULL
+        Node::In { node, list } => {
+            let value = evaluate_node(node, identifier_map)?;
+            let equal = LexerToken::CompareOp("=".into());
+            let or = LexerToken::LogicalOp("or".into());
+
+            let mut result = NodeValue::Bool(false);
+            for item in list {
+                let item_value = evaluate_node(item, identifier_map)?;
+                let is_equal = evaluate_binary_op(&value, &equal, &item_value)?;
+                result = evaluate_binary_op(&result, &or, &is_equal)?;
+            }
+            Ok(result)
+        }
+        Node::Between { node, low, high } => {
+            let value = evaluate_node(node, identifier_map)?;
+            let low_value = evaluate_node(low, identifier_map)?;
+            let high_value = evaluate_node(high, identifier_map)?;
+
+            let above_low =
+                evaluate_binary_op(&value, &LexerToken::CompareOp(">=".into()), &low_value)?;
+            let below_high =
+                evaluate_binary_op(&value, &LexerToken::CompareOp("<=".into()), &high_value)?;
+            evaluate_binary_op(&above_low, &LexerToken::LogicalOp("and".into()), &below_high)
+        }
+        // aggregates are computed per group beforehand and passed under their query text
+        Node::Aggregate { .. } => {
+            let key = node.to_string();
+            match identifier_map.get(&key) {
+                None => Err(ParseError::MisplacedAggregate(key)),
+                Some(value) => Ok(value.clone()),
+            }
+        }
+        Node::Cast { node, data_type } => {
+            let value = evaluate_node(node, identifier_map)?;
+            let Some(data_type) = DataType::from_name(data_type) else {
+                return Err(ParseError::UnexpectedToken("data-type".into(), LexerToken::DataType(data_type.clone())));
+            };
+            cast_value(value, &data_type, Coercion::Explicit)
+        }
+        Node::Call { function, arguments } => evaluate_call(*function, arguments, identifier_map),
+        // the result of the first WHEN, which is true or equal to the operand, NULL never matches
+        Node::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let operand = match operand {
+                Some(operand) => Some(evaluate_node(operand, identifier_map)?),
+                None => None,
+            };
+            for (value, result) in branches {
+                let value = evaluate_node(value, identifier_map)?;
+                let is_match = match &operand {
+                    Some(operand) => evaluate_binary_op(operand, &LexerToken::CompareOp("=".into()), &value)?,
+                    None => value,
+                };
+                match is_match {
+                    NodeValue::Bool(true) => return evaluate_node(result, identifier_map),
+                    NodeValue::Bool(false) | NodeValue::Null => {}
+                    _ => return Err(ParseError::InvalidType("bool".into(), is_match)),
                 }
-                NodeValue::Int(_) | NodeValue::Float(_) => {
-                    evaluate_binary_number_op(&left_value, &right_value, op.try_into()?)
+            }
+            match else_result {
+                Some(else_result) => evaluate_node(else_result, identifier_map),
+                None => Ok(NodeValue::Null),
+            }
+        }
+    }
+}
+
+/// COALESCE and IFNULL evaluate the arguments only until the first value, which isn't NULL,
+/// NULLIF is NULL when its arguments are equal and CONCAT skips NULL. The other functions
+/// are NULL when any of their arguments is NULL.
+fn evaluate_call(
+    function: ScalarFunction,
+    arguments: &[Node],
+    identifier_map: &HashMap<String, NodeValue>,
+) -> Result<NodeValue, ParseError> {
+    if let ScalarFunction::Coalesce | ScalarFunction::IfNull = function {
+        for argument in arguments {
+            let value = evaluate_node(argument, identifier_map)?;
+            if value != NodeValue::Null {
+                return Ok(value);
+            }
+        }
+        return Ok(NodeValue::Null);
+    }
+
+    let values = arguments
+        .iter()
+        .map(|argument| evaluate_node(argument, identifier_map))
+        .collect::<Result<Vec<_>, _>>()?;
+    match function {
+        ScalarFunction::Concat => {
+            let strings: Vec<String> = values
+                .iter()
+                .filter(|value| **value != NodeValue::Null)
+                .map(value_string)
+                .collect();
+            return Ok(NodeValue::String(strings.concat()));
+        }
+        ScalarFunction::NullIf => {
+            let is_equal = evaluate_binary_op(&values[0], &LexerToken::CompareOp("=".into()), &values[1])?;
+            return match is_equal {
+                NodeValue::Bool(true) => Ok(NodeValue::Null),
+                _ => Ok(values[0].clone()),
+            };
+        }
+        _ if values.contains(&NodeValue::Null) => return Ok(NodeValue::Null),
+        _ => {}
+    }
+
+    let string = |index: usize| match &values[index] {
+        NodeValue::String(string) => Ok(string.as_str()),
+        value => Err(ParseError::InvalidType("string".into(), value.clone())),
+    };
+    let integer = |index: usize| {
+        bigint_value(&values[index])
+            .ok_or_else(|| ParseError::InvalidType("int, bigint".into(), values[index].clone()))
+    };
+    let float = |index: usize| {
+        float_value(&values[index])
+            .ok_or_else(|| ParseError::InvalidType("int, bigint, decimal, float".into(), values[index].clone()))
+    };
+    let invalid_argument = |value: &NodeValue| ParseError::InvalidArgument(function.to_string(), value.clone());
+
+    match function {
+        ScalarFunction::Upper => Ok(NodeValue::String(string(0)?.to_uppercase())),
+        ScalarFunction::Lower => Ok(NodeValue::String(string(0)?.to_lowercase())),
+        ScalarFunction::Length => match &values[0] {
+            NodeValue::Blob(bytes) => Ok(NodeValue::Int(bytes.len() as i32)),
+            _ => Ok(NodeValue::Int(string(0)?.chars().count() as i32)),
+        },
+        // the characters are counted from 1, the ones before the first are counted too,
+        // e.g. substr('abc', 0, 2) is 'a'
+        ScalarFunction::Substr => {
+            let start = integer(1)?;
+            let end = match values.get(2) {
+                Some(length) if integer(2)? < 0 => return Err(invalid_argument(length)),
+                Some(_) => start.saturating_add(integer(2)?),
+                None => i64::MAX,
+            };
+            let skipped = start.max(1) - 1;
+            let taken = end.saturating_sub(start.max(1)).max(0);
+            let substring = string(0)?.chars().skip(skipped as usize).take(taken as usize).collect();
+            Ok(NodeValue::String(substring))
+        }
+        ScalarFunction::Trim => match values.get(1) {
+            Some(_) => {
+                let characters: Vec<char> = string(1)?.chars().collect();
+                Ok(NodeValue::String(string(0)?.trim_matches(characters.as_slice()).to_string()))
+            }
+            None => Ok(NodeValue::String(string(0)?.trim().to_string())),
+        },
+        ScalarFunction::Replace => match string(1)? {
+            "" => Ok(NodeValue::String(string(0)?.to_string())),
+            from => Ok(NodeValue::String(string(0)?.replace(from, string(2)?))),
+        },
+        ScalarFunction::Abs => match &values[0] {
+            NodeValue::Int(number) => number
+                .checked_abs()
+                .map(NodeValue::Int)
+                .ok_or_else(|| ParseError::OutOfRange("INT".into())),
+            NodeValue::BigInt(number) => number
+                .checked_abs()
+                .map(NodeValue::BigInt)
+                .ok_or_else(|| ParseError::OutOfRange("BIGINT".into())),
+            NodeValue::Decimal(decimal) => Ok(NodeValue::Decimal(Decimal::new(decimal.mantissa.abs(), decimal.scale))),
+            _ => Ok(NodeValue::Float(float(0)?.abs())),
+        },
+        // the digits are rounded half away from zero, negative digits round the integer part
+        ScalarFunction::Round => {
+            let digits = match values.get(1) {
+                Some(_) => integer(1)?.clamp(-38, 38) as i32,
+                None => 0,
+            };
+            match &values[0] {
+                NodeValue::Int(number) => round_integer(*number as i64, digits)
+                    .and_then(|number| i32::try_from(number).ok())
+                    .map(NodeValue::Int)
+                    .ok_or_else(|| ParseError::OutOfRange("INT".into())),
+                NodeValue::BigInt(number) => round_integer(*number, digits)
+                    .map(NodeValue::BigInt)
+                    .ok_or_else(|| ParseError::OutOfRange("BIGINT".into())),
+                NodeValue::Decimal(decimal) => round_decimal(*decimal, digits)
+                    .map(NodeValue::Decimal)
+                    .ok_or_else(|| ParseError::OutOfRange("DECIMAL".into())),
+                _ => {
+                    let factor = 10_f64.powi(digits);
+                    Ok(NodeValue::Float((float(0)? * factor).round() / factor))
                 }
-                NodeValue::Null => match op {
-                    LexerToken::CompareOp(op) if op == "=" => {
-                        Ok(NodeValue::Bool(right_value == NodeValue::Null))
-                    }
-                    // notice: != behaves differently than <> for null values
-                    LexerToken::CompareOp(op) if op == "<>" => {
-                        Ok(NodeValue::Bool(right_value != NodeValue::Null))
-                    }
-                    // all other operations with null result to null
-                    _ => Ok(NodeValue::Null),
+            }
+        }
+        ScalarFunction::Floor | ScalarFunction::Ceil => {
+            let is_floor = function == ScalarFunction::Floor;
+            match &values[0] {
+                NodeValue::Int(_) | NodeValue::BigInt(_) => Ok(values[0].clone()),
+                NodeValue::Decimal(decimal) => {
+                    let divisor = 10_i128.pow(decimal.scale);
+                    let integer = match is_floor {
+                        true => decimal.mantissa.div_euclid(divisor),
+                        false => -(-decimal.mantissa).div_euclid(divisor),
+                    };
+                    Ok(NodeValue::Decimal(Decimal::new(integer, 0)))
+                }
+                _ => match is_floor {
+                    true => Ok(NodeValue::Float(float(0)?.floor())),
+                    false => Ok(NodeValue::Float(float(0)?.ceil())),
                 },
             }
         }
+        ScalarFunction::Power => {
+            let power = float(0)?.powf(float(1)?);
+            match power {
+                _ if power.is_nan() => Err(invalid_argument(&values[0])),
+                _ if power.is_infinite() => Err(ParseError::OutOfRange("FLOAT".into())),
+                _ => Ok(NodeValue::Float(power)),
+            }
+        }
+        ScalarFunction::Sqrt => match float(0)? {
+            number if number < 0.0 => Err(invalid_argument(&values[0])),
+            number => Ok(NodeValue::Float(number.sqrt())),
+        },
+        ScalarFunction::Concat
+        | ScalarFunction::NullIf
+        | ScalarFunction::Coalesce
+        | ScalarFunction::IfNull => unreachable!("{} is evaluated above", function),
+    }
+}
+
+/// The integer rounded to the digits, the positive digits keep it.
+fn round_integer(number: i64, digits: i32) -> Option<i64> {
+    match digits {
+        0.. => Some(number),
+        _ => round_decimal(Decimal::from_int(number), digits)?.to_integer(),
+    }
+}
+
+/// The decimal rounded to the digits after the point, to the tens, hundreds, ... for negative digits.
+fn round_decimal(decimal: Decimal, digits: i32) -> Option<Decimal> {
+    if digits >= 0 {
+        return decimal.rescale(digits as u32);
+    }
+    let shift = digits.unsigned_abs();
+    let rounded = Decimal::new(decimal.mantissa, decimal.scale + shift).rescale(0)?;
+    let mantissa = rounded.mantissa.checked_mul(10_i128.checked_pow(shift)?)?;
+    Some(Decimal::new(mantissa, 0))
+}
+
+fn evaluate_binary_op(
+    left_value: &NodeValue,
+    op: &LexerToken,
+    right_value: &NodeValue,
+) -> Result<NodeValue, ParseError> {
+    // the string compared with DATE, TIMESTAMP, UUID or DECIMAL is converted to its type
+    if let LexerToken::CompareOp(_) = op {
+        match (left_value, right_value) {
+            (NodeValue::String(string), other) => {
+                if let Some(left_value) = compared_string(string, &comparison_type(other)) {
+                    return evaluate_binary_op(&left_value?, op, right_value);
+                }
+            }
+            (other, NodeValue::String(string)) => {
+                if let Some(right_value) = compared_string(string, &comparison_type(other)) {
+                    return evaluate_binary_op(left_value, op, &right_value?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // let the left type decide, which type of operation is expected
+    match left_value {
+        NodeValue::Bool(_) => evaluate_bool_op(left_value, right_value, op.try_into()?),
+        NodeValue::String(_) => evaluate_string_op(left_value, right_value, op.try_into()?),
+        NodeValue::Blob(_) => evaluate_blob_op(left_value, right_value, op.try_into()?),
+        NodeValue::Int(_) | NodeValue::Float(_) | NodeValue::BigInt(_) | NodeValue::Decimal(_) => {
+            evaluate_binary_number_op(left_value, right_value, op.try_into()?)
+        }
+        NodeValue::Date(_) | NodeValue::Timestamp(_) => evaluate_datetime_op(left_value, right_value, op),
+        NodeValue::Uuid(_) => evaluate_uuid_op(left_value, right_value, op),
+        // NULL is unknown, so only `NULL OR true` and `NULL AND false` are known
+        NodeValue::Null => match (op, right_value) {
+            (LexerToken::LogicalOp(op), NodeValue::Bool(true)) if op == "or" => {
+                Ok(NodeValue::Bool(true))
+            }
+            (LexerToken::LogicalOp(op), NodeValue::Bool(false)) if op == "and" => {
+                Ok(NodeValue::Bool(false))
+            }
+            // all other operations with null result to null
+            _ => Ok(NodeValue::Null),
+        },
+    }
+}
+
+/// The type, which a string compared with the value is converted to, only the types
+/// converted by compared_string matter.
+fn comparison_type(value: &NodeValue) -> DataType {
+    match value {
+        NodeValue::Decimal(decimal) => DataType::DECIMAL {
+            precision: decimal.precision() as u8,
+            scale: decimal.scale as u8,
+        },
+        NodeValue::Date(_) => DataType::DATE,
+        NodeValue::Timestamp(_) => DataType::TIMESTAMP,
+        NodeValue::Uuid(_) => DataType::UUID,
+        _ => DataType::TEXT,
     }
 }
 
@@ -185,8 +524,14 @@ fn evaluate_leaf(
     match token {
         LexerToken::BoolLiteral(value) => Ok(NodeValue::Bool(*value)),
         LexerToken::StringLiteral(value) => Ok(NodeValue::String(value.clone())),
+        LexerToken::BlobLiteral(value) => Ok(NodeValue::Blob(value.clone())),
         LexerToken::NumberLiteral(value) => Ok(NodeValue::Int(*value)),
         LexerToken::FloatNumberLiteral(value) => Ok(NodeValue::Float(*value)),
+        LexerToken::BigNumberLiteral(value) => Ok(NodeValue::BigInt(*value)),
+        LexerToken::DecimalLiteral(value) => Ok(NodeValue::Decimal(*value)),
+        LexerToken::DateLiteral(value) => Ok(NodeValue::Date(*value)),
+        LexerToken::TimestampLiteral(value) => Ok(NodeValue::Timestamp(*value)),
+        LexerToken::UuidLiteral(value) => Ok(NodeValue::Uuid(*value)),
         LexerToken::Identifier(id) => match identifier_map.get(id) {
             None => Err(ParseError::IdentifierNotFound(id.clone())),
             Some(value) => Ok(value.clone()),
@@ -207,32 +552,67 @@ fn evaluate_unary(op: &LexerToken, node_value: NodeValue) -> Result<NodeValue, P
             _ => Err(ParseError::InvalidType("bool".into(), node_value)),
         },
         LexerToken::Minus => match node_value {
-            NodeValue::Int(value) => Ok(NodeValue::Int(-value)),
+            NodeValue::Int(value) => value
+                .checked_neg()
+                .map(NodeValue::Int)
+                .ok_or_else(|| ParseError::OutOfRange("INT".into())),
             NodeValue::Float(value) => Ok(NodeValue::Float(-value)),
+            NodeValue::BigInt(value) => value
+                .checked_neg()
+                .map(NodeValue::BigInt)
+                .ok_or_else(|| ParseError::OutOfRange("BIGINT".into())),
+            NodeValue::Decimal(value) => Ok(NodeValue::Decimal(Decimal::new(-value.mantissa, value.scale))),
             NodeValue::Null => Ok(NodeValue::Null),
-            _ => Err(ParseError::InvalidType("int, float".into(), node_value)),
+            _ => Err(ParseError::InvalidType("int, bigint, decimal, float".into(), node_value)),
         },
         _ => unreachable!("unary operator should be one of !, not, -"),
     }
 }
 
+/// The numbers are converted to the wider of their types, INT < BIGINT < DECIMAL < FLOAT.
 fn evaluate_binary_number_op(
     left_value: &NodeValue,
     right_value: &NodeValue,
     op: NumberBinOp,
 ) -> Result<NodeValue, ParseError> {
+    let invalid_type = || ParseError::InvalidType("int, bigint, decimal, float".into(), right_value.clone());
     match (&left_value, &right_value) {
         (NodeValue::Int(i1), NodeValue::Int(i2)) => evaluate_int_number_op(*i1, *i2, op),
-        (NodeValue::Float(f1), NodeValue::Float(f2)) => evaluate_float_number_op(*f1, *f2, op),
-        (NodeValue::Int(i1), NodeValue::Float(f2)) => evaluate_float_number_op(*i1 as f64, *f2, op),
-        (NodeValue::Float(f1), NodeValue::Int(i2)) => evaluate_float_number_op(*f1, *i2 as f64, op),
-        (NodeValue::Int(_), NodeValue::Null) | (NodeValue::Float(_), NodeValue::Null) => {
-            Ok(NodeValue::Null)
+        (_, NodeValue::Null) => Ok(NodeValue::Null),
+        (NodeValue::Float(_), _) | (_, NodeValue::Float(_)) => {
+            let f2 = float_value(right_value).ok_or_else(invalid_type)?;
+            evaluate_float_number_op(float_value(left_value).unwrap(), f2, op)
+        }
+        (NodeValue::Decimal(_), _) | (_, NodeValue::Decimal(_)) => {
+            let d2 = decimal_value(right_value).ok_or_else(invalid_type)?;
+            evaluate_decimal_number_op(decimal_value(left_value).unwrap(), d2, op)
+        }
+        _ => {
+            let i2 = bigint_value(right_value).ok_or_else(invalid_type)?;
+            evaluate_bigint_number_op(bigint_value(left_value).unwrap(), i2, op)
         }
-        _ => Err(ParseError::InvalidType(
-            "int, float".into(),
-            right_value.clone(),
-        )),
+    }
+}
+
+fn bigint_value(value: &NodeValue) -> Option<i64> {
+    match value {
+        NodeValue::Int(value) => Some(*value as i64),
+        NodeValue::BigInt(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn decimal_value(value: &NodeValue) -> Option<Decimal> {
+    match value {
+        NodeValue::Decimal(value) => Some(*value),
+        _ => bigint_value(value).map(Decimal::from_int),
+    }
+}
+
+fn float_value(value: &NodeValue) -> Option<f64> {
+    match value {
+        NodeValue::Float(value) => Some(*value),
+        _ => decimal_value(value).map(Decimal::to_f64),
     }
 }
 
@@ -253,12 +633,14 @@ fn evaluate_float_number_op(f1: f64, f2: f64, op: NumberBinOp) -> Result<NodeVal
 }
 
 fn evaluate_int_number_op(i1: i32, i2: i32, op: NumberBinOp) -> Result<NodeValue, ParseError> {
+    let checked = |result: Option<i32>| result.map(NodeValue::Int).ok_or_else(|| ParseError::OutOfRange("INT".into()));
     match op {
-        NumberBinOp::Add => Ok(NodeValue::Int(i1 + i2)),
-        NumberBinOp::Sub => Ok(NodeValue::Int(i1 - i2)),
-        NumberBinOp::Mul => Ok(NodeValue::Int(i1 * i2)),
-        NumberBinOp::Div => Ok(NodeValue::Int(i1 / i2)),
-        NumberBinOp::Mod => Ok(NodeValue::Int(i1 % i2)),
+        NumberBinOp::Add => checked(i1.checked_add(i2)),
+        NumberBinOp::Sub => checked(i1.checked_sub(i2)),
+        NumberBinOp::Mul => checked(i1.checked_mul(i2)),
+        NumberBinOp::Div | NumberBinOp::Mod if i2 == 0 => Err(ParseError::DivisionByZero),
+        NumberBinOp::Div => checked(i1.checked_div(i2)),
+        NumberBinOp::Mod => checked(i1.checked_rem(i2)),
         NumberBinOp::Greater => Ok(NodeValue::Bool(i1 > i2)),
         NumberBinOp::Less => Ok(NodeValue::Bool(i1 < i2)),
         NumberBinOp::GreaterEqual => Ok(NodeValue::Bool(i1 >= i2)),
@@ -268,6 +650,140 @@ fn evaluate_int_number_op(i1: i32, i2: i32, op: NumberBinOp) -> Result<NodeValue
     }
 }
 
+fn evaluate_bigint_number_op(i1: i64, i2: i64, op: NumberBinOp) -> Result<NodeValue, ParseError> {
+    let checked = |result: Option<i64>| {
+        result
+            .map(NodeValue::BigInt)
+            .ok_or_else(|| ParseError::OutOfRange("BIGINT".into()))
+    };
+    match op {
+        NumberBinOp::Add => checked(i1.checked_add(i2)),
+        NumberBinOp::Sub => checked(i1.checked_sub(i2)),
+        NumberBinOp::Mul => checked(i1.checked_mul(i2)),
+        NumberBinOp::Div | NumberBinOp::Mod if i2 == 0 => Err(ParseError::DivisionByZero),
+        NumberBinOp::Div => checked(i1.checked_div(i2)),
+        NumberBinOp::Mod => checked(i1.checked_rem(i2)),
+        NumberBinOp::Greater => Ok(NodeValue::Bool(i1 > i2)),
+        NumberBinOp::Less => Ok(NodeValue::Bool(i1 < i2)),
+        NumberBinOp::GreaterEqual => Ok(NodeValue::Bool(i1 >= i2)),
+        NumberBinOp::LessEqual => Ok(NodeValue::Bool(i1 <= i2)),
+        NumberBinOp::Equal => Ok(NodeValue::Bool(i1 == i2)),
+        NumberBinOp::NotEqual => Ok(NodeValue::Bool(i1 != i2)),
+    }
+}
+
+fn evaluate_decimal_number_op(d1: Decimal, d2: Decimal, op: NumberBinOp) -> Result<NodeValue, ParseError> {
+    let checked = |result: Option<Decimal>| {
+        result
+            .map(NodeValue::Decimal)
+            .ok_or_else(|| ParseError::OutOfRange("DECIMAL".into()))
+    };
+    match op {
+        NumberBinOp::Add => checked(d1.checked_add(d2)),
+        NumberBinOp::Sub => checked(d1.checked_sub(d2)),
+        NumberBinOp::Mul => checked(d1.checked_mul(d2)),
+        NumberBinOp::Div | NumberBinOp::Mod if d2.mantissa == 0 => Err(ParseError::DivisionByZero),
+        NumberBinOp::Div => checked(d1.checked_div(d2)),
+        NumberBinOp::Mod => checked(d1.checked_rem(d2)),
+        NumberBinOp::Greater => Ok(NodeValue::Bool(d1 > d2)),
+        NumberBinOp::Less => Ok(NodeValue::Bool(d1 < d2)),
+        NumberBinOp::GreaterEqual => Ok(NodeValue::Bool(d1 >= d2)),
+        NumberBinOp::LessEqual => Ok(NodeValue::Bool(d1 <= d2)),
+        NumberBinOp::Equal => Ok(NodeValue::Bool(d1 == d2)),
+        NumberBinOp::NotEqual => Ok(NodeValue::Bool(d1 != d2)),
+    }
+}
+
+/// DATE and TIMESTAMP are compared with each other, DATE as its midnight. A number of days
+/// is added to them or subtracted from them, the days of TIMESTAMP can have a fraction.
+/// The difference of two dates is the INT of days, of two timestamps the FLOAT of days.
+fn evaluate_datetime_op(
+    left_value: &NodeValue,
+    right_value: &NodeValue,
+    op: &LexerToken,
+) -> Result<NodeValue, ParseError> {
+    let invalid_operator = || ParseError::InvalidOperator("+, -, comparison".into(), op.clone());
+    let number_op: NumberBinOp = op.try_into()?;
+    let micros = |value: &NodeValue| match value {
+        NodeValue::Date(days) => Some(*days as i64 * MICROS_PER_DAY),
+        NodeValue::Timestamp(micros) => Some(*micros),
+        _ => None,
+    };
+    match (left_value, right_value) {
+        (_, NodeValue::Null) => Ok(NodeValue::Null),
+        (NodeValue::Date(d1), NodeValue::Date(d2)) if number_op == NumberBinOp::Sub => {
+            Ok(NodeValue::Int(d1 - d2))
+        }
+        (_, NodeValue::Date(_) | NodeValue::Timestamp(_)) => {
+            let (t1, t2) = (micros(left_value).unwrap(), micros(right_value).unwrap());
+            match number_op {
+                NumberBinOp::Sub => Ok(NodeValue::Float((t1 - t2) as f64 / MICROS_PER_DAY as f64)),
+                NumberBinOp::Add | NumberBinOp::Mul | NumberBinOp::Div | NumberBinOp::Mod => {
+                    Err(invalid_operator())
+                }
+                _ => evaluate_bigint_number_op(t1, t2, number_op),
+            }
+        }
+        (NodeValue::Date(days), _) => {
+            let Some(offset) = bigint_value(right_value) else {
+                return Err(ParseError::InvalidType("int, bigint, date, timestamp".into(), right_value.clone()));
+            };
+            let days = match number_op {
+                NumberBinOp::Add => (*days as i64).checked_add(offset),
+                NumberBinOp::Sub => (*days as i64).checked_sub(offset),
+                _ => return Err(invalid_operator()),
+            };
+            match days {
+                Some(days) if is_valid_date(days) => Ok(NodeValue::Date(days as i32)),
+                _ => Err(ParseError::OutOfRange("DATE".into())),
+            }
+        }
+        (_, _) => {
+            let offset = match right_value {
+                NodeValue::Float(days) => days * MICROS_PER_DAY as f64,
+                _ => match decimal_value(right_value) {
+                    Some(days) => days.to_f64() * MICROS_PER_DAY as f64,
+                    None => {
+                        return Err(ParseError::InvalidType("number, date, timestamp".into(), right_value.clone()))
+                    }
+                },
+            };
+            let micros = micros(left_value).unwrap() as f64;
+            let micros = match number_op {
+                NumberBinOp::Add => (micros + offset).round(),
+                NumberBinOp::Sub => (micros - offset).round(),
+                _ => return Err(invalid_operator()),
+            };
+            match micros.abs() < i64::MAX as f64 && is_valid_timestamp(micros as i64) {
+                true => Ok(NodeValue::Timestamp(micros as i64)),
+                false => Err(ParseError::OutOfRange("TIMESTAMP".into())),
+            }
+        }
+    }
+}
+
+/// The UUIDs are only compared.
+fn evaluate_uuid_op(
+    left_value: &NodeValue,
+    right_value: &NodeValue,
+    op: &LexerToken,
+) -> Result<NodeValue, ParseError> {
+    let number_op: NumberBinOp = op.try_into()?;
+    match (left_value, right_value) {
+        (NodeValue::Uuid(_), NodeValue::Null) => Ok(NodeValue::Null),
+        (NodeValue::Uuid(u1), NodeValue::Uuid(u2)) => match number_op {
+            NumberBinOp::Greater => Ok(NodeValue::Bool(u1 > u2)),
+            NumberBinOp::Less => Ok(NodeValue::Bool(u1 < u2)),
+            NumberBinOp::GreaterEqual => Ok(NodeValue::Bool(u1 >= u2)),
+            NumberBinOp::LessEqual => Ok(NodeValue::Bool(u1 <= u2)),
+            NumberBinOp::Equal => Ok(NodeValue::Bool(u1 == u2)),
+            NumberBinOp::NotEqual => Ok(NodeValue::Bool(u1 != u2)),
+            _ => Err(ParseError::InvalidOperator("comparison".into(), op.clone())),
+        },
+        _ => Err(ParseError::InvalidType("uuid".into(), right_value.clone())),
+    }
+}
+
 fn evaluate_string_op(
     left_value: &NodeValue,
     right_value: &NodeValue,
@@ -278,6 +794,15 @@ fn evaluate_string_op(
             StringOp::Concat => Ok(NodeValue::String(format!("{}{}", s1, s2))),
             StringOp::Equal => Ok(NodeValue::Bool(s1 == s2)),
             StringOp::NotEqual => Ok(NodeValue::Bool(s1 != s2)),
+            StringOp::Less => Ok(NodeValue::Bool(s1 < s2)),
+            StringOp::Greater => Ok(NodeValue::Bool(s1 > s2)),
+            StringOp::LessEqual => Ok(NodeValue::Bool(s1 <= s2)),
+            StringOp::GreaterEqual => Ok(NodeValue::Bool(s1 >= s2)),
+            StringOp::Like => Ok(NodeValue::Bool(matches_like_pattern(s1, s2))),
+            StringOp::ILike => Ok(NodeValue::Bool(matches_like_pattern(
+                &s1.to_lowercase(),
+                &s2.to_lowercase(),
+            ))),
         },
         (NodeValue::String(_), NodeValue::Null) => Ok(NodeValue::Null),
         _ => Err(ParseError::InvalidType(
@@ -287,6 +812,66 @@ fn evaluate_string_op(
     }
 }
 
+/// The blobs are concatenated and compared like the strings, LIKE isn't defined for them.
+fn evaluate_blob_op(
+    left_value: &NodeValue,
+    right_value: &NodeValue,
+    op: StringOp,
+) -> Result<NodeValue, ParseError> {
+    match (&left_value, &right_value) {
+        (NodeValue::Blob(b1), NodeValue::Blob(b2)) => match op {
+            StringOp::Concat => Ok(NodeValue::Blob([b1.as_slice(), b2].concat())),
+            StringOp::Equal => Ok(NodeValue::Bool(b1 == b2)),
+            StringOp::NotEqual => Ok(NodeValue::Bool(b1 != b2)),
+            StringOp::Less => Ok(NodeValue::Bool(b1 < b2)),
+            StringOp::Greater => Ok(NodeValue::Bool(b1 > b2)),
+            StringOp::LessEqual => Ok(NodeValue::Bool(b1 <= b2)),
+            StringOp::GreaterEqual => Ok(NodeValue::Bool(b1 >= b2)),
+            StringOp::Like | StringOp::ILike => {
+                Err(ParseError::InvalidType("string".into(), left_value.clone()))
+            }
+        },
+        (NodeValue::Blob(_), NodeValue::Null) => Ok(NodeValue::Null),
+        _ => Err(ParseError::InvalidType(
+            "blob".into(),
+            right_value.clone(),
+        )),
+    }
+}
+
+/// Match the text against the LIKE pattern, where `%` matches any sequence
+/// of characters and `_` matches a single character.
+fn matches_like_pattern(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+
+    // position of the last % in the pattern and of the text matched by it
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut t, mut p) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '_' || c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            // let the last % match one more character
+            _ => match backtrack {
+                Some((percent, matched)) => {
+                    backtrack = Some((percent, matched + 1));
+                    p = percent + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
 fn evaluate_bool_op(
     left_value: &NodeValue,
     right_value: &NodeValue,
@@ -300,6 +885,12 @@ fn evaluate_bool_op(
             BoolBinOp::Equal => Ok(NodeValue::Bool(*b1 == *b2)),
             BoolBinOp::NotEqual => Ok(NodeValue::Bool(*b1 != *b2)),
         },
+        (NodeValue::Bool(true), NodeValue::Null) if op == BoolBinOp::Or => {
+            Ok(NodeValue::Bool(true))
+        }
+        (NodeValue::Bool(false), NodeValue::Null) if op == BoolBinOp::And => {
+            Ok(NodeValue::Bool(false))
+        }
         (NodeValue::Bool(_), NodeValue::Null) => Ok(NodeValue::Null),
         _ => Err(ParseError::InvalidType("bool".into(), right_value.clone())),
     }
@@ -388,6 +979,13 @@ mod tests {
             evaluate_expression(stringify!("foo" = "foo")).unwrap(),
             NodeValue::Bool(true)
         );
+
+        // the strings are ordered by their bytes, as in the indexes
+        assert_eq!(evaluate_expression("'n1020' < 'n102'").unwrap(), NodeValue::Bool(false));
+        assert_eq!(evaluate_expression("'Z' < 'a'").unwrap(), NodeValue::Bool(true));
+        assert_eq!(evaluate_expression("'abc' >= 'abc'").unwrap(), NodeValue::Bool(true));
+        assert_eq!(evaluate_expression("'abc' > 'abd'").unwrap(), NodeValue::Bool(false));
+        assert_eq!(evaluate_expression("'abc' <= null").unwrap(), NodeValue::Null);
     }
 
     #[test]
@@ -425,24 +1023,385 @@ mod tests {
             NodeValue::Null
         );
         assert_eq!(
-            evaluate_expression("(nil + \"aa\") = NULL").unwrap(),
+            evaluate_expression("(nil + \"aa\") is null").unwrap(),
             NodeValue::Bool(true)
         );
     }
 
     #[test]
     fn null_eq_null() {
+        assert_eq!(evaluate_expression("nil = NULL").unwrap(), NodeValue::Null);
+        assert_eq!(evaluate_expression("nil != NULL").unwrap(), NodeValue::Null);
+        assert_eq!(evaluate_expression("nil <> NULL").unwrap(), NodeValue::Null);
+    }
+
+    #[test]
+    fn test_is_null() {
+        assert_eq!(
+            evaluate_expression("nil is null").unwrap(),
+            NodeValue::Bool(true)
+        );
         assert_eq!(
-            evaluate_expression("nil = NULL").unwrap(),
+            evaluate_expression("x is null").unwrap(),
+            NodeValue::Bool(false)
+        );
+        assert_eq!(
+            evaluate_expression("x + 1 is not null").unwrap(),
             NodeValue::Bool(true)
         );
+    }
 
-        assert_eq!(evaluate_expression("nil != NULL").unwrap(), NodeValue::Null);
+    #[test]
+    fn test_null_logic() {
+        assert_eq!(
+            evaluate_expression("nil = 1 or x = 100").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("x = 100 or nil = 1").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("nil = 1 and x = 1").unwrap(),
+            NodeValue::Bool(false)
+        );
+        assert_eq!(
+            evaluate_expression("nil = 1 and x = 100").unwrap(),
+            NodeValue::Null
+        );
+    }
 
+    #[test]
+    fn test_in() {
+        assert_eq!(
+            evaluate_expression("x in (1, 100, 3)").unwrap(),
+            NodeValue::Bool(true)
+        );
         assert_eq!(
-            evaluate_expression("nil <> NULL").unwrap(),
+            evaluate_expression("x not in (1, 50 * 2)").unwrap(),
             NodeValue::Bool(false)
         );
+        assert_eq!(
+            evaluate_expression("x in (1, NULL)").unwrap(),
+            NodeValue::Null
+        );
+        assert_eq!(
+            evaluate_expression("x in (100, NULL)").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression(stringify!(abc in ("a", "abc") and x in (100))).unwrap(),
+            NodeValue::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_between() {
+        assert_eq!(
+            evaluate_expression("x between 100 and 200").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("x between 1 and 10 * 5 or x = 100").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("x not between 1 and 99 and x > 0").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("nil between 1 and 2").unwrap(),
+            NodeValue::Null
+        );
+    }
+
+    #[test]
+    fn test_like() {
+        assert_eq!(
+            evaluate_expression(stringify!(abc like "a%")).unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression(stringify!(abc like "_b_")).unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression(stringify!(abc like "%c%c")).unwrap(),
+            NodeValue::Bool(false)
+        );
+        assert_eq!(
+            evaluate_expression(stringify!("abcbc" like "%b_")).unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression(stringify!(abc not like "A%")).unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression(stringify!(abc ilike "A%")).unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression(stringify!(nil like "%")).unwrap(),
+            NodeValue::Null
+        );
+    }
+
+    #[test]
+    fn test_blob() {
+        assert_eq!(
+            evaluate_expression("x'0a' + X'FF00'").unwrap(),
+            NodeValue::Blob(vec![10, 255, 0])
+        );
+        assert_eq!(
+            evaluate_expression("x'0a0b' = x'0a0b'").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("x'' <> nil").unwrap(),
+            NodeValue::Null
+        );
+        assert!(evaluate_expression("x'0a' = abc").is_err());
+        assert!(matches!(
+            lex("x'0a1'"),
+            Err(ParseError::InvalidBlobLiteral(digits)) if digits == "0a1"
+        ));
+    }
+
+    #[test]
+    fn test_bigint_and_decimal() {
+        assert_eq!(
+            evaluate_expression("3000000000 + x").unwrap(),
+            NodeValue::BigInt(3_000_000_100)
+        );
+        assert_eq!(
+            evaluate_expression("-3000000000 * 3 < x").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("decimal '0.10' + decimal '0.2' = decimal '0.3'").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("decimal '1.5' * 3").unwrap(),
+            NodeValue::Decimal(Decimal::new(45, 1))
+        );
+        let NodeValue::Decimal(quotient) = evaluate_expression("decimal '2' / 3").unwrap() else {
+            panic!()
+        };
+        assert_eq!(quotient.to_string(), "0.666667");
+        assert_eq!(
+            evaluate_expression("decimal '1.5' + 0.25").unwrap(),
+            NodeValue::Float(1.75)
+        );
+        assert!(matches!(
+            evaluate_expression("9223372036854775807 + 1"),
+            Err(ParseError::OutOfRange(data_type)) if data_type == "BIGINT"
+        ));
+        assert!(matches!(
+            evaluate_expression("x / 0"),
+            Err(ParseError::DivisionByZero)
+        ));
+        assert!(matches!(
+            evaluate_expression("decimal '1' % decimal '0.0'"),
+            Err(ParseError::DivisionByZero)
+        ));
+        assert!(matches!(
+            lex("select decimal '1.2.3'"),
+            Err(ParseError::InvalidTypedLiteral(..))
+        ));
+    }
+
+    #[test]
+    fn test_date_timestamp_and_uuid() {
+        assert_eq!(
+            evaluate_expression("date '2024-02-28' + 2 = date '2024-03-01'").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("date '2024-03-01' - date '2023-03-01'").unwrap(),
+            NodeValue::Int(366)
+        );
+        assert_eq!(
+            evaluate_expression("date '2024-01-31' < timestamp '2024-01-31 00:00:01'").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("timestamp '2024-01-31 18:00:00' + 0.25 = timestamp '2024-02-01'").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("timestamp '2024-01-02 12:00:00' - date '2024-01-01'").unwrap(),
+            NodeValue::Float(1.5)
+        );
+        assert_eq!(
+            evaluate_expression("uuid 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11' = uuid 'a0eebc999c0b4ef8bb6d6bb9bd380a11'")
+                .unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert!(matches!(
+            evaluate_expression("date '9999-12-31' + 1"),
+            Err(ParseError::OutOfRange(data_type)) if data_type == "DATE"
+        ));
+        assert!(evaluate_expression("date '2024-01-01' * 2").is_err());
+        assert!(evaluate_expression("uuid 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11' + 1").is_err());
+        for literal in ["date '2023-02-29'", "timestamp '2024-01-01 24:00:00'", "uuid 'a0eebc99'"] {
+            assert!(matches!(lex(literal), Err(ParseError::InvalidTypedLiteral(..))));
+        }
+        assert_eq!(
+            lex("timestamp '2024-01-31T12:30:00.250'").unwrap()[0].to_string(),
+            "timestamp '2024-01-31 12:30:00.25'"
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(evaluate_expression("cast('42' as int) + 1").unwrap(), NodeValue::Int(43));
+        assert_eq!(evaluate_expression("'42'::int + 1").unwrap(), NodeValue::Int(43));
+        assert_eq!(evaluate_expression("-x::float / 8").unwrap(), NodeValue::Float(-12.5));
+        assert_eq!(evaluate_expression("(x + 0.5)::int").unwrap(), NodeValue::Int(101));
+        assert_eq!(
+            evaluate_expression("cast(x / 3 as decimal(6, 2))").unwrap(),
+            NodeValue::Decimal(Decimal::new(3300, 2))
+        );
+        assert_eq!(
+            evaluate_expression("cast(x as varchar(2)) + abc::text").unwrap(),
+            NodeValue::String("10abc".into())
+        );
+        assert_eq!(evaluate_expression("'yes'::boolean and x::boolean").unwrap(), NodeValue::Bool(true));
+        assert_eq!(evaluate_expression("nil::int").unwrap(), NodeValue::Null);
+        assert!(matches!(
+            evaluate_expression("abc::int"),
+            Err(ParseError::InvalidTypedLiteral(..))
+        ));
+        assert!(matches!(
+            evaluate_expression("cast(date '2024-01-01' as float)"),
+            Err(ParseError::InvalidCast(..))
+        ));
+        assert!(matches!(evaluate_expression("x::bigint * 3000000000"), Ok(NodeValue::BigInt(300000000000))));
+    }
+
+    #[test]
+    fn test_string_compared_with_typed_values() {
+        assert_eq!(
+            evaluate_expression("date '2024-01-31' = '2024-01-31'").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(
+            evaluate_expression("'2024-01-31 12:00:00' < timestamp '2024-02-01'").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert_eq!(evaluate_expression("decimal '1.50' = '1.5'").unwrap(), NodeValue::Bool(true));
+        assert_eq!(
+            evaluate_expression("date '2024-01-31' in ('2024-01-30', '2024-01-31')").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert!(matches!(
+            evaluate_expression("date '2024-01-31' = 'tomorrow'"),
+            Err(ParseError::InvalidTypedLiteral(..))
+        ));
+        // the strings are converted only in the comparisons
+        assert!(evaluate_expression("date '2024-01-31' + '1'").is_err());
+        assert!(evaluate_expression("x = '100'").is_err());
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let string = |value: &str| NodeValue::String(value.into());
+        assert_eq!(evaluate_expression("upper(abc) + lower('DeF')").unwrap(), string("ABCdef"));
+        assert_eq!(evaluate_expression("length('žluť') + length(x'0a0b')").unwrap(), NodeValue::Int(6));
+        assert_eq!(evaluate_expression("substr('abcdef', 2, 3)").unwrap(), string("bcd"));
+        assert_eq!(evaluate_expression("substr('abcdef', 5)").unwrap(), string("ef"));
+        assert_eq!(evaluate_expression("substr(abc, 0, 2)").unwrap(), string("a"));
+        assert_eq!(evaluate_expression("substr(abc, 10)").unwrap(), string(""));
+        assert_eq!(evaluate_expression("trim('  a b ')").unwrap(), string("a b"));
+        assert_eq!(evaluate_expression("trim('xxaxyx', 'xy')").unwrap(), string("a"));
+        assert_eq!(evaluate_expression("replace('a-b-c', '-', '+')").unwrap(), string("a+b+c"));
+        assert_eq!(evaluate_expression("replace(abc, '', 'x')").unwrap(), string("abc"));
+        assert_eq!(evaluate_expression("concat(abc, '-', x, '-', true)").unwrap(), string("abc-100-true"));
+        assert_eq!(evaluate_expression("CONCAT(nil, abc, nil)").unwrap(), string("abc"));
+        assert!(matches!(
+            evaluate_expression("substr(abc, 1, -1)"),
+            Err(ParseError::InvalidArgument(name, NodeValue::Int(-1))) if name == "substr"
+        ));
+        assert!(matches!(evaluate_expression("upper(x)"), Err(ParseError::InvalidType(..))));
+        assert!(matches!(evaluate_expression("substr(abc, '1')"), Err(ParseError::InvalidType(..))));
+    }
+
+    #[test]
+    fn test_math_functions() {
+        assert_eq!(evaluate_expression("abs(-x) + abs(-3000000000)").unwrap(), NodeValue::BigInt(3_000_000_100));
+        assert_eq!(evaluate_expression("abs(decimal '-1.50')").unwrap(), NodeValue::Decimal(Decimal::new(150, 2)));
+        assert_eq!(evaluate_expression("round(2.5) + round(-2.5)").unwrap(), NodeValue::Float(0.0));
+        assert_eq!(evaluate_expression("round(2.71828, 2)").unwrap(), NodeValue::Float(2.72));
+        assert_eq!(evaluate_expression("round(decimal '2.345', 2)").unwrap(), NodeValue::Decimal(Decimal::new(235, 2)));
+        assert_eq!(evaluate_expression("round(decimal '14.5', -1)").unwrap(), NodeValue::Decimal(Decimal::new(10, 0)));
+        assert_eq!(evaluate_expression("round(x + 50, -2)").unwrap(), NodeValue::Int(200));
+        assert_eq!(evaluate_expression("round(x, 2)").unwrap(), NodeValue::Int(100));
+        assert_eq!(evaluate_expression("floor(-1.5) + ceil(1.2)").unwrap(), NodeValue::Float(0.0));
+        assert_eq!(evaluate_expression("floor(decimal '-1.5')").unwrap(), NodeValue::Decimal(Decimal::new(-2, 0)));
+        assert_eq!(evaluate_expression("ceil(decimal '1.01')").unwrap(), NodeValue::Decimal(Decimal::new(2, 0)));
+        assert_eq!(evaluate_expression("ceil(x)").unwrap(), NodeValue::Int(100));
+        assert_eq!(evaluate_expression("power(2, 10)").unwrap(), NodeValue::Float(1024.0));
+        assert_eq!(evaluate_expression("sqrt(x) * 2").unwrap(), NodeValue::Float(20.0));
+        assert!(matches!(
+            evaluate_expression("sqrt(-1)"),
+            Err(ParseError::InvalidArgument(name, _)) if name == "sqrt"
+        ));
+        assert!(matches!(evaluate_expression("power(-8, 0.5)"), Err(ParseError::InvalidArgument(..))));
+        assert!(matches!(evaluate_expression("power(10, 400)"), Err(ParseError::OutOfRange(..))));
+        assert!(matches!(evaluate_expression("abs(-2147483647 - 1)"), Err(ParseError::OutOfRange(..))));
+        assert!(matches!(evaluate_expression("round(2147483647, -1)"), Err(ParseError::OutOfRange(..))));
+        assert!(matches!(evaluate_expression("abs(abc)"), Err(ParseError::InvalidType(..))));
+    }
+
+    #[test]
+    fn test_null_functions() {
+        assert_eq!(evaluate_expression("coalesce(nil, nil, x, 1)").unwrap(), NodeValue::Int(100));
+        assert_eq!(evaluate_expression("coalesce(nil)").unwrap(), NodeValue::Null);
+        // the arguments after the first value aren't evaluated
+        assert_eq!(evaluate_expression("ifnull(abc, 1 / 0)").unwrap(), NodeValue::String("abc".into()));
+        assert_eq!(evaluate_expression("ifnull(nil, 'none')").unwrap(), NodeValue::String("none".into()));
+        assert_eq!(evaluate_expression("nullif(x, 100)").unwrap(), NodeValue::Null);
+        assert_eq!(evaluate_expression("nullif(x, 1)").unwrap(), NodeValue::Int(100));
+        assert_eq!(evaluate_expression("nullif(x, nil)").unwrap(), NodeValue::Int(100));
+        assert_eq!(evaluate_expression("nullif(nil, 1)").unwrap(), NodeValue::Null);
+        // the other functions are NULL for a NULL argument
+        for expression in ["upper(nil)", "length(nil)", "substr(abc, nil)", "replace(abc, 'a', nil)", "round(x, nil)", "sqrt(nil)"] {
+            assert_eq!(evaluate_expression(expression).unwrap(), NodeValue::Null, "{}", expression);
+        }
+        assert_eq!(evaluate_expression("upper(nil) is null").unwrap(), NodeValue::Bool(true));
+    }
+
+    #[test]
+    fn test_case() {
+        let string = |value: &str| NodeValue::String(value.into());
+        assert_eq!(
+            evaluate_expression("case when x < 18 then 'minor' when x < 65 then 'adult' else 'senior' end").unwrap(),
+            string("senior")
+        );
+        assert_eq!(
+            evaluate_expression("case x / 10 when 1 then 'one' when 10 then 'ten' end").unwrap(),
+            string("ten")
+        );
+        assert_eq!(evaluate_expression("case abc when 'x' then 1 end").unwrap(), NodeValue::Null);
+        // NULL is neither true nor equal to anything
+        assert_eq!(evaluate_expression("case when nil = 1 then 1 else 2 end").unwrap(), NodeValue::Int(2));
+        assert_eq!(evaluate_expression("case nil when nil then 1 else 2 end").unwrap(), NodeValue::Int(2));
+        // only the result of the matching branch is evaluated
+        assert_eq!(evaluate_expression("case when x > 0 then x else x / 0 end").unwrap(), NodeValue::Int(100));
+        assert_eq!(
+            evaluate_expression("case when x > 0 then 1 else 0 end + case when x < 0 then 1 else 0 end").unwrap(),
+            NodeValue::Int(1)
+        );
+        assert_eq!(
+            evaluate_expression("case when abc like 'a%' then upper(abc) end = 'ABC' and x = 100").unwrap(),
+            NodeValue::Bool(true)
+        );
+        assert!(matches!(
+            evaluate_expression("case when x then 1 end"),
+            Err(ParseError::InvalidType(_, NodeValue::Int(100)))
+        ));
     }
 
     #[test]
@@ -476,6 +1435,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_precedence_regression() {
+        // the expressions of the tests above and their parentheses written out by the binding powers
+        let cases = [
+            ("(x - 100) = (10 + 100 - 110)", "(x - 100) = ((10 + 100) - 110)"),
+            ("(x - 101) = -1", "(x - 101) = (-1)"),
+            ("((2 * x) = (3 + (122 * 300)))", "(2 * x) = (3 + (122 * 300))"),
+            ("(nil * 3) + 2", "((nil * 3) + 2)"),
+            ("x + 1 is not null", "not ((x + 1) is null)"),
+            ("nil = 1 and x = 100", "(nil = 1) and (x = 100)"),
+            ("x not in (1, 50 * 2)", "not (x in (1, (50 * 2)))"),
+            ("abc in ('a', 'abc') and x in (100)", "(abc in ('a', 'abc')) and (x in (100))"),
+            ("x between 1 and 10 * 5 or x = 100", "(x between 1 and (10 * 5)) or (x = 100)"),
+            ("x not between 1 and 99 and x > 0", "(not (x between 1 and 99)) and (x > 0)"),
+            ("abc not like 'A%'", "not (abc like 'A%')"),
+            ("-3000000000 * 3 < x", "((-3000000000) * 3) < x"),
+            ("decimal '0.10' + decimal '0.2' = decimal '0.3'", "(decimal '0.10' + decimal '0.2') = decimal '0.3'"),
+            ("date '2024-02-28' + 2 = date '2024-03-01'", "(date '2024-02-28' + 2) = date '2024-03-01'"),
+            ("cast('42' as int) + 1", "(cast(('42') as int)) + 1"),
+            ("-x::float / 8", "(-(x::float)) / 8"),
+            ("'yes'::boolean and x::boolean", "('yes'::boolean) and (x::boolean)"),
+            ("upper(abc) + lower('DeF')", "(upper(abc)) + (lower('DeF'))"),
+            ("sqrt(x) * 2", "(sqrt(x)) * 2"),
+            ("case when x > 0 then 1 else 0 end + case when x < 0 then 1 else 0 end",
+             "(case when (x > 0) then 1 else 0 end) + (case when (x < 0) then 1 else 0 end)"),
+            ("-2 * -3", "(-2) * (-3)"),
+            ("not(x = 100)", "not (x = 100)"),
+            ("false = true xor true", "(false = true) xor true"),
+            ("'abc' + abc = abc + 'abc'", "('abc' + abc) = (abc + 'abc')"),
+            ("100 >= 30 + 10", "100 >= (30 + 10)"),
+            ("(11 + 1) / 2 + 6", "((11 + 1) / 2) + 6"),
+            ("x > 4 and x <= 130.5", "(x > 4) and (x <= 130.5)"),
+            // the operators of the same power are left-associative, AND binds tighter than OR
+            ("10 - 4 - 3", "(10 - 4) - 3"),
+            ("x / 10 / 5", "(x / 10) / 5"),
+            ("false and true or true", "(false and true) or true"),
+            ("true or true xor true", "true or (true xor true)"),
+            // NOT binds looser and the unary minus tighter than the other operators
+            ("not x = 100 or true", "(not (x = 100)) or true"),
+            ("x - -1 * -x", "x - ((-1) * (-x))"),
+            ("x = 100 is not null", "not ((x = 100) is null)"),
+            ("abc like 'a%' = true", "(abc like 'a%') = true"),
+        ];
+        for (expression, parenthesised) in cases {
+            let tree = parse_tree(lex(expression).unwrap()).unwrap();
+            assert_eq!(tree, parse_tree(lex(parenthesised).unwrap()).unwrap(), "{}", expression);
+            assert_eq!(
+                evaluate_expression(expression).unwrap(),
+                evaluate_expression(parenthesised).unwrap(),
+                "{}",
+                expression
+            );
+        }
+        assert_eq!(evaluate_expression("10 - 4 - 3").unwrap(), NodeValue::Int(3));
+        assert_eq!(evaluate_expression("false and true or true").unwrap(), NodeValue::Bool(true));
+    }
+
+    #[test]
+    fn test_aggregate_lookup() {
+        let tree = parse_tree(lex("count(*) + sum(x * 2) > 10").unwrap())
+            .unwrap()
+            .unwrap();
+
+        let mut map = HashMap::new();
+        assert!(matches!(
+            evaluate_node(&tree, &map),
+            Err(ParseError::MisplacedAggregate(key)) if key == "count(*)"
+        ));
+
+        map.insert("count(*)".to_string(), NodeValue::Int(3));
+        map.insert("sum(x * 2)".to_string(), NodeValue::Int(8));
+        assert_eq!(evaluate_node(&tree, &map).unwrap(), NodeValue::Bool(true));
+    }
+
     #[test]
     fn test_operator_precedence() {
         assert_eq!(
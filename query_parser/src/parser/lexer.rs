@@ -1,6 +1,11 @@
 use super::errors::{ParseError, ParseResult};
 use super::tokenizer::tokenize;
 
+use common::models::{
+    datetime::{date_from_string, date_string, timestamp_from_string, timestamp_string},
+    db::{uuid_from_string, uuid_string},
+    decimal::Decimal,
+};
 use std::fmt;
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -8,19 +13,75 @@ pub enum LexerToken {
     Select,
     Insert,
     Delete,
+    Update,
+    Set,
     Create,
     Drop,
+    Alter,
+    Add,
+    Column,
+    Rename,
+    To,
+    Default,
+    Begin,
+    Commit,
+    Rollback,
+    Transaction,
     Table,
     Index,
     Where,
     From,
     Into,
     On,
+    As,
+    Join,
+    Inner,
+    Left,
+    Outer,
+    Is,
+    In,
+    Between,
+    Like,
+    ILike,
     Values,
+    Group,
+    Having,
+    Order,
+    By,
+    Asc,
+    Desc,
+    Nulls,
+    First,
+    Last,
+    Limit,
+    Offset,
+    Using,
+    Primary,
+    Key,
+    Unique,
+    Check,
+    Cast,
+    Case,
+    When,
+    Then,
+    Else,
+    End,
     #[default]
     Null,
     StringLiteral(String),
+    /// The bytes of `x'0a1b'`.
+    BlobLiteral(Vec<u8>),
     NumberLiteral(i32),
+    /// The integer out of the range of INT.
+    BigNumberLiteral(i64),
+    /// `decimal '12.30'`
+    DecimalLiteral(Decimal),
+    /// `date '2024-01-31'`, the days since 1970-01-01.
+    DateLiteral(i32),
+    /// `timestamp '2024-01-31 12:00:00'`, the microseconds since 1970-01-01.
+    TimestampLiteral(i64),
+    /// `uuid 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'`
+    UuidLiteral(u128),
     FloatNumberLiteral(f64), // does not impl Eq
     BoolLiteral(bool),
     Identifier(String),
@@ -40,6 +101,8 @@ pub enum LexerToken {
     Not,
     ExclamationMark,
     Percent,
+    /// `::` of the cast `value::type`
+    DoubleColon,
 }
 
 impl fmt::Display for LexerToken {
@@ -48,18 +111,74 @@ impl fmt::Display for LexerToken {
             LexerToken::Select => write!(f, "select"),
             LexerToken::Insert => write!(f, "insert"),
             LexerToken::Delete => write!(f, "delete"),
+            LexerToken::Update => write!(f, "update"),
+            LexerToken::Set => write!(f, "set"),
             LexerToken::Create => write!(f, "create"),
             LexerToken::Drop => write!(f, "drop"),
+            LexerToken::Alter => write!(f, "alter"),
+            LexerToken::Add => write!(f, "add"),
+            LexerToken::Column => write!(f, "column"),
+            LexerToken::Rename => write!(f, "rename"),
+            LexerToken::To => write!(f, "to"),
+            LexerToken::Default => write!(f, "default"),
+            LexerToken::Begin => write!(f, "begin"),
+            LexerToken::Commit => write!(f, "commit"),
+            LexerToken::Rollback => write!(f, "rollback"),
+            LexerToken::Transaction => write!(f, "transaction"),
             LexerToken::Table => write!(f, "table"),
             LexerToken::Index => write!(f, "index"),
             LexerToken::Where => write!(f, "where"),
             LexerToken::From => write!(f, "from"),
             LexerToken::Into => write!(f, "into"),
             LexerToken::On => write!(f, "on"),
+            LexerToken::As => write!(f, "as"),
+            LexerToken::Join => write!(f, "join"),
+            LexerToken::Inner => write!(f, "inner"),
+            LexerToken::Left => write!(f, "left"),
+            LexerToken::Outer => write!(f, "outer"),
+            LexerToken::Is => write!(f, "is"),
+            LexerToken::In => write!(f, "in"),
+            LexerToken::Between => write!(f, "between"),
+            LexerToken::Like => write!(f, "like"),
+            LexerToken::ILike => write!(f, "ilike"),
             LexerToken::Values => write!(f, "values"),
+            LexerToken::Group => write!(f, "group"),
+            LexerToken::Having => write!(f, "having"),
+            LexerToken::Order => write!(f, "order"),
+            LexerToken::By => write!(f, "by"),
+            LexerToken::Asc => write!(f, "asc"),
+            LexerToken::Desc => write!(f, "desc"),
+            LexerToken::Nulls => write!(f, "nulls"),
+            LexerToken::First => write!(f, "first"),
+            LexerToken::Last => write!(f, "last"),
+            LexerToken::Limit => write!(f, "limit"),
+            LexerToken::Offset => write!(f, "offset"),
+            LexerToken::Using => write!(f, "using"),
+            LexerToken::Primary => write!(f, "primary"),
+            LexerToken::Key => write!(f, "key"),
+            LexerToken::Unique => write!(f, "unique"),
+            LexerToken::Check => write!(f, "check"),
+            LexerToken::Cast => write!(f, "cast"),
+            LexerToken::Case => write!(f, "case"),
+            LexerToken::When => write!(f, "when"),
+            LexerToken::Then => write!(f, "then"),
+            LexerToken::Else => write!(f, "else"),
+            LexerToken::End => write!(f, "end"),
             LexerToken::Null => write!(f, "null"),
             LexerToken::StringLiteral(s) => write!(f, "{}", s),
+            LexerToken::BlobLiteral(bytes) => {
+                write!(f, "x'")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "'")
+            }
             LexerToken::NumberLiteral(i) => write!(f, "{}", i),
+            LexerToken::BigNumberLiteral(i) => write!(f, "{}", i),
+            LexerToken::DecimalLiteral(decimal) => write!(f, "decimal '{}'", decimal),
+            LexerToken::DateLiteral(days) => write!(f, "date '{}'", date_string(*days)),
+            LexerToken::TimestampLiteral(micros) => write!(f, "timestamp '{}'", timestamp_string(*micros)),
+            LexerToken::UuidLiteral(uuid) => write!(f, "uuid '{}'", uuid_string(*uuid)),
             LexerToken::FloatNumberLiteral(fl) => write!(f, "{}", fl),
             LexerToken::BoolLiteral(b) => write!(f, "{}", b),
             LexerToken::Identifier(s) => write!(f, "{}", s),
@@ -77,6 +196,7 @@ impl fmt::Display for LexerToken {
             LexerToken::Not => write!(f, "not"),
             LexerToken::ExclamationMark => write!(f, "!"),
             LexerToken::Percent => write!(f, "%"),
+            LexerToken::DoubleColon => write!(f, "::"),
         }
     }
 }
@@ -87,20 +207,62 @@ pub fn lex(input: &str) -> ParseResult<Vec<LexerToken>> {
     for token_str in tokenize(input)? {
         let token_lower = token_str.to_lowercase();
         match token_lower.as_str() {
-            // todo: "as" ???
             "select" => tokens.push(LexerToken::Select),
             "insert" => tokens.push(LexerToken::Insert),
             "delete" => tokens.push(LexerToken::Delete),
+            "update" => tokens.push(LexerToken::Update),
+            "set" => tokens.push(LexerToken::Set),
             "create" => tokens.push(LexerToken::Create),
             "drop" => tokens.push(LexerToken::Drop),
+            "alter" => tokens.push(LexerToken::Alter),
+            "add" => tokens.push(LexerToken::Add),
+            "column" => tokens.push(LexerToken::Column),
+            "rename" => tokens.push(LexerToken::Rename),
+            "to" => tokens.push(LexerToken::To),
+            "default" => tokens.push(LexerToken::Default),
+            "begin" => tokens.push(LexerToken::Begin),
+            "commit" => tokens.push(LexerToken::Commit),
+            "rollback" => tokens.push(LexerToken::Rollback),
+            "transaction" => tokens.push(LexerToken::Transaction),
             "table" => tokens.push(LexerToken::Table),
             "index" => tokens.push(LexerToken::Index),
-            // we do not need to have 'update' implemented
             "where" => tokens.push(LexerToken::Where),
             "from" => tokens.push(LexerToken::From),
             "into" => tokens.push(LexerToken::Into),
             "on" => tokens.push(LexerToken::On),
+            "as" => tokens.push(LexerToken::As),
+            "join" => tokens.push(LexerToken::Join),
+            "inner" => tokens.push(LexerToken::Inner),
+            "left" => tokens.push(LexerToken::Left),
+            "outer" => tokens.push(LexerToken::Outer),
+            "is" => tokens.push(LexerToken::Is),
+            "in" => tokens.push(LexerToken::In),
+            "between" => tokens.push(LexerToken::Between),
+            "like" => tokens.push(LexerToken::Like),
+            "ilike" => tokens.push(LexerToken::ILike),
             "values" => tokens.push(LexerToken::Values),
+            "group" => tokens.push(LexerToken::Group),
+            "having" => tokens.push(LexerToken::Having),
+            "order" => tokens.push(LexerToken::Order),
+            "by" => tokens.push(LexerToken::By),
+            "asc" => tokens.push(LexerToken::Asc),
+            "desc" => tokens.push(LexerToken::Desc),
+            "nulls" => tokens.push(LexerToken::Nulls),
+            "first" => tokens.push(LexerToken::First),
+            "last" => tokens.push(LexerToken::Last),
+            "limit" => tokens.push(LexerToken::Limit),
+            "offset" => tokens.push(LexerToken::Offset),
+            "using" => tokens.push(LexerToken::Using),
+            "primary" => tokens.push(LexerToken::Primary),
+            "key" => tokens.push(LexerToken::Key),
+            "unique" => tokens.push(LexerToken::Unique),
+            "check" => tokens.push(LexerToken::Check),
+            "cast" => tokens.push(LexerToken::Cast),
+            "case" => tokens.push(LexerToken::Case),
+            "when" => tokens.push(LexerToken::When),
+            "then" => tokens.push(LexerToken::Then),
+            "else" => tokens.push(LexerToken::Else),
+            "end" => tokens.push(LexerToken::End),
             "null" => tokens.push(LexerToken::Null),
             "true" => tokens.push(LexerToken::BoolLiteral(true)),
             "false" => tokens.push(LexerToken::BoolLiteral(false)),
@@ -110,7 +272,8 @@ pub fn lex(input: &str) -> ParseResult<Vec<LexerToken>> {
             "(" => tokens.push(LexerToken::ParOpen),
             ")" => tokens.push(LexerToken::ParClose),
             // TODO: which data types we want to have ?
-            "int" | "varchar" | "float" | "boolean" => {
+            "int" | "varchar" | "float" | "boolean" | "text" | "blob" | "bigint" | "decimal" | "date"
+            | "timestamp" | "uuid" => {
                 tokens.push(LexerToken::DataType(token_lower.clone()))
             }
             "and" | "or" | "xor" => tokens.push(LexerToken::LogicalOp(token_lower.clone())),
@@ -123,16 +286,27 @@ pub fn lex(input: &str) -> ParseResult<Vec<LexerToken>> {
             "," => tokens.push(LexerToken::Comma),
             ";" => tokens.push(LexerToken::Semicolon),
             "!" => tokens.push(LexerToken::ExclamationMark),
+            "::" => tokens.push(LexerToken::DoubleColon),
             _ => {
-                if (token_str.starts_with('"') && token_str.ends_with('"'))
+                if token_lower.starts_with("x'") && token_str.len() > 2 && token_str.ends_with('\'') {
+                    tokens.push(LexerToken::BlobLiteral(blob_from_hex(&token_str[2..token_str.len() - 1])?));
+                } else if (token_str.starts_with('"') && token_str.ends_with('"'))
                     || (token_str.starts_with('\'') && token_str.ends_with('\''))
                 {
-                    tokens.push(LexerToken::StringLiteral(
-                        token_str[1..token_str.len() - 1].into(),
-                    ));
+                    let string = &token_str[1..token_str.len() - 1];
+                    // the string after the data type is the literal of the type, e.g. date '2024-01-31'
+                    match tokens.last() {
+                        Some(LexerToken::DataType(data_type)) if is_typed_literal(data_type) => {
+                            let literal = typed_literal(data_type, string)?;
+                            *tokens.last_mut().unwrap() = literal;
+                        }
+                        _ => tokens.push(LexerToken::StringLiteral(string.into())),
+                    }
                 } else if let Ok(number) = token_lower.parse::<i32>() {
                     // token_lower is already String, use it for num parsing
                     tokens.push(LexerToken::NumberLiteral(number));
+                } else if let Ok(number) = token_lower.parse::<i64>() {
+                    tokens.push(LexerToken::BigNumberLiteral(number));
                 } else if let Ok(number) = token_lower.parse::<f64>() {
                     tokens.push(LexerToken::FloatNumberLiteral(number));
                 } else {
@@ -145,6 +319,12 @@ pub fn lex(input: &str) -> ParseResult<Vec<LexerToken>> {
                             ));
                         }
                     }
+                    // qualified identifier, e.g. table.column
+                    if token_str.split('.').count() > 2
+                        || token_str.split('.').any(|part| part.is_empty())
+                    {
+                        return Err(ParseError::InvalidIdentifier('.', token_str.into()));
+                    }
                     tokens.push(LexerToken::Identifier(token_str.into()));
                 }
             }
@@ -153,6 +333,37 @@ pub fn lex(input: &str) -> ParseResult<Vec<LexerToken>> {
     Ok(tokens)
 }
 
+fn is_typed_literal(data_type: &str) -> bool {
+    matches!(data_type, "decimal" | "date" | "timestamp" | "uuid")
+}
+
+fn typed_literal(data_type: &str, string: &str) -> ParseResult<LexerToken> {
+    let literal = match data_type {
+        "decimal" => Decimal::parse(string).map(LexerToken::DecimalLiteral),
+        "date" => date_from_string(string).map(LexerToken::DateLiteral),
+        "timestamp" => timestamp_from_string(string).map(LexerToken::TimestampLiteral),
+        _ => uuid_from_string(string).map(LexerToken::UuidLiteral),
+    };
+    literal.ok_or_else(|| ParseError::InvalidTypedLiteral(data_type.to_uppercase(), string.to_string()))
+}
+
+/// The bytes of the blob literal given by two hex digits per byte.
+fn blob_from_hex(digits: &str) -> ParseResult<Vec<u8>> {
+    let invalid = || ParseError::InvalidBlobLiteral(digits.to_string());
+    if !digits.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|position| {
+            digits
+                .get(position..position + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -280,6 +491,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_update() {
+        let expr = "update table_name set x = x + 1 where y = 'a'";
+        assert_eq!(
+            vec![
+                LexerToken::Update,
+                LexerToken::Identifier("table_name".to_string()),
+                LexerToken::Set,
+                LexerToken::Identifier("x".to_string()),
+                LexerToken::CompareOp("=".to_string()),
+                LexerToken::Identifier("x".to_string()),
+                LexerToken::Plus,
+                LexerToken::NumberLiteral(1),
+                LexerToken::Where,
+                LexerToken::Identifier("y".to_string()),
+                LexerToken::CompareOp("=".to_string()),
+                LexerToken::StringLiteral("a".to_string()),
+            ],
+            lex(expr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_group_by() {
+        let expr = "select count(*) from t group by x having count(*) > 1";
+        assert_eq!(
+            vec![
+                LexerToken::Select,
+                LexerToken::Identifier("count".to_string()),
+                LexerToken::ParOpen,
+                LexerToken::Star,
+                LexerToken::ParClose,
+                LexerToken::From,
+                LexerToken::Identifier("t".to_string()),
+                LexerToken::Group,
+                LexerToken::By,
+                LexerToken::Identifier("x".to_string()),
+                LexerToken::Having,
+                LexerToken::Identifier("count".to_string()),
+                LexerToken::ParOpen,
+                LexerToken::Star,
+                LexerToken::ParClose,
+                LexerToken::CompareOp(">".to_string()),
+                LexerToken::NumberLiteral(1),
+            ],
+            lex(expr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_join() {
+        let expr = "select a.x from t1 as a left join t2 b on a.x = b.y";
+        assert_eq!(
+            vec![
+                LexerToken::Select,
+                LexerToken::Identifier("a.x".to_string()),
+                LexerToken::From,
+                LexerToken::Identifier("t1".to_string()),
+                LexerToken::As,
+                LexerToken::Identifier("a".to_string()),
+                LexerToken::Left,
+                LexerToken::Join,
+                LexerToken::Identifier("t2".to_string()),
+                LexerToken::Identifier("b".to_string()),
+                LexerToken::On,
+                LexerToken::Identifier("a.x".to_string()),
+                LexerToken::CompareOp("=".to_string()),
+                LexerToken::Identifier("b.y".to_string()),
+            ],
+            lex(expr).unwrap()
+        );
+
+        assert!(lex("select a.b.c from t").is_err());
+        assert!(lex("select a. from t").is_err());
+        assert!(lex("select .b from t").is_err());
+    }
+
+    #[test]
+    fn test_predicates() {
+        let expr = "x IN (1) and y BETWEEN 1 AND 2 and z IS NOT NULL and s ILIKE 'a' or s like '_'";
+        assert_eq!(
+            vec![
+                LexerToken::Identifier("x".to_string()),
+                LexerToken::In,
+                LexerToken::ParOpen,
+                LexerToken::NumberLiteral(1),
+                LexerToken::ParClose,
+                LexerToken::LogicalOp("and".to_string()),
+                LexerToken::Identifier("y".to_string()),
+                LexerToken::Between,
+                LexerToken::NumberLiteral(1),
+                LexerToken::LogicalOp("and".to_string()),
+                LexerToken::NumberLiteral(2),
+                LexerToken::LogicalOp("and".to_string()),
+                LexerToken::Identifier("z".to_string()),
+                LexerToken::Is,
+                LexerToken::Not,
+                LexerToken::Null,
+                LexerToken::LogicalOp("and".to_string()),
+                LexerToken::Identifier("s".to_string()),
+                LexerToken::ILike,
+                LexerToken::StringLiteral("a".to_string()),
+                LexerToken::LogicalOp("or".to_string()),
+                LexerToken::Identifier("s".to_string()),
+                LexerToken::Like,
+                LexerToken::StringLiteral("_".to_string()),
+            ],
+            lex(expr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_alter_table() {
+        let expr = "ALTER TABLE t ADD COLUMN c int DEFAULT 1; alter table t rename column c to d";
+        assert_eq!(
+            vec![
+                LexerToken::Alter,
+                LexerToken::Table,
+                LexerToken::Identifier("t".to_string()),
+                LexerToken::Add,
+                LexerToken::Column,
+                LexerToken::Identifier("c".to_string()),
+                LexerToken::DataType("int".to_string()),
+                LexerToken::Default,
+                LexerToken::NumberLiteral(1),
+                LexerToken::Semicolon,
+                LexerToken::Alter,
+                LexerToken::Table,
+                LexerToken::Identifier("t".to_string()),
+                LexerToken::Rename,
+                LexerToken::Column,
+                LexerToken::Identifier("c".to_string()),
+                LexerToken::To,
+                LexerToken::Identifier("d".to_string()),
+            ],
+            lex(expr).unwrap()
+        );
+    }
+
     #[test]
     fn test_create_table() {
         let expr = "create table table_name x int, y varchar";
@@ -1,3 +1,4 @@
+pub mod cast;
 pub mod errors;
 pub mod expression_tree;
 pub mod expression_tree_eval;
@@ -1,38 +1,164 @@
+use common::models::decimal::MAX_PRECISION;
+
 use super::errors::{ParseError, ParseResult};
 use super::expression_tree::{parse_tree, Node};
 use super::lexer::{lex, LexerToken};
 
 #[derive(Debug, PartialEq)]
-pub enum Query {
-    Select {
-        body: Vec<LexerToken>,
-        table_name: String,
-        where_body: Option<Node>,
+pub struct OrderBy {
+    pub node: Node,
+    pub descending: bool,
+    pub nulls_first: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Limit {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Projection item of the SELECT query, `expression [AS alias]`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SelectItem {
+    pub node: Node,
+    pub alias: Option<String>,
+}
+
+/// Table in the FROM clause, `table_name [AS alias]`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TableReference {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl TableReference {
+    /// The name used to qualify the columns of the table.
+    pub fn qualifier(&self) -> &str {
+        self.alias.as_ref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum JoinKind {
+    Inner,
+    Left,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub table: TableReference,
+    pub on: Node,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SelectQuery {
+    pub body: Vec<SelectItem>,
+    pub table: TableReference,
+    pub joins: Vec<Join>,
+    pub where_body: Option<Node>,
+    pub group_by: Vec<String>,
+    pub having: Option<Node>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<Limit>,
+}
+
+/// Rows of the INSERT query, `VALUES (...), (...)` or `SELECT ...`.
+#[derive(Debug, PartialEq)]
+pub enum InsertSource {
+    Values(Vec<Vec<LexerToken>>),
+    Select(Box<SelectQuery>),
+}
+
+/// Schema change of the ALTER TABLE query.
+#[derive(Debug, PartialEq)]
+pub enum AlterTableAction {
+    /// `ADD [COLUMN] column_name data_type [DEFAULT expression]`
+    AddColumn {
+        column_name: String,
+        data_type: String,
+        default: Option<Node>,
     },
+    /// `DROP [COLUMN] column_name`
+    DropColumn(String),
+    /// `RENAME [COLUMN] old_name TO new_name`
+    RenameColumn { old_name: String, new_name: String },
+    /// `RENAME TO new_name`
+    RenameTable(String),
+}
+
+/// Kind of the index created by `CREATE INDEX ... [USING HASH | BTREE]`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IndexKind {
+    /// Index for `column = value`, the default one.
+    Hash,
+    /// Ordered index, also for ranges, prefixes and ORDER BY.
+    BTree,
+}
+
+/// Column of the CREATE TABLE query,
+/// `name data_type [NOT NULL | NULL] [DEFAULT expression] [CHECK (expression)]`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub data_type: String,
+    pub not_null: bool,
+    pub default: Option<Node>,
+    pub check: Option<Node>,
+}
+
+/// Constraint of the CREATE TABLE query, given after the data type of a column
+/// or as `PRIMARY KEY (a, b)` / `UNIQUE (a, b)` among the columns.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TableConstraint {
+    PrimaryKey(Vec<String>),
+    Unique(Vec<String>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Query {
+    Select(Box<SelectQuery>),
     Insert {
-        values: Vec<LexerToken>,
+        source: InsertSource,
         columns: Vec<String>,
         table_name: String,
     },
     Delete {
         table_name: String,
         where_body: Option<Node>,
+        limit: Option<Limit>,
+    },
+    Update {
+        table_name: String,
+        assignments: Vec<(String, Node)>,
+        where_body: Option<Node>,
     },
     CreateTable {
         table_name: String,
-        columns_definition: Vec<(String, String)>,
+        columns_definition: Vec<ColumnDefinition>,
+        constraints: Vec<TableConstraint>,
     },
+    /// The index of the single column named by the index, unless the columns are listed.
     CreateIndex {
-        column_name: String,
+        index_name: String,
         table_name: String,
+        columns: Vec<String>,
+        kind: IndexKind,
     },
     DropIndex {
-        column_name: String,
+        index_name: String,
         table_name: String,
     },
     DropTable {
         table_name: String,
     },
+    AlterTable {
+        table_name: String,
+        action: AlterTableAction,
+    },
+    Begin,
+    Commit,
+    Rollback,
 }
 
 struct QueryParser {
@@ -76,6 +202,12 @@ impl QueryParser {
                 | LexerToken::BoolLiteral(_)
                 | LexerToken::NumberLiteral(_)
                 | LexerToken::StringLiteral(_)
+                | LexerToken::BlobLiteral(_)
+                | LexerToken::BigNumberLiteral(_)
+                | LexerToken::DecimalLiteral(_)
+                | LexerToken::DateLiteral(_)
+                | LexerToken::TimestampLiteral(_)
+                | LexerToken::UuidLiteral(_)
                 | LexerToken::Star
                 | LexerToken::ParOpen
                 | LexerToken::ParClose
@@ -101,13 +233,50 @@ impl QueryParser {
         Err(ParseError::UnexpectedQueryEnding)
     }
 
+    /// The name of the data type, the length of VARCHAR is kept as `varchar(n)`
+    /// and the precision and scale of DECIMAL as `decimal(p,s)`.
     fn require_datatype(&mut self) -> ParseResult<String> {
-        if let Some(token) = self.next() {
-            return match token.clone() {
+        if let Some(token) = self.next().cloned() {
+            return match token {
+                LexerToken::DataType(datatype) if datatype == "varchar" && self.try_next(LexerToken::ParOpen) => {
+                    let length = match self.next() {
+                        Some(LexerToken::NumberLiteral(length)) if *length > 0 => *length,
+                        Some(token) => {
+                            return Err(ParseError::UnexpectedToken("length of varchar".into(), token.clone()))
+                        }
+                        None => return Err(ParseError::UnexpectedQueryEnding),
+                    };
+                    self.require_token(LexerToken::ParClose)?;
+                    Ok(format!("varchar({})", length))
+                }
+                // DECIMAL(p, s) of at most 18 digits, the scale is 0 without it
+                LexerToken::DataType(datatype) if datatype == "decimal" && self.try_next(LexerToken::ParOpen) => {
+                    let precision = match self.next() {
+                        Some(LexerToken::NumberLiteral(precision)) if (1..=MAX_PRECISION as i32).contains(precision) => {
+                            *precision
+                        }
+                        Some(token) => {
+                            return Err(ParseError::UnexpectedToken("precision of decimal".into(), token.clone()))
+                        }
+                        None => return Err(ParseError::UnexpectedQueryEnding),
+                    };
+                    let mut scale = 0;
+                    if self.try_next(LexerToken::Comma) {
+                        scale = match self.next() {
+                            Some(LexerToken::NumberLiteral(scale)) if (0..=precision).contains(scale) => *scale,
+                            Some(token) => {
+                                return Err(ParseError::UnexpectedToken("scale of decimal".into(), token.clone()))
+                            }
+                            None => return Err(ParseError::UnexpectedQueryEnding),
+                        };
+                    }
+                    self.require_token(LexerToken::ParClose)?;
+                    Ok(format!("decimal({},{})", precision, scale))
+                }
                 LexerToken::DataType(datatype) => Ok(datatype),
                 _ => Err(ParseError::UnexpectedToken(
                     "data-type".into(),
-                    token.clone(),
+                    token,
                 )),
             };
         }
@@ -154,18 +323,7 @@ impl QueryParser {
         let query_type = self.next().ok_or(ParseError::UnexpectedQueryEnding)?;
 
         let query = match query_type {
-            LexerToken::Select => {
-                let body = self.parse_query_body()?;
-                self.require_token(LexerToken::From)?;
-                let table_name = self.require_identifier()?;
-                let where_body = self.parse_where_body()?;
-
-                Ok(Query::Select {
-                    body,
-                    table_name,
-                    where_body,
-                })
-            }
+            LexerToken::Select => Ok(Query::Select(Box::new(self.parse_select()?))),
             LexerToken::Insert => {
                 self.require_token(LexerToken::Into)?;
                 let table_name = self.require_identifier()?;
@@ -179,28 +337,24 @@ impl QueryParser {
                     }
                 }
 
-                self.require_token(LexerToken::Values)?;
-                let is_parenthesised = self.try_next(LexerToken::ParOpen);
-                // todo: restrict this to some subset of 'query body' (e.g. star not allowed)
-                let mut values = self.parse_query_body()?;
-                let last_value = values.last();
-                if is_parenthesised {
-                    if last_value == Some(&LexerToken::ParClose) {
-                        values.pop(); // remove the closing parenthesis from values
+                let source = if self.try_next(LexerToken::Select) {
+                    InsertSource::Select(Box::new(self.parse_select()?))
+                } else {
+                    self.require_token(LexerToken::Values)?;
+                    let rows = if self.head() == Some(&LexerToken::ParOpen) {
+                        self.parse_values_rows()?
                     } else {
-                        return Err(ParseError::UnexpectedToken(
-                            "closing parenthesis".into(),
-                            last_value.unwrap().clone(),
-                        ));
+                        // a single row can be written without the parentheses
+                        vec![self.parse_query_body()?]
+                    };
+                    if !columns.is_empty() && rows.iter().any(|row| row.len() != columns.len()) {
+                        return Err(ParseError::InsertQueryValuesMismatch);
                     }
-                }
-
-                if !columns.is_empty() && (columns.len() != values.len()) {
-                    return Err(ParseError::InsertQueryValuesMismatch);
-                }
+                    InsertSource::Values(rows)
+                };
 
                 Ok(Query::Insert {
-                    values,
+                    source,
                     columns,
                     table_name,
                 })
@@ -209,10 +363,24 @@ impl QueryParser {
                 self.require_token(LexerToken::From)?;
                 let table_name = self.require_identifier()?;
                 let where_body = self.parse_where_body()?;
+                let limit = self.parse_limit()?;
 
                 Ok(Query::Delete {
                     table_name,
                     where_body,
+                    limit,
+                })
+            }
+            LexerToken::Update => {
+                let table_name = self.require_identifier()?;
+                self.require_token(LexerToken::Set)?;
+                let assignments = self.parse_assignments()?;
+                let where_body = self.parse_where_body()?;
+
+                Ok(Query::Update {
+                    table_name,
+                    assignments,
+                    where_body,
                 })
             }
             LexerToken::Create => {
@@ -220,23 +388,34 @@ impl QueryParser {
                     let table_name = self.require_identifier()?;
 
                     let is_parenthesised = self.try_next(LexerToken::ParOpen);
-                    let columns_definition = self.parse_columns_definition()?;
+                    let (columns_definition, constraints) = self.parse_columns_definition()?;
                     if is_parenthesised {
                         self.require_token(LexerToken::ParClose)?;
                     }
                     Ok(Query::CreateTable {
                         table_name,
                         columns_definition,
+                        constraints,
                     })
                 } else {
                     // index
-                    let column_name = self.require_identifier()?;
+                    let index_name = self.require_identifier()?;
                     self.require_token(LexerToken::On)?;
                     let table_name = self.require_identifier()?;
+                    let columns = if self.try_next(LexerToken::ParOpen) {
+                        let columns = self.parse_columns()?;
+                        self.require_token(LexerToken::ParClose)?;
+                        columns
+                    } else {
+                        vec![index_name.clone()]
+                    };
+                    let kind = self.parse_index_kind()?;
 
                     Ok(Query::CreateIndex {
-                        column_name,
+                        index_name,
                         table_name,
+                        columns,
+                        kind,
                     })
                 }
             }
@@ -246,17 +425,36 @@ impl QueryParser {
                     return Ok(Query::DropTable { table_name });
                 } else {
                     // drop index
-                    let column_name = self.require_identifier()?;
+                    let index_name = self.require_identifier()?;
                     self.require_token(LexerToken::On)?;
                     let table_name = self.require_identifier()?;
                     Ok(Query::DropIndex {
-                        column_name,
+                        index_name,
                         table_name,
                     })
                 }
             }
+            LexerToken::Alter => {
+                self.require_token(LexerToken::Table)?;
+                let table_name = self.require_identifier()?;
+                let action = self.parse_alter_table_action()?;
+
+                Ok(Query::AlterTable { table_name, action })
+            }
+            LexerToken::Begin => {
+                self.try_next(LexerToken::Transaction);
+                Ok(Query::Begin)
+            }
+            LexerToken::Commit => {
+                self.try_next(LexerToken::Transaction);
+                Ok(Query::Commit)
+            }
+            LexerToken::Rollback => {
+                self.try_next(LexerToken::Transaction);
+                Ok(Query::Rollback)
+            }
             _ => Err(ParseError::UnexpectedToken(
-                "SELECT/INSERT/DELETE".into(),
+                "SELECT/INSERT/DELETE/UPDATE".into(),
                 query_type.clone(),
             )),
         };
@@ -267,17 +465,289 @@ impl QueryParser {
         query
     }
 
+    /// Parse the SELECT query following the SELECT keyword.
+    fn parse_select(&mut self) -> ParseResult<SelectQuery> {
+        let body = self.parse_projection()?;
+        self.require_token(LexerToken::From)?;
+        let table = self.parse_table_reference()?;
+        let joins = self.parse_joins()?;
+        let where_body = self.parse_where_body()?;
+        let group_by = self.parse_group_by()?;
+        let having = self.parse_having()?;
+        let order_by = self.parse_order_by()?;
+        let limit = self.parse_limit()?;
+
+        Ok(SelectQuery {
+            body,
+            table,
+            joins,
+            where_body,
+            group_by,
+            having,
+            order_by,
+            limit,
+        })
+    }
+
+    /// `[USING HASH | BTREE]`
+    fn parse_index_kind(&mut self) -> ParseResult<IndexKind> {
+        if !self.try_next(LexerToken::Using) {
+            return Ok(IndexKind::Hash);
+        }
+        match self.next() {
+            Some(LexerToken::Identifier(kind)) if kind.eq_ignore_ascii_case("hash") => Ok(IndexKind::Hash),
+            Some(LexerToken::Identifier(kind)) if kind.eq_ignore_ascii_case("btree") => Ok(IndexKind::BTree),
+            Some(token) => Err(ParseError::UnexpectedToken("HASH or BTREE".into(), token.clone())),
+            None => Err(ParseError::UnexpectedQueryEnding),
+        }
+    }
+
+    fn parse_alter_table_action(&mut self) -> ParseResult<AlterTableAction> {
+        let action = self.next().ok_or(ParseError::UnexpectedQueryEnding)?.clone();
+        match action {
+            LexerToken::Add => {
+                self.try_next(LexerToken::Column);
+                let column_name = self.require_identifier()?;
+                let data_type = self.require_datatype()?;
+                let default = if self.try_next(LexerToken::Default) {
+                    let expression = self.collect_expression_tokens(&[]);
+                    Some(parse_tree(expression)?.ok_or(ParseError::UnexpectedQueryEnding)?)
+                } else {
+                    None
+                };
+                Ok(AlterTableAction::AddColumn {
+                    column_name,
+                    data_type,
+                    default,
+                })
+            }
+            LexerToken::Drop => {
+                self.try_next(LexerToken::Column);
+                Ok(AlterTableAction::DropColumn(self.require_identifier()?))
+            }
+            LexerToken::Rename => {
+                if self.try_next(LexerToken::To) {
+                    return Ok(AlterTableAction::RenameTable(self.require_identifier()?));
+                }
+                self.try_next(LexerToken::Column);
+                let old_name = self.require_identifier()?;
+                self.require_token(LexerToken::To)?;
+                let new_name = self.require_identifier()?;
+                Ok(AlterTableAction::RenameColumn { old_name, new_name })
+            }
+            _ => Err(ParseError::UnexpectedToken("ADD/DROP/RENAME".into(), action)),
+        }
+    }
+
+    /// Parse the comma separated rows `(value, ...), (value, ...)` of INSERT.
+    fn parse_values_rows(&mut self) -> ParseResult<Vec<Vec<LexerToken>>> {
+        let mut rows = Vec::new();
+        loop {
+            self.require_token(LexerToken::ParOpen)?;
+            let mut row = vec![self.require_value()?];
+            while self.try_next(LexerToken::Comma) {
+                row.push(self.require_value()?);
+            }
+            self.require_token(LexerToken::ParClose)?;
+            rows.push(row);
+
+            if !self.try_next(LexerToken::Comma) {
+                return Ok(rows);
+            }
+        }
+    }
+
+    fn require_value(&mut self) -> ParseResult<LexerToken> {
+        if let Some(token) = self.next() {
+            return match token {
+                LexerToken::FloatNumberLiteral(_)
+                | LexerToken::BoolLiteral(_)
+                | LexerToken::NumberLiteral(_)
+                | LexerToken::StringLiteral(_)
+                | LexerToken::BlobLiteral(_)
+                | LexerToken::BigNumberLiteral(_)
+                | LexerToken::DecimalLiteral(_)
+                | LexerToken::DateLiteral(_)
+                | LexerToken::TimestampLiteral(_)
+                | LexerToken::UuidLiteral(_)
+                | LexerToken::Null => Ok(token.clone()),
+                _ => Err(ParseError::UnexpectedToken("value".into(), token.clone())),
+            };
+        }
+        Err(ParseError::UnexpectedQueryEnding)
+    }
+
     fn parse_where_body(&mut self) -> ParseResult<Option<Node>> {
-        // where body (the last (optional) part of Query)
+        // where body (optional, only GROUP BY, HAVING, ORDER BY or LIMIT can follow)
         let mut where_body = Vec::new();
         if self.try_next(LexerToken::Where) {
-            while let Some(token) = self.next() {
-                where_body.push(token.clone());
-            }
+            where_body = self.collect_expression_tokens(&[
+                LexerToken::Group,
+                LexerToken::Having,
+                LexerToken::Order,
+                LexerToken::Limit,
+            ]);
         }
         parse_tree(where_body)
     }
 
+    fn parse_table_reference(&mut self) -> ParseResult<TableReference> {
+        let name = self.require_identifier()?;
+        let alias = if self.try_next(LexerToken::As) {
+            Some(self.require_identifier()?)
+        } else {
+            None
+        };
+        Ok(TableReference { name, alias })
+    }
+
+    /// Parse `[INNER | LEFT [OUTER]] JOIN table [AS alias] ON expression` clauses.
+    fn parse_joins(&mut self) -> ParseResult<Vec<Join>> {
+        let mut joins = Vec::new();
+        loop {
+            let kind = if self.try_next(LexerToken::Join) {
+                JoinKind::Inner
+            } else if self.try_next(LexerToken::Inner) {
+                self.require_token(LexerToken::Join)?;
+                JoinKind::Inner
+            } else if self.try_next(LexerToken::Left) {
+                self.try_next(LexerToken::Outer);
+                self.require_token(LexerToken::Join)?;
+                JoinKind::Left
+            } else {
+                return Ok(joins);
+            };
+
+            let table = self.parse_table_reference()?;
+            self.require_token(LexerToken::On)?;
+            let expression = self.collect_expression_tokens(&[
+                LexerToken::Join,
+                LexerToken::Inner,
+                LexerToken::Left,
+                LexerToken::Where,
+                LexerToken::Group,
+                LexerToken::Having,
+                LexerToken::Order,
+                LexerToken::Limit,
+            ]);
+            let on = parse_tree(expression)?.ok_or(ParseError::UnexpectedQueryEnding)?;
+            joins.push(Join { kind, table, on });
+        }
+    }
+
+    fn parse_group_by(&mut self) -> ParseResult<Vec<String>> {
+        if !self.try_next(LexerToken::Group) {
+            return Ok(Vec::new());
+        }
+        self.require_token(LexerToken::By)?;
+        self.parse_columns()
+    }
+
+    fn parse_having(&mut self) -> ParseResult<Option<Node>> {
+        let mut having = Vec::new();
+        if self.try_next(LexerToken::Having) {
+            having = self.collect_expression_tokens(&[LexerToken::Order, LexerToken::Limit]);
+            if having.is_empty() {
+                return Err(ParseError::UnexpectedQueryEnding);
+            }
+        }
+        parse_tree(having)
+    }
+
+    /// Collect the tokens of one expression. The expression ends on a terminator
+    /// token outside of parenthesis, on a semicolon or on the query end.
+    fn collect_expression_tokens(&mut self, terminators: &[LexerToken]) -> Vec<LexerToken> {
+        let mut expression = Vec::new();
+        let mut depth = 0;
+        while let Some(token) = self.head() {
+            match token {
+                LexerToken::Semicolon => break,
+                // the parenthesis enclosing the expression
+                LexerToken::ParClose if depth == 0 && terminators.contains(token) => break,
+                LexerToken::ParOpen => depth += 1,
+                LexerToken::ParClose => depth -= 1,
+                _ if depth == 0 && terminators.contains(token) => break,
+                _ => {}
+            }
+            expression.push(token.clone());
+            self.index += 1;
+        }
+        expression
+    }
+
+    fn parse_order_by(&mut self) -> ParseResult<Vec<OrderBy>> {
+        let mut order_by = Vec::new();
+        if !self.try_next(LexerToken::Order) {
+            return Ok(order_by);
+        }
+        self.require_token(LexerToken::By)?;
+
+        let mut _cont = true;
+        while _cont {
+            let expression = self.collect_expression_tokens(&[
+                LexerToken::Comma,
+                LexerToken::Asc,
+                LexerToken::Desc,
+                LexerToken::Nulls,
+                LexerToken::Limit,
+            ]);
+            let node = parse_tree(expression)?.ok_or(ParseError::UnexpectedQueryEnding)?;
+
+            let descending = if self.try_next(LexerToken::Desc) {
+                true
+            } else {
+                self.try_next(LexerToken::Asc);
+                false
+            };
+            // NULL is treated as the greatest value unless specified otherwise
+            let nulls_first = if self.try_next(LexerToken::Nulls) {
+                match self.next() {
+                    Some(LexerToken::First) => true,
+                    Some(LexerToken::Last) => false,
+                    Some(token) => {
+                        return Err(ParseError::UnexpectedToken(
+                            "FIRST or LAST".into(),
+                            token.clone(),
+                        ))
+                    }
+                    None => return Err(ParseError::UnexpectedQueryEnding),
+                }
+            } else {
+                descending
+            };
+
+            order_by.push(OrderBy {
+                node,
+                descending,
+                nulls_first,
+            });
+            _cont = self.try_next(LexerToken::Comma);
+        }
+        Ok(order_by)
+    }
+
+    /// Parse comma-separated `expression [AS alias]` items of the SELECT query, ended by FROM.
+    fn parse_projection(&mut self) -> ParseResult<Vec<SelectItem>> {
+        let mut projection = Vec::new();
+        let mut _cont = true;
+        while _cont {
+            let expression = self.collect_expression_tokens(&[
+                LexerToken::Comma,
+                LexerToken::From,
+                LexerToken::As,
+            ]);
+            let node = parse_tree(expression)?.ok_or(ParseError::UnexpectedQueryEnding)?;
+            let alias = if self.try_next(LexerToken::As) {
+                Some(self.require_identifier()?)
+            } else {
+                None
+            };
+            projection.push(SelectItem { node, alias });
+            _cont = self.try_next(LexerToken::Comma);
+        }
+        Ok(projection)
+    }
+
     fn parse_query_body(&mut self) -> ParseResult<Vec<LexerToken>> {
         let mut body = Vec::new();
         let mut _cont = true;
@@ -294,20 +764,121 @@ impl QueryParser {
         Ok(body)
     }
 
-    fn parse_columns_definition(&mut self) -> ParseResult<Vec<(String, String)>> {
+    fn parse_limit(&mut self) -> ParseResult<Option<Limit>> {
+        if !self.try_next(LexerToken::Limit) {
+            return Ok(None);
+        }
+        let limit = self.require_non_negative_number()?;
+        let offset = if self.try_next(LexerToken::Offset) {
+            self.require_non_negative_number()?
+        } else {
+            0
+        };
+        Ok(Some(Limit { limit, offset }))
+    }
+
+    fn require_non_negative_number(&mut self) -> ParseResult<usize> {
+        if let Some(token) = self.next() {
+            return match *token {
+                LexerToken::NumberLiteral(number) if number >= 0 => Ok(number as usize),
+                _ => Err(ParseError::UnexpectedToken(
+                    "non-negative number".into(),
+                    token.clone(),
+                )),
+            };
+        }
+        Err(ParseError::UnexpectedQueryEnding)
+    }
+
+    /// Parse comma-separated `column = expression` pairs of the UPDATE query.
+    fn parse_assignments(&mut self) -> ParseResult<Vec<(String, Node)>> {
+        let mut assignments = Vec::new();
+        let mut _cont = true;
+        while _cont {
+            let column = self.require_identifier()?;
+            self.require_token(LexerToken::CompareOp("=".into()))?;
+
+            let expression =
+                self.collect_expression_tokens(&[LexerToken::Comma, LexerToken::Where]);
+            let node = parse_tree(expression)?.ok_or(ParseError::UnexpectedQueryEnding)?;
+            assignments.push((column, node));
+            _cont = self.try_next(LexerToken::Comma);
+        }
+        Ok(assignments)
+    }
+
+    fn parse_columns_definition(&mut self) -> ParseResult<(Vec<ColumnDefinition>, Vec<TableConstraint>)> {
         let mut columns = Vec::new();
+        let mut constraints = Vec::new();
         let mut _cont = true;
-        // comma-separated identifiers
+        // comma-separated columns and constraints of listed columns
         while _cont {
-            let identifier = self.require_identifier()?;
-            let datatype = self.require_datatype()?;
-            columns.push((identifier, datatype));
+            if self.try_next(LexerToken::Primary) {
+                self.require_token(LexerToken::Key)?;
+                constraints.push(TableConstraint::PrimaryKey(self.parse_constraint_columns()?));
+            } else if self.try_next(LexerToken::Unique) {
+                constraints.push(TableConstraint::Unique(self.parse_constraint_columns()?));
+            } else {
+                let name = self.require_identifier()?;
+                let data_type = self.require_datatype()?;
+                let mut column = ColumnDefinition {
+                    name,
+                    data_type,
+                    not_null: false,
+                    default: None,
+                    check: None,
+                };
+                loop {
+                    if self.try_next(LexerToken::Primary) {
+                        self.require_token(LexerToken::Key)?;
+                        constraints.push(TableConstraint::PrimaryKey(vec![column.name.clone()]));
+                    } else if self.try_next(LexerToken::Unique) {
+                        constraints.push(TableConstraint::Unique(vec![column.name.clone()]));
+                    } else if self.try_next(LexerToken::Not) {
+                        self.require_token(LexerToken::Null)?;
+                        column.not_null = true;
+                    } else if self.try_next(LexerToken::Null) {
+                        column.not_null = false;
+                    } else if self.try_next(LexerToken::Default) {
+                        let expression = self.collect_expression_tokens(&[
+                            LexerToken::Comma,
+                            LexerToken::ParClose,
+                            LexerToken::Primary,
+                            LexerToken::Unique,
+                            LexerToken::Not,
+                            LexerToken::Check,
+                        ]);
+                        column.default = Some(parse_tree(expression)?.ok_or(ParseError::UnexpectedQueryEnding)?);
+                    } else if self.try_next(LexerToken::Check) {
+                        self.require_token(LexerToken::ParOpen)?;
+                        let expression = self.collect_expression_tokens(&[LexerToken::ParClose]);
+                        self.require_token(LexerToken::ParClose)?;
+                        column.check = Some(parse_tree(expression)?.ok_or(ParseError::UnexpectedQueryEnding)?);
+                    } else {
+                        break;
+                    }
+                }
+                columns.push(column);
+            }
             _cont = self.try_next(LexerToken::Comma);
         }
+        if columns.is_empty() {
+            return Err(match self.head() {
+                Some(token) => ParseError::UnexpectedToken("identifier".into(), token.clone()),
+                None => ParseError::UnexpectedQueryEnding,
+            });
+        }
+        Ok((columns, constraints))
+    }
+
+    /// `(a, b)` after `PRIMARY KEY` or `UNIQUE`.
+    fn parse_constraint_columns(&mut self) -> ParseResult<Vec<String>> {
+        self.require_token(LexerToken::ParOpen)?;
+        let columns = self.parse_columns()?;
+        self.require_token(LexerToken::ParClose)?;
         Ok(columns)
     }
 
-    #[allow(dead_code)]
     fn parse_columns(&mut self) -> ParseResult<Vec<String>> {
         let mut columns = Vec::new();
         let mut _cont = true;
@@ -328,22 +899,55 @@ pub fn parse(query: &str) -> ParseResult<Query> {
     parser.parse_query()
 }
 
+/// Parse the data type written by the tokens, e.g. `decimal(10,2)` of the cast `value::decimal(10,2)`.
+pub(crate) fn parse_data_type(tokens: Vec<LexerToken>) -> ParseResult<String> {
+    let mut parser = QueryParser::from(tokens);
+    let data_type = parser.require_datatype()?;
+    match parser.head() {
+        Some(token) => Err(ParseError::UnexpectedToken("end of data-type".into(), token.clone())),
+        None => Ok(data_type),
+    }
+}
+
+/// Parse the expression stored as its text, e.g. DEFAULT or CHECK of a column.
+pub fn parse_expression(expression: &str) -> ParseResult<Node> {
+    parse_tree(lex(expression)?)?.ok_or(ParseError::UnexpectedQueryEnding)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::expression_tree::{AggregateFunction, ScalarFunction};
 
     #[test]
     fn test_select() {
         let expr = "select id, name, lastname from person";
-        let expected = Query::Select {
+        let expected = Query::Select(Box::new(SelectQuery {
             body: vec![
-                LexerToken::Identifier("id".to_string()),
-                LexerToken::Identifier("name".to_string()),
-                LexerToken::Identifier("lastname".to_string()),
+                SelectItem {
+                    node: Node::Leaf(LexerToken::Identifier("id".to_string())),
+                    alias: None,
+                },
+                SelectItem {
+                    node: Node::Leaf(LexerToken::Identifier("name".to_string())),
+                    alias: None,
+                },
+                SelectItem {
+                    node: Node::Leaf(LexerToken::Identifier("lastname".to_string())),
+                    alias: None,
+                },
             ],
-            table_name: "person".to_string(),
+            table: TableReference {
+                name: "person".to_string(),
+                alias: None,
+            },
+            joins: Vec::new(),
             where_body: None,
-        };
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None,
+        }));
 
         let result = parse(expr).unwrap();
         assert_eq!(expected, result);
@@ -352,28 +956,355 @@ mod tests {
     #[test]
     fn test_select_with_where() {
         let expr = "select * from person where id = 3";
-        let expected = Query::Select {
-            body: vec![LexerToken::Star],
-            table_name: "person".to_string(),
+        let expected = Query::Select(Box::new(SelectQuery {
+            body: vec![SelectItem {
+                node: Node::Leaf(LexerToken::Star),
+                alias: None,
+            }],
+            table: TableReference {
+                name: "person".to_string(),
+                alias: None,
+            },
+            joins: Vec::new(),
             where_body: Some(Node::new_binary(
                 Node::Leaf(LexerToken::Identifier("id".into())),
                 LexerToken::CompareOp("=".into()),
                 Node::Leaf(LexerToken::NumberLiteral(3)),
             )),
-        };
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None,
+        }));
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_select_with_order_by() {
+        let expr = "select * from person where id > 3 order by age desc, name nulls first, id asc";
+        let expected = Query::Select(Box::new(SelectQuery {
+            body: vec![SelectItem {
+                node: Node::Leaf(LexerToken::Star),
+                alias: None,
+            }],
+            table: TableReference {
+                name: "person".to_string(),
+                alias: None,
+            },
+            joins: Vec::new(),
+            where_body: Some(Node::new_binary(
+                Node::Leaf(LexerToken::Identifier("id".into())),
+                LexerToken::CompareOp(">".into()),
+                Node::Leaf(LexerToken::NumberLiteral(3)),
+            )),
+            group_by: Vec::new(),
+            having: None,
+            order_by: vec![
+                OrderBy {
+                    node: Node::Leaf(LexerToken::Identifier("age".into())),
+                    descending: true,
+                    nulls_first: true,
+                },
+                OrderBy {
+                    node: Node::Leaf(LexerToken::Identifier("name".into())),
+                    descending: false,
+                    nulls_first: true,
+                },
+                OrderBy {
+                    node: Node::Leaf(LexerToken::Identifier("id".into())),
+                    descending: false,
+                    nulls_first: false,
+                },
+            ],
+            limit: None,
+        }));
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_select_order_by_expression() {
+        let expr = "select name from person order by (a - b) desc nulls last";
+        let expected = Query::Select(Box::new(SelectQuery {
+            body: vec![SelectItem {
+                node: Node::Leaf(LexerToken::Identifier("name".to_string())),
+                alias: None,
+            }],
+            table: TableReference {
+                name: "person".to_string(),
+                alias: None,
+            },
+            joins: Vec::new(),
+            where_body: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: vec![OrderBy {
+                node: Node::new_binary(
+                    Node::Leaf(LexerToken::Identifier("a".into())),
+                    LexerToken::Minus,
+                    Node::Leaf(LexerToken::Identifier("b".into())),
+                ),
+                descending: true,
+                nulls_first: false,
+            }],
+            limit: None,
+        }));
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_select_order_by_fails() {
+        assert!(parse("select * from person order age").is_err());
+        assert!(parse("select * from person order by").is_err());
+        assert!(parse("select * from person order by age nulls").is_err());
+        assert!(parse("select * from person order by age nulls desc").is_err());
+    }
+
+    #[test]
+    fn test_select_with_limit() {
+        let expr = "select * from person order by age limit 10 offset 20;";
+        let expected = Query::Select(Box::new(SelectQuery {
+            body: vec![SelectItem {
+                node: Node::Leaf(LexerToken::Star),
+                alias: None,
+            }],
+            table: TableReference {
+                name: "person".to_string(),
+                alias: None,
+            },
+            joins: Vec::new(),
+            where_body: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: vec![OrderBy {
+                node: Node::Leaf(LexerToken::Identifier("age".into())),
+                descending: false,
+                nulls_first: false,
+            }],
+            limit: Some(Limit {
+                limit: 10,
+                offset: 20,
+            }),
+        }));
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+
+        let expr = "select * from person where id > 3 limit 5";
+        match parse(expr).unwrap() {
+            Query::Select(query) => {
+                assert!(query.where_body.is_some());
+                assert_eq!(query.limit, Some(Limit { limit: 5, offset: 0 }));
+            }
+            _ => panic!("expected select query"),
+        }
+    }
+
+    #[test]
+    fn test_limit_fails() {
+        assert!(parse("select * from person limit").is_err());
+        assert!(parse("select * from person limit -1").is_err());
+        assert!(parse("select * from person limit 1.5").is_err());
+        assert!(parse("select * from person limit 5 offset").is_err());
+        assert!(parse("select * from person limit 5 order by age").is_err());
+    }
+
+    #[test]
+    fn test_select_with_group_by() {
+        let expr =
+            "select dept, count(*), avg(salary) as avg_salary from person where age > 30 group by dept having max(salary) > 100 order by dept";
+        let expected = Query::Select(Box::new(SelectQuery {
+            body: vec![
+                SelectItem {
+                    node: Node::Leaf(LexerToken::Identifier("dept".to_string())),
+                    alias: None,
+                },
+                SelectItem {
+                    node: Node::Aggregate {
+                        function: AggregateFunction::Count,
+                        argument: None,
+                    },
+                    alias: None,
+                },
+                SelectItem {
+                    node: Node::Aggregate {
+                        function: AggregateFunction::Avg,
+                        argument: Some(Box::new(Node::Leaf(LexerToken::Identifier(
+                            "salary".to_string(),
+                        )))),
+                    },
+                    alias: Some("avg_salary".to_string()),
+                },
+            ],
+            table: TableReference {
+                name: "person".to_string(),
+                alias: None,
+            },
+            joins: Vec::new(),
+            where_body: Some(Node::new_binary(
+                Node::Leaf(LexerToken::Identifier("age".into())),
+                LexerToken::CompareOp(">".into()),
+                Node::Leaf(LexerToken::NumberLiteral(30)),
+            )),
+            group_by: vec!["dept".to_string()],
+            having: Some(Node::new_binary(
+                Node::Aggregate {
+                    function: AggregateFunction::Max,
+                    argument: Some(Box::new(Node::Leaf(LexerToken::Identifier(
+                        "salary".to_string(),
+                    )))),
+                },
+                LexerToken::CompareOp(">".into()),
+                Node::Leaf(LexerToken::NumberLiteral(100)),
+            )),
+            order_by: vec![OrderBy {
+                node: Node::Leaf(LexerToken::Identifier("dept".into())),
+                descending: false,
+                nulls_first: false,
+            }],
+            limit: None,
+        }));
 
         let result = parse(expr).unwrap();
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_group_by_fails() {
+        assert!(parse("select count(*) from person group dept").is_err());
+        assert!(parse("select count(*) from person group by").is_err());
+        assert!(parse("select count(*) from person group by dept having").is_err());
+        assert!(parse("select count(*) from person having count(*) > 1 group by dept").is_err());
+        assert!(parse("select sum(*) from person").is_err());
+        assert!(parse("select foo(x) from person").is_err());
+        assert!(parse("select count(x from person").is_err());
+    }
+
+    #[test]
+    fn test_select_with_joins() {
+        let expr = "select a.x, b.y from t1 as a join t2 as b on a.x = b.x left outer join t3 on t3.z = b.y where a.x > 1";
+        let result = parse(expr).unwrap();
+        let Query::Select(query) = result else {
+            panic!("expected select query");
+        };
+
+        assert_eq!(
+            query.table,
+            TableReference {
+                name: "t1".to_string(),
+                alias: Some("a".to_string()),
+            }
+        );
+        assert_eq!(
+            query.joins,
+            vec![
+                Join {
+                    kind: JoinKind::Inner,
+                    table: TableReference {
+                        name: "t2".to_string(),
+                        alias: Some("b".to_string()),
+                    },
+                    on: Node::new_binary(
+                        Node::Leaf(LexerToken::Identifier("a.x".into())),
+                        LexerToken::CompareOp("=".into()),
+                        Node::Leaf(LexerToken::Identifier("b.x".into())),
+                    ),
+                },
+                Join {
+                    kind: JoinKind::Left,
+                    table: TableReference {
+                        name: "t3".to_string(),
+                        alias: None,
+                    },
+                    on: Node::new_binary(
+                        Node::Leaf(LexerToken::Identifier("t3.z".into())),
+                        LexerToken::CompareOp("=".into()),
+                        Node::Leaf(LexerToken::Identifier("b.y".into())),
+                    ),
+                },
+            ]
+        );
+        assert!(query.where_body.is_some());
+        assert_eq!(query.joins[1].table.qualifier(), "t3");
+    }
+
+    #[test]
+    fn test_select_with_predicates() {
+        let expr = "select * from t where x in (1, 2) and y not between 1 and 2 and z is not null and s like 'a%'";
+        let Query::Select(query) = parse(expr).unwrap() else {
+            panic!("expected select query");
+        };
+
+        let identifier = |name: &str| Node::Leaf(LexerToken::Identifier(name.into()));
+        let number = |value| Node::Leaf(LexerToken::NumberLiteral(value));
+        let and = || LexerToken::LogicalOp("and".into());
+        // AND is left-associative
+        let expected = Node::new_binary(
+            Node::new_binary(
+                Node::new_binary(
+                    Node::In {
+                        node: Box::new(identifier("x")),
+                        list: vec![number(1), number(2)],
+                    },
+                    and(),
+                    Node::new_unary(
+                        LexerToken::Not,
+                        Node::Between {
+                            node: Box::new(identifier("y")),
+                            low: Box::new(number(1)),
+                            high: Box::new(number(2)),
+                        },
+                    ),
+                ),
+                and(),
+                Node::new_unary(LexerToken::Not, Node::IsNull(Box::new(identifier("z")))),
+            ),
+            and(),
+            Node::new_binary(
+                identifier("s"),
+                LexerToken::Like,
+                Node::Leaf(LexerToken::StringLiteral("a%".into())),
+            ),
+        );
+        assert_eq!(query.where_body, Some(expected));
+        assert_eq!(
+            query.where_body.unwrap().to_string(),
+            "(((x in (1, 2)) and (not (y between 1 and 2))) and (not (z is null))) and (s like 'a%')"
+        );
+    }
+
+    #[test]
+    fn test_predicates_fail() {
+        assert!(parse("select * from t where x in ()").is_err());
+        assert!(parse("select * from t where x in 1, 2").is_err());
+        assert!(parse("select * from t where x between 1").is_err());
+        assert!(parse("select * from t where x between 1 or 2").is_err());
+        assert!(parse("select * from t where x is 1").is_err());
+        assert!(parse("select * from t where x not = 1").is_err());
+    }
+
+    #[test]
+    fn test_join_fails() {
+        assert!(parse("select * from t1 join t2").is_err());
+        assert!(parse("select * from t1 join t2 on").is_err());
+        assert!(parse("select * from t1 left t2 on t1.x = t2.x").is_err());
+        assert!(parse("select * from t1 inner t2 on t1.x = t2.x").is_err());
+        assert!(parse("select * from t1 as join t2 on t1.x = t2.x").is_err());
+    }
+
     #[test]
     fn test_insert() {
         let expr = "insert into mira values 'Mira', 24";
         let expected = Query::Insert {
-            values: vec![
+            source: InsertSource::Values(vec![vec![
                 LexerToken::StringLiteral("Mira".to_string()),
                 LexerToken::NumberLiteral(24),
-            ],
+            ]]),
             columns: Vec::new(),
             table_name: "mira".into(),
         };
@@ -386,10 +1317,10 @@ mod tests {
     fn test_insert_parentheses() {
         let expr = "insert into mira values ('Mira', 24)";
         let expected = Query::Insert {
-            values: vec![
+            source: InsertSource::Values(vec![vec![
                 LexerToken::StringLiteral("Mira".to_string()),
                 LexerToken::NumberLiteral(24),
-            ],
+            ]]),
             columns: Vec::new(),
             table_name: "mira".into(),
         };
@@ -402,11 +1333,11 @@ mod tests {
     fn test_insert_selected_columns() {
         let expr = "insert into mira (abc, def, ijk) values ('Mira', 24, 33)";
         let expected = Query::Insert {
-            values: vec![
+            source: InsertSource::Values(vec![vec![
                 LexerToken::StringLiteral("Mira".to_string()),
                 LexerToken::NumberLiteral(24),
                 LexerToken::NumberLiteral(33),
-            ],
+            ]]),
             columns: vec!["abc".into(), "def".into(), "ijk".into()],
             table_name: "mira".into(),
         };
@@ -415,26 +1346,213 @@ mod tests {
         assert_eq!(expected, result);
     }
 
-    // currently, we don't support this
-    // #[test]
-    // fn test_select_with_expr() {
-    //     let expr = "select (app_resets - pda_resets), lastname from person";
-    //     let expected =
-    //         Query::Select {
-    //             body: vec![
-    //                 LexerToken::ParOpen,
-    //                 LexerToken::Identifier("app_resets".to_string()),
-    //                 LexerToken::Minus,
-    //                 LexerToken::Identifier("pda_resets".to_string()),
-    //                 LexerToken::ParClose,
-    //                 LexerToken::Identifier("lastname".to_string())
-    //             ],
-    //             table_name: "person".to_string(),
-    //             where_body: Vec::new() };
-
-    //     let result = parse(expr).unwrap();
-    //     assert_eq!(expected, result);
-    // }
+    #[test]
+    fn test_insert_multiple_rows() {
+        let expr = "insert into mira (abc, def) values ('Mira', 24), ('Ema', NULL);";
+        let expected = Query::Insert {
+            source: InsertSource::Values(vec![
+                vec![
+                    LexerToken::StringLiteral("Mira".to_string()),
+                    LexerToken::NumberLiteral(24),
+                ],
+                vec![
+                    LexerToken::StringLiteral("Ema".to_string()),
+                    LexerToken::Null,
+                ],
+            ]),
+            columns: vec!["abc".into(), "def".into()],
+            table_name: "mira".into(),
+        };
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_insert_select() {
+        let expr = "insert into mira (abc) select x from other where x > 1 limit 2";
+        let Query::Insert {
+            source: InsertSource::Select(query),
+            columns,
+            table_name,
+        } = parse(expr).unwrap()
+        else {
+            panic!("expected insert with select");
+        };
+
+        assert_eq!(columns, vec!["abc".to_string()]);
+        assert_eq!(table_name, "mira");
+        assert_eq!(query.table.name, "other");
+        assert!(query.where_body.is_some());
+        assert_eq!(query.limit, Some(Limit { limit: 2, offset: 0 }));
+    }
+
+    #[test]
+    fn test_insert_fails() {
+        assert!(matches!(
+            parse("insert into mira (abc, def) values ('Mira', 24), ('Ema')"),
+            Err(ParseError::InsertQueryValuesMismatch)
+        ));
+        assert!(parse("insert into mira values ('Mira', 24) ('Ema', 1)").is_err());
+        assert!(parse("insert into mira values ('Mira', 24),").is_err());
+        assert!(parse("insert into mira values ('Mira', x)").is_err());
+        assert!(parse("insert into mira values ('Mira', 24").is_err());
+        assert!(parse("insert into mira select from other").is_err());
+    }
+
+    #[test]
+    fn test_select_with_expr() {
+        let expr = "select (app_resets - pda_resets) as diff, lastname from person";
+        let expected = Query::Select(Box::new(SelectQuery {
+            body: vec![
+                SelectItem {
+                    node: Node::new_binary(
+                        Node::Leaf(LexerToken::Identifier("app_resets".to_string())),
+                        LexerToken::Minus,
+                        Node::Leaf(LexerToken::Identifier("pda_resets".to_string())),
+                    ),
+                    alias: Some("diff".to_string()),
+                },
+                SelectItem {
+                    node: Node::Leaf(LexerToken::Identifier("lastname".to_string())),
+                    alias: None,
+                },
+            ],
+            table: TableReference {
+                name: "person".to_string(),
+                alias: None,
+            },
+            joins: Vec::new(),
+            where_body: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None,
+        }));
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_select_with_cast() {
+        let Query::Select(query) = parse("select cast(a + 1 as decimal(10, 2)) as price, -b::int from t").unwrap() else {
+            panic!()
+        };
+        assert_eq!(
+            query.body[0].node,
+            Node::Cast {
+                node: Box::new(Node::new_binary(
+                    Node::Leaf(LexerToken::Identifier("a".to_string())),
+                    LexerToken::Plus,
+                    Node::Leaf(LexerToken::NumberLiteral(1)),
+                )),
+                data_type: "decimal(10,2)".to_string(),
+            }
+        );
+        assert_eq!(query.body[0].alias, Some("price".to_string()));
+        assert_eq!(query.body[1].node.to_string(), "-cast(b as int)");
+        // the text of the expression is parsed back to the same tree
+        let text = query.body[0].node.to_string();
+        assert_eq!(parse_expression(&text).unwrap(), query.body[0].node);
+
+        assert!(parse("select cast(a as) from t").is_err());
+        assert!(parse("select cast(a int) from t").is_err());
+        assert!(parse("select a::decimal(20) from t").is_err());
+        assert!(parse("select a::b from t").is_err());
+    }
+
+    #[test]
+    fn test_select_with_functions() {
+        let Query::Select(query) = parse("select upper(name), round(avg(price) * 1.1, -2) from t").unwrap() else {
+            panic!()
+        };
+        assert_eq!(
+            query.body[0].node,
+            Node::Call {
+                function: ScalarFunction::Upper,
+                arguments: vec![Node::Leaf(LexerToken::Identifier("name".to_string()))],
+            }
+        );
+        assert_eq!(query.body[1].node.to_string(), "round(avg(price) * 1.1, -2)");
+        assert!(query.body[1].node.contains_aggregate());
+
+        let node = parse_expression("coalesce(substr(a, 2, n + 1), 'x') + concat(b, 1)::text").unwrap();
+        let Node::Binary { left, .. } = &node else { panic!() };
+        assert_eq!(left.to_string(), "coalesce(substr(a, 2, n + 1), 'x')");
+        // the text of the expression is parsed back to the same tree
+        assert_eq!(parse_expression(&node.to_string()).unwrap(), node);
+
+        assert!(matches!(
+            parse("select lpad(a, 3) from t"),
+            Err(ParseError::UnknownFunction(name)) if name == "lpad"
+        ));
+        assert!(matches!(
+            parse("select sqrt(a, 2) from t"),
+            Err(ParseError::InvalidArgumentCount(name, 2)) if name == "sqrt"
+        ));
+        assert!(parse("select upper(a from t").is_err());
+        assert!(parse("select concat(a,) from t").is_err());
+    }
+
+    #[test]
+    fn test_select_with_case() {
+        let Query::Select(query) = parse(
+            "select case when age < 18 then 'minor' when age between 18 and 64 then 'adult' end as bucket, \
+             case status when 1 then 'new' else 'other' end from t",
+        )
+        .unwrap() else {
+            panic!()
+        };
+        let identifier = |name: &str| Node::Leaf(LexerToken::Identifier(name.to_string()));
+        let Node::Case {
+            operand: None,
+            branches,
+            else_result: None,
+        } = &query.body[0].node
+        else {
+            panic!()
+        };
+        assert_eq!(
+            branches[0],
+            (
+                Node::new_binary(identifier("age"), LexerToken::CompareOp("<".into()), Node::Leaf(LexerToken::NumberLiteral(18))),
+                Node::Leaf(LexerToken::StringLiteral("minor".into())),
+            )
+        );
+        assert!(matches!(branches[1].0, Node::Between { .. }));
+        assert_eq!(query.body[0].alias, Some("bucket".to_string()));
+        assert_eq!(
+            query.body[1].node,
+            Node::Case {
+                operand: Some(Box::new(identifier("status"))),
+                branches: vec![(
+                    Node::Leaf(LexerToken::NumberLiteral(1)),
+                    Node::Leaf(LexerToken::StringLiteral("new".into())),
+                )],
+                else_result: Some(Box::new(Node::Leaf(LexerToken::StringLiteral("other".into())))),
+            }
+        );
+        // the text of the expression is parsed back to the same tree
+        for item in &query.body {
+            assert_eq!(parse_expression(&item.node.to_string()).unwrap(), item.node);
+        }
+
+        let node = parse_expression("1 + case -x when y + 1 then 2 else 3 end * 2 = 7").unwrap();
+        assert_eq!(node.to_string(), "(1 + (case -x when y + 1 then 2 else 3 end * 2)) = 7");
+
+        assert!(parse("select case end from t").is_err());
+        assert!(parse("select case x else 1 end from t").is_err());
+        assert!(parse("select case when a then 1 from t").is_err());
+        assert!(parse("select case when a 1 end from t").is_err());
+    }
+
+    #[test]
+    fn test_select_alias_fails() {
+        assert!(parse("select a as from person").is_err());
+        assert!(parse("select a as 'b' from person").is_err());
+        assert!(parse("select as b from person").is_err());
+    }
 
     #[test]
     fn test_delete() {
@@ -446,22 +1564,185 @@ mod tests {
                 LexerToken::CompareOp(">".into()),
                 Node::Leaf(LexerToken::NumberLiteral(1)),
             )),
+            limit: None,
+        };
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_update() {
+        let expr = "update person set age = age + 1, name = 'Mira' where id = 3";
+        let expected = Query::Update {
+            table_name: "person".to_string(),
+            assignments: vec![
+                (
+                    "age".to_string(),
+                    Node::new_binary(
+                        Node::Leaf(LexerToken::Identifier("age".into())),
+                        LexerToken::Plus,
+                        Node::Leaf(LexerToken::NumberLiteral(1)),
+                    ),
+                ),
+                (
+                    "name".to_string(),
+                    Node::Leaf(LexerToken::StringLiteral("Mira".into())),
+                ),
+            ],
+            where_body: Some(Node::new_binary(
+                Node::Leaf(LexerToken::Identifier("id".into())),
+                LexerToken::CompareOp("=".into()),
+                Node::Leaf(LexerToken::NumberLiteral(3)),
+            )),
         };
 
         let result = parse(expr).unwrap();
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_update_without_where() {
+        let expr = "update person set active = (age > 18)";
+        let expected = Query::Update {
+            table_name: "person".to_string(),
+            assignments: vec![(
+                "active".to_string(),
+                Node::new_binary(
+                    Node::Leaf(LexerToken::Identifier("age".into())),
+                    LexerToken::CompareOp(">".into()),
+                    Node::Leaf(LexerToken::NumberLiteral(18)),
+                ),
+            )],
+            where_body: None,
+        };
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_update_fails_without_value() {
+        assert!(parse("update person set age = where id = 3").is_err());
+        assert!(parse("update person set age").is_err());
+    }
+
+    #[test]
+    fn test_delete_with_limit() {
+        let expr = "delete from table_name where x > 1 limit 2 offset 1";
+        let expected = Query::Delete {
+            table_name: "table_name".to_string(),
+            where_body: Some(Node::new_binary(
+                Node::Leaf(LexerToken::Identifier("x".into())),
+                LexerToken::CompareOp(">".into()),
+                Node::Leaf(LexerToken::NumberLiteral(1)),
+            )),
+            limit: Some(Limit {
+                limit: 2,
+                offset: 1,
+            }),
+        };
+
+        let result = parse(expr).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_alter_table() {
+        let alter = |action| Query::AlterTable {
+            table_name: "t".into(),
+            action,
+        };
+        assert_eq!(
+            parse("alter table t add column c varchar default 'x'").unwrap(),
+            alter(AlterTableAction::AddColumn {
+                column_name: "c".into(),
+                data_type: "varchar".into(),
+                default: Some(Node::Leaf(LexerToken::StringLiteral("x".into()))),
+            })
+        );
+        assert_eq!(
+            parse("alter table t add column c int default -4;").unwrap(),
+            alter(AlterTableAction::AddColumn {
+                column_name: "c".into(),
+                data_type: "int".into(),
+                default: Some(parse_expression("-4").unwrap()),
+            })
+        );
+        assert_eq!(
+            parse("alter table t add c int default 1 + 1").unwrap(),
+            alter(AlterTableAction::AddColumn {
+                column_name: "c".into(),
+                data_type: "int".into(),
+                default: Some(parse_expression("1 + 1").unwrap()),
+            })
+        );
+        assert_eq!(
+            parse("alter table t add c int").unwrap(),
+            alter(AlterTableAction::AddColumn {
+                column_name: "c".into(),
+                data_type: "int".into(),
+                default: None,
+            })
+        );
+        assert_eq!(
+            parse("alter table t drop column c").unwrap(),
+            alter(AlterTableAction::DropColumn("c".into()))
+        );
+        assert_eq!(
+            parse("alter table t rename c to d;").unwrap(),
+            alter(AlterTableAction::RenameColumn {
+                old_name: "c".into(),
+                new_name: "d".into(),
+            })
+        );
+        assert_eq!(
+            parse("alter table t rename to u").unwrap(),
+            alter(AlterTableAction::RenameTable("u".into()))
+        );
+    }
+
+    #[test]
+    fn test_alter_table_fails() {
+        assert!(parse("alter t add column c int").is_err());
+        assert!(parse("alter table t add column c").is_err());
+        assert!(parse("alter table t add column c int default").is_err());
+        assert!(parse("alter table t add column c int default 1 +").is_err());
+        assert!(parse("alter table t rename column c d").is_err());
+        assert!(parse("alter table t modify c int").is_err());
+    }
+
+    #[test]
+    fn test_transaction_control() {
+        assert_eq!(parse("begin").unwrap(), Query::Begin);
+        assert_eq!(parse("BEGIN TRANSACTION;").unwrap(), Query::Begin);
+        assert_eq!(parse("commit").unwrap(), Query::Commit);
+        assert_eq!(parse("rollback transaction").unwrap(), Query::Rollback);
+        assert!(parse("begin select").is_err());
+        assert!(parse("commit table").is_err());
+    }
+
+    fn column_definition(name: &str, data_type: &str) -> ColumnDefinition {
+        ColumnDefinition {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            not_null: false,
+            default: None,
+            check: None,
+        }
+    }
+
     #[test]
     fn test_create_table() {
         let expr = "create table table_name x int, y varchar, bool_column boolean";
         let expected = Query::CreateTable {
             table_name: "table_name".to_string(),
             columns_definition: vec![
-                ("x".to_string(), "int".to_string()),
-                ("y".to_string(), "varchar".to_string()),
-                ("bool_column".to_string(), "boolean".to_string()),
+                column_definition("x", "int"),
+                column_definition("y", "varchar"),
+                column_definition("bool_column", "boolean"),
             ],
+            constraints: vec![],
         };
 
         let result = parse(expr).unwrap();
@@ -474,16 +1755,135 @@ mod tests {
         let expected = Query::CreateTable {
             table_name: "table_name".to_string(),
             columns_definition: vec![
-                ("x".to_string(), "int".to_string()),
-                ("y".to_string(), "varchar".to_string()),
-                ("bool_column".to_string(), "boolean".to_string()),
+                column_definition("x", "int"),
+                column_definition("y", "varchar"),
+                column_definition("bool_column", "boolean"),
             ],
+            constraints: vec![],
         };
 
         let result = parse(expr).unwrap();
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_create_table_constraints() {
+        let expr = "create table t (id int primary key, a int, b varchar unique, unique (a, b))";
+        let expected = Query::CreateTable {
+            table_name: "t".to_string(),
+            columns_definition: vec![
+                column_definition("id", "int"),
+                column_definition("a", "int"),
+                column_definition("b", "varchar"),
+            ],
+            constraints: vec![
+                TableConstraint::PrimaryKey(vec!["id".to_string()]),
+                TableConstraint::Unique(vec!["b".to_string()]),
+                TableConstraint::Unique(vec!["a".to_string(), "b".to_string()]),
+            ],
+        };
+        assert_eq!(expected, parse(expr).unwrap());
+
+        let expr = "create table t (a int, b int, primary key (a, b))";
+        let Query::CreateTable { constraints, .. } = parse(expr).unwrap() else {
+            panic!()
+        };
+        assert_eq!(
+            constraints,
+            vec![TableConstraint::PrimaryKey(vec!["a".to_string(), "b".to_string()])]
+        );
+
+        assert!(parse("create table t (a int primary)").is_err());
+        assert!(parse("create table t (a int, unique a)").is_err());
+        assert!(parse("create table t (primary key (a))").is_err());
+    }
+
+    #[test]
+    fn test_create_table_column_constraints() {
+        let expr = "create table t (id int not null primary key, x int default 1 + 2 check (x > 0 and x < id), \
+                    y varchar null default 'a', z int check (z in (1, 2)) not null)";
+        let Query::CreateTable {
+            columns_definition,
+            constraints,
+            ..
+        } = parse(expr).unwrap()
+        else {
+            panic!()
+        };
+        assert_eq!(constraints, vec![TableConstraint::PrimaryKey(vec!["id".to_string()])]);
+        let not_null: Vec<bool> = columns_definition.iter().map(|column| column.not_null).collect();
+        assert_eq!(not_null, vec![true, false, false, true]);
+        let expressions: Vec<(Option<String>, Option<String>)> = columns_definition
+            .iter()
+            .map(|column| {
+                (
+                    column.default.as_ref().map(Node::to_string),
+                    column.check.as_ref().map(Node::to_string),
+                )
+            })
+            .collect();
+        assert_eq!(
+            expressions,
+            vec![
+                (None, None),
+                (Some("1 + 2".to_string()), Some("(x > 0) and (x < id)".to_string())),
+                (Some("'a'".to_string()), None),
+                (None, Some("z in (1, 2)".to_string())),
+            ]
+        );
+        assert_eq!(
+            parse_expression("(x > 0) and (x < id)").unwrap(),
+            columns_definition[1].check.clone().unwrap()
+        );
+
+        assert!(parse("create table t (x int not)").is_err());
+        assert!(parse("create table t (x int default)").is_err());
+        assert!(parse("create table t (x int check x > 0)").is_err());
+    }
+
+    #[test]
+    fn test_create_table_variable_size_types() {
+        let Query::CreateTable {
+            columns_definition, ..
+        } = parse("create table t (a varchar(10), b varchar, c text, d blob)").unwrap()
+        else {
+            panic!()
+        };
+        let data_types: Vec<&str> = columns_definition
+            .iter()
+            .map(|column| column.data_type.as_str())
+            .collect();
+        assert_eq!(data_types, vec!["varchar(10)", "varchar", "text", "blob"]);
+
+        assert!(parse("create table t (a varchar(0))").is_err());
+        assert!(parse("create table t (a varchar(x))").is_err());
+        assert!(parse("insert into t values (x'0a1')").is_err());
+    }
+
+    #[test]
+    fn test_create_table_scalar_types() {
+        let Query::CreateTable {
+            columns_definition, ..
+        } = parse("create table t (a bigint, b decimal(10,2), c decimal(5), d decimal, e date, f timestamp, g uuid)")
+            .unwrap()
+        else {
+            panic!()
+        };
+        let data_types: Vec<&str> = columns_definition
+            .iter()
+            .map(|column| column.data_type.as_str())
+            .collect();
+        assert_eq!(
+            data_types,
+            vec!["bigint", "decimal(10,2)", "decimal(5,0)", "decimal", "date", "timestamp", "uuid"]
+        );
+
+        assert!(parse("create table t (a decimal(19,2))").is_err());
+        assert!(parse("create table t (a decimal(0))").is_err());
+        assert!(parse("create table t (a decimal(4,5))").is_err());
+        assert!(parse("insert into t values (date '2024-02-30')").is_err());
+    }
+
     #[test]
     fn test_drop_table() {
         let expr = "drop table table_name";
@@ -499,7 +1899,7 @@ mod tests {
     fn test_drop_index() {
         let expr = "drop index column_name on table_name";
         let expected = Query::DropIndex {
-            column_name: "column_name".to_string(),
+            index_name: "column_name".to_string(),
             table_name: "table_name".to_string(),
         };
 
@@ -511,8 +1911,10 @@ mod tests {
     fn test_create_index() {
         let expr = "create index column_name on table_name";
         let expected = Query::CreateIndex {
-            column_name: "column_name".to_string(),
+            index_name: "column_name".to_string(),
             table_name: "table_name".to_string(),
+            columns: vec!["column_name".to_string()],
+            kind: IndexKind::Hash,
         };
 
         let result = parse(expr).unwrap();
@@ -520,9 +1922,36 @@ mod tests {
     }
 
     #[test]
-    fn test_create_index_fails_multiple_columns() {
-        let expr = "create index index_name on table_name (column1, column2)";
-        let result = parse(expr);
-        assert!(result.is_err());
+    fn test_create_index_using_btree() {
+        let expr = "create index column_name on table_name using BTREE";
+        let expected = Query::CreateIndex {
+            index_name: "column_name".to_string(),
+            table_name: "table_name".to_string(),
+            columns: vec!["column_name".to_string()],
+            kind: IndexKind::BTree,
+        };
+        assert_eq!(expected, parse(expr).unwrap());
+
+        let expr = "create index column_name on table_name using hash";
+        assert!(matches!(parse(expr), Ok(Query::CreateIndex { kind: IndexKind::Hash, .. })));
+        let expr = "create index column_name on table_name using gist";
+        assert!(parse(expr).is_err());
+    }
+
+    #[test]
+    fn test_create_index_multiple_columns() {
+        let expr = "create index index_name on table_name (column1, column2) using btree";
+        let expected = Query::CreateIndex {
+            index_name: "index_name".to_string(),
+            table_name: "table_name".to_string(),
+            columns: vec!["column1".to_string(), "column2".to_string()],
+            kind: IndexKind::BTree,
+        };
+        assert_eq!(expected, parse(expr).unwrap());
+
+        let expr = "create index index_name on table_name (column1,)";
+        assert!(parse(expr).is_err());
+        let expr = "create index index_name on table_name ()";
+        assert!(parse(expr).is_err());
     }
 }
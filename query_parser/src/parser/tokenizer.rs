@@ -14,7 +14,7 @@ pub fn tokenize(input: &str) -> ParseResult<Vec<&str>> {
         Normal,                 // ^---------------------------------------^                ^
         StrLit,                 // iterator is inside the string literal    ^--------------^
         StrLitEscapedChar,      // current char is to be escaped                   ^      ^
-        DoubleCharSizeOperator, // current char is second char of double char size operator - >=, <=, <>, !=
+        DoubleCharSizeOperator, // current char is second char of double char size operator - >=, <=, <>, !=, ::
     }
     let mut state = State::Normal;
     let mut token_start_i: usize = 0;
@@ -41,10 +41,10 @@ pub fn tokenize(input: &str) -> ParseResult<Vec<&str>> {
                     '\\' => {
                         return Err(ParseError::InvalidChar('\\', char_pos));
                     }
-                    '>' | '<' | '!' => {
+                    '>' | '<' | '!' | ':' => {
                         if let Some(next_char) = input[token_current_i..].chars().next() {
                             match format!("{char}{next_char}").as_str() {
-                                "<=" | ">=" | "<>" | "!=" => {
+                                "<=" | ">=" | "<>" | "!=" | "::" => {
                                     state = State::DoubleCharSizeOperator;
                                 }
                                 _ => {
@@ -74,10 +74,10 @@ pub fn tokenize(input: &str) -> ParseResult<Vec<&str>> {
                         tokens.push(&input[token_start_i..token_current_i]);
                         token_start_i = token_current_i;
 
-                        // check that the next char is either of [' ', ',', ';', '=']
+                        // check that the next char is either of [' ', ',', ';', '=', ')', ':']
                         // e.g. "hello"-1 is invalid
                         if let Some(next_char) = input[token_current_i..].chars().next() {
-                            if ![' ', ',', ';', '=', ')'].contains(&next_char) {
+                            if ![' ', ',', ';', '=', ')', ':'].contains(&next_char) {
                                 return Err(ParseError::InvalidChar(next_char, char_pos + 1));
                             }
                         }
@@ -235,6 +235,11 @@ mod tests {
             vec!["where", "x", "<>", "44"],
             tokenize("where x<>44").unwrap()
         );
+
+        assert_eq!(
+            vec!["where", "x", "::", "int", "=", "'4'", "::", "int"],
+            tokenize("where x::int='4'::int").unwrap()
+        );
     }
 
     #[test]
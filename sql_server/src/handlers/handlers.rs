@@ -1,6 +1,5 @@
 use actix_web::{web, post};
 use common::models::webserver_models::{QueryRequestData, QueryStatus, QueryResponseData};
-use transaction_control::{process_query};
 use std::{time::Instant};
 
 use crate::models::AppState;
@@ -12,20 +11,28 @@ pub async fn query(
     data: web::Data<AppState>
 ) -> web::Json<QueryResponseData> {
     let now = Instant::now();
-    let result = process_query(&req.query, data.acid_sync.clone());
+    let (session_id, session) = data.get_session(req.session_id.as_deref());
+    let result = {
+        let mut session = session.lock().unwrap();
+        let result = session.process_query(&req.query, data.acid_sync.clone());
+        data.release_session(&session_id, &session);
+        result
+    };
 
     match result {
-        Ok(data) => web::Json(QueryResponseData {
+        Ok(result) => web::Json(QueryResponseData {
             status: QueryStatus::Ok,
-            data: data.data,
-            message: data.message,
-            duration: format!("{:.2} ms", (now.elapsed().as_nanos() as f32 / 1_000_000.0))
+            data: result.data,
+            message: result.message,
+            duration: format!("{:.2} ms", (now.elapsed().as_nanos() as f32 / 1_000_000.0)),
+            session_id: Some(session_id),
         }),
         Err(e) => web::Json(QueryResponseData {
             status: QueryStatus::Err,
             data: None,
             message: Some(format!("DB Error: {}", e)),
-            duration: format!("{:.2} ms", (now.elapsed().as_nanos() as f32 / 1_000_000.0))
+            duration: format!("{:.2} ms", (now.elapsed().as_nanos() as f32 / 1_000_000.0)),
+            session_id: Some(session_id),
         })
     }
-}
\ No newline at end of file
+}
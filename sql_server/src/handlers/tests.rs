@@ -5,23 +5,34 @@ mod integration_tests {
     use actix_web::http::header::ContentType;
     use actix_web::test::{TestRequest, init_service, call_service, read_body_json};
     use actix_web::web::{Data};
-    use common::models::acid_sync::AcidSync;
     use common::models::webserver_models::{QueryRequestData, QueryStatus, QueryResponseData};
     use crate::handlers;
     use crate::models::AppState;
+    use std::time::Duration;
 
     fn setup_requst(payload: String) -> TestRequest {
         TestRequest::post()
             .insert_header(ContentType::json())
             .uri("/query")
             .set_json(QueryRequestData {
-                query: payload
+                query: payload,
+                session_id: None,
+            })
+    }
+
+    fn setup_session_request(payload: &str, session_id: &Option<String>) -> TestRequest {
+        TestRequest::post()
+            .insert_header(ContentType::json())
+            .uri("/query")
+            .set_json(QueryRequestData {
+                query: payload.to_string(),
+                session_id: session_id.clone(),
             })
     }
 
     #[actix_web::test]
     async fn simple_table_creation() {
-        let app_data = Data::new(AppState { acid_sync: AcidSync::default() });
+        let app_data = Data::new(AppState::default());
         let app = init_service(App::new().app_data(app_data.clone()).service(handlers::query)).await;
         
         let req_create = setup_requst("CREATE TABLE employees name varchar, age int".to_string());
@@ -43,7 +54,7 @@ mod integration_tests {
 
     #[actix_web::test]
     async fn insert_data_in_table() {
-        let app_data = Data::new(AppState { acid_sync: AcidSync::default() });
+        let app_data = Data::new(AppState::default());
         let app = init_service(App::new().app_data(app_data.clone()).service(handlers::query)).await;
         
         // Crete table
@@ -87,4 +98,81 @@ mod integration_tests {
         assert_eq!(body_drop.status, QueryStatus::Ok);
     }
 
+    #[actix_web::test]
+    async fn rollback_transaction() {
+        let app_data = Data::new(AppState::default());
+        let app = init_service(App::new().app_data(app_data.clone()).service(handlers::query)).await;
+
+        let req_create = setup_requst("CREATE TABLE visitors id int, age int".to_string());
+        let body_create: QueryResponseData = read_body_json(call_service(&app, req_create.to_request()).await).await;
+        assert_eq!(body_create.status, QueryStatus::Ok);
+
+        let req_begin = setup_requst("BEGIN".to_string());
+        let body_begin: QueryResponseData = read_body_json(call_service(&app, req_begin.to_request()).await).await;
+        assert_eq!(body_begin.status, QueryStatus::Ok);
+        let session_id = body_begin.session_id;
+        assert!(session_id.is_some());
+
+        let req_insert = setup_session_request("INSERT INTO visitors VALUES 1, 30", &session_id);
+        let body_insert: QueryResponseData = read_body_json(call_service(&app, req_insert.to_request()).await).await;
+        assert_eq!(body_insert.status, QueryStatus::Ok);
+        assert_eq!(body_insert.session_id, session_id);
+
+        // the table is used by the transaction
+        let req_select = setup_requst("SELECT * FROM visitors".to_string());
+        let body_select: QueryResponseData = read_body_json(call_service(&app, req_select.to_request()).await).await;
+        assert_eq!(body_select.status, QueryStatus::Err);
+
+        let req_rollback = setup_session_request("ROLLBACK", &session_id);
+        let body_rollback: QueryResponseData = read_body_json(call_service(&app, req_rollback.to_request()).await).await;
+        assert_eq!(body_rollback.status, QueryStatus::Ok);
+        assert_eq!(body_rollback.message, Some("Transaction rolled back.".to_string()));
+
+        let req_select = setup_requst("SELECT * FROM visitors".to_string());
+        let body_select: QueryResponseData = read_body_json(call_service(&app, req_select.to_request()).await).await;
+        assert_eq!(body_select.status, QueryStatus::Ok);
+        assert!(body_select.data.unwrap().rows.is_empty());
+
+        let req_drop = setup_requst("DROP TABLE visitors".to_string());
+        let body_drop: QueryResponseData = read_body_json(call_service(&app, req_drop.to_request()).await).await;
+        assert_eq!(body_drop.status, QueryStatus::Ok);
+    }
+
+    #[actix_web::test]
+    async fn idle_session_expired() {
+        let app_data = Data::new(AppState::default());
+        let app = init_service(App::new().app_data(app_data.clone()).service(handlers::query)).await;
+
+        let req_create = setup_requst("CREATE TABLE guests id int".to_string());
+        let body_create: QueryResponseData = read_body_json(call_service(&app, req_create.to_request()).await).await;
+        assert_eq!(body_create.status, QueryStatus::Ok);
+
+        let req_begin = setup_requst("BEGIN".to_string());
+        let body_begin: QueryResponseData = read_body_json(call_service(&app, req_begin.to_request()).await).await;
+        let session_id = body_begin.session_id;
+        let req_insert = setup_session_request("INSERT INTO guests VALUES 1", &session_id);
+        let body_insert: QueryResponseData = read_body_json(call_service(&app, req_insert.to_request()).await).await;
+        assert_eq!(body_insert.status, QueryStatus::Ok);
+
+        app_data.expire_sessions(Duration::from_secs(60));
+        let req_select = setup_requst("SELECT * FROM guests".to_string());
+        let body_select: QueryResponseData = read_body_json(call_service(&app, req_select.to_request()).await).await;
+        assert_eq!(body_select.status, QueryStatus::Err);
+
+        // the client went away, its transaction is rolled back
+        app_data.expire_sessions(Duration::ZERO);
+        let req_select = setup_requst("SELECT * FROM guests".to_string());
+        let body_select: QueryResponseData = read_body_json(call_service(&app, req_select.to_request()).await).await;
+        assert_eq!(body_select.status, QueryStatus::Ok);
+        assert!(body_select.data.unwrap().rows.is_empty());
+        let req_commit = setup_session_request("COMMIT", &session_id);
+        let body_commit: QueryResponseData = read_body_json(call_service(&app, req_commit.to_request()).await).await;
+        assert_eq!(body_commit.status, QueryStatus::Err);
+        assert_ne!(body_commit.session_id, session_id);
+
+        let req_drop = setup_requst("DROP TABLE guests".to_string());
+        let body_drop: QueryResponseData = read_body_json(call_service(&app, req_drop.to_request()).await).await;
+        assert_eq!(body_drop.status, QueryStatus::Ok);
+    }
+
 }
\ No newline at end of file
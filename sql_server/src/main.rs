@@ -4,13 +4,40 @@ use actix_web::{http, web, App, HttpServer};
 mod handlers;
 mod models;
 
-use common::models::acid_sync::AcidSync;
 use models::AppState;
+use persistence::wal::{self, SyncPolicy};
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(300);
+const SESSION_EXPIRATION_INTERVAL: Duration = Duration::from_secs(10);
 
 #[actix_web::main]
 async fn main() -> anyhow::Result<()> {
-    let app_data = web::Data::new(AppState {
-        acid_sync: AcidSync::default(),
+    // WAL_SYNC=never leaves flushing of the files to the operating system
+    if std::env::var("WAL_SYNC").is_ok_and(|policy| policy.eq_ignore_ascii_case("never")) {
+        wal::set_sync_policy(SyncPolicy::Never);
+    }
+    let app_data = web::Data::new(AppState::default());
+    let recovery = transaction_control::recover(app_data.acid_sync.clone())?;
+    if recovery.replayed_changes > 0 {
+        println!("Replayed {} unfinished changes from the write-ahead log.", recovery.replayed_changes);
+    }
+    if !recovery.restored_tables.is_empty() {
+        println!(
+            "Rolled back the unfinished transactions of the tables {}.",
+            recovery.restored_tables.join(", ")
+        );
+    }
+    // SESSION_TIMEOUT=seconds after which the transaction of an idle session is rolled back
+    let session_timeout = std::env::var("SESSION_TIMEOUT")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .map_or(DEFAULT_SESSION_TIMEOUT, Duration::from_secs);
+    let expired_app_data = app_data.clone();
+    thread::spawn(move || loop {
+        thread::sleep(SESSION_EXPIRATION_INTERVAL);
+        expired_app_data.expire_sessions(session_timeout);
     });
 
     HttpServer::new(move || {
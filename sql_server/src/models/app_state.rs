@@ -1,5 +1,91 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use common::models::acid_sync::AcidSync;
+use transaction_control::Session;
 
+#[derive(Default)]
 pub struct AppState {
-    pub acid_sync: AcidSync
+    pub acid_sync: AcidSync,
+    /// Sessions with an open transaction, by the id sent to the client.
+    sessions: Mutex<HashMap<String, ClientSession>>,
+    random_state: RandomState,
+    sessions_count: AtomicU64,
+}
+
+/// Session of the client together with the time of its last query.
+struct ClientSession {
+    session: Arc<Mutex<Session>>,
+    last_used: Instant,
+}
+
+impl AppState {
+    /// Session of the client, a new one is started when the id is missing or unknown.
+    pub fn get_session(&self, session_id: Option<&str>) -> (String, Arc<Mutex<Session>>) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(client_session) = session_id.and_then(|session_id| sessions.get_mut(session_id)) {
+            client_session.last_used = Instant::now();
+            return (session_id.unwrap().to_string(), Arc::clone(&client_session.session));
+        }
+
+        let session_id = self.new_session_id();
+        let session = Arc::new(Mutex::new(Session::new()));
+        sessions.insert(
+            session_id.clone(),
+            ClientSession {
+                session: Arc::clone(&session),
+                last_used: Instant::now(),
+            },
+        );
+        (session_id, session)
+    }
+
+    /// Forget the session once it has no open transaction.
+    pub fn release_session(&self, session_id: &str, session: &Session) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if !session.is_in_transaction() {
+            sessions.remove(session_id);
+        } else if let Some(client_session) = sessions.get_mut(session_id) {
+            client_session.last_used = Instant::now();
+        }
+    }
+
+    /// Roll back and forget the sessions which weren't used for the `idle_timeout`,
+    /// so the transaction of a client which went away doesn't keep its tables.
+    /// The sessions running a query are kept.
+    pub fn expire_sessions(&self, idle_timeout: Duration) {
+        let expired_sessions: Vec<(String, Arc<Mutex<Session>>)> = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let expired_ids: Vec<String> = sessions
+                .iter()
+                .filter(|(_session_id, client_session)| {
+                    Arc::strong_count(&client_session.session) == 1
+                        && client_session.last_used.elapsed() >= idle_timeout
+                })
+                .map(|(session_id, _client_session)| session_id.clone())
+                .collect();
+            expired_ids
+                .into_iter()
+                .filter_map(|session_id| {
+                    let client_session = sessions.remove(&session_id)?;
+                    Some((session_id, client_session.session))
+                })
+                .collect()
+        };
+
+        for (session_id, session) in expired_sessions {
+            if let Err(error) = session.lock().unwrap().rollback(self.acid_sync.clone()) {
+                eprintln!("Rollback of the expired session {} failed: {}", session_id, error);
+            }
+        }
+    }
+
+    fn new_session_id(&self) -> String {
+        let count = self.sessions_count.fetch_add(1, Ordering::Relaxed);
+        format!("{:016x}{:08x}", self.random_state.hash_one(count), count)
+    }
 }
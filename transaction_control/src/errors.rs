@@ -15,18 +15,78 @@ pub enum QueryError {
     #[error("column {0} does not exist in table {1}")]
     ColumnNotExists(String, String),
 
+    #[error("index {0} does not exist in table {1}")]
+    IndexNotExists(String, String),
+
+    #[error("index {0} enforces a constraint of table {1} and can't be changed")]
+    IndexOfConstraint(String, String),
+
+    #[error("multiple primary keys for table {0} are not allowed")]
+    MultiplePrimaryKeys(String),
+
+    #[error("duplicate key ({1})=({2}) violates unique constraint {0}")]
+    DuplicateKey(String, String, String),
+
+    #[error("null value in column {0} violates constraint {1}")]
+    NullValue(String, String),
+
+    #[error("row violates constraint CHECK ({1}) of column {0}")]
+    CheckViolation(String, String),
+
     #[error("column {0} can't be presented multiple times")]
     DuplicateColumn(String),
 
     #[error("table {0} already exist")]
     TableAlreadyExists(String),
 
+    #[error("column {0} is the only column of table {1} and can't be dropped")]
+    CannotDropLastColumn(String, String),
+
     #[error("table has {0} columns but {1} values provided")]
     IncorrectNumberOfValues(usize, usize),
 
     #[error("column {0} has type {1} but the value with type {2} provided")]
     InvalidDataType(String, String, String),
 
+    #[error("value too long for type VARCHAR({1}) of column {0}")]
+    ValueTooLong(String, i32),
+
+    #[error("column {0} of type {1} can't be indexed by a B+tree")]
+    UnorderedIndexColumn(String, String),
+
+    #[error("column reference {0} is ambiguous")]
+    AmbiguousColumn(String),
+
+    #[error("table name {0} specified more than once")]
+    DuplicateTableName(String),
+
+    #[error("missing FROM-clause entry for table {0}")]
+    UnknownTableReference(String),
+
+    #[error("column {0} must appear in the GROUP BY clause or be used in an aggregate function")]
+    NotGroupedColumn(String),
+
+    #[error("aggregate function {0} can't be applied to type {1}")]
+    InvalidAggregateArgument(String, String),
+
+    #[error("{0} types {1} and {2} cannot be matched")]
+    MismatchedTypes(String, String, String),
+
+    #[error("value {0} is out of range for type {1}")]
+    ValueOutOfRange(String, String),
+
+    #[error("transaction is already in progress")]
+    TransactionAlreadyStarted,
+
+    #[error("there is no transaction in progress")]
+    NoTransaction,
+
+    #[error("transactions can be used only in a session")]
+    TransactionWithoutSession,
+
+    #[error("table {0} is used by a transaction of another session")]
+    TableInTransaction(String),
+
     #[error(transparent)]
     Persistence(#[from] PersistenceErrors),
 }
@@ -1,9 +1,11 @@
 use common::models::acid_sync::AcidSync;
 use common::models::webserver_models::QueryResultData;
+use persistence::wal::{self, Recovery};
 use query_parser::parser::query_parser::{parse, Query};
 
 mod errors;
 mod queries;
+mod session;
 mod utils;
 
 use errors::QueryError;
@@ -11,38 +13,71 @@ use queries::delete::process_delete_query;
 use queries::index::{process_create_index_query, process_drop_index_query};
 use queries::insert::process_insert_query;
 use queries::select::process_select_query;
-use queries::table::{process_create_table_query, process_drop_table_query};
+use queries::table::{
+    process_alter_table_query, process_create_table_query, process_drop_table_query,
+};
+use queries::update::process_update_query;
+pub use session::Session;
 
 type QueryResult = Result<QueryResultData, QueryError>;
 
+/// Finish the changes interrupted by a crash and roll back the transactions which never
+/// finished, the catalog rows of their tables are rewritten. Has to be called before
+/// any query is run.
+pub fn recover(sync: AcidSync) -> Result<Recovery, QueryError> {
+    let recovery = wal::recover()?;
+    for table_name in &recovery.restored_tables {
+        utils::db_info::refresh_info_table(table_name.clone(), sync.clone())?;
+    }
+    Ok(recovery)
+}
+
+/// Run a single query in a session of its own, so it doesn't use the tables
+/// of the transactions. BEGIN, COMMIT and ROLLBACK need a `Session`.
 pub fn process_query(query: &str, sync: AcidSync) -> QueryResult {
     match parse(query)? {
+        Query::Begin | Query::Commit | Query::Rollback => Err(QueryError::TransactionWithoutSession),
+        query => Session::new().execute_statement(query, sync),
+    }
+}
+
+fn execute_query(query: Query, sync: AcidSync) -> QueryResult {
+    match query {
         Query::CreateTable {
             table_name,
             columns_definition,
-        } => process_create_table_query(table_name, columns_definition, sync),
+            constraints,
+        } => process_create_table_query(table_name, columns_definition, constraints, sync),
         Query::Insert {
-            values,
+            source,
             table_name,
             columns,
-        } => process_insert_query(values, table_name, columns, sync),
-        Query::Select {
-            body,
-            table_name,
-            where_body,
-        } => process_select_query(body, table_name, where_body, sync),
+        } => process_insert_query(source, table_name, columns, sync),
+        Query::Select(select_query) => process_select_query(*select_query, sync),
         Query::CreateIndex {
-            column_name,
+            index_name,
             table_name,
-        } => process_create_index_query(column_name, table_name, sync),
+            columns,
+            kind,
+        } => process_create_index_query(index_name, table_name, columns, kind, sync),
         Query::DropIndex {
-            column_name,
+            index_name,
             table_name,
-        } => process_drop_index_query(column_name, table_name, sync),
+        } => process_drop_index_query(index_name, table_name, sync),
         Query::DropTable { table_name } => process_drop_table_query(table_name, sync),
+        Query::AlterTable { table_name, action } => {
+            process_alter_table_query(table_name, action, sync)
+        }
+        Query::Begin | Query::Commit | Query::Rollback => Err(QueryError::TransactionWithoutSession),
         Query::Delete {
             table_name,
             where_body,
-        } => process_delete_query(table_name, where_body, sync),
+            limit,
+        } => process_delete_query(table_name, where_body, limit, sync),
+        Query::Update {
+            table_name,
+            assignments,
+            where_body,
+        } => process_update_query(table_name, assignments, where_body, sync),
     }
 }
@@ -1,19 +1,25 @@
 use common::models::{acid_sync::AcidSync, webserver_models::QueryResultData};
 use persistence::table::table::Table;
-use query_parser::parser::expression_tree::Node;
+use query_parser::parser::{expression_tree::Node, query_parser::Limit};
 
 use crate::{utils::common::get_rows_for_where_condition, QueryResult};
 
 pub fn process_delete_query(
     table_name: String,
     where_body: Option<Node>,
+    limit: Option<Limit>,
     sync: AcidSync,
 ) -> QueryResult {
     let rw_lock = sync.get_rw_lock(table_name.clone());
-    let _x = rw_lock.read().unwrap();
+    let _x = rw_lock.write().unwrap();
 
     let table = Table::load(table_name.clone())?;
-    let row_numbers = get_rows_for_where_condition(&table, where_body)?;
+    let max_rows = limit.map(|limit| limit.limit.saturating_add(limit.offset));
+    let row_numbers: Vec<u64> = get_rows_for_where_condition(&table, where_body, max_rows)?
+        .into_iter()
+        .skip(limit.map_or(0, |limit| limit.offset))
+        .map(|(row_number, _)| row_number)
+        .collect();
     let rows_amount = row_numbers.len();
     table.delete_rows(row_numbers)?;
 
@@ -1,39 +1,64 @@
-use common::models::{acid_sync::AcidSync, webserver_models::QueryResultData};
-use persistence::table::table::Table;
+use common::models::{acid_sync::AcidSync, db::DataType, webserver_models::QueryResultData};
+use persistence::table::table::{self, Table, TableIndex};
+use query_parser::parser::query_parser::IndexKind;
 
 use crate::{errors::QueryError, utils::common::get_columns_definition_map, QueryResult};
 
 pub fn process_create_index_query(
-    column_name: String,
+    index_name: String,
     table_name: String,
+    columns: Vec<String>,
+    kind: IndexKind,
     sync: AcidSync,
 ) -> QueryResult {
     let rw_lock = sync.get_rw_lock(table_name.clone());
     let _x = rw_lock.write().unwrap();
 
     let mut table = Table::load(table_name.clone())?;
+    check_not_constraint(&table, &index_name)?;
     let columns_def_map = get_columns_definition_map(&table);
 
-    if let Some((column_number, _)) = columns_def_map.get(&column_name) {
-        table.add_index(*column_number)?;
-    } else {
-        return Err(QueryError::ColumnNotExists(
-            column_name.clone(),
-            table_name,
-        ));
+    for (position, column_name) in columns.iter().enumerate() {
+        if !columns_def_map.contains_key(column_name) {
+            return Err(QueryError::ColumnNotExists(
+                column_name.clone(),
+                table_name,
+            ));
+        }
+        if columns[..position].contains(column_name) {
+            return Err(QueryError::DuplicateColumn(column_name.clone()));
+        }
+        let (_position, data_type) = columns_def_map[column_name];
+        if kind == IndexKind::BTree && matches!(data_type, DataType::TEXT | DataType::BLOB) {
+            return Err(QueryError::UnorderedIndexColumn(
+                column_name.clone(),
+                data_type.to_string(),
+            ));
+        }
     }
+    table.add_index(TableIndex {
+        name: index_name.clone(),
+        columns: columns.clone(),
+        kind: match kind {
+            IndexKind::Hash => table::IndexKind::Hash,
+            IndexKind::BTree => table::IndexKind::BTree,
+        },
+        constraint: None,
+    })?;
 
     Ok(QueryResultData {
         data: None,
         message: Some(format!(
-            "Index on column {} at table {} created succesfully.",
-            column_name, table_name
+            "Index {} on columns {} at table {} created succesfully.",
+            index_name,
+            columns.join(", "),
+            table_name
         )),
     })
 }
 
 pub fn process_drop_index_query(
-    column_name: String,
+    index_name: String,
     table_name: String,
     sync: AcidSync,
 ) -> QueryResult {
@@ -41,22 +66,31 @@ pub fn process_drop_index_query(
     let _x = rw_lock.write().unwrap();
 
     let mut table = Table::load(table_name.clone())?;
-    let columns_def_map = get_columns_definition_map(&table);
-
-    if let Some((column_number, _)) = columns_def_map.get(&column_name) {
-        table.remove_index(*column_number)?;
-    } else {
-        return Err(QueryError::ColumnNotExists(
-            column_name.clone(),
-            table_name,
-        ));
+    check_not_constraint(&table, &index_name)?;
+    if !table.remove_index(&index_name)? {
+        return Err(QueryError::IndexNotExists(index_name, table_name));
     }
 
     Ok(QueryResultData {
         data: None,
         message: Some(format!(
-            "Index on column {} at table {} dropped succesfully.",
-            column_name, table_name
+            "Index {} at table {} dropped succesfully.",
+            index_name, table_name
         )),
     })
 }
+
+/// The index of a constraint is kept as long as the constraint.
+fn check_not_constraint(table: &Table, index_name: &str) -> Result<(), QueryError> {
+    let is_constraint = table
+        .indexes
+        .iter()
+        .any(|index| index.name == index_name && index.constraint.is_some());
+    if is_constraint {
+        return Err(QueryError::IndexOfConstraint(
+            index_name.to_string(),
+            table.name.clone(),
+        ));
+    }
+    Ok(())
+}
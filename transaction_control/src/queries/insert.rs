@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 use common::models::{
     acid_sync::AcidSync,
@@ -6,29 +6,51 @@ use common::models::{
     webserver_models::QueryResultData,
 };
 use persistence::table::table::Table;
-use query_parser::parser::lexer::LexerToken;
+use query_parser::parser::query_parser::InsertSource;
 
-use crate::{errors::QueryError, utils::common::get_columns_definition_map, QueryResult};
+use crate::{
+    errors::QueryError,
+    queries::select::{get_table_names, select},
+    utils::{
+        common::{column_value, data_from_token, get_columns_definition_map},
+        constraints::{check_column_constraints, check_constraints, get_default_value},
+    },
+    QueryResult,
+};
 
 pub fn process_insert_query(
-    values: Vec<LexerToken>,
+    source: InsertSource,
     table_name: String,
     columns: Vec<String>,
     sync: AcidSync,
 ) -> QueryResult {
-    let rw_lock = sync.get_rw_lock(table_name.clone());
-    let _x = rw_lock.write().unwrap();
+    // the tables are locked in the order of their names, the selected tables only for reading
+    let mut table_names = match &source {
+        InsertSource::Values(_) => Vec::new(),
+        InsertSource::Select(query) => get_table_names(query),
+    };
+    if !table_names.contains(&table_name) {
+        table_names.push(table_name.clone());
+        table_names.sort();
+    }
+    let rw_locks: Vec<_> = table_names
+        .iter()
+        .map(|name| (name, sync.get_rw_lock(name.clone())))
+        .collect();
+    let mut _read_guards = Vec::new();
+    let mut _write_guards = Vec::new();
+    for (name, rw_lock) in &rw_locks {
+        if **name == table_name {
+            _write_guards.push(rw_lock.write().unwrap());
+        } else {
+            _read_guards.push(rw_lock.read().unwrap());
+        }
+    }
 
     let table = Table::load(table_name.clone())?;
     let columns_def_map = get_columns_definition_map(&table);
 
     let columns = if columns.is_empty() {
-        if values.len() != table.columns.len() {
-            return Err(QueryError::IncorrectNumberOfValues(
-                table.columns.len(),
-                values.len(),
-            ));
-        }
         table
             .columns
             .iter()
@@ -37,7 +59,7 @@ pub fn process_insert_query(
     } else {
         let mut column_usage: HashSet<String> = HashSet::new();
         for column_name in &columns {
-            if columns_def_map.get(column_name).is_none() {
+            if !columns_def_map.contains_key(column_name) {
                 return Err(QueryError::ColumnNotExists(
                     column_name.clone(),
                     table_name,
@@ -51,45 +73,75 @@ pub fn process_insert_query(
         columns
     };
 
-    let data_map: HashMap<_, _> = columns
-        .iter()
-        .enumerate()
-        .map(|(i, column)| (column.clone(), values[i].clone()))
-        .collect();
+    let values_rows: Vec<Vec<Data>> = match source {
+        InsertSource::Values(rows) => rows
+            .iter()
+            .map(|row| row.iter().map(data_from_token).collect())
+            .collect(),
+        InsertSource::Select(query) => {
+            let (_, data) = select(*query)?;
+            if data.columns.len() != columns.len() {
+                return Err(QueryError::IncorrectNumberOfValues(
+                    columns.len(),
+                    data.columns.len(),
+                ));
+            }
+            data.rows.into_iter().map(|row| row.values).collect()
+        }
+    };
 
-    let insert_values: Vec<Data> = table
+    // position of every table column in the inserted values, missing columns get their default
+    let positions: Vec<Option<usize>> = table
         .columns
         .iter()
-        .map(|column| data_from_token(data_map.get(&column.name).unwrap_or(&LexerToken::Null)))
+        .map(|column| columns.iter().position(|name| *name == column.name))
         .collect();
+    let mut defaults = vec![];
+    for (column, position) in table.columns.iter().zip(&positions) {
+        defaults.push(match position {
+            Some(_) => Data::NULL,
+            None => get_default_value(column)?,
+        });
+    }
 
-    // Check matching datatypes
-    for (i, value) in insert_values.iter().enumerate() {
-        if *value != Data::NULL && !value.is_valid_data_for_type(&table.columns[i].data_type) {
-            return Err(QueryError::InvalidDataType(
-                table.columns[i].name.clone(),
-                table.columns[i].data_type.to_string(),
-                value.to_type(),
+    // the whole batch is validated before anything is written
+    let mut rows = Vec::new();
+    for mut values in values_rows {
+        if values.len() != columns.len() {
+            return Err(QueryError::IncorrectNumberOfValues(
+                columns.len(),
+                values.len(),
             ));
         }
+
+        let mut insert_values: Vec<Data> = positions
+            .iter()
+            .zip(&defaults)
+            .map(|(position, default)| match position {
+                Some(position) => std::mem::replace(&mut values[*position], Data::NULL),
+                None => default.clone(),
+            })
+            .collect();
+
+        // Check matching datatypes
+        for (column, value) in table.columns.iter().zip(&mut insert_values) {
+            *value = column_value(column, std::mem::replace(value, Data::NULL))?;
+        }
+        rows.push(Row {
+            values: insert_values,
+        });
     }
 
-    table.insert_row(&Row {
-        values: insert_values,
-    })?;
+    check_column_constraints(&table, rows.iter())?;
+    check_constraints(&table, rows.iter(), &[])?;
+    table.insert_rows(&rows)?;
 
+    let message = match rows.len() {
+        1 => "1 row was succesfully inserted".to_string(),
+        rows_count => format!("{} rows were succesfully inserted", rows_count),
+    };
     Ok(QueryResultData {
         data: None,
-        message: Some("1 row was succesfully inserted".to_string()),
+        message: Some(message),
     })
 }
-
-fn data_from_token(token: &LexerToken) -> Data {
-    match token {
-        LexerToken::NumberLiteral(number) => Data::INT(*number),
-        LexerToken::StringLiteral(string) => Data::STRING(string.clone()),
-        LexerToken::FloatNumberLiteral(f64) => Data::FLOAT(*f64),
-        LexerToken::BoolLiteral(bool) => Data::BOOLEAN(*bool),
-        _ => Data::NULL,
-    }
-}
@@ -3,3 +3,4 @@ pub mod index;
 pub mod insert;
 pub mod select;
 pub mod table;
+pub mod update;
@@ -1,96 +1,358 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    iter,
+};
 
 use common::models::{
     acid_sync::AcidSync,
-    db::{Column, Data, DataType, Row},
+    db::{Column, ColumnConstraints, Data, DataType, Row},
     webserver_models::{QueryResultData, TableData},
 };
 use persistence::table::table::Table;
-use query_parser::parser::{expression_tree::Node, lexer::LexerToken};
+use query_parser::parser::{
+    expression_tree::Node,
+    expression_tree_eval::{evaluate_binary_node, evaluate_node, NodeValue},
+    lexer::LexerToken,
+    query_parser::{Limit, OrderBy, SelectItem, SelectQuery},
+};
 
 use crate::{
     errors::QueryError,
-    utils::common::{get_columns_definition_map, get_rows_for_where_condition},
+    utils::{
+        common::{
+            apply_row_predicate, check_identifiers_exist, data_from_node_value,
+            get_columns_definition_map, get_identifier_map, get_rows_for_where_condition,
+            get_rows_in_index_order, infer_data_type, promoted_value,
+        },
+        grouping::{group_rows, Aggregate},
+        join::JoinScope,
+    },
     QueryResult,
 };
 
-pub fn process_select_query(
-    body: Vec<LexerToken>,
-    table_name: String,
-    where_body: Option<Node>,
-    sync: AcidSync,
-) -> QueryResult {
-    let rw_lock = sync.get_rw_lock(table_name.clone());
-    let _x = rw_lock.read().unwrap();
-
-    let table = Table::load(table_name.clone())?;
-    let columns_def_map = get_columns_definition_map(&table);
-
-    let rows_numbers = get_rows_for_where_condition(&table, where_body)?;
-    let mut rows = Vec::new();
-    for row_number in rows_numbers {
-        rows.push(table.seek_row(row_number)?);
-    }
-
-    let columns = get_projection_columns(body, table_name.clone(), table, &columns_def_map)?;
-
-    let rows: Vec<Row> = rows
+pub fn process_select_query(query: SelectQuery, sync: AcidSync) -> QueryResult {
+    // the tables are locked in the order of their names, so that joins can't deadlock
+    let rw_locks: Vec<_> = get_table_names(&query)
         .into_iter()
-        .map(|data_row| project_row(data_row, &columns, &columns_def_map))
+        .map(|table_name| sync.get_rw_lock(table_name))
         .collect();
+    let _x: Vec<_> = rw_locks.iter().map(|rw_lock| rw_lock.read().unwrap()).collect();
 
-    let rows_count = rows.len();
-    let data = TableData { columns, rows };
+    let (table_name, data) = select(query)?;
     Ok(QueryResultData {
-        data: Some(data),
         message: Some(format!(
             "Retrieved {} rows from table {}.",
-            rows_count, table_name
+            data.rows.len(),
+            table_name
         )),
+        data: Some(data),
     })
 }
 
-fn get_projection_columns(
-    body: Vec<LexerToken>,
-    table_name: String,
-    table: Table,
-    columns_def_map: &HashMap<String, (usize, DataType)>,
-) -> Result<Vec<Column>, QueryError> {
-    let mut columns: Vec<Column> = Vec::new();
-    for token in body {
-        match token {
-            LexerToken::Identifier(column) => {
-                if !columns_def_map.contains_key(&column) {
-                    return Err(QueryError::ColumnNotExists(column, table_name));
+/// Names of all the tables used by the query, sorted and without duplicates.
+pub fn get_table_names(query: &SelectQuery) -> Vec<String> {
+    let mut table_names: Vec<String> = iter::once(&query.table)
+        .chain(query.joins.iter().map(|join| &join.table))
+        .map(|table| table.name.clone())
+        .collect();
+    table_names.sort();
+    table_names.dedup();
+    table_names
+}
+
+/// Evaluate the query, the caller is expected to hold the locks of all its tables.
+/// Returns the name of the (joined) table together with the result.
+pub fn select(query: SelectQuery) -> Result<(String, TableData), QueryError> {
+    let scope = JoinScope::load(&query)?;
+    let query = scope.qualify_query(resolve_order_by_aliases(query))?;
+    let table = scope.get_table();
+
+    let is_grouped = !query.group_by.is_empty()
+        || query.having.is_some()
+        || query.body.iter().any(|item| item.node.contains_aggregate())
+        || query.order_by.iter().any(|item| item.node.contains_aggregate());
+
+    // without sorting or grouping, the scan can stop as soon as enough rows are found
+    let max_rows = match query.limit {
+        Some(limit) if query.order_by.is_empty() && !is_grouped => {
+            Some(limit.limit.saturating_add(limit.offset))
+        }
+        _ => None,
+    };
+    // ORDER BY the column with the B+tree index reads the rows in the order of the index
+    let index_ordered_rows = match query.order_by.as_slice() {
+        [order_by] if !scope.is_join() && !is_grouped => {
+            let max_rows = query.limit.map(|limit| limit.limit.saturating_add(limit.offset));
+            get_rows_in_index_order(&table, query.where_body.as_ref(), order_by, max_rows)?
+        }
+        _ => None,
+    };
+    let is_sorted = index_ordered_rows.is_some();
+    let rows: Vec<Row> = if let Some(rows) = index_ordered_rows {
+        rows
+    } else if scope.is_join() {
+        let mut rows = Vec::new();
+        for row in scope.join_rows(&query.joins)? {
+            match &query.where_body {
+                Some(where_body) if !apply_row_predicate(&row, &table, where_body)? => {}
+                _ => rows.push(row),
+            }
+        }
+        rows
+    } else {
+        get_rows_for_where_condition(&table, query.where_body.clone(), max_rows)?
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect()
+    };
+
+    let (columns, rows) = if is_grouped {
+        select_groups(rows, &query, &table)?
+    } else {
+        select_rows(rows, &query, &table, is_sorted)?
+    };
+
+    Ok((table.name, TableData { columns, rows }))
+}
+
+/// ORDER BY may name an expression of the SELECT list by its alias,
+/// the alias is replaced by the expression before the columns are resolved.
+fn resolve_order_by_aliases(mut query: SelectQuery) -> SelectQuery {
+    for item in &mut query.order_by {
+        if let Node::Leaf(LexerToken::Identifier(name)) = &item.node {
+            let aliased_node = query
+                .body
+                .iter()
+                .find(|select_item| select_item.alias.as_ref() == Some(name))
+                .map(|select_item| select_item.node.clone());
+            if let Some(node) = aliased_node {
+                item.node = node;
+            }
+        }
+    }
+    query
+}
+
+fn select_rows(
+    rows: Vec<Row>,
+    query: &SelectQuery,
+    table: &Table,
+    is_sorted: bool,
+) -> Result<(Vec<Column>, Vec<Row>), QueryError> {
+    let columns_def_map = get_columns_definition_map(table);
+
+    let mut rows = rows;
+    if !query.order_by.is_empty() && !is_sorted {
+        for item in &query.order_by {
+            check_identifiers_exist(&item.node, table)?;
+        }
+        rows = sort_rows(rows, &query.order_by, |row| get_identifier_map(row, table))?;
+    }
+    let rows = apply_limit(rows, query.limit);
+
+    let projection = get_projection(&query.body, table)?;
+    let rows = rows
+        .into_iter()
+        .map(|data_row| project_row(data_row, &projection, table, &columns_def_map))
+        .collect::<Result<_, _>>()?;
+
+    let columns = projection.into_iter().map(|(column, _)| column).collect();
+    Ok((columns, rows))
+}
+
+/// Group the rows and compute the aggregates, then HAVING, ORDER BY and the projection
+/// are evaluated on the groups.
+fn select_groups(
+    rows: Vec<Row>,
+    query: &SelectQuery,
+    table: &Table,
+) -> Result<(Vec<Column>, Vec<Row>), QueryError> {
+    let projection = get_projection(&query.body, table)?;
+    for node in projection
+        .iter()
+        .map(|(_, node)| node)
+        .chain(query.having.iter())
+        .chain(query.order_by.iter().map(|item| &item.node))
+    {
+        check_grouped_identifiers(node, &query.group_by)?;
+    }
+
+    // every distinct aggregate is computed only once
+    let mut aggregate_nodes = Vec::new();
+    for node in projection
+        .iter()
+        .map(|(_, node)| node)
+        .chain(query.having.iter())
+        .chain(query.order_by.iter().map(|item| &item.node))
+    {
+        node.collect_aggregates(&mut aggregate_nodes);
+    }
+    let mut aggregate_keys = HashSet::new();
+    let mut aggregates = Vec::new();
+    for node in aggregate_nodes {
+        if aggregate_keys.insert(node.to_string()) {
+            aggregates.push(Aggregate::from_node(node, table)?);
+        }
+    }
+
+    let mut groups = group_rows(rows, table, &query.group_by, &aggregates)?;
+    if let Some(having) = &query.having {
+        let mut filtered_groups = Vec::new();
+        for group in groups {
+            if evaluate_binary_node(having, &group)? {
+                filtered_groups.push(group);
+            }
+        }
+        groups = filtered_groups;
+    }
+    if !query.order_by.is_empty() {
+        groups = sort_rows(groups, &query.order_by, |group| group.clone())?;
+    }
+    let groups = apply_limit(groups, query.limit);
+
+    let mut rows = Vec::new();
+    for group in groups {
+        let mut values = Vec::new();
+        for (column, node) in &projection {
+            let value = promoted_value(evaluate_node(node, &group)?, column.data_type);
+            values.push(data_from_node_value(value));
+        }
+        rows.push(Row { values });
+    }
+
+    let columns = projection.into_iter().map(|(column, _)| column).collect();
+    Ok((columns, rows))
+}
+
+/// Outside of aggregates, only the grouping columns can be used.
+fn check_grouped_identifiers(node: &Node, group_by: &[String]) -> Result<(), QueryError> {
+    match node {
+        Node::Leaf(LexerToken::Identifier(name)) if !group_by.contains(name) => {
+            Err(QueryError::NotGroupedColumn(name.clone()))
+        }
+        Node::Leaf(_) | Node::Aggregate { .. } => Ok(()),
+        _ => {
+            for child in node.children() {
+                check_grouped_identifiers(child, group_by)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn apply_limit<T>(items: Vec<T>, limit: Option<Limit>) -> Vec<T> {
+    match limit {
+        Some(limit) => items
+            .into_iter()
+            .skip(limit.offset)
+            .take(limit.limit)
+            .collect(),
+        None => items,
+    }
+}
+
+/// Sort the whole rows (or groups) before the projection,
+/// so the sort keys can use columns which are not in the projection.
+fn sort_rows<T>(
+    rows: Vec<T>,
+    order_by: &[OrderBy],
+    get_identifier_map: impl Fn(&T) -> HashMap<String, NodeValue>,
+) -> Result<Vec<T>, QueryError> {
+    // evaluate the sort keys only once per row
+    let mut keyed_rows = Vec::new();
+    for row in rows {
+        let identifier_map = get_identifier_map(&row);
+        let mut keys = Vec::new();
+        for item in order_by {
+            keys.push(data_from_node_value(evaluate_node(
+                &item.node,
+                &identifier_map,
+            )?));
+        }
+        keyed_rows.push((keys, row));
+    }
+
+    keyed_rows.sort_by(|(keys_a, _), (keys_b, _)| {
+        order_by
+            .iter()
+            .zip(keys_a.iter().zip(keys_b))
+            .fold(Ordering::Equal, |ordering, (item, (a, b))| {
+                ordering.then_with(|| compare_sort_keys(a, b, item))
+            })
+    });
+    Ok(keyed_rows.into_iter().map(|(_, row)| row).collect())
+}
+
+fn compare_sort_keys(a: &Data, b: &Data, order_by: &OrderBy) -> Ordering {
+    let null_ordering = if order_by.nulls_first {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    };
+    match (a, b) {
+        (Data::NULL, Data::NULL) => Ordering::Equal,
+        (Data::NULL, _) => null_ordering,
+        (_, Data::NULL) => null_ordering.reverse(),
+        _ if order_by.descending => a.total_cmp(b).reverse(),
+        _ => a.total_cmp(b),
+    }
+}
+
+/// Pair every projected expression with the column describing its result,
+/// the star is expanded to all the columns of the table.
+fn get_projection(body: &[SelectItem], table: &Table) -> Result<Vec<(Column, Node)>, QueryError> {
+    let mut projection = Vec::new();
+    for item in body {
+        match &item.node {
+            Node::Leaf(LexerToken::Star) => {
+                for column in &table.columns {
+                    let node = Node::Leaf(LexerToken::Identifier(column.name.clone()));
+                    projection.push((column.clone(), node));
                 }
-                let data_type = columns_def_map.get(&column).unwrap().1;
+            }
+            node => {
+                check_identifiers_exist(node, table)?;
+                // the type of NULL literal is unknown, INT is reported to the client then
+                let data_type = infer_data_type(node, table)?.unwrap_or(DataType::INT);
                 let column = Column {
-                    name: column,
+                    name: item.alias.clone().unwrap_or_else(|| node.to_string()),
                     data_type,
-                    is_indexed: false,
+                    constraints: ColumnConstraints::default(),
                 };
-                columns.push(column)
+                projection.push((column, node.clone()));
             }
-            LexerToken::Star => columns.extend(table.columns.iter().cloned()),
-            _ => unimplemented!(),
         }
     }
-    Ok(columns)
+    Ok(projection)
 }
 
+/// Plain columns are copied from the row, other expressions are evaluated on the row.
 fn project_row(
     row: Row,
-    columns_res: &Vec<Column>,
+    projection: &[(Column, Node)],
+    table: &Table,
     columns_def_map: &HashMap<String, (usize, DataType)>,
-) -> Row {
+) -> Result<Row, QueryError> {
+    let mut identifier_map = None;
     let mut row_projection: Vec<Data> = Vec::new();
-    for column in columns_res {
-        let index = columns_def_map.get(&column.name).unwrap().0;
-        let value = row.values[index].clone();
+    for (column, node) in projection {
+        let value = match node {
+            Node::Leaf(LexerToken::Identifier(name)) => {
+                let index = columns_def_map.get(name).unwrap().0;
+                row.values[index].clone()
+            }
+            _ => {
+                let identifier_map =
+                    identifier_map.get_or_insert_with(|| get_identifier_map(&row, table));
+                let value = evaluate_node(node, identifier_map)?;
+                data_from_node_value(promoted_value(value, column.data_type))
+            }
+        };
         row_projection.push(value);
     }
 
-    Row {
+    Ok(Row {
         values: row_projection,
-    }
+    })
 }
@@ -1,15 +1,28 @@
 use common::models::{
     acid_sync::AcidSync,
-    db::{Column, DataType},
+    db::{Column, ColumnConstraints, DataType},
     webserver_models::QueryResultData,
 };
-use persistence::table::table::Table;
+use persistence::table::table::{IndexConstraint, IndexKind, Table, TableIndex};
+use query_parser::parser::{
+    expression_tree::Node,
+    query_parser::{parse_expression, AlterTableAction, ColumnDefinition, TableConstraint},
+};
 
-use crate::{errors::QueryError, utils, QueryResult};
+use crate::{
+    errors::QueryError,
+    utils::{
+        self,
+        common::column_value,
+        constraints::get_default_value,
+    },
+    QueryResult,
+};
 
 pub fn process_create_table_query(
     table_name: String,
-    columns_definition: Vec<(String, String)>,
+    columns_definition: Vec<ColumnDefinition>,
+    constraints: Vec<TableConstraint>,
     sync: AcidSync,
 ) -> QueryResult {
     let rw_lock = sync.get_rw_lock(table_name.clone());
@@ -19,19 +32,21 @@ pub fn process_create_table_query(
         return Err(QueryError::TableAlreadyExists(table_name));
     }
 
-    let columns: Vec<Column> = columns_definition
-        .into_iter()
-        .map(|(name, data_type)| Column {
-            name,
-            data_type: from_string_to_data_type(data_type),
-            is_indexed: false,
-        })
+    let names: Vec<String> = columns_definition
+        .iter()
+        .map(|definition| definition.name.clone())
         .collect();
+    let mut columns = vec![];
+    for definition in columns_definition {
+        columns.push(get_column(definition, &names, &table_name)?);
+    }
     let cols_length = columns.len();
-    let table = Table {
+    let mut table = Table {
         name: table_name.clone(),
         columns,
+        indexes: vec![],
     };
+    table.indexes = get_constraint_indexes(&table, constraints)?;
 
     table.create()?;
 
@@ -56,12 +71,183 @@ pub fn process_drop_table_query(name: String, sync: AcidSync) -> QueryResult {
     })
 }
 
-fn from_string_to_data_type(data_type: String) -> DataType {
-    match data_type.as_str() {
-        "varchar" => DataType::STRING { size: 256 },
-        "int" => DataType::INT,
-        "boolean" => DataType::BOOLEAN,
-        "float" => DataType::FLOAT,
-        _ => unimplemented!(),
+pub fn process_alter_table_query(
+    table_name: String,
+    action: AlterTableAction,
+    sync: AcidSync,
+) -> QueryResult {
+    // the new name of the table is locked too, in the order of the names
+    let mut table_names = vec![table_name.clone()];
+    if let AlterTableAction::RenameTable(new_name) = &action {
+        table_names.push(new_name.clone());
+        table_names.sort();
+        table_names.dedup();
+    }
+    let rw_locks: Vec<_> = table_names
+        .into_iter()
+        .map(|name| sync.get_rw_lock(name))
+        .collect();
+    let _x: Vec<_> = rw_locks.iter().map(|rw_lock| rw_lock.write().unwrap()).collect();
+
+    let mut table = Table::load(table_name.clone())?;
+    let message = match action {
+        AlterTableAction::AddColumn {
+            column_name,
+            data_type,
+            default,
+        } => {
+            if table.columns.iter().any(|column| column.name == column_name) {
+                return Err(QueryError::DuplicateColumn(column_name));
+            }
+            let column = Column {
+                name: column_name.clone(),
+                data_type: DataType::from_name(&data_type).unwrap(),
+                constraints: ColumnConstraints {
+                    default: default.as_ref().map(Node::to_string),
+                    ..ColumnConstraints::default()
+                },
+            };
+            // the existing rows get the default, as the later inserts without the column
+            let default = column_value(&column, get_default_value(&column)?)?;
+            table.add_column(column, &default)?;
+            format!("Column {} added to table {}.", column_name, table_name)
+        }
+        AlterTableAction::DropColumn(column_name) => {
+            let position = get_column_position(&table, &column_name)?;
+            if table.columns.len() == 1 {
+                return Err(QueryError::CannotDropLastColumn(column_name, table_name));
+            }
+            // the checks referring to the column are dropped with it
+            for column in &mut table.columns {
+                if refers_to(&column.constraints.check, &column_name)? {
+                    column.constraints.check = None;
+                }
+            }
+            table.drop_column(position)?;
+            format!("Column {} dropped from table {}.", column_name, table_name)
+        }
+        AlterTableAction::RenameColumn { old_name, new_name } => {
+            let position = get_column_position(&table, &old_name)?;
+            if table.columns.iter().any(|column| column.name == new_name) {
+                return Err(QueryError::DuplicateColumn(new_name));
+            }
+            for column in &mut table.columns {
+                if let Some(check) = &column.constraints.check {
+                    let check = parse_expression(check)?.try_map_identifiers(&mut |name| {
+                        Ok::<_, QueryError>(match name == old_name {
+                            true => new_name.clone(),
+                            false => name.to_string(),
+                        })
+                    })?;
+                    column.constraints.check = Some(check.to_string());
+                }
+            }
+            table.rename_column(position, new_name.clone())?;
+            format!(
+                "Column {} renamed to {} in table {}.",
+                old_name, new_name, table_name
+            )
+        }
+        AlterTableAction::RenameTable(new_name) => {
+            if Table::load(new_name.clone()).is_ok() {
+                return Err(QueryError::TableAlreadyExists(new_name));
+            }
+            table.rename(new_name.clone())?;
+            format!("Table {} renamed to {}.", table_name, new_name)
+        }
+    };
+
+    utils::db_info::update_info_table(table_name, table.name.clone(), table.columns.len(), sync)?;
+
+    Ok(QueryResultData {
+        data: None,
+        message: Some(message),
+    })
+}
+
+/// The column with its constraints, the expressions are validated before they are stored.
+fn get_column(
+    definition: ColumnDefinition,
+    names: &[String],
+    table_name: &str,
+) -> Result<Column, QueryError> {
+    let column = Column {
+        name: definition.name,
+        data_type: DataType::from_name(&definition.data_type).unwrap(),
+        constraints: ColumnConstraints {
+            not_null: definition.not_null,
+            default: definition.default.as_ref().map(Node::to_string),
+            check: definition.check.as_ref().map(Node::to_string),
+        },
+    };
+    // the default can't refer to the columns, it's evaluated without them
+    column_value(&column, get_default_value(&column)?)?;
+    if let Some(check) = definition.check {
+        let mut identifiers = vec![];
+        check.collect_identifiers(&mut identifiers);
+        if let Some(name) = identifiers.into_iter().find(|name| !names.contains(name)) {
+            return Err(QueryError::ColumnNotExists(name, table_name.to_string()));
+        }
+    }
+    Ok(column)
+}
+
+/// Hash index of every constraint, named like `table_pkey` and `table_a_b_key`.
+fn get_constraint_indexes(
+    table: &Table,
+    constraints: Vec<TableConstraint>,
+) -> Result<Vec<TableIndex>, QueryError> {
+    let mut indexes: Vec<TableIndex> = vec![];
+    for table_constraint in constraints {
+        let (columns, constraint, name) = match table_constraint {
+            TableConstraint::PrimaryKey(columns) => {
+                let primary_key = Some(IndexConstraint::PrimaryKey);
+                if indexes.iter().any(|index| index.constraint == primary_key) {
+                    return Err(QueryError::MultiplePrimaryKeys(table.name.clone()));
+                }
+                (columns, primary_key, format!("{}_pkey", table.name))
+            }
+            TableConstraint::Unique(columns) => {
+                let name = format!("{}_{}_key", table.name, columns.join("_"));
+                (columns, Some(IndexConstraint::Unique), name)
+            }
+        };
+        for (position, column_name) in columns.iter().enumerate() {
+            get_column_position(table, column_name)?;
+            if columns[..position].contains(column_name) {
+                return Err(QueryError::DuplicateColumn(column_name.clone()));
+            }
+        }
+        // the same columns may be constrained more than once
+        let mut unique_name = name.clone();
+        let mut number = 0;
+        while indexes.iter().any(|index| index.name == unique_name) {
+            number += 1;
+            unique_name = format!("{}{}", name, number);
+        }
+        indexes.push(TableIndex {
+            name: unique_name,
+            columns,
+            kind: IndexKind::Hash,
+            constraint,
+        });
     }
+    Ok(indexes)
+}
+
+fn refers_to(expression: &Option<String>, column_name: &str) -> Result<bool, QueryError> {
+    let Some(expression) = expression else {
+        return Ok(false);
+    };
+    let mut identifiers = vec![];
+    parse_expression(expression)?.collect_identifiers(&mut identifiers);
+    Ok(identifiers.iter().any(|name| name == column_name))
+}
+
+fn get_column_position(table: &Table, column_name: &str) -> Result<usize, QueryError> {
+    table
+        .columns
+        .iter()
+        .position(|column| column.name == column_name)
+        .ok_or_else(|| QueryError::ColumnNotExists(column_name.to_string(), table.name.clone()))
 }
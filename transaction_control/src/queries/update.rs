@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+use common::models::{acid_sync::AcidSync, webserver_models::QueryResultData};
+use persistence::table::table::Table;
+use query_parser::parser::{expression_tree::Node, expression_tree_eval::evaluate_node};
+
+use crate::{
+    errors::QueryError,
+    utils::{
+        common::{
+            column_value, data_from_node_value, get_columns_definition_map,
+            get_identifier_map, get_rows_for_where_condition,
+        },
+        constraints::{check_column_constraints, check_constraints},
+    },
+    QueryResult,
+};
+
+pub fn process_update_query(
+    table_name: String,
+    assignments: Vec<(String, Node)>,
+    where_body: Option<Node>,
+    sync: AcidSync,
+) -> QueryResult {
+    let rw_lock = sync.get_rw_lock(table_name.clone());
+    let _x = rw_lock.write().unwrap();
+
+    let table = Table::load(table_name.clone())?;
+    let columns_def_map = get_columns_definition_map(&table);
+
+    let mut column_usage: HashSet<String> = HashSet::new();
+    let mut assignments_positions = Vec::new();
+    for (column_name, node) in &assignments {
+        match columns_def_map.get(column_name) {
+            None => {
+                return Err(QueryError::ColumnNotExists(
+                    column_name.clone(),
+                    table_name,
+                ))
+            }
+            Some((position, _)) => assignments_positions.push((*position, node)),
+        }
+        if !column_usage.insert(column_name.clone()) {
+            return Err(QueryError::DuplicateColumn(column_name.clone()));
+        }
+    }
+
+    let rows = get_rows_for_where_condition(&table, where_body, None)?;
+    let mut updated_rows = Vec::new();
+    for (row_number, mut row) in rows {
+        // all expressions are evaluated against the old row
+        let identifier_map = get_identifier_map(&row, &table);
+        for (position, node) in &assignments_positions {
+            let value = data_from_node_value(evaluate_node(node, &identifier_map)?);
+            row.values[*position] = column_value(&table.columns[*position], value)?;
+        }
+        updated_rows.push((row_number, row));
+    }
+
+    let row_numbers: Vec<u64> = updated_rows.iter().map(|(row_number, _row)| *row_number).collect();
+    check_column_constraints(&table, updated_rows.iter().map(|(_row_number, row)| row))?;
+    check_constraints(&table, updated_rows.iter().map(|(_row_number, row)| row), &row_numbers)?;
+
+    let rows_amount = updated_rows.len();
+    table.update_rows(&updated_rows)?;
+
+    Ok(QueryResultData {
+        data: None,
+        message: Some(format!(
+            "Updated {} rows in table {}.",
+            rows_amount, table_name
+        )),
+    })
+}
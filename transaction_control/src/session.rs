@@ -0,0 +1,192 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use common::models::{acid_sync::AcidSync, webserver_models::QueryResultData};
+use persistence::{table::backup::TableBackup, wal};
+use query_parser::parser::query_parser::{parse, AlterTableAction, InsertSource, Query};
+
+use crate::{
+    errors::QueryError, execute_query, queries::select::get_table_names, utils::db_info,
+    QueryResult,
+};
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Client session, the transaction started by BEGIN spans the following
+/// queries of the session until COMMIT or ROLLBACK.
+///
+/// The tables changed in the transaction are reserved for the session in `AcidSync`,
+/// so the other sessions can't use them until the transaction ends. Before the first
+/// change, all files of the table are copied aside and ROLLBACK puts the copies back.
+/// The tables catalog isn't reserved, its rows of the restored tables are rewritten instead.
+pub struct Session {
+    id: u64,
+    transaction: Option<Transaction>,
+}
+
+/// Backups of the tables changed by the transaction, in the order of their first change.
+/// The transaction and its backups are logged, so the recovery can roll it back.
+struct Transaction {
+    id: u64,
+    backups: Vec<TableBackup>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            transaction: None,
+        }
+    }
+
+    pub fn is_in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    pub fn process_query(&mut self, query: &str, sync: AcidSync) -> QueryResult {
+        let message = match parse(query)? {
+            Query::Begin => {
+                if self.transaction.is_some() {
+                    return Err(QueryError::TransactionAlreadyStarted);
+                }
+                self.transaction = Some(Transaction {
+                    id: wal::begin_transaction()?,
+                    backups: Vec::new(),
+                });
+                "Transaction started."
+            }
+            Query::Commit => {
+                self.finish_transaction(true, sync)?;
+                "Transaction committed."
+            }
+            Query::Rollback => {
+                self.finish_transaction(false, sync)?;
+                "Transaction rolled back."
+            }
+            query => return self.execute_statement(query, sync),
+        };
+
+        Ok(QueryResultData {
+            data: None,
+            message: Some(message.to_string()),
+        })
+    }
+
+    /// Run the query other than BEGIN, COMMIT and ROLLBACK, within the transaction if any.
+    pub(crate) fn execute_statement(&mut self, query: Query, sync: AcidSync) -> QueryResult {
+        let table_names = self.prepare_tables(&query, &sync)?;
+        let result = execute_query(query, sync.clone());
+        sync.end_statement(self.id, &table_names);
+        result
+    }
+
+    /// Check that the query doesn't use tables of other transactions, no other transaction
+    /// can claim the tables until the statement ends. In the transaction, the changed tables
+    /// are reserved and backed up. Returns the tables of the statement.
+    fn prepare_tables(&mut self, query: &Query, sync: &AcidSync) -> Result<Vec<String>, QueryError> {
+        let (read_tables, written_tables) = get_query_tables(query);
+        sync.check_tables(Some(self.id), &read_tables)
+            .map_err(QueryError::TableInTransaction)?;
+
+        if let Some(transaction) = &mut self.transaction {
+            sync.claim_tables(self.id, &written_tables)
+                .map_err(QueryError::TableInTransaction)?;
+            for table_name in &written_tables {
+                if transaction
+                    .backups
+                    .iter()
+                    .any(|backup| backup.table_name() == table_name)
+                {
+                    continue;
+                }
+                let rw_lock = sync.get_rw_lock(table_name.clone());
+                let _x = rw_lock.write().unwrap();
+                let backup = TableBackup::create(table_name)?;
+                wal::log_backup(transaction.id, &backup)?;
+                transaction.backups.push(backup);
+            }
+        }
+        let table_names = [read_tables, written_tables].concat();
+        sync.begin_statement(self.id, &table_names)
+            .map_err(QueryError::TableInTransaction)?;
+        Ok(table_names)
+    }
+
+    /// Undo the open transaction of the session, e.g. when its client went away.
+    pub fn rollback(&mut self, sync: AcidSync) -> Result<(), QueryError> {
+        match self.transaction {
+            Some(_) => self.finish_transaction(false, sync),
+            None => Ok(()),
+        }
+    }
+
+    /// Keep (on COMMIT) or undo (on ROLLBACK) the changes and release the tables.
+    fn finish_transaction(&mut self, commit: bool, sync: AcidSync) -> Result<(), QueryError> {
+        let transaction = self.transaction.take().ok_or(QueryError::NoTransaction)?;
+
+        // the committed transaction mustn't be rolled back by the recovery
+        // once its backups start to disappear
+        let mut result = match commit {
+            true => wal::finish_transaction(transaction.id).map_err(QueryError::from),
+            false => Ok(()),
+        };
+        let mut table_names = Vec::new();
+        for backup in transaction.backups.into_iter().rev() {
+            let rw_lock = sync.get_rw_lock(backup.table_name().to_string());
+            let _x = rw_lock.write().unwrap();
+            let backup_result = if commit {
+                backup.discard()
+            } else {
+                table_names.push(backup.table_name().to_string());
+                backup.restore()
+            };
+            if let Err(error) = backup_result {
+                result = result.and(Err(error.into()));
+            }
+        }
+        // the recovery restores the backups again unless all were put back
+        if !commit && result.is_ok() {
+            result = wal::finish_transaction(transaction.id).map_err(QueryError::from);
+        }
+        for table_name in table_names {
+            result = result.and(db_info::refresh_info_table(table_name, sync.clone()));
+        }
+        sync.release_tables(self.id);
+        result
+    }
+}
+
+/// Names of the tables read and written by the query.
+fn get_query_tables(query: &Query) -> (Vec<String>, Vec<String>) {
+    match query {
+        Query::Select(select_query) => (get_table_names(select_query), Vec::new()),
+        Query::Insert {
+            source, table_name, ..
+        } => {
+            let read_tables = match source {
+                InsertSource::Values(_) => Vec::new(),
+                InsertSource::Select(select_query) => get_table_names(select_query),
+            };
+            (read_tables, vec![table_name.clone()])
+        }
+        Query::Delete { table_name, .. }
+        | Query::Update { table_name, .. }
+        | Query::CreateIndex { table_name, .. }
+        | Query::DropIndex { table_name, .. }
+        | Query::CreateTable { table_name, .. }
+        | Query::DropTable { table_name } => (Vec::new(), vec![table_name.clone()]),
+        Query::AlterTable { table_name, action } => {
+            let mut written_tables = vec![table_name.clone()];
+            if let AlterTableAction::RenameTable(new_name) = action {
+                written_tables.push(new_name.clone());
+            }
+            (Vec::new(), written_tables)
+        }
+        Query::Begin | Query::Commit | Query::Rollback => (Vec::new(), Vec::new()),
+    }
+}
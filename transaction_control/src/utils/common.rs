@@ -1,21 +1,35 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Bound, slice};
 
-use common::models::db::{Column, Data, DataType, Row};
-use persistence::table::{row::PersistenceData, table::Table, table_iterator::RowsIterator};
+use common::models::{
+    datetime::MICROS_PER_DAY,
+    db::{Column, Data, DataType, Row},
+    decimal::{Decimal, MAX_PRECISION},
+};
+use persistence::table::{
+    errors::PersistenceErrors,
+    table::{IndexKind, Table, TableIndex},
+    table_iterator::RowsIterator,
+};
 use query_parser::parser::{
-    expression_tree::Node,
+    cast::{cast_value, compared_string, value_string, Coercion},
+    errors::ParseError,
+    expression_tree::{AggregateFunction, Node, ScalarFunction},
     expression_tree_eval::{evaluate_binary_node, evaluate_node, NodeValue},
     lexer::LexerToken,
+    query_parser::OrderBy,
 };
 
 use crate::errors::QueryError;
 
+/// Find the rows matching the 'where body' together with their row numbers,
+/// in the order they are stored in the table.
+/// The table is read only until `max_rows` matching rows are found.
 pub fn get_rows_for_where_condition(
     table: &Table,
     where_body: Option<Node>,
-) -> Result<Vec<u64>, QueryError> {
+    max_rows: Option<usize>,
+) -> Result<Vec<(u64, Row)>, QueryError> {
     let columns_def_map = get_columns_definition_map(table);
-    let rows_iterator = RowsIterator::from_table(table)?;
 
     // prepare a vector of columns that are used in 'where body'
     let mut where_body_columns: Vec<&Column> = Vec::new();
@@ -33,46 +47,402 @@ pub fn get_rows_for_where_condition(
         }
     }
 
-    // NOW proces only 'where body' in format of <WHERE><identifier><operator><value>
-    let row_numbers = match &where_body {
-        None => {
+    let max_rows = max_rows.unwrap_or(usize::MAX);
+    if max_rows == 0 {
+        return Ok(Vec::new());
+    }
+
+    // check if we support indexing for this query
+    // 'where column = value' and 'where column in (values)' are searched in the hash index of the column
+    let lookup_values = match &where_body {
+        Some(node) if where_body_columns.len() == 1 => {
+            match find_index(table, IndexKind::Hash, |columns| {
+                *columns == [where_body_columns[0].name.clone()]
+            }) {
+                Some(index) => match get_index_lookup_values(node) {
+                    Some(nodes) => nodes
+                        .into_iter()
+                        .map(|node| searched_value(node, where_body_columns[0]))
+                        .collect::<Result<Option<Vec<Data>>, QueryError>>()?
+                        .map(|values| (index, values)),
+                    None => None,
+                },
+                None => None,
+            }
+        }
+        _ => None,
+    };
+
+    // ranges, prefixes and equality can be searched in the B+tree index starting with the column
+    let index_range = match &where_body {
+        Some(node) if where_body_columns.len() == 1 && lookup_values.is_none() => {
+            match find_index(table, IndexKind::BTree, |columns| {
+                columns[0] == where_body_columns[0].name
+            }) {
+                Some(index) => {
+                    get_index_range(node, where_body_columns[0])?.map(|range| (index, range))
+                }
+                None => None,
+            }
+        }
+        _ => None,
+    };
+
+    // 'where a = value and b = value ...' is searched in the index with the most of its leading columns
+    // compared, the hash index needs all of its columns
+    let index_key = match &where_body {
+        Some(node) if lookup_values.is_none() && index_range.is_none() => {
+            get_index_key(table, node)?
+        }
+        _ => None,
+    };
+
+    let rows = match (&where_body, lookup_values, index_range, index_key) {
+        (None, _, _, _) => {
             // no where condition, return all rows
-            (0..rows_iterator.count() as u64).collect()
+            RowsIterator::from_table(table)?
+                .take(max_rows)
+                .enumerate()
+                .map(|(i, row)| Ok((i as u64, row?)))
+                .collect::<Result<_, QueryError>>()?
         }
-        // check if we support indexing for this query
-        // currently, we should support only 'where column = value' queries
-        Some(Node::Binary { left: _, op, right })
-            if *op == LexerToken::CompareOp("=".into())
-                && where_body_columns.len() == 1
-                && where_body_columns[0].is_indexed =>
-        {
+        // one index lookup for every searched value
+        (Some(node), Some((index, lookup_values)), _, _) => {
             let mut result_rows = Vec::new();
 
-            let index = table.get_index(where_body_columns[0])?;
-            let searched_value = data_from_node(right)?; // we expect that the value is on the right side
-            let index_row = index.rows.get(&searched_value.calculate_hash());
-            if let Some(index_row) = index_row {
-                for (data, row_number) in &index_row.values {
-                    if *data == searched_value {
-                        result_rows.push(*row_number);
-                    }
+            let index = table.get_index(&index.name)?;
+            for searched_value in lookup_values {
+                // NULL is never equal to anything
+                if searched_value == Data::NULL {
+                    continue;
                 }
+                result_rows.extend(index.get_rows(&[searched_value]));
             }
-            result_rows
+            // only the selected rows are read from the table, each of them once
+            result_rows.sort_unstable();
+            result_rows.dedup();
+            filter_index_rows(table, node, result_rows, max_rows)?
+        }
+        // the rows found by the index are checked by the predicate, e.g. NULL is never in the range
+        (Some(node), None, Some((index, (lower, upper))), _) => {
+            let row_numbers = table
+                .get_btree_index(&index.name)?
+                .range(
+                    lower.as_ref().map(slice::from_ref),
+                    upper.as_ref().map(slice::from_ref),
+                )?
+                .into_iter()
+                .map(|(_, row_number)| row_number)
+                .collect();
+            filter_index_rows(table, node, row_numbers, max_rows)?
+        }
+        (Some(node), None, None, Some((index, key))) => {
+            let row_numbers = match index.kind {
+                IndexKind::Hash => table.get_index(&index.name)?.get_rows(&key),
+                IndexKind::BTree => table
+                    .get_btree_index(&index.name)?
+                    .range(Bound::Included(&key), Bound::Included(&key))?
+                    .into_iter()
+                    .map(|(_, row_number)| row_number)
+                    .collect(),
+            };
+            filter_index_rows(table, node, row_numbers, max_rows)?
         }
         // we cannot use index, let's apply the predicate on each row
-        Some(node) => {
-            let mut rows_i = Vec::new();
-            for (i, row) in rows_iterator.enumerate() {
-                if apply_row_predicate(&row, table, node)? {
-                    rows_i.push(i as u64);
+        // only the columns of the predicate are decoded, the matching rows are read whole
+        (Some(node), None, None, None) => {
+            let projection = table
+                .columns
+                .iter()
+                .map(|column| {
+                    where_body_columns
+                        .iter()
+                        .any(|where_column| where_column.name == column.name)
+                })
+                .collect();
+            let mut rows = Vec::new();
+            for (i, row) in RowsIterator::from_table(table)?
+                .with_projection(projection)
+                .enumerate()
+            {
+                if apply_row_predicate(&row?, table, node)? {
+                    rows.push((i as u64, table.seek_row(i as u64)?));
+                    if rows.len() >= max_rows {
+                        break;
+                    }
                 }
             }
-            rows_i
+            rows
         }
     };
 
-    Ok(row_numbers)
+    Ok(rows)
+}
+
+/// The rows of the row numbers found by the index, which match the predicate.
+fn filter_index_rows(
+    table: &Table,
+    node: &Node,
+    mut row_numbers: Vec<u64>,
+    max_rows: usize,
+) -> Result<Vec<(u64, Row)>, QueryError> {
+    row_numbers.sort_unstable();
+    let mut rows = Vec::new();
+    for row_number in row_numbers {
+        let row = table.seek_row(row_number)?;
+        if apply_row_predicate(&row, table, node)? {
+            rows.push((row_number, row));
+            if rows.len() >= max_rows {
+                break;
+            }
+        }
+    }
+    Ok(rows)
+}
+
+fn find_index(
+    table: &Table,
+    kind: IndexKind,
+    columns: impl Fn(&[String]) -> bool,
+) -> Option<&TableIndex> {
+    table
+        .indexes
+        .iter()
+        .find(|index| index.kind == kind && columns(&index.columns))
+}
+
+/// The index with the most of its leading columns compared by `column = value` in the
+/// conjunction, together with the searched values of those columns.
+fn get_index_key<'a>(
+    table: &'a Table,
+    node: &Node,
+) -> Result<Option<(&'a TableIndex, Vec<Data>)>, QueryError> {
+    let mut equalities = HashMap::new();
+    collect_equalities(node, &mut equalities);
+    let Some((index, length)) = table
+        .indexes
+        .iter()
+        .map(|index| {
+            let length = index
+                .columns
+                .iter()
+                .take_while(|column| equalities.contains_key(column.as_str()))
+                .count();
+            (index, length)
+        })
+        .filter(|(index, length)| {
+            *length > 0 && (index.kind == IndexKind::BTree || *length == index.columns.len())
+        })
+        .max_by_key(|(_index, length)| *length)
+    else {
+        return Ok(None);
+    };
+    let mut key = vec![];
+    for name in &index.columns[..length] {
+        let column = table
+            .columns
+            .iter()
+            .find(|column| column.name == *name)
+            .unwrap();
+        match searched_value(equalities[name.as_str()], column)? {
+            Some(value) => key.push(value),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some((index, key)))
+}
+
+/// The `column = value` terms of the conjunction, where the value doesn't reference any column.
+fn collect_equalities<'a>(node: &'a Node, equalities: &mut HashMap<&'a str, &'a Node>) {
+    match node {
+        Node::Binary {
+            left,
+            op: LexerToken::LogicalOp(op),
+            right,
+        } if op == "and" => {
+            collect_equalities(left, equalities);
+            collect_equalities(right, equalities);
+        }
+        Node::Binary { left, op, right } if *op == LexerToken::CompareOp("=".into()) => {
+            let (column, value) = match (left.as_ref(), right.as_ref()) {
+                (Node::Leaf(LexerToken::Identifier(column)), value) => (column, value),
+                (value, Node::Leaf(LexerToken::Identifier(column))) => (column, value),
+                _ => return,
+            };
+            let mut identifiers = Vec::new();
+            value.collect_identifiers(&mut identifiers);
+            if identifiers.is_empty() {
+                equalities.insert(column, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The values searched by `column = value` or `column IN (values)`,
+/// the caller makes sure that the values don't reference any column.
+fn get_index_lookup_values(node: &Node) -> Option<Vec<&Node>> {
+    match node {
+        Node::Binary { left, op, right }
+            if *op == LexerToken::CompareOp("=".into())
+                && matches!(left.as_ref(), Node::Leaf(LexerToken::Identifier(_))) =>
+        {
+            Some(vec![right])
+        }
+        Node::In { node, list }
+            if matches!(node.as_ref(), Node::Leaf(LexerToken::Identifier(_))) =>
+        {
+            Some(list.iter().collect())
+        }
+        _ => None,
+    }
+}
+
+/// Lower and upper bound of the values searched in the B+tree index.
+type IndexRange = (Bound<Data>, Bound<Data>);
+
+/// The bounds of the values searched by `column <op> value`, `column BETWEEN low AND high`
+/// or `column LIKE 'prefix%'`, the caller makes sure that the values don't reference any column.
+fn get_index_range(node: &Node, column: &Column) -> Result<Option<IndexRange>, QueryError> {
+    let is_column = |node: &Node| matches!(node, Node::Leaf(LexerToken::Identifier(_)));
+    let range = match node {
+        Node::Binary {
+            left,
+            op: LexerToken::CompareOp(op),
+            right,
+        } if is_column(left) || is_column(right) => {
+            // `value < column` is searched as `column > value`
+            let (value, op) = match is_column(left) {
+                true => (right, op.as_str()),
+                false => (
+                    left,
+                    match op.as_str() {
+                        "<" => ">",
+                        ">" => "<",
+                        "<=" => ">=",
+                        ">=" => "<=",
+                        op => op,
+                    },
+                ),
+            };
+            let Some(value) = searched_value(value, column)? else {
+                return Ok(None);
+            };
+            match op {
+                "=" => (Bound::Included(value.clone()), Bound::Included(value)),
+                "<" => (Bound::Unbounded, Bound::Excluded(value)),
+                "<=" => (Bound::Unbounded, Bound::Included(value)),
+                ">" => (Bound::Excluded(value), Bound::Unbounded),
+                ">=" => (Bound::Included(value), Bound::Unbounded),
+                _ => return Ok(None),
+            }
+        }
+        Node::Between { node, low, high } if is_column(node) => {
+            match (searched_value(low, column)?, searched_value(high, column)?) {
+                (Some(low), Some(high)) => (Bound::Included(low), Bound::Included(high)),
+                _ => return Ok(None),
+            }
+        }
+        Node::Binary {
+            left,
+            op: LexerToken::Like,
+            right,
+        } if is_column(left) => {
+            let Node::Leaf(LexerToken::StringLiteral(pattern)) = right.as_ref() else {
+                return Ok(None);
+            };
+            let prefix = match pattern.strip_suffix('%') {
+                Some(prefix) if !prefix.is_empty() && !prefix.contains(['%', '_']) => prefix,
+                _ => return Ok(None),
+            };
+            let upper = match prefix_successor(prefix) {
+                Some(successor) => Bound::Excluded(Data::STRING(successor)),
+                None => Bound::Unbounded,
+            };
+            (Bound::Included(Data::STRING(prefix.to_string())), upper)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(range))
+}
+
+/// The smallest string greater than all the strings starting with the prefix, if there's any.
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        // the surrogates are skipped, they aren't chars
+        if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+/// The rows matching the 'where body' in the order of the column, when the column has
+/// the B+tree index, otherwise None. The table is read only until `max_rows` matching rows are found.
+pub fn get_rows_in_index_order(
+    table: &Table,
+    where_body: Option<&Node>,
+    order_by: &OrderBy,
+    max_rows: Option<usize>,
+) -> Result<Option<Vec<Row>>, QueryError> {
+    let Node::Leaf(LexerToken::Identifier(column_name)) = &order_by.node else {
+        return Ok(None);
+    };
+    let Some(index) = find_index(table, IndexKind::BTree, |columns| {
+        *columns == [column_name.clone()]
+    }) else {
+        return Ok(None);
+    };
+    if let Some(node) = where_body {
+        check_identifiers_exist(node, table)?;
+    }
+
+    // NULLs are at the end of the index, they are read from that end when they come first
+    let is_null = |entry: &Result<(Vec<Data>, u64), PersistenceErrors>| {
+        entry.as_ref().is_ok_and(|(key, _)| key[0] == Data::NULL)
+    };
+    let values = table
+        .get_btree_index(&index.name)?
+        .into_ordered_entries(order_by.descending)
+        .skip_while(is_null)
+        .take_while(move |entry| !is_null(entry));
+    let nulls = table
+        .get_btree_index(&index.name)?
+        .into_ordered_entries(true)
+        .take_while(move |entry| entry.as_ref().map_or(true, |(key, _)| key[0] == Data::NULL));
+    let entries: Box<dyn Iterator<Item = _>> = match order_by.nulls_first {
+        true => Box::new(nulls.chain(values)),
+        false => Box::new(values.chain(nulls)),
+    };
+
+    let max_rows = max_rows.unwrap_or(usize::MAX);
+    let mut rows = Vec::new();
+    for entry in entries {
+        if rows.len() >= max_rows {
+            break;
+        }
+        let (_, row_number) = entry?;
+        let row = table.seek_row(row_number)?;
+        match where_body {
+            Some(node) if !apply_row_predicate(&row, table, node)? => {}
+            _ => rows.push(row),
+        }
+    }
+    Ok(Some(rows))
+}
+
+/// Check that every identifier used in the expression is a column of the table.
+pub fn check_identifiers_exist(node: &Node, table: &Table) -> Result<(), QueryError> {
+    let mut identifiers = Vec::new();
+    node.collect_identifiers(&mut identifiers);
+    for ident in identifiers {
+        if !table.columns.iter().any(|column| column.name == ident) {
+            return Err(QueryError::ColumnNotExists(ident, table.name.clone()));
+        }
+    }
+    Ok(())
 }
 
 pub fn get_columns_definition_map(table: &Table) -> HashMap<String, (usize, DataType)> {
@@ -85,35 +455,384 @@ pub fn get_columns_definition_map(table: &Table) -> HashMap<String, (usize, Data
         .collect()
 }
 
-fn apply_row_predicate(db_row: &Row, table: &Table, query_node: &Node) -> Result<bool, QueryError> {
-    // combine the db_row and column definitions
-    // so that it can be used in the query evaluator
+pub fn apply_row_predicate(
+    db_row: &Row,
+    table: &Table,
+    query_node: &Node,
+) -> Result<bool, QueryError> {
+    let identifier_map = get_identifier_map(db_row, table);
+    let bool_val = evaluate_binary_node(query_node, &identifier_map)?;
+    Ok(bool_val)
+}
 
+/// Combine the db_row and column definitions
+/// so that it can be used in the query evaluator.
+pub fn get_identifier_map(db_row: &Row, table: &Table) -> HashMap<String, NodeValue> {
     let mut identifier_map = HashMap::new();
     for (i, data_cell) in db_row.values.iter().enumerate() {
         let column = &table.columns[i];
-        let data_value = match data_cell {
-            Data::INT(number) => NodeValue::Int(*number),
-            Data::STRING(string) => NodeValue::String(string.clone()),
-            Data::NULL => NodeValue::Null,
-            Data::BOOLEAN(bool) => NodeValue::Bool(*bool),
-            Data::FLOAT(float) => NodeValue::Float(*float),
+        identifier_map.insert(column.name.clone(), node_value_from_data(data_cell));
+    }
+    identifier_map
+}
+
+pub fn node_value_from_data(data: &Data) -> NodeValue {
+    match data {
+        Data::INT(number) => NodeValue::Int(*number),
+        Data::STRING(string) => NodeValue::String(string.clone()),
+        Data::NULL => NodeValue::Null,
+        Data::BOOLEAN(bool) => NodeValue::Bool(*bool),
+        Data::FLOAT(float) => NodeValue::Float(*float),
+        Data::BLOB(bytes) => NodeValue::Blob(bytes.clone()),
+        Data::BIGINT(number) => NodeValue::BigInt(*number),
+        Data::DECIMAL(decimal) => NodeValue::Decimal(*decimal),
+        Data::DATE(days) => NodeValue::Date(*days),
+        Data::TIMESTAMP(micros) => NodeValue::Timestamp(*micros),
+        Data::UUID(uuid) => NodeValue::Uuid(*uuid),
+    }
+}
+
+/// The value as it's stored in the column, converted by the assignment rules of the cast module,
+/// NULL fits any column.
+pub fn column_value(column: &Column, value: Data) -> Result<Data, QueryError> {
+    let node_value = node_value_from_data(&value);
+    let value = match cast_value(node_value.clone(), &column.data_type, Coercion::Assignment) {
+        Ok(value) => data_from_node_value(value),
+        Err(ParseError::OutOfRange(_)) => {
+            return Err(QueryError::ValueOutOfRange(
+                value_string(&node_value),
+                column.data_type.to_string(),
+            ))
+        }
+        Err(_) => {
+            return Err(QueryError::InvalidDataType(
+                column.name.clone(),
+                column.data_type.to_string(),
+                value.to_type(),
+            ))
+        }
+    };
+    match (column.data_type, &value) {
+        (DataType::STRING { size }, Data::STRING(string))
+            if string.chars().count() > size as usize =>
+        {
+            Err(QueryError::ValueTooLong(column.name.clone(), size))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// Infer the type of the expression result from the column types of the table.
+/// None is returned when the type can't be known, e.g. for the NULL literal.
+pub fn infer_data_type(node: &Node, table: &Table) -> Result<Option<DataType>, QueryError> {
+    let data_type = match node {
+        Node::Leaf(token) => match token {
+            LexerToken::Identifier(name) => match table.columns.iter().find(|c| &c.name == name) {
+                Some(column) => Some(column.data_type),
+                None => {
+                    return Err(QueryError::ColumnNotExists(
+                        name.clone(),
+                        table.name.clone(),
+                    ))
+                }
+            },
+            LexerToken::NumberLiteral(_) => Some(DataType::INT),
+            LexerToken::FloatNumberLiteral(_) => Some(DataType::FLOAT),
+            LexerToken::BoolLiteral(_) => Some(DataType::BOOLEAN),
+            LexerToken::StringLiteral(string) => Some(DataType::STRING {
+                size: string.len() as i32,
+            }),
+            LexerToken::BlobLiteral(_) => Some(DataType::BLOB),
+            LexerToken::BigNumberLiteral(_) => Some(DataType::BIGINT),
+            LexerToken::DecimalLiteral(decimal) => Some(DataType::DECIMAL {
+                precision: decimal.precision().max(decimal.scale) as u8,
+                scale: decimal.scale as u8,
+            }),
+            LexerToken::DateLiteral(_) => Some(DataType::DATE),
+            LexerToken::TimestampLiteral(_) => Some(DataType::TIMESTAMP),
+            LexerToken::UuidLiteral(_) => Some(DataType::UUID),
+            _ => None,
+        },
+        Node::Unary { op, node } => match op {
+            LexerToken::Minus => infer_data_type(node, table)?,
+            _ => Some(DataType::BOOLEAN),
+        },
+        Node::Binary { left, op, right } => match op {
+            LexerToken::Plus
+            | LexerToken::Minus
+            | LexerToken::Star
+            | LexerToken::Slash
+            | LexerToken::Percent => {
+                let is_minus = *op == LexerToken::Minus;
+                let left = infer_data_type(left, table)?;
+                let right = infer_data_type(right, table)?;
+                // the left type decides the operation, same as in the evaluation,
+                // the numbers are promoted INT < BIGINT < DECIMAL < FLOAT
+                match (left, right) {
+                    (Some(DataType::STRING { size }), Some(DataType::STRING { size: other })) => {
+                        Some(DataType::STRING { size: size + other })
+                    }
+                    (Some(DataType::DATE), Some(DataType::DATE)) if is_minus => Some(DataType::INT),
+                    (Some(DataType::TIMESTAMP), Some(DataType::DATE | DataType::TIMESTAMP))
+                        if is_minus =>
+                    {
+                        Some(DataType::FLOAT)
+                    }
+                    (Some(left), Some(right))
+                        if number_rank(right) > number_rank(left) && number_rank(left) > 0 =>
+                    {
+                        Some(right)
+                    }
+                    (None, right) => right,
+                    (left, _) => left,
+                }
+            }
+            _ => Some(DataType::BOOLEAN),
+        },
+        Node::IsNull(_) | Node::In { .. } | Node::Between { .. } => Some(DataType::BOOLEAN),
+        Node::Cast { node, data_type } => {
+            infer_data_type(node, table)?;
+            DataType::from_name(data_type)
+        }
+        Node::Aggregate { function, argument } => {
+            let argument_type = match argument {
+                Some(argument) => infer_data_type(argument, table)?,
+                None => None,
+            };
+            aggregate_data_type(*function, argument_type)?
+        }
+        Node::Call {
+            function,
+            arguments,
+        } => {
+            let argument_types = arguments
+                .iter()
+                .map(|argument| infer_data_type(argument, table))
+                .collect::<Result<Vec<_>, _>>()?;
+            function_data_type(*function, &argument_types)?
+        }
+        Node::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            if let Some(operand) = operand {
+                infer_data_type(operand, table)?;
+            }
+            let mut result_types = Vec::new();
+            for (value, result) in branches {
+                infer_data_type(value, table)?;
+                result_types.push(infer_data_type(result, table)?);
+            }
+            if let Some(else_result) = else_result {
+                result_types.push(infer_data_type(else_result, table)?);
+            }
+            unified_data_type("CASE", &result_types)?
+        }
+    };
+    Ok(data_type)
+}
+
+/// The string functions keep the VARCHAR of their argument, unless the length can change,
+/// the math functions keep the number type except POWER and SQRT, which are FLOAT.
+fn function_data_type(
+    function: ScalarFunction,
+    argument_types: &[Option<DataType>],
+) -> Result<Option<DataType>, QueryError> {
+    let data_type = match function {
+        ScalarFunction::Upper
+        | ScalarFunction::Lower
+        | ScalarFunction::Trim
+        | ScalarFunction::Substr => match argument_types[0] {
+            Some(DataType::STRING { size }) => Some(DataType::STRING { size }),
+            _ => Some(DataType::TEXT),
+        },
+        ScalarFunction::Replace | ScalarFunction::Concat => Some(DataType::TEXT),
+        ScalarFunction::Length => Some(DataType::INT),
+        ScalarFunction::Floor | ScalarFunction::Ceil => match argument_types[0] {
+            Some(DataType::DECIMAL { precision, .. }) => Some(DataType::DECIMAL {
+                precision,
+                scale: 0,
+            }),
+            data_type => data_type,
+        },
+        ScalarFunction::Abs | ScalarFunction::Round | ScalarFunction::NullIf => argument_types[0],
+        ScalarFunction::Power | ScalarFunction::Sqrt => Some(DataType::FLOAT),
+        ScalarFunction::Coalesce | ScalarFunction::IfNull => {
+            unified_data_type(&function.to_string().to_uppercase(), argument_types)?
+        }
+    };
+    Ok(data_type)
+}
+
+/// The type of the results of CASE or COALESCE, which can be any of the types. The values of
+/// the narrower types are converted by promoted_value. The unknown types of NULL are skipped.
+fn unified_data_type(
+    expression: &str,
+    data_types: &[Option<DataType>],
+) -> Result<Option<DataType>, QueryError> {
+    let mut unified: Option<DataType> = None;
+    for data_type in data_types.iter().flatten() {
+        unified = match unified {
+            None => Some(*data_type),
+            Some(unified) => match common_data_type(unified, *data_type) {
+                Some(common) => Some(common),
+                None => {
+                    return Err(QueryError::MismatchedTypes(
+                        expression.to_string(),
+                        unified.to_string(),
+                        data_type.to_string(),
+                    ))
+                }
+            },
         };
-        identifier_map.insert(column.name.clone(), data_value);
     }
+    Ok(unified)
+}
 
-    let bool_val = evaluate_binary_node(query_node, &identifier_map)?;
-    Ok(bool_val)
+/// The wider of the numbers, the longer VARCHAR and TIMESTAMP for DATE, None for other different types.
+pub fn common_data_type(first: DataType, second: DataType) -> Option<DataType> {
+    match (first, second) {
+        _ if first == second => Some(first),
+        (DataType::STRING { size }, DataType::STRING { size: other }) => Some(DataType::STRING {
+            size: size.max(other),
+        }),
+        _ if first.is_string() && second.is_string() => Some(DataType::TEXT),
+        (
+            DataType::DECIMAL { precision, scale },
+            DataType::DECIMAL {
+                precision: other_precision,
+                scale: other_scale,
+            },
+        ) => {
+            let integer_digits = (precision - scale).max(other_precision - other_scale);
+            let scale = scale.max(other_scale);
+            Some(DataType::DECIMAL {
+                precision: (integer_digits + scale).min(MAX_PRECISION),
+                scale,
+            })
+        }
+        _ if number_rank(first) > 0 && number_rank(second) > 0 => {
+            match number_rank(first) > number_rank(second) {
+                true => Some(first),
+                false => Some(second),
+            }
+        }
+        (DataType::DATE | DataType::TIMESTAMP, DataType::DATE | DataType::TIMESTAMP) => {
+            Some(DataType::TIMESTAMP)
+        }
+        _ => None,
+    }
+}
+
+/// The value converted to the type unified by unified_data_type, when its type is narrower.
+pub fn promoted_value(value: NodeValue, data_type: DataType) -> NodeValue {
+    match (value, data_type) {
+        (NodeValue::Int(number), DataType::BIGINT) => NodeValue::BigInt(number as i64),
+        (NodeValue::Int(number), DataType::DECIMAL { .. }) => {
+            NodeValue::Decimal(Decimal::from_int(number as i64))
+        }
+        (NodeValue::BigInt(number), DataType::DECIMAL { .. }) => {
+            NodeValue::Decimal(Decimal::from_int(number))
+        }
+        (NodeValue::Int(number), DataType::FLOAT) => NodeValue::Float(number as f64),
+        (NodeValue::BigInt(number), DataType::FLOAT) => NodeValue::Float(number as f64),
+        (NodeValue::Decimal(decimal), DataType::FLOAT) => NodeValue::Float(decimal.to_f64()),
+        (NodeValue::Date(days), DataType::TIMESTAMP) => {
+            NodeValue::Timestamp(days as i64 * MICROS_PER_DAY)
+        }
+        (value, _) => value,
+    }
+}
+
+/// Rank of the numeric type in the promotion of the arithmetic, 0 for the other types.
+fn number_rank(data_type: DataType) -> u8 {
+    match data_type {
+        DataType::INT => 1,
+        DataType::BIGINT => 2,
+        DataType::DECIMAL { .. } => 3,
+        DataType::FLOAT => 4,
+        _ => 0,
+    }
+}
+
+/// COUNT is always INT and AVG is always FLOAT, the other aggregates keep the argument type.
+/// SUM and AVG need a number.
+pub fn aggregate_data_type(
+    function: AggregateFunction,
+    argument_type: Option<DataType>,
+) -> Result<Option<DataType>, QueryError> {
+    match (function, argument_type) {
+        (AggregateFunction::Count, _) => Ok(Some(DataType::INT)),
+        (AggregateFunction::Avg | AggregateFunction::Sum, Some(data_type))
+            if number_rank(data_type) == 0 =>
+        {
+            Err(QueryError::InvalidAggregateArgument(
+                function.to_string(),
+                data_type.to_string(),
+            ))
+        }
+        (AggregateFunction::Avg, _) => Ok(Some(DataType::FLOAT)),
+        (AggregateFunction::Sum | AggregateFunction::Min | AggregateFunction::Max, _) => {
+            Ok(argument_type)
+        }
+    }
 }
 
-fn data_from_node(node: &Node) -> Result<Data, QueryError> {
+/// The value searched in the index of the column. A string is converted to the DATE, TIMESTAMP,
+/// UUID or DECIMAL of the column and the numbers to FLOAT, as when they're compared with the column.
+/// None when the value doesn't have the type of the column, the index can't be searched for it then.
+fn searched_value(node: &Node, column: &Column) -> Result<Option<Data>, QueryError> {
     let node_value = evaluate_node(node, &HashMap::new())?;
+    let node_value = match (&node_value, column.data_type) {
+        (NodeValue::String(string), data_type) => match compared_string(string, &data_type) {
+            Some(converted) => converted?,
+            None => node_value,
+        },
+        (NodeValue::Int(_) | NodeValue::BigInt(_) | NodeValue::Decimal(_), DataType::FLOAT) => {
+            cast_value(node_value, &DataType::FLOAT, Coercion::Assignment)?
+        }
+        (NodeValue::Int(_), DataType::BIGINT) => {
+            cast_value(node_value, &DataType::BIGINT, Coercion::Assignment)?
+        }
+        _ => node_value,
+    };
+
+    let data = data_from_node_value(node_value);
+    Ok(data
+        .is_valid_data_for_type(&column.data_type)
+        .then_some(data))
+}
 
-    Ok(match node_value {
+pub fn data_from_node_value(node_value: NodeValue) -> Data {
+    match node_value {
         NodeValue::Int(number) => Data::INT(number),
         NodeValue::String(string) => Data::STRING(string),
         NodeValue::Bool(bool) => Data::BOOLEAN(bool),
         NodeValue::Float(float) => Data::FLOAT(float),
+        NodeValue::Blob(bytes) => Data::BLOB(bytes),
         NodeValue::Null => Data::NULL,
-    })
+        NodeValue::BigInt(number) => Data::BIGINT(number),
+        NodeValue::Decimal(decimal) => Data::DECIMAL(decimal),
+        NodeValue::Date(days) => Data::DATE(days),
+        NodeValue::Timestamp(micros) => Data::TIMESTAMP(micros),
+        NodeValue::Uuid(uuid) => Data::UUID(uuid),
+    }
+}
+
+/// Convert the literal token of INSERT or DEFAULT, other tokens are NULL.
+pub fn data_from_token(token: &LexerToken) -> Data {
+    match token {
+        LexerToken::NumberLiteral(number) => Data::INT(*number),
+        LexerToken::StringLiteral(string) => Data::STRING(string.clone()),
+        LexerToken::BlobLiteral(bytes) => Data::BLOB(bytes.clone()),
+        LexerToken::FloatNumberLiteral(f64) => Data::FLOAT(*f64),
+        LexerToken::BoolLiteral(bool) => Data::BOOLEAN(*bool),
+        LexerToken::BigNumberLiteral(number) => Data::BIGINT(*number),
+        LexerToken::DecimalLiteral(decimal) => Data::DECIMAL(*decimal),
+        LexerToken::DateLiteral(days) => Data::DATE(*days),
+        LexerToken::TimestampLiteral(micros) => Data::TIMESTAMP(*micros),
+        LexerToken::UuidLiteral(uuid) => Data::UUID(*uuid),
+        _ => Data::NULL,
+    }
 }
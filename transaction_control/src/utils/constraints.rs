@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use common::models::{
+    datetime::{date_string, timestamp_string},
+    db::{hex_string, uuid_string, Column, Data, Row},
+};
+use persistence::table::table::{IndexConstraint, Table, TableIndex};
+use query_parser::parser::{
+    expression_tree_eval::{evaluate_node, NodeValue},
+    query_parser::parse_expression,
+};
+
+use crate::{
+    errors::QueryError,
+    utils::common::{data_from_node_value, get_identifier_map},
+};
+
+/// The value of the column omitted by INSERT.
+pub fn get_default_value(column: &Column) -> Result<Data, QueryError> {
+    match &column.constraints.default {
+        Some(default) => {
+            let value = evaluate_node(&parse_expression(default)?, &HashMap::new())?;
+            Ok(data_from_node_value(value))
+        }
+        None => Ok(Data::NULL),
+    }
+}
+
+/// Check the NOT NULL and CHECK constraints of the columns for the rows about to be written.
+pub fn check_column_constraints<'a>(
+    table: &Table,
+    rows: impl Iterator<Item = &'a Row>,
+) -> Result<(), QueryError> {
+    let mut checks = vec![];
+    for column in &table.columns {
+        if let Some(check) = &column.constraints.check {
+            checks.push((column, check, parse_expression(check)?));
+        }
+    }
+    for row in rows {
+        for (column, value) in table.columns.iter().zip(&row.values) {
+            if column.constraints.not_null && *value == Data::NULL {
+                return Err(QueryError::NullValue(
+                    column.name.clone(),
+                    String::from("NOT NULL"),
+                ));
+            }
+        }
+        if checks.is_empty() {
+            continue;
+        }
+        let identifier_map = get_identifier_map(row, table);
+        for (column, check, node) in &checks {
+            // NULL satisfies the check, as it isn't false
+            if evaluate_node(node, &identifier_map)? == NodeValue::Bool(false) {
+                return Err(QueryError::CheckViolation(
+                    column.name.clone(),
+                    check.to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check the PRIMARY KEY and UNIQUE constraints of the table for the rows about to be written.
+/// The rows of `replaced_rows` are overwritten by them, so their current keys don't conflict.
+pub fn check_constraints<'a>(
+    table: &Table,
+    rows: impl Iterator<Item = &'a Row> + Clone,
+    replaced_rows: &[u64],
+) -> Result<(), QueryError> {
+    let mut replaced_rows = replaced_rows.to_vec();
+    replaced_rows.sort_unstable();
+    for index in &table.indexes {
+        let Some(constraint) = index.constraint else {
+            continue;
+        };
+        let positions = get_positions(table, index)?;
+        let mut keys = Vec::new();
+        for row in rows.clone() {
+            let key: Vec<Data> = positions
+                .iter()
+                .map(|position| row.values[*position].clone())
+                .collect();
+            if let Some(null_position) = key.iter().position(|value| *value == Data::NULL) {
+                match constraint {
+                    IndexConstraint::PrimaryKey => {
+                        return Err(QueryError::NullValue(
+                            index.columns[null_position].clone(),
+                            index.name.clone(),
+                        ))
+                    }
+                    // NULL is not equal to any value, such keys never conflict
+                    IndexConstraint::Unique => continue,
+                }
+            }
+            keys.push(key);
+        }
+        if keys.is_empty() {
+            continue;
+        }
+
+        let hash_index = table.get_index(&index.name)?;
+        for key in &keys {
+            let conflicts = hash_index
+                .get_rows(key)
+                .iter()
+                .any(|row_number| replaced_rows.binary_search(row_number).is_err());
+            if conflicts {
+                return Err(duplicate_key(index, key));
+            }
+        }
+
+        keys.sort_by(|a, b| compare_keys(a, b));
+        if let Some(pair) = keys
+            .windows(2)
+            .find(|pair| compare_keys(&pair[0], &pair[1]) == Ordering::Equal)
+        {
+            return Err(duplicate_key(index, &pair[0]));
+        }
+    }
+    Ok(())
+}
+
+fn get_positions(table: &Table, index: &TableIndex) -> Result<Vec<usize>, QueryError> {
+    index
+        .columns
+        .iter()
+        .map(|name| {
+            table
+                .columns
+                .iter()
+                .position(|column| column.name == *name)
+                .ok_or_else(|| QueryError::ColumnNotExists(name.clone(), table.name.clone()))
+        })
+        .collect()
+}
+
+fn compare_keys(a: &[Data], b: &[Data]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| a.total_cmp(b))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+fn duplicate_key(index: &TableIndex, key: &[Data]) -> QueryError {
+    let values: Vec<String> = key
+        .iter()
+        .map(|value| match value {
+            Data::INT(value) => value.to_string(),
+            Data::FLOAT(value) => value.to_string(),
+            Data::STRING(value) => value.clone(),
+            Data::BOOLEAN(value) => value.to_string(),
+            Data::BLOB(value) => hex_string(value),
+            Data::BIGINT(value) => value.to_string(),
+            Data::DECIMAL(value) => value.to_string(),
+            Data::DATE(value) => date_string(*value),
+            Data::TIMESTAMP(value) => timestamp_string(*value),
+            Data::UUID(value) => uuid_string(*value),
+            Data::NULL => String::from("null"),
+        })
+        .collect();
+    QueryError::DuplicateKey(index.name.clone(), index.columns.join(", "), values.join(", "))
+}
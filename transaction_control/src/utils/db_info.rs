@@ -1,6 +1,6 @@
 use common::models::{
     acid_sync::AcidSync,
-    db::{Column, DataType},
+    db::{Column, ColumnConstraints, DataType},
 };
 use persistence::table::table::Table;
 
@@ -9,11 +9,16 @@ use crate::errors::QueryError;
 static TABLES_INFO_NAME: &str = "all_tables";
 
 pub fn add_to_info_table(table_name: String, cols_count: usize, sync: AcidSync) -> Result<(), QueryError> {
-    let table_exists = Table::load(TABLES_INFO_NAME.to_string());
+    {
+        // concurrently created tables must not create the info table twice
+        let rw_lock = sync.get_rw_lock(TABLES_INFO_NAME.to_string());
+        let _x = rw_lock.write().unwrap();
+        let table_exists = Table::load(TABLES_INFO_NAME.to_string());
 
-    if table_exists.is_err() {
-        create_info_table()?;
-    };
+        if table_exists.is_err() {
+            create_info_table()?;
+        };
+    }
 
     let query = format!(
         "INSERT INTO {} VALUES ('{}', {})",
@@ -34,6 +39,33 @@ pub fn remove_from_info_table(table_name: String, sync: AcidSync) -> Result<(),
     Ok(())
 }
 
+/// Replace the catalog row of the table by its current state, e.g. after a rollback.
+pub fn refresh_info_table(table_name: String, sync: AcidSync) -> Result<(), QueryError> {
+    if Table::load(TABLES_INFO_NAME.to_string()).is_ok() {
+        remove_from_info_table(table_name.clone(), sync.clone())?;
+    }
+    if let Ok(table) = Table::load(table_name.clone()) {
+        add_to_info_table(table_name, table.columns.len(), sync)?;
+    }
+    Ok(())
+}
+
+/// Keep the catalog row in sync after the table was renamed or its columns changed.
+pub fn update_info_table(
+    table_name: String,
+    new_table_name: String,
+    cols_count: usize,
+    sync: AcidSync,
+) -> Result<(), QueryError> {
+    let query = format!(
+        "UPDATE {} SET table_name = '{}', columns_count = {} WHERE table_name = '{}'",
+        TABLES_INFO_NAME, new_table_name, cols_count, table_name
+    );
+    crate::process_query(query.as_str(), sync)?;
+
+    Ok(())
+}
+
 fn create_info_table() -> Result<Table, QueryError> {
     let table = Table {
         name: TABLES_INFO_NAME.to_string(),
@@ -41,14 +73,15 @@ fn create_info_table() -> Result<Table, QueryError> {
             Column {
                 name: "table_name".to_string(),
                 data_type: DataType::STRING { size: 256 },
-                is_indexed: false,
+                constraints: ColumnConstraints::default(),
             },
             Column {
                 name: "columns_count".to_string(),
                 data_type: DataType::INT,
-                is_indexed: false,
+                constraints: ColumnConstraints::default(),
             },
         ],
+        indexes: vec![],
     };
 
     table.create()?;
@@ -0,0 +1,302 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use common::models::{
+    db::{Data, DataType, Row},
+    decimal::Decimal,
+};
+use persistence::table::table::Table;
+use query_parser::parser::{
+    errors::ParseError,
+    expression_tree::{AggregateFunction, Node},
+    expression_tree_eval::{evaluate_node, NodeValue},
+};
+
+use crate::{
+    errors::QueryError,
+    utils::common::{
+        aggregate_data_type, check_identifiers_exist, data_from_node_value, get_identifier_map,
+        infer_data_type, node_value_from_data, promoted_value,
+    },
+};
+
+/// Aggregate function call used in the query, its value is computed for every group.
+pub struct Aggregate<'a> {
+    pub node: &'a Node,
+    pub data_type: Option<DataType>,
+    function: AggregateFunction,
+    argument: Option<&'a Node>,
+    /// The type of the argument, its values of narrower types are promoted to it.
+    argument_type: Option<DataType>,
+}
+
+impl<'a> Aggregate<'a> {
+    pub fn from_node(node: &'a Node, table: &Table) -> Result<Self, QueryError> {
+        let Node::Aggregate { function, argument } = node else {
+            unreachable!("only aggregate nodes are collected");
+        };
+        let argument = argument.as_deref();
+
+        let mut argument_type = None;
+        if let Some(argument) = argument {
+            if argument.contains_aggregate() {
+                return Err(ParseError::MisplacedAggregate(node.to_string()).into());
+            }
+            check_identifiers_exist(argument, table)?;
+            argument_type = infer_data_type(argument, table)?;
+        }
+
+        Ok(Aggregate {
+            node,
+            data_type: aggregate_data_type(*function, argument_type)?,
+            function: *function,
+            argument,
+            argument_type,
+        })
+    }
+}
+
+/// Group key with total equality, so that it can be used in the hash map.
+struct GroupKey(Vec<Data>);
+
+impl PartialEq for GroupKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&other.0)
+                .all(|(a, b)| a.total_cmp(b) == Ordering::Equal)
+    }
+}
+
+impl Eq for GroupKey {}
+
+impl Hash for GroupKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+enum Accumulator {
+    Count(i32),
+    IntSum(Option<i64>),
+    BigIntSum(Option<i128>),
+    DecimalSum(Option<Decimal>),
+    /// The sum which doesn't fit DECIMAL, kept only to be reported.
+    DecimalOverflow(f64),
+    FloatSum(Option<f64>),
+    Avg { sum: f64, count: u64 },
+    Min(Data),
+    Max(Data),
+}
+
+impl Accumulator {
+    fn new(aggregate: &Aggregate) -> Self {
+        match aggregate.function {
+            AggregateFunction::Count => Accumulator::Count(0),
+            AggregateFunction::Sum if aggregate.data_type == Some(DataType::INT) => {
+                Accumulator::IntSum(None)
+            }
+            AggregateFunction::Sum if aggregate.data_type == Some(DataType::BIGINT) => {
+                Accumulator::BigIntSum(None)
+            }
+            AggregateFunction::Sum if matches!(aggregate.data_type, Some(DataType::DECIMAL { .. })) => {
+                Accumulator::DecimalSum(None)
+            }
+            AggregateFunction::Sum => Accumulator::FloatSum(None),
+            AggregateFunction::Avg => Accumulator::Avg { sum: 0.0, count: 0 },
+            AggregateFunction::Min => Accumulator::Min(Data::NULL),
+            AggregateFunction::Max => Accumulator::Max(Data::NULL),
+        }
+    }
+
+    /// Add the value of the aggregate argument, None stands for the row of count(*).
+    /// NULL values are ignored by all aggregates. The values of the sums have the type
+    /// of the argument, they are promoted to it first.
+    fn add(&mut self, value: Option<Data>) -> Result<(), QueryError> {
+        let value = match value {
+            None => {
+                if let Accumulator::Count(count) = self {
+                    *count += 1;
+                }
+                return Ok(());
+            }
+            Some(Data::NULL) => return Ok(()),
+            Some(value) => value,
+        };
+        let invalid_argument = |function: AggregateFunction, value: &Data| {
+            QueryError::InvalidAggregateArgument(function.to_string(), value.to_type())
+        };
+
+        match self {
+            Accumulator::Count(count) => *count += 1,
+            Accumulator::IntSum(sum) => match value {
+                Data::INT(number) => *sum = Some(sum.unwrap_or(0) + number as i64),
+                _ => return Err(invalid_argument(AggregateFunction::Sum, &value)),
+            },
+            Accumulator::BigIntSum(sum) => match value {
+                Data::BIGINT(number) => *sum = Some(sum.unwrap_or(0) + number as i128),
+                _ => return Err(invalid_argument(AggregateFunction::Sum, &value)),
+            },
+            Accumulator::DecimalSum(sum) => match value {
+                Data::DECIMAL(number) => {
+                    let total = sum.unwrap_or(Decimal::from_int(0));
+                    // the sum of the expressions may not fit the mantissa, it's reported by finish
+                    *self = match total.checked_add(number) {
+                        Some(total) => Accumulator::DecimalSum(Some(total)),
+                        None => Accumulator::DecimalOverflow(total.to_f64() + number.to_f64()),
+                    };
+                }
+                _ => return Err(invalid_argument(AggregateFunction::Sum, &value)),
+            },
+            Accumulator::DecimalOverflow(sum) => match value {
+                Data::DECIMAL(number) => *sum += number.to_f64(),
+                _ => return Err(invalid_argument(AggregateFunction::Sum, &value)),
+            },
+            Accumulator::FloatSum(sum) => match number_from_data(&value) {
+                Some(number) => *sum = Some(sum.unwrap_or(0.0) + number),
+                None => return Err(invalid_argument(AggregateFunction::Sum, &value)),
+            },
+            Accumulator::Avg { sum, count } => match number_from_data(&value) {
+                Some(number) => {
+                    *sum += number;
+                    *count += 1;
+                }
+                None => return Err(invalid_argument(AggregateFunction::Avg, &value)),
+            },
+            Accumulator::Min(min) => {
+                if *min == Data::NULL || value.total_cmp(min) == Ordering::Less {
+                    *min = value;
+                }
+            }
+            Accumulator::Max(max) => {
+                if *max == Data::NULL || value.total_cmp(max) == Ordering::Greater {
+                    *max = value;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The aggregate of no values is NULL, except for COUNT.
+    fn finish(&self) -> Result<NodeValue, QueryError> {
+        let value = match self {
+            Accumulator::Count(count) => NodeValue::Int(*count),
+            Accumulator::IntSum(None)
+            | Accumulator::BigIntSum(None)
+            | Accumulator::DecimalSum(None)
+            | Accumulator::FloatSum(None) => NodeValue::Null,
+            Accumulator::IntSum(Some(sum)) => match i32::try_from(*sum) {
+                Ok(sum) => NodeValue::Int(sum),
+                Err(_) => {
+                    return Err(QueryError::ValueOutOfRange(
+                        sum.to_string(),
+                        DataType::INT.to_string(),
+                    ))
+                }
+            },
+            Accumulator::BigIntSum(Some(sum)) => match i64::try_from(*sum) {
+                Ok(sum) => NodeValue::BigInt(sum),
+                Err(_) => {
+                    return Err(QueryError::ValueOutOfRange(
+                        sum.to_string(),
+                        DataType::BIGINT.to_string(),
+                    ))
+                }
+            },
+            Accumulator::DecimalSum(Some(sum)) => NodeValue::Decimal(*sum),
+            Accumulator::DecimalOverflow(sum) => {
+                return Err(QueryError::ValueOutOfRange(sum.to_string(), String::from("DECIMAL")))
+            }
+            Accumulator::FloatSum(Some(sum)) => NodeValue::Float(*sum),
+            Accumulator::Avg { count: 0, .. } => NodeValue::Null,
+            Accumulator::Avg { sum, count } => NodeValue::Float(sum / *count as f64),
+            Accumulator::Min(value) | Accumulator::Max(value) => node_value_from_data(value),
+        };
+        Ok(value)
+    }
+}
+
+fn number_from_data(data: &Data) -> Option<f64> {
+    match data {
+        Data::INT(number) => Some(*number as f64),
+        Data::FLOAT(number) => Some(*number),
+        Data::BIGINT(number) => Some(*number as f64),
+        Data::DECIMAL(decimal) => Some(decimal.to_f64()),
+        _ => None,
+    }
+}
+
+/// Hash-based grouping of the rows by the values of `group_by` columns.
+/// Every group is returned as an identifier map containing the grouping columns
+/// and the aggregate values (under the query text of the aggregate),
+/// so that HAVING, ORDER BY and the projection can be evaluated on it.
+/// The groups are in the order of their first row.
+pub fn group_rows(
+    rows: Vec<Row>,
+    table: &Table,
+    group_by: &[String],
+    aggregates: &[Aggregate],
+) -> Result<Vec<HashMap<String, NodeValue>>, QueryError> {
+    let mut positions = Vec::new();
+    for column_name in group_by {
+        match table.columns.iter().position(|c| &c.name == column_name) {
+            Some(position) => positions.push(position),
+            None => {
+                return Err(QueryError::ColumnNotExists(
+                    column_name.clone(),
+                    table.name.clone(),
+                ))
+            }
+        }
+    }
+
+    let new_accumulators = || aggregates.iter().map(Accumulator::new).collect::<Vec<_>>();
+    let mut group_indexes: HashMap<GroupKey, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<Data>, Vec<Accumulator>)> = Vec::new();
+    for row in rows {
+        let key: Vec<Data> = positions.iter().map(|&i| row.values[i].clone()).collect();
+        let group_index = *group_indexes
+            .entry(GroupKey(key.clone()))
+            .or_insert_with(|| {
+                groups.push((key, new_accumulators()));
+                groups.len() - 1
+            });
+
+        let identifier_map = get_identifier_map(&row, table);
+        let accumulators = &mut groups[group_index].1;
+        for (accumulator, aggregate) in accumulators.iter_mut().zip(aggregates) {
+            let value = match (aggregate.argument, aggregate.argument_type) {
+                (Some(argument), Some(argument_type)) => Some(data_from_node_value(promoted_value(
+                    evaluate_node(argument, &identifier_map)?,
+                    argument_type,
+                ))),
+                (Some(argument), None) => Some(data_from_node_value(evaluate_node(argument, &identifier_map)?)),
+                (None, _) => None,
+            };
+            accumulator.add(value)?;
+        }
+    }
+
+    // without GROUP BY the whole table is a single group, even when it's empty
+    if group_by.is_empty() && groups.is_empty() {
+        groups.push((Vec::new(), new_accumulators()));
+    }
+
+    let mut result = Vec::new();
+    for (key, accumulators) in groups {
+        let mut identifier_map = HashMap::new();
+        for (column_name, value) in group_by.iter().zip(&key) {
+            identifier_map.insert(column_name.clone(), node_value_from_data(value));
+        }
+        for (aggregate, accumulator) in aggregates.iter().zip(&accumulators) {
+            identifier_map.insert(aggregate.node.to_string(), accumulator.finish()?);
+        }
+        result.push(identifier_map);
+    }
+    Ok(result)
+}
@@ -0,0 +1,359 @@
+use std::{collections::HashMap, iter, slice};
+
+use common::models::db::{Column, ColumnConstraints, Data, DataType, Row};
+use persistence::table::{
+    row::PersistenceData,
+    table::{IndexKind, Table},
+    table_iterator::RowsIterator,
+};
+use query_parser::parser::{
+    expression_tree::{split_qualified_identifier, Node},
+    expression_tree_eval::evaluate_binary_node,
+    lexer::LexerToken,
+    query_parser::{Join, JoinKind, OrderBy, SelectItem, SelectQuery},
+};
+
+use crate::{
+    errors::QueryError,
+    utils::common::{
+        common_data_type, data_from_node_value, get_identifier_map, node_value_from_data,
+        promoted_value,
+    },
+};
+
+/// Tables of the SELECT query (the FROM table followed by the joined tables)
+/// together with the qualifiers of their columns.
+pub struct JoinScope {
+    tables: Vec<(String, Table)>,
+}
+
+impl JoinScope {
+    pub fn load(query: &SelectQuery) -> Result<Self, QueryError> {
+        let mut tables: Vec<(String, Table)> = Vec::new();
+        for reference in iter::once(&query.table).chain(query.joins.iter().map(|join| &join.table))
+        {
+            let qualifier = reference.qualifier().to_string();
+            if tables.iter().any(|(other, _)| *other == qualifier) {
+                return Err(QueryError::DuplicateTableName(qualifier));
+            }
+            tables.push((qualifier, Table::load(reference.name.clone())?));
+        }
+        Ok(JoinScope { tables })
+    }
+
+    pub fn is_join(&self) -> bool {
+        self.tables.len() > 1
+    }
+
+    /// Resolve every identifier of the query to a column of the first `tables_count` tables.
+    /// The columns of a single table keep their names, the columns of the join
+    /// are named `qualifier.column`.
+    fn qualify_identifier(
+        &self,
+        identifier: &str,
+        tables_count: usize,
+    ) -> Result<String, QueryError> {
+        let tables = &self.tables[..tables_count];
+        let (qualifier, column_name) = split_qualified_identifier(identifier);
+        let mut candidates = tables.iter().filter(|(table_qualifier, table)| {
+            qualifier.is_none_or(|qualifier| qualifier == table_qualifier)
+                && table.columns.iter().any(|column| column.name == column_name)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some((table_qualifier, _)), None) => Ok(self.column_name(table_qualifier, column_name)),
+            (Some(_), Some(_)) => Err(QueryError::AmbiguousColumn(identifier.to_string())),
+            (None, _) => match qualifier {
+                Some(qualifier) if !tables.iter().any(|(other, _)| other == qualifier) => {
+                    Err(QueryError::UnknownTableReference(qualifier.to_string()))
+                }
+                _ => Err(QueryError::ColumnNotExists(
+                    identifier.to_string(),
+                    self.table_name(tables_count),
+                )),
+            },
+        }
+    }
+
+    fn qualify(&self, node: &Node, tables_count: usize) -> Result<Node, QueryError> {
+        node.try_map_identifiers(&mut |identifier| {
+            self.qualify_identifier(identifier, tables_count)
+        })
+    }
+
+    /// Rewrite all identifiers of the query to the column names of `get_table`.
+    /// The join conditions can use only the tables joined so far.
+    pub fn qualify_query(&self, query: SelectQuery) -> Result<SelectQuery, QueryError> {
+        let all = self.tables.len();
+
+        let mut body = Vec::new();
+        for item in query.body {
+            let node = self.qualify(&item.node, all)?;
+            // the result column keeps the name written in the query
+            let alias = match (item.alias, &node) {
+                (Some(alias), _) => Some(alias),
+                (None, Node::Leaf(LexerToken::Star)) => None,
+                (None, _) if node != item.node => Some(item.node.to_string()),
+                (None, _) => None,
+            };
+            body.push(SelectItem { node, alias });
+        }
+
+        let mut joins = Vec::new();
+        for (i, join) in query.joins.into_iter().enumerate() {
+            joins.push(Join {
+                on: self.qualify(&join.on, i + 2)?,
+                ..join
+            });
+        }
+
+        let mut group_by = Vec::new();
+        for column in &query.group_by {
+            group_by.push(self.qualify_identifier(column, all)?);
+        }
+
+        let mut order_by = Vec::new();
+        for item in query.order_by {
+            order_by.push(OrderBy {
+                node: self.qualify(&item.node, all)?,
+                ..item
+            });
+        }
+
+        Ok(SelectQuery {
+            body,
+            joins,
+            where_body: match &query.where_body {
+                Some(node) => Some(self.qualify(node, all)?),
+                None => None,
+            },
+            group_by,
+            having: match &query.having {
+                Some(node) => Some(self.qualify(node, all)?),
+                None => None,
+            },
+            order_by,
+            ..query
+        })
+    }
+
+    /// The FROM table itself, or the columns of all the joined tables
+    /// when the query is a join.
+    pub fn get_table(&self) -> Table {
+        if self.is_join() {
+            self.joined_table(self.tables.len())
+        } else {
+            self.tables[0].1.clone()
+        }
+    }
+
+    fn joined_table(&self, tables_count: usize) -> Table {
+        let mut columns = Vec::new();
+        for (qualifier, table) in &self.tables[..tables_count] {
+            for column in &table.columns {
+                columns.push(Column {
+                    name: self.column_name(qualifier, &column.name),
+                    data_type: column.data_type,
+                    constraints: ColumnConstraints::default(),
+                });
+            }
+        }
+        Table {
+            name: self.table_name(tables_count),
+            columns,
+            indexes: vec![],
+        }
+    }
+
+    fn column_name(&self, qualifier: &str, column_name: &str) -> String {
+        if self.is_join() {
+            format!("{}.{}", qualifier, column_name)
+        } else {
+            column_name.to_string()
+        }
+    }
+
+    fn table_name(&self, tables_count: usize) -> String {
+        self.tables[..tables_count]
+            .iter()
+            .map(|(_, table)| table.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" JOIN ")
+    }
+
+    /// Join the tables one by one, the qualified `joins` are expected.
+    pub fn join_rows(&self, joins: &[Join]) -> Result<Vec<Row>, QueryError> {
+        let mut rows = RowsIterator::from_table(&self.tables[0].1)?.collect::<Result<Vec<Row>, _>>()?;
+        for (i, join) in joins.iter().enumerate() {
+            let right = &self.tables[i + 1].1;
+            rows = join_table(rows, right, join, &self.joined_table(i + 2))?;
+        }
+        Ok(rows)
+    }
+}
+
+/// Join the rows with the `right` table. When the join condition contains an equality
+/// of columns, the right rows are found through the column index if there is one,
+/// or through a hash table built over the right table. Otherwise all pairs are tried.
+/// The values of the columns are compared in the type unifying both of them, as by `=`.
+fn join_table(
+    left_rows: Vec<Row>,
+    right: &Table,
+    join: &Join,
+    table: &Table,
+) -> Result<Vec<Row>, QueryError> {
+    let left_width = table.columns.len() - right.columns.len();
+    let mut result = Vec::new();
+
+    // the columns of the types without a common type are left to the join condition
+    let join_key =
+        find_join_key(&join.on, table, left_width).and_then(|(left_position, right_position)| {
+            let right_type = right.columns[right_position].data_type;
+            let key_type = common_data_type(table.columns[left_position].data_type, right_type)?;
+            Some((left_position, right_position, key_type))
+        });
+    let join_index = join_key.and_then(|(_left_position, right_position, key_type)| {
+        let column = &right.columns[right_position];
+        if !keeps_values(column.data_type, key_type) {
+            return None;
+        }
+        right
+            .indexes
+            .iter()
+            .find(|index| index.kind == IndexKind::Hash && index.columns == [column.name.clone()])
+    });
+    match (join_key, join_index) {
+        (Some((left_position, _right_position, key_type)), Some(index)) => {
+            let index = right.get_index(&index.name)?;
+            for left_row in left_rows {
+                let value = promoted_data(&left_row.values[left_position], key_type);
+                let mut row_numbers = Vec::new();
+                // NULL never matches
+                if value != Data::NULL {
+                    row_numbers = index.get_rows(slice::from_ref(&value));
+                }
+                row_numbers.sort_unstable();
+
+                let mut right_rows = Vec::new();
+                for row_number in row_numbers {
+                    right_rows.push(right.seek_row(row_number)?);
+                }
+                push_joined_rows(&mut result, left_row, &right_rows, join, table)?;
+            }
+        }
+        (Some((left_position, right_position, key_type)), None) => {
+            let right_rows = RowsIterator::from_table(right)?.collect::<Result<Vec<Row>, _>>()?;
+            let mut hash_table: HashMap<u64, Vec<(Data, &Row)>> = HashMap::new();
+            for row in &right_rows {
+                let value = promoted_data(&row.values[right_position], key_type);
+                if value != Data::NULL {
+                    hash_table
+                        .entry(value.calculate_hash())
+                        .or_default()
+                        .push((value, row));
+                }
+            }
+
+            for left_row in left_rows {
+                let value = promoted_data(&left_row.values[left_position], key_type);
+                let mut matching_rows = Vec::new();
+                if value != Data::NULL {
+                    if let Some(rows) = hash_table.get(&value.calculate_hash()) {
+                        for (right_value, row) in rows {
+                            if *right_value == value {
+                                matching_rows.push((*row).clone());
+                            }
+                        }
+                    }
+                }
+                push_joined_rows(&mut result, left_row, &matching_rows, join, table)?;
+            }
+        }
+        (None, _) => {
+            let right_rows = RowsIterator::from_table(right)?.collect::<Result<Vec<Row>, _>>()?;
+            for left_row in left_rows {
+                push_joined_rows(&mut result, left_row, &right_rows, join, table)?;
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The value converted to the type of the join key, when its type is narrower.
+fn promoted_data(value: &Data, key_type: DataType) -> Data {
+    data_from_node_value(promoted_value(node_value_from_data(value), key_type))
+}
+
+/// Whether the values of the column keep their hash as the values of the key type,
+/// the strings of any length and the decimals of any scale do.
+fn keeps_values(data_type: DataType, key_type: DataType) -> bool {
+    data_type == key_type
+        || data_type.is_string() && key_type.is_string()
+        || matches!(
+            (data_type, key_type),
+            (DataType::DECIMAL { .. }, DataType::DECIMAL { .. })
+        )
+}
+
+/// Combine the left row with every right row satisfying the join condition.
+/// The left row without a match is kept for LEFT JOIN, padded with NULLs.
+fn push_joined_rows(
+    result: &mut Vec<Row>,
+    left_row: Row,
+    right_rows: &[Row],
+    join: &Join,
+    table: &Table,
+) -> Result<(), QueryError> {
+    let mut is_matched = false;
+    for right_row in right_rows {
+        let mut values = left_row.values.clone();
+        values.extend(right_row.values.iter().cloned());
+        let row = Row { values };
+        if evaluate_binary_node(&join.on, &get_identifier_map(&row, table))? {
+            result.push(row);
+            is_matched = true;
+        }
+    }
+
+    if !is_matched && join.kind == JoinKind::Left {
+        let right_width = table.columns.len() - left_row.values.len();
+        let mut values = left_row.values;
+        values.extend(iter::repeat_n(Data::NULL, right_width));
+        result.push(Row { values });
+    }
+    Ok(())
+}
+
+/// Find `left_column = right_column` in the join condition (possibly inside AND),
+/// returns the position of the column in the left rows and in the right table.
+fn find_join_key(on: &Node, table: &Table, left_width: usize) -> Option<(usize, usize)> {
+    match on {
+        Node::Binary {
+            left,
+            op: LexerToken::LogicalOp(op),
+            right,
+        } if op == "and" => find_join_key(left, table, left_width)
+            .or_else(|| find_join_key(right, table, left_width)),
+        Node::Binary {
+            left,
+            op: LexerToken::CompareOp(op),
+            right,
+        } if op == "=" => {
+            let (Node::Leaf(LexerToken::Identifier(a)), Node::Leaf(LexerToken::Identifier(b))) =
+                (left.as_ref(), right.as_ref())
+            else {
+                return None;
+            };
+            let a = table.columns.iter().position(|column| &column.name == a)?;
+            let b = table.columns.iter().position(|column| &column.name == b)?;
+            if a < left_width && b >= left_width {
+                Some((a, b - left_width))
+            } else if b < left_width && a >= left_width {
+                Some((b, a - left_width))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
@@ -1,3 +1,6 @@
 pub mod db_info;
 pub mod tests;
 pub mod common;
+pub mod grouping;
+pub mod join;
+pub mod constraints;
@@ -1,14 +1,31 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use common::models::{
         acid_sync::AcidSync,
-        db::{Column, Data, DataType, Row},
+        datetime::timestamp_from_string,
+        db::{Column, ColumnConstraints, Data, DataType, Row},
+        decimal::Decimal,
     };
-    use persistence::table::table::Table;
+    use persistence::table::table::{IndexConstraint, IndexKind, Table, TableIndex};
+    use query_parser::parser::errors::ParseError;
 
-    use crate::{errors::QueryError, process_query};
+    use crate::{errors::QueryError, process_query, Session};
 
     use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn hash_index(column: &str) -> TableIndex {
+        TableIndex {
+            name: column.to_string(),
+            columns: vec![column.to_string()],
+            kind: IndexKind::Hash,
+            constraint: None,
+        }
+    }
 
     fn sync_guard() -> AcidSync {
         AcidSync::default()
@@ -341,7 +358,7 @@ mod tests {
         if let Err(e) = result {
             match e {
                 QueryError::ColumnNotExists(_, _) => (),
-                _ => assert!(false, "The error should be ColumnNotExists"),
+                _ => panic!("The error should be ColumnNotExists"),
             }
         }
 
@@ -397,16 +414,17 @@ mod tests {
         let column1 = Column {
             name: String::from("Id"),
             data_type: DataType::INT,
-            is_indexed: true,
+            constraints: ColumnConstraints::default(),
         };
         let column2 = Column {
             name: String::from("Name"),
             data_type: DataType::STRING { size: 256 },
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let table = Table {
             name: String::from(table_name),
             columns: vec![column1, column2],
+            indexes: vec![hash_index("Id")],
         };
         assert!(table.create().is_ok());
 
@@ -454,6 +472,17 @@ mod tests {
         }];
         let data = result.unwrap().data.unwrap().rows;
         assert_eq!(expected, data);
+        // the string isn't compared with the integers, with the index or without it
+        let result = process_query(
+            format!("SELECT * FROM {} WHERE Id = '2'", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_err(), "String compared with the indexed integers");
+        let result = process_query(
+            format!("SELECT * FROM {} WHERE Id IN (2, 3000000000)", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert_eq!(result.unwrap().data.unwrap().rows, expected);
 
         drop_table(table_name);
     }
@@ -582,16 +611,17 @@ mod tests {
         let column1 = Column {
             name: String::from("Id"),
             data_type: DataType::INT,
-            is_indexed: true,
+            constraints: ColumnConstraints::default(),
         };
         let column2 = Column {
             name: String::from("Name"),
             data_type: DataType::STRING { size: 256 },
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let table = Table {
             name: String::from(table_name),
             columns: vec![column1, column2],
+            indexes: vec![hash_index("Id")],
         };
         assert!(table.create().is_ok());
 
@@ -652,20 +682,21 @@ mod tests {
     #[test]
     fn test_select_float_with_index() {
         let table_name = "test_select_float_with_index";
-        let sync_guard = sync_guard.clone();
+        let sync_guard = sync_guard;
         let column1 = Column {
             name: String::from("Id"),
             data_type: DataType::INT,
-            is_indexed: false,
+            constraints: ColumnConstraints::default(),
         };
         let column2 = Column {
             name: String::from("float_column"),
             data_type: DataType::FLOAT,
-            is_indexed: true,
+            constraints: ColumnConstraints::default(),
         };
         let table = Table {
             name: String::from(table_name),
             columns: vec![column1, column2],
+            indexes: vec![hash_index("float_column")],
         };
         assert!(table.create().is_ok());
 
@@ -756,14 +787,7 @@ mod tests {
             "Index on column x should be created"
         );
         let table_after_index_creation = Table::load(table_name.to_string()).unwrap();
-        assert_eq!(
-            table_after_index_creation
-                .columns
-                .get(0)
-                .unwrap()
-                .is_indexed,
-            true
-        );
+        assert_eq!(table_after_index_creation.indexes, vec![hash_index("x")]);
 
         let result = process_query(
             format!("SELECT * FROM {} WHERE x = 1", table_name).as_str(),
@@ -787,11 +811,2067 @@ mod tests {
         );
 
         let table_after_index_drop = Table::load(table_name.to_string()).unwrap();
+        assert!(table_after_index_drop.indexes.is_empty());
+        assert!(matches!(
+            process_query(format!("DROP INDEX x ON {}", table_name).as_str(), sync_guard.clone()),
+            Err(QueryError::IndexNotExists(..))
+        ));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_update_basic() {
+        let table_name = "test_update_basic";
+        let sync_guard = sync_guard();
+        assert!(
+            process_query(
+                format!("CREATE TABLE {} x int, y varchar", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Table not created"
+        );
+        for (x, y) in [(1, "text"), (2, "text2"), (3, "text3")] {
+            assert!(
+                process_query(
+                    format!("INSERT INTO {} VALUES {}, '{}'", table_name, x, y).as_str(),
+                    sync_guard.clone()
+                )
+                .is_ok(),
+                "Row should be successfully inserted"
+            );
+        }
+
+        let result = process_query(
+            format!(
+                "UPDATE {} SET x = x * 10, y = y + '_updated' WHERE x >= 2",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Update failed");
+        assert_eq!(
+            result.unwrap().message.unwrap(),
+            format!("Updated 2 rows in table {}.", table_name)
+        );
+
+        let result = process_query(
+            format!("SELECT * FROM {}", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Select failed");
+
+        let expected = vec![
+            Row {
+                values: vec![Data::INT(1), Data::STRING("text".to_string())],
+            },
+            Row {
+                values: vec![Data::INT(20), Data::STRING("text2_updated".to_string())],
+            },
+            Row {
+                values: vec![Data::INT(30), Data::STRING("text3_updated".to_string())],
+            },
+        ];
+        let data = result.unwrap().data.unwrap().rows;
+        assert_eq!(expected, data);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_update_indexed_column() {
+        let table_name = "test_update_indexed_column";
+        let sync_guard = sync_guard();
+
+        let column1 = Column {
+            name: String::from("Id"),
+            data_type: DataType::INT,
+            constraints: ColumnConstraints::default(),
+        };
+        let column2 = Column {
+            name: String::from("Name"),
+            data_type: DataType::STRING { size: 256 },
+            constraints: ColumnConstraints::default(),
+        };
+        let table = Table {
+            name: String::from(table_name),
+            columns: vec![column1, column2],
+            indexes: vec![hash_index("Id")],
+        };
+        assert!(table.create().is_ok());
+
+        for (id, name) in [(1, "text"), (2, "text2")] {
+            assert!(
+                process_query(
+                    format!("INSERT INTO {} VALUES {}, '{}'", table_name, id, name).as_str(),
+                    sync_guard.clone()
+                )
+                .is_ok(),
+                "Row should be successfully inserted"
+            );
+        }
+
+        assert!(
+            process_query(
+                format!("UPDATE {} SET Id = 5 WHERE Id = 2", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Update failed"
+        );
+
+        let result = process_query(
+            format!("SELECT * FROM {} WHERE Id = 2", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.unwrap().data.unwrap().rows.is_empty());
+
+        let result = process_query(
+            format!("SELECT * FROM {} WHERE Id = 5", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        let expected = vec![Row {
+            values: vec![Data::INT(5), Data::STRING("text2".to_string())],
+        }];
+        assert_eq!(expected, result.unwrap().data.unwrap().rows);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_update_with_wrong_datatype_value() {
+        let table_name = "test_update_with_wrong_datatype_value";
+        let sync_guard = sync_guard();
+        assert!(
+            process_query(
+                format!("CREATE TABLE {} x int, y varchar", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Table not created"
+        );
+        assert!(
+            process_query(
+                format!("INSERT INTO {} VALUES 24, 'text'", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Row should be successfully inserted"
+        );
+
+        let result = process_query(
+            format!("UPDATE {} SET x = y", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::InvalidDataType(_, _, _))));
+
+        let result = process_query(
+            format!("UPDATE {} SET unknown = 1", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::ColumnNotExists(_, _))));
+
+        let result = process_query(
+            format!("SELECT * FROM {}", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        let expected = vec![Row {
+            values: vec![Data::INT(24), Data::STRING("text".to_string())],
+        }];
+        assert_eq!(expected, result.unwrap().data.unwrap().rows);
+
+        drop_table(table_name);
+    }
+    fn create_order_by_table(table_name: &str, sync_guard: AcidSync) {
+        assert!(
+            process_query(
+                format!("CREATE TABLE {} x int, y varchar, f float", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Table not created"
+        );
+        for values in [
+            "2, 'b', 1.5",
+            "1, 'c', NULL",
+            "2, 'a', 0.25",
+            "3, 'd', 0.5",
+        ] {
+            assert!(
+                process_query(
+                    format!("INSERT INTO {} VALUES {}", table_name, values).as_str(),
+                    sync_guard.clone()
+                )
+                .is_ok(),
+                "Row should be successfully inserted"
+            );
+        }
+    }
+
+    fn select_column(query: String, sync_guard: AcidSync, column: usize) -> Vec<Data> {
+        let result = process_query(query.as_str(), sync_guard);
+        assert!(result.is_ok(), "Select failed");
+        result
+            .unwrap()
+            .data
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|row| row.values[column].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_order_by_multiple_keys() {
+        let table_name = "test_select_order_by_multiple_keys";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        let result = process_query(
+            format!("SELECT x, y FROM {} ORDER BY x DESC, y", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Select failed");
+        let expected = vec![
+            Row {
+                values: vec![Data::INT(3), Data::STRING("d".to_string())],
+            },
+            Row {
+                values: vec![Data::INT(2), Data::STRING("a".to_string())],
+            },
+            Row {
+                values: vec![Data::INT(2), Data::STRING("b".to_string())],
+            },
+            Row {
+                values: vec![Data::INT(1), Data::STRING("c".to_string())],
+            },
+        ];
+        assert_eq!(expected, result.unwrap().data.unwrap().rows);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_select_order_by_nulls() {
+        let table_name = "test_select_order_by_nulls";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        assert_eq!(
+            select_column(
+                format!("SELECT f FROM {} ORDER BY f", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![
+                Data::FLOAT(0.25),
+                Data::FLOAT(0.5),
+                Data::FLOAT(1.5),
+                Data::NULL
+            ]
+        );
+        assert_eq!(
+            select_column(
+                format!("SELECT f FROM {} ORDER BY f DESC", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![
+                Data::NULL,
+                Data::FLOAT(1.5),
+                Data::FLOAT(0.5),
+                Data::FLOAT(0.25)
+            ]
+        );
+        assert_eq!(
+            select_column(
+                format!("SELECT f FROM {} ORDER BY f ASC NULLS FIRST", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![
+                Data::NULL,
+                Data::FLOAT(0.25),
+                Data::FLOAT(0.5),
+                Data::FLOAT(1.5)
+            ]
+        );
+        assert_eq!(
+            select_column(
+                format!("SELECT f FROM {} ORDER BY f DESC NULLS LAST", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![
+                Data::FLOAT(1.5),
+                Data::FLOAT(0.5),
+                Data::FLOAT(0.25),
+                Data::NULL
+            ]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_select_order_by_column_not_in_projection() {
+        let table_name = "test_select_order_by_column_not_in_projection";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        assert_eq!(
+            select_column(
+                format!("SELECT y FROM {} ORDER BY x * f NULLS FIRST, y", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![
+                Data::STRING("c".to_string()),
+                Data::STRING("a".to_string()),
+                Data::STRING("d".to_string()),
+                Data::STRING("b".to_string())
+            ]
+        );
+
+        let result = process_query(
+            format!("SELECT y FROM {} ORDER BY unknown", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::ColumnNotExists(_, _))));
+
+        drop_table(table_name);
+    }
+    #[test]
+    fn test_select_with_limit_and_offset() {
+        let table_name = "test_select_with_limit_and_offset";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        assert_eq!(
+            select_column(
+                format!("SELECT x FROM {} LIMIT 2", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(2), Data::INT(1)]
+        );
+        assert_eq!(
+            select_column(
+                format!("SELECT x FROM {} WHERE x > 1 LIMIT 2 OFFSET 1", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(2), Data::INT(3)]
+        );
+        assert_eq!(
+            select_column(
+                format!("SELECT y FROM {} ORDER BY f LIMIT 2 OFFSET 1", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::STRING("d".to_string()), Data::STRING("b".to_string())]
+        );
+        assert!(select_column(
+            format!("SELECT x FROM {} LIMIT 0", table_name),
+            sync_guard.clone(),
+            0
+        )
+        .is_empty());
+        assert!(select_column(
+            format!("SELECT x FROM {} LIMIT 10 OFFSET 4", table_name),
+            sync_guard.clone(),
+            0
+        )
+        .is_empty());
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_delete_with_limit() {
+        let table_name = "test_delete_with_limit";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        let result = process_query(
+            format!("DELETE FROM {} WHERE x >= 2 LIMIT 1 OFFSET 1", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Delete failed");
+        assert_eq!(
+            result.unwrap().message.unwrap(),
+            format!("Deleted 1 rows from table {}.", table_name)
+        );
+        assert_eq!(
+            select_column(format!("SELECT y FROM {}", table_name), sync_guard.clone(), 0),
+            vec![
+                Data::STRING("b".to_string()),
+                Data::STRING("c".to_string()),
+                Data::STRING("d".to_string())
+            ]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_select_aggregates_without_group_by() {
+        let table_name = "test_select_aggregates_without_group_by";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        let result = process_query(
+            format!(
+                "SELECT COUNT(*), COUNT(f), SUM(x), AVG(x), MIN(y), MAX(f) FROM {}",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Select failed");
+        let data = result.unwrap().data.unwrap();
+        let column_types: Vec<DataType> = data.columns.iter().map(|c| c.data_type).collect();
+        assert_eq!(
+            column_types,
+            vec![
+                DataType::INT,
+                DataType::INT,
+                DataType::INT,
+                DataType::FLOAT,
+                DataType::STRING { size: 256 },
+                DataType::FLOAT
+            ]
+        );
+        assert_eq!(data.columns[0].name, "count(*)");
+        assert_eq!(
+            data.rows,
+            vec![Row {
+                values: vec![
+                    Data::INT(4),
+                    Data::INT(3),
+                    Data::INT(8),
+                    Data::FLOAT(2.0),
+                    Data::STRING("a".to_string()),
+                    Data::FLOAT(1.5)
+                ]
+            }]
+        );
+
+        // a table without matching rows is still a single group
+        let result = process_query(
+            format!("SELECT COUNT(*), SUM(x) FROM {} WHERE x > 10", table_name).as_str(),
+            sync_guard.clone(),
+        );
         assert_eq!(
-            table_after_index_drop.columns.get(0).unwrap().is_indexed,
-            false
+            result.unwrap().data.unwrap().rows,
+            vec![Row {
+                values: vec![Data::INT(0), Data::NULL]
+            }]
         );
 
         drop_table(table_name);
     }
+
+    #[test]
+    fn test_select_group_by_with_having() {
+        let table_name = "test_select_group_by_with_having";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        let result = process_query(
+            format!(
+                "SELECT x, COUNT(*), SUM(f) FROM {} GROUP BY x HAVING COUNT(*) > 1 OR x = 3 ORDER BY x DESC",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Select failed");
+        assert_eq!(
+            result.unwrap().data.unwrap().rows,
+            vec![
+                Row {
+                    values: vec![Data::INT(3), Data::INT(1), Data::FLOAT(0.5)]
+                },
+                Row {
+                    values: vec![Data::INT(2), Data::INT(2), Data::FLOAT(1.75)]
+                }
+            ]
+        );
+
+        // groups can be sorted by an aggregate which is not in the projection
+        assert_eq!(
+            select_column(
+                format!(
+                    "SELECT x FROM {} GROUP BY x ORDER BY MAX(y) DESC LIMIT 2",
+                    table_name
+                ),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(3), Data::INT(1)]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_select_group_by_errors() {
+        let table_name = "test_select_group_by_errors";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        let result = process_query(
+            format!("SELECT y, COUNT(*) FROM {} GROUP BY x", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::NotGroupedColumn(column)) if column == "y"));
+
+        let result = process_query(
+            format!("SELECT * FROM {} HAVING COUNT(*) > 1", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::NotGroupedColumn(_))));
+
+        let result = process_query(
+            format!("SELECT SUM(y) FROM {}", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(
+            result,
+            Err(QueryError::InvalidAggregateArgument(_, _))
+        ));
+
+        let result = process_query(
+            format!("SELECT x FROM {} WHERE COUNT(*) > 1", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::ParseError(_))));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_select_with_expressions() {
+        let table_name = "test_select_with_expressions";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        let result = process_query(
+            format!(
+                "SELECT x * 2 AS double, y + '!', f > 0.3 AS big, x FROM {} WHERE x < 3",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Select failed");
+        let data = result.unwrap().data.unwrap();
+        assert_eq!(
+            data.columns,
+            vec![
+                Column {
+                    name: "double".to_string(),
+                    data_type: DataType::INT,
+                    constraints: ColumnConstraints::default(),
+                },
+                Column {
+                    name: "y + '!'".to_string(),
+                    data_type: DataType::STRING { size: 257 },
+                    constraints: ColumnConstraints::default(),
+                },
+                Column {
+                    name: "big".to_string(),
+                    data_type: DataType::BOOLEAN,
+                    constraints: ColumnConstraints::default(),
+                },
+                Column {
+                    name: "x".to_string(),
+                    data_type: DataType::INT,
+                    constraints: ColumnConstraints::default(),
+                },
+            ]
+        );
+        assert_eq!(
+            data.rows,
+            vec![
+                Row {
+                    values: vec![
+                        Data::INT(4),
+                        Data::STRING("b!".to_string()),
+                        Data::BOOLEAN(true),
+                        Data::INT(2)
+                    ]
+                },
+                Row {
+                    values: vec![
+                        Data::INT(2),
+                        Data::STRING("c!".to_string()),
+                        Data::NULL,
+                        Data::INT(1)
+                    ]
+                },
+                Row {
+                    values: vec![
+                        Data::INT(4),
+                        Data::STRING("a!".to_string()),
+                        Data::BOOLEAN(false),
+                        Data::INT(2)
+                    ]
+                },
+            ]
+        );
+
+        // ORDER BY the alias of an expression
+        let result = process_query(
+            format!(
+                "SELECT x * 2 AS double, y FROM {} WHERE x < 3 ORDER BY double DESC, y",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        let data = result.unwrap().data.unwrap();
+        assert_eq!(
+            data.rows,
+            vec![
+                Row {
+                    values: vec![Data::INT(4), Data::STRING("a".to_string())]
+                },
+                Row {
+                    values: vec![Data::INT(4), Data::STRING("b".to_string())]
+                },
+                Row {
+                    values: vec![Data::INT(2), Data::STRING("c".to_string())]
+                },
+            ]
+        );
+
+        // expressions over the groups
+        let result = process_query(
+            format!(
+                "SELECT x + 1 AS next, SUM(f) / COUNT(*) AS mean FROM {} GROUP BY x ORDER BY x",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        let data = result.unwrap().data.unwrap();
+        assert_eq!(data.columns[1].name, "mean");
+        assert_eq!(data.columns[1].data_type, DataType::FLOAT);
+        assert_eq!(
+            data.rows,
+            vec![
+                Row {
+                    values: vec![Data::INT(2), Data::NULL]
+                },
+                Row {
+                    values: vec![Data::INT(3), Data::FLOAT(0.875)]
+                },
+                Row {
+                    values: vec![Data::INT(4), Data::FLOAT(0.5)]
+                },
+            ]
+        );
+
+        let result = process_query(
+            format!("SELECT x - unknown FROM {}", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::ColumnNotExists(_, _))));
+
+        drop_table(table_name);
+    }
+
+    fn create_join_tables(people: &str, depts: &str, sync_guard: AcidSync) {
+        for query in [
+            format!("CREATE TABLE {} id int, name varchar, dept int", people),
+            format!("CREATE TABLE {} id int, title varchar", depts),
+            format!("INSERT INTO {} VALUES 1, 'ann', 10", people),
+            format!("INSERT INTO {} VALUES 2, 'bob', 20", people),
+            format!("INSERT INTO {} VALUES 3, 'cid', NULL", people),
+            format!("INSERT INTO {} VALUES 4, 'dan', 10", people),
+            format!("INSERT INTO {} VALUES 10, 'eng'", depts),
+            format!("INSERT INTO {} VALUES 30, 'ops'", depts),
+        ] {
+            assert!(
+                process_query(query.as_str(), sync_guard.clone()).is_ok(),
+                "Query {} failed",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn test_select_inner_join() {
+        let (people, depts) = ("test_select_inner_join_people", "test_select_inner_join_depts");
+        let sync_guard = sync_guard();
+        create_join_tables(people, depts, sync_guard.clone());
+
+        let query = format!(
+            "SELECT p.name, d.title FROM {} AS p JOIN {} AS d ON p.dept = d.id",
+            people, depts
+        );
+        let expected_rows = vec![
+            Row {
+                values: vec![
+                    Data::STRING("ann".to_string()),
+                    Data::STRING("eng".to_string()),
+                ],
+            },
+            Row {
+                values: vec![
+                    Data::STRING("dan".to_string()),
+                    Data::STRING("eng".to_string()),
+                ],
+            },
+        ];
+
+        // hash join
+        let result = process_query(query.as_str(), sync_guard.clone());
+        assert!(result.is_ok(), "Select failed");
+        let data = result.unwrap().data.unwrap();
+        assert_eq!(data.columns[0].name, "p.name");
+        assert_eq!(data.rows, expected_rows);
+
+        // nested loop join probing the index
+        assert!(process_query(
+            format!("CREATE INDEX id ON {}", depts).as_str(),
+            sync_guard.clone()
+        )
+        .is_ok());
+        let result = process_query(query.as_str(), sync_guard.clone());
+        assert_eq!(result.unwrap().data.unwrap().rows, expected_rows);
+
+        // unqualified columns are resolved when they are not ambiguous
+        assert_eq!(
+            select_column(
+                format!(
+                    "SELECT name FROM {} JOIN {} ON dept = {}.id WHERE title = 'eng' AND name <> 'ann'",
+                    people, depts, depts
+                ),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::STRING("dan".to_string())]
+        );
+
+        // the join condition without equality tries all pairs
+        assert_eq!(
+            select_column(
+                format!(
+                    "SELECT COUNT(*) FROM {} AS p JOIN {} AS d ON p.dept < d.id",
+                    people, depts
+                ),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(3)]
+        );
+
+        // the keys of different types are compared as by `=`, with the index and without it
+        let keys = format!("{}_keys", depts);
+        for query in [
+            format!("CREATE TABLE {} code float, big bigint", keys),
+            format!("INSERT INTO {} VALUES (10.0, 10), (30.5, 30)", keys),
+        ] {
+            assert!(process_query(query.as_str(), sync_guard.clone()).is_ok(), "Query {} failed", query);
+        }
+        let names = vec![Data::STRING("ann".to_string()), Data::STRING("dan".to_string())];
+        let select_names = |on: &str| {
+            select_column(
+                format!("SELECT p.name FROM {} AS p JOIN {} AS k ON {} ORDER BY p.id", people, keys, on),
+                sync_guard.clone(),
+                0,
+            )
+        };
+        assert_eq!(select_names("p.dept = k.code"), names);
+        assert!(process_query(format!("CREATE INDEX big ON {}", keys).as_str(), sync_guard.clone()).is_ok());
+        assert_eq!(select_names("k.big = p.dept"), names);
+        assert_eq!(
+            select_column(
+                format!("SELECT d.title FROM {} AS k JOIN {} AS d ON k.code = d.id", keys, depts),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::STRING("eng".to_string())]
+        );
+
+        drop_table(people);
+        drop_table(depts);
+        drop_table(&keys);
+    }
+
+    #[test]
+    fn test_select_left_join() {
+        let (people, depts) = ("test_select_left_join_people", "test_select_left_join_depts");
+        let sync_guard = sync_guard();
+        create_join_tables(people, depts, sync_guard.clone());
+
+        let result = process_query(
+            format!(
+                "SELECT p.name, d.title FROM {} AS p LEFT JOIN {} AS d ON p.dept = d.id ORDER BY p.id DESC",
+                people, depts
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Select failed");
+        assert_eq!(
+            result
+                .unwrap()
+                .data
+                .unwrap()
+                .rows
+                .into_iter()
+                .map(|row| row.values)
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    Data::STRING("dan".to_string()),
+                    Data::STRING("eng".to_string())
+                ],
+                vec![Data::STRING("cid".to_string()), Data::NULL],
+                vec![Data::STRING("bob".to_string()), Data::NULL],
+                vec![
+                    Data::STRING("ann".to_string()),
+                    Data::STRING("eng".to_string())
+                ],
+            ]
+        );
+
+        assert_eq!(
+            select_column(
+                format!(
+                    "SELECT COUNT(d.id) FROM {} AS p LEFT JOIN {} AS d ON p.dept = d.id",
+                    people, depts
+                ),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(2)]
+        );
+
+        drop_table(people);
+        drop_table(depts);
+    }
+
+    #[test]
+    fn test_select_join_errors() {
+        let (people, depts) = ("test_select_join_errors_people", "test_select_join_errors_depts");
+        let sync_guard = sync_guard();
+        create_join_tables(people, depts, sync_guard.clone());
+
+        let result = process_query(
+            format!("SELECT id FROM {} JOIN {} ON dept = title", people, depts).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::AmbiguousColumn(column)) if column == "id"));
+
+        let result = process_query(
+            format!("SELECT x.id FROM {} AS p JOIN {} ON p.dept = 1", people, depts).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::UnknownTableReference(table)) if table == "x"));
+
+        let result = process_query(
+            format!("SELECT p.id FROM {} AS p JOIN {} AS p ON p.dept = 1", people, depts).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::DuplicateTableName(_))));
+
+        // the join condition can use only the tables joined so far
+        let result = process_query(
+            format!(
+                "SELECT * FROM {} AS p JOIN {} AS a ON a.id = b.id JOIN {} AS b ON p.id = b.id",
+                people, depts, depts
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::UnknownTableReference(table)) if table == "b"));
+
+        drop_table(people);
+        drop_table(depts);
+    }
+
+    #[test]
+    fn test_select_with_predicates() {
+        let table_name = "test_select_with_predicates";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+
+        let select_y = |condition: &str| {
+            select_column(
+                format!("SELECT y FROM {} WHERE {} ORDER BY y", table_name, condition),
+                sync_guard.clone(),
+                0,
+            )
+        };
+        let strings = |values: &[&str]| -> Vec<Data> {
+            values.iter().map(|value| Data::STRING(value.to_string())).collect()
+        };
+
+        assert_eq!(select_y("x IN (1, 3)"), strings(&["c", "d"]));
+        assert_eq!(select_y("x NOT IN (1, 3)"), strings(&["a", "b"]));
+        // NOT IN with NULL in the list is never true
+        assert_eq!(select_y("x NOT IN (1, NULL)"), strings(&[]));
+        assert_eq!(select_y("f BETWEEN 0.3 AND 1.5"), strings(&["b", "d"]));
+        assert_eq!(select_y("f NOT BETWEEN 0.3 AND 1.5"), strings(&["a"]));
+        assert_eq!(select_y("y LIKE '_' AND y NOT LIKE 'a%'"), strings(&["b", "c", "d"]));
+        assert_eq!(select_y("y ILIKE 'B'"), strings(&["b"]));
+        assert_eq!(select_y("f IS NULL"), strings(&["c"]));
+        assert_eq!(select_y("f IS NOT NULL AND x = 2"), strings(&["a", "b"]));
+        // comparison with NULL is unknown
+        assert_eq!(select_y("f = NULL OR f <> NULL"), strings(&[]));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_in_with_index() {
+        let table_name = "test_in_with_index";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        assert!(
+            process_query(
+                format!("CREATE INDEX x ON {}", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Index not created"
+        );
+
+        // every row is returned once, even for a repeated value
+        assert_eq!(
+            select_column(
+                format!("SELECT y FROM {} WHERE x IN (3, 2, NULL, 3)", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![
+                Data::STRING("b".to_string()),
+                Data::STRING("a".to_string()),
+                Data::STRING("d".to_string())
+            ]
+        );
+
+        let result = process_query(
+            format!("DELETE FROM {} WHERE x IN (1, 3)", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Delete failed");
+        assert_eq!(
+            select_column(
+                format!("SELECT x FROM {}", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(2), Data::INT(2)]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_btree_index() {
+        let table_name = "test_btree_index";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        for column in ["x", "y", "f"] {
+            assert!(
+                process_query(
+                    format!("CREATE INDEX {} ON {} USING BTREE", column, table_name).as_str(),
+                    sync_guard.clone()
+                )
+                .is_ok(),
+                "Index not created"
+            );
+        }
+        assert!(Path::new(&format!("{}#x_btree", table_name)).exists());
+        // the indexes are maintained by the changes of the rows
+        for query in [
+            "INSERT INTO {} VALUES (4, 'ab', 0.5), (2, 'ba', 2.5)",
+            "INSERT INTO {} (y, f) VALUES ('e', 0.75)",
+            "DELETE FROM {} WHERE y = 'c'",
+            "UPDATE {} SET x = 0 WHERE y = 'd'",
+        ] {
+            let result = process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+            assert!(result.is_ok(), "Rows not changed");
+        }
+
+        let queries = [
+            "SELECT y FROM {} WHERE x < 2",
+            "SELECT y FROM {} WHERE x >= 2",
+            "SELECT y FROM {} WHERE 2 < x",
+            "SELECT y FROM {} WHERE x = 2",
+            "SELECT y FROM {} WHERE x BETWEEN 1 AND 2",
+            "SELECT y FROM {} WHERE y LIKE 'a%'",
+            "SELECT y FROM {} WHERE y LIKE 'b%' LIMIT 1",
+            "SELECT y FROM {} WHERE f > 0.4",
+            "SELECT y FROM {} ORDER BY x",
+            "SELECT y FROM {} ORDER BY x DESC",
+            "SELECT y FROM {} ORDER BY x NULLS FIRST",
+            "SELECT y FROM {} ORDER BY f DESC LIMIT 3",
+            "SELECT y FROM {} WHERE x < 4 ORDER BY f DESC LIMIT 2 OFFSET 1",
+            "SELECT y FROM {} ORDER BY y DESC",
+            "SELECT y FROM {} WHERE y < 'b'",
+            "SELECT y FROM {} WHERE y >= 'b' ORDER BY y",
+        ];
+        let select = |query: &str| select_column(query.replace("{}", table_name), sync_guard.clone(), 0);
+        let indexed_results: Vec<Vec<Data>> = queries.iter().map(|query| select(query)).collect();
+        assert_eq!(
+            indexed_results[0],
+            vec![Data::STRING("d".to_string())]
+        );
+        assert_eq!(
+            indexed_results[5],
+            vec![Data::STRING("a".to_string()), Data::STRING("ab".to_string())]
+        );
+        assert_eq!(
+            indexed_results[9],
+            vec!["e", "ab", "b", "a", "ba", "d"]
+                .into_iter()
+                .map(|y| Data::STRING(y.to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        // the same rows in the same order are found without the indexes
+        for column in ["x", "y", "f"] {
+            assert!(
+                process_query(
+                    format!("DROP INDEX {} ON {}", column, table_name).as_str(),
+                    sync_guard.clone()
+                )
+                .is_ok(),
+                "Index not dropped"
+            );
+        }
+        assert!(!Path::new(&format!("{}#x_btree", table_name)).exists());
+        let results: Vec<Vec<Data>> = queries.iter().map(|query| select(query)).collect();
+        assert_eq!(indexed_results, results);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_composite_index() {
+        let table_name = "test_composite_index";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE INDEX x_y ON {} (x, y) USING BTREE").is_ok());
+        assert!(query("CREATE INDEX y_x ON {} (y, x)").is_ok());
+        assert!(matches!(
+            query("CREATE INDEX x_z ON {} (x, z)"),
+            Err(QueryError::ColumnNotExists(..))
+        ));
+        assert!(matches!(
+            query("CREATE INDEX x_x ON {} (x, x)"),
+            Err(QueryError::DuplicateColumn(..))
+        ));
+        let indexes: Vec<Vec<String>> = Table::load(table_name.to_string())
+            .unwrap()
+            .indexes
+            .into_iter()
+            .map(|index| index.columns)
+            .collect();
+        assert_eq!(indexes, vec![vec!["x", "y"], vec!["y", "x"]]);
+        // the indexes are maintained by the changes of the rows
+        for change in [
+            "INSERT INTO {} VALUES (2, 'a', 3.5), (2, 'e', 0.75)",
+            "DELETE FROM {} WHERE y = 'd'",
+            "UPDATE {} SET x = 3 WHERE y = 'b'",
+        ] {
+            assert!(query(change).is_ok(), "Rows not changed");
+        }
+
+        let queries = [
+            "SELECT f FROM {} WHERE x = 2 AND y = 'a'",
+            "SELECT f FROM {} WHERE y = 'a' AND 2 = x AND f > 1",
+            "SELECT y FROM {} WHERE x = 2 AND f < 1",
+            "SELECT y FROM {} WHERE x = 1 AND y = 'c'",
+            "SELECT y FROM {} WHERE x = 3 AND y = 'b' OR y = 'e'",
+            "SELECT y FROM {} WHERE x = 2 AND y = NULL",
+        ];
+        let select = |query: &str| select_column(query.replace("{}", table_name), sync_guard.clone(), 0);
+        let indexed_results: Vec<Vec<Data>> = queries.iter().map(|query| select(query)).collect();
+        assert_eq!(indexed_results[0], vec![Data::FLOAT(0.25), Data::FLOAT(3.5)]);
+        assert_eq!(indexed_results[1], vec![Data::FLOAT(3.5)]);
+        assert_eq!(
+            indexed_results[2],
+            vec![Data::STRING("a".to_string()), Data::STRING("e".to_string())]
+        );
+        assert!(indexed_results[5].is_empty());
+
+        // the same rows are found without the indexes
+        for index in ["x_y", "y_x"] {
+            assert!(query(&format!("DROP INDEX {} ON {{}}", index)).is_ok(), "Index not dropped");
+        }
+        let results: Vec<Vec<Data>> = queries.iter().map(|query| select(query)).collect();
+        assert_eq!(indexed_results, results);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_primary_key() {
+        let table_name = "test_primary_key";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (id int PRIMARY KEY, x int)").is_ok());
+        let table = Table::load(table_name.to_string()).unwrap();
+        assert_eq!(
+            table.indexes,
+            vec![TableIndex {
+                name: "test_primary_key_pkey".to_string(),
+                columns: vec!["id".to_string()],
+                kind: IndexKind::Hash,
+                constraint: Some(IndexConstraint::PrimaryKey),
+            }]
+        );
+
+        assert!(query("INSERT INTO {} VALUES (1, 1), (2, 1)").is_ok());
+        let result = query("INSERT INTO {} VALUES (3, 1), (1, 2)");
+        assert!(matches!(result, Err(QueryError::DuplicateKey(..))));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "duplicate key (id)=(1) violates unique constraint test_primary_key_pkey"
+        );
+        // nothing of the rejected batch is inserted
+        assert!(matches!(
+            query("INSERT INTO {} VALUES (3, 1), (3, 2)"),
+            Err(QueryError::DuplicateKey(..))
+        ));
+        assert!(matches!(
+            query("INSERT INTO {} (x) VALUES (3)"),
+            Err(QueryError::NullValue(..))
+        ));
+        assert!(query("INSERT INTO {} VALUES (3, 1)").is_ok());
+
+        assert!(matches!(
+            query("UPDATE {} SET id = 2 WHERE id = 1"),
+            Err(QueryError::DuplicateKey(..))
+        ));
+        assert!(matches!(
+            query("UPDATE {} SET id = 4"),
+            Err(QueryError::DuplicateKey(..))
+        ));
+        // the keys only have to be unique after the update
+        assert!(query("UPDATE {} SET id = id + 1").is_ok());
+        assert!(query("UPDATE {} SET x = 5 WHERE id = 2").is_ok());
+        let select = |query: &str| select_column(query.replace("{}", table_name), sync_guard.clone(), 0);
+        assert_eq!(
+            select("SELECT id FROM {} ORDER BY id"),
+            vec![Data::INT(2), Data::INT(3), Data::INT(4)]
+        );
+        assert_eq!(select("SELECT x FROM {} WHERE id = 2"), vec![Data::INT(5)]);
+
+        assert!(matches!(
+            query("DROP INDEX test_primary_key_pkey ON {}"),
+            Err(QueryError::IndexOfConstraint(..))
+        ));
+        assert!(matches!(
+            query("CREATE TABLE test_primary_key_2 (x int PRIMARY KEY, y int PRIMARY KEY)"),
+            Err(QueryError::MultiplePrimaryKeys(..))
+        ));
+        assert!(matches!(
+            query("CREATE TABLE test_primary_key_2 (x int, PRIMARY KEY (y))"),
+            Err(QueryError::ColumnNotExists(..))
+        ));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_unique_constraint() {
+        let table_name = "test_unique_constraint";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (x int, y int, z int UNIQUE, UNIQUE (x, y))").is_ok());
+        let names: Vec<String> = Table::load(table_name.to_string())
+            .unwrap()
+            .indexes
+            .into_iter()
+            .map(|index| index.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["test_unique_constraint_z_key", "test_unique_constraint_x_y_key"]
+        );
+
+        assert!(query("INSERT INTO {} VALUES (1, 1, 1), (1, 2, 2)").is_ok());
+        let result = query("INSERT INTO {} VALUES (1, 2, 3)");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "duplicate key (x, y)=(1, 2) violates unique constraint test_unique_constraint_x_y_key"
+        );
+        // the keys with NULL don't conflict
+        assert!(query("INSERT INTO {} (x, y) VALUES (1, NULL), (1, NULL)").is_ok());
+        assert!(matches!(
+            query("UPDATE {} SET z = 2 WHERE y = 1"),
+            Err(QueryError::DuplicateKey(..))
+        ));
+        // the constraint is dropped with its column
+        assert!(query("ALTER TABLE {} DROP COLUMN z").is_ok());
+        assert!(query("INSERT INTO {} VALUES (2, 2)").is_ok());
+        let table = Table::load(table_name.to_string()).unwrap();
+        assert_eq!(table.indexes.len(), 1);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_column_constraints() {
+        let table_name = "test_column_constraints";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (x int NOT NULL, y int DEFAULT 2 + 3 CHECK (y > x), z varchar DEFAULT 'a')").is_ok());
+        let columns = Table::load(table_name.to_string()).unwrap().columns;
+        assert_eq!(
+            columns[1].constraints,
+            ColumnConstraints {
+                not_null: false,
+                default: Some("2 + 3".to_string()),
+                check: Some("y > x".to_string()),
+            }
+        );
+        assert!(columns[0].constraints.not_null);
+
+        assert!(query("INSERT INTO {} (x) VALUES (1)").is_ok());
+        assert!(query("INSERT INTO {} (x, y) VALUES (2, NULL)").is_ok());
+        let result = query("INSERT INTO {} (y) VALUES (3)");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "null value in column x violates constraint NOT NULL"
+        );
+        let result = query("INSERT INTO {} VALUES (10, 5, 'b')");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "row violates constraint CHECK (y > x) of column y"
+        );
+        assert!(matches!(
+            query("UPDATE {} SET x = 7 WHERE y = 5"),
+            Err(QueryError::CheckViolation(..))
+        ));
+        assert!(matches!(
+            query("UPDATE {} SET x = NULL"),
+            Err(QueryError::NullValue(..))
+        ));
+        let select = |query: &str| select_column(query.replace("{}", table_name), sync_guard.clone(), 0);
+        assert_eq!(select("SELECT y FROM {} WHERE x = 1"), vec![Data::INT(5)]);
+        assert_eq!(
+            select("SELECT z FROM {}"),
+            vec![Data::STRING("a".to_string()), Data::STRING("a".to_string())]
+        );
+
+        // the check follows the renamed column and it's dropped with the column
+        assert!(query("ALTER TABLE {} RENAME COLUMN x TO w").is_ok());
+        assert_eq!(
+            query("INSERT INTO {} (w) VALUES (6)").unwrap_err().to_string(),
+            "row violates constraint CHECK (y > w) of column y"
+        );
+        assert!(query("ALTER TABLE {} DROP COLUMN w").is_ok());
+        assert!(query("INSERT INTO {} (y) VALUES (1)").is_ok());
+
+        assert!(matches!(
+            query("CREATE TABLE test_column_constraints_2 (x int, y int DEFAULT x)"),
+            Err(QueryError::ParseError(..))
+        ));
+        assert!(matches!(
+            query("CREATE TABLE test_column_constraints_2 (x int DEFAULT 'a')"),
+            Err(QueryError::InvalidDataType(..))
+        ));
+        assert!(matches!(
+            query("CREATE TABLE test_column_constraints_2 (x int CHECK (x > y))"),
+            Err(QueryError::ColumnNotExists(..))
+        ));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_null_and_zero_values() {
+        let table_name = "test_null_and_zero_values";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (id int, s varchar, f float, b boolean)").is_ok());
+        assert!(query("INSERT INTO {} VALUES (1, '', 0.0, false), (2, NULL, NULL, NULL), (3, 'x', 1.5, true)").is_ok());
+        assert!(query("INSERT INTO {} (id) VALUES (4)").is_ok());
+
+        let result = query("SELECT * FROM {}").unwrap().data.unwrap();
+        let values: Vec<Vec<Data>> = result.rows.into_iter().map(|row| row.values).collect();
+        assert_eq!(
+            values,
+            vec![
+                vec![Data::INT(1), Data::STRING(String::new()), Data::FLOAT(0.0), Data::BOOLEAN(false)],
+                vec![Data::INT(2), Data::NULL, Data::NULL, Data::NULL],
+                vec![Data::INT(3), Data::STRING("x".to_string()), Data::FLOAT(1.5), Data::BOOLEAN(true)],
+                vec![Data::INT(4), Data::NULL, Data::NULL, Data::NULL],
+            ]
+        );
+        let select = |query: &str| select_column(query.replace("{}", table_name), sync_guard.clone(), 0);
+        assert_eq!(select("SELECT id FROM {} WHERE s IS NULL"), vec![Data::INT(2), Data::INT(4)]);
+        assert_eq!(select("SELECT id FROM {} WHERE f = 0.0"), vec![Data::INT(1)]);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_variable_size_values() {
+        let table_name = "test_variable_size_values";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (id int, name varchar(5), note text, data blob)").is_ok());
+        let long_note = "a note longer than the slot of its column ".repeat(10);
+        assert!(query(&format!("INSERT INTO {{}} VALUES (1, 'ab', '{}', x'00ff10'), (2, 'żółwi', 'short', NULL)", long_note)).is_ok());
+        assert!(matches!(
+            query("INSERT INTO {} VALUES (3, 'abcdef', 'x', NULL)"),
+            Err(QueryError::ValueTooLong(column, 5)) if column == "name"
+        ));
+        assert!(matches!(
+            query("UPDATE {} SET name = 'abcdef' WHERE id = 1"),
+            Err(QueryError::ValueTooLong(..))
+        ));
+        assert!(matches!(
+            query("INSERT INTO {} VALUES (3, 'a', 'x', 'not a blob')"),
+            Err(QueryError::InvalidDataType(..))
+        ));
+        assert!(matches!(
+            query("CREATE INDEX note ON {} USING BTREE"),
+            Err(QueryError::UnorderedIndexColumn(..))
+        ));
+
+        let select = |query: &str, column| select_column(query.replace("{}", table_name), sync_guard.clone(), column);
+        assert_eq!(
+            select("SELECT note FROM {} WHERE id = 1", 0),
+            vec![Data::STRING(long_note.clone())]
+        );
+        assert_eq!(
+            select("SELECT data FROM {} WHERE data = x'00ff10'", 0),
+            vec![Data::BLOB(vec![0x00, 0xff, 0x10])]
+        );
+        assert_eq!(select("SELECT id FROM {} WHERE name = 'żółwi'", 0), vec![Data::INT(2)]);
+
+        // the long values are kept when the rows are rewritten
+        assert!(query("UPDATE {} SET note = note + '!' WHERE id = 1").is_ok());
+        assert!(query("ALTER TABLE {} ADD COLUMN x int").is_ok());
+        assert_eq!(
+            select("SELECT note FROM {} WHERE id = 1", 0),
+            vec![Data::STRING(format!("{}!", long_note))]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_scalar_types() {
+        let table_name = "test_scalar_types";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (id bigint, price decimal(6,2), day date, at timestamp, uid uuid)").is_ok());
+        assert!(query(
+            "INSERT INTO {} VALUES (3000000000, 12.345, '2024-02-29', '2024-02-29 12:30:00', \
+             '123e4567-e89b-12d3-a456-426614174000'), \
+             (1, decimal '0.5', date '2024-03-01', timestamp '2024-03-01 00:00:00.25', \
+             uuid '00000000-0000-0000-0000-000000000001')"
+        )
+        .is_ok());
+        assert!(matches!(
+            query("INSERT INTO {} VALUES (2, 10000, NULL, NULL, NULL)"),
+            Err(QueryError::ValueOutOfRange(value, _)) if value == "10000"
+        ));
+        assert!(matches!(
+            query("INSERT INTO {} VALUES (2, 1, '2024-13-01', NULL, NULL)"),
+            Err(QueryError::InvalidDataType(column, ..)) if column == "day"
+        ));
+        assert!(query("CREATE INDEX uid ON {}").is_ok());
+        assert!(query("CREATE INDEX id ON {} USING BTREE").is_ok());
+
+        let select = |query: &str, column| select_column(query.replace("{}", table_name), sync_guard.clone(), column);
+        assert_eq!(
+            select("SELECT price, day, at FROM {} WHERE id > 2147483647", 0),
+            vec![Data::DECIMAL(Decimal::new(1235, 2))]
+        );
+        assert_eq!(
+            select("SELECT id FROM {} WHERE uid = uuid '123E4567E89B12D3A456426614174000'", 0),
+            vec![Data::BIGINT(3000000000)]
+        );
+        assert_eq!(select("SELECT id FROM {} WHERE id <= 1", 0), vec![Data::BIGINT(1)]);
+        assert_eq!(select("SELECT day - date '2024-01-01' FROM {}", 0), vec![Data::INT(59), Data::INT(60)]);
+        assert_eq!(
+            select("SELECT id FROM {} WHERE day + 1 = date '2024-03-01'", 0),
+            vec![Data::BIGINT(3000000000)]
+        );
+        assert_eq!(select("SELECT id FROM {} WHERE at > day", 0), vec![Data::BIGINT(3000000000), Data::BIGINT(1)]);
+        assert_eq!(select("SELECT sum(price) FROM {}", 0), vec![Data::DECIMAL(Decimal::new(1285, 2))]);
+
+        assert!(query("UPDATE {} SET id = id * 4000000000 WHERE id = 3000000000").is_err());
+        assert!(query("UPDATE {} SET at = day WHERE id = 1").is_ok());
+        assert_eq!(
+            select("SELECT at FROM {} WHERE id = 1", 0),
+            vec![Data::TIMESTAMP(timestamp_from_string("2024-03-01").unwrap())]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_cast_and_coercion() {
+        let table_name = "test_cast_and_coercion";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (id int, f float, day date, note varchar(3))").is_ok());
+        assert!(query("INSERT INTO {} VALUES (1, 3, '2024-01-31', 'abc'), (2, 2.5, '2024-02-01', NULL)").is_ok());
+        assert!(matches!(
+            query("INSERT INTO {} VALUES (3, '2.5', NULL, NULL)"),
+            Err(QueryError::InvalidDataType(column, ..)) if column == "f"
+        ));
+        assert!(query("CREATE INDEX day ON {}").is_ok());
+        assert!(query("CREATE INDEX f ON {}").is_ok());
+
+        let select = |query: &str, column| select_column(query.replace("{}", table_name), sync_guard.clone(), column);
+        assert_eq!(select("SELECT f FROM {} WHERE id = 1", 0), vec![Data::FLOAT(3.0)]);
+        // the searched values are converted to the type of the indexed column
+        assert_eq!(select("SELECT id FROM {} WHERE day = '2024-01-31'", 0), vec![Data::INT(1)]);
+        assert_eq!(select("SELECT id FROM {} WHERE f = 3", 0), vec![Data::INT(1)]);
+        assert_eq!(select("SELECT id FROM {} WHERE day > '2024-01-31'", 0), vec![Data::INT(2)]);
+
+        let result = query("SELECT cast(f AS int), day::text, id::varchar(10) + note FROM {} ORDER BY id").unwrap();
+        let data = result.data.unwrap();
+        let column_types: Vec<DataType> = data.columns.iter().map(|column| column.data_type).collect();
+        assert_eq!(column_types, vec![DataType::INT, DataType::TEXT, DataType::STRING { size: 13 }]);
+        assert_eq!(
+            data.rows[1].values,
+            vec![Data::INT(3), Data::STRING("2024-02-01".into()), Data::NULL]
+        );
+
+        assert!(query("UPDATE {} SET f = '0.5'::float, note = cast(f AS varchar(3)) WHERE id = 2").is_ok());
+        assert_eq!(
+            select("SELECT note, f FROM {} WHERE id = 2", 0),
+            vec![Data::STRING("2.5".into())]
+        );
+        assert!(matches!(
+            query("UPDATE {} SET id = note::int"),
+            Err(QueryError::ParseError(ParseError::InvalidTypedLiteral(..)))
+        ));
+        assert!(matches!(
+            query("UPDATE {} SET note = id"),
+            Err(QueryError::InvalidDataType(..))
+        ));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_scalar_functions() {
+        let table_name = "test_scalar_functions";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (id int, name varchar(10), price decimal(6,2), note text)").is_ok());
+        assert!(query("INSERT INTO {} VALUES (1, ' Ann ', '10.25', NULL), (2, 'bob', '3.5', 'x'), (3, 'Bob', NULL, NULL)").is_ok());
+        assert!(query("CREATE INDEX name ON {}").is_ok());
+
+        let select = |query: &str, column| select_column(query.replace("{}", table_name), sync_guard.clone(), column);
+        assert_eq!(
+            select("SELECT id FROM {} WHERE lower(name) = 'bob' ORDER BY id", 0),
+            vec![Data::INT(2), Data::INT(3)]
+        );
+        // the argument of the function is evaluated before the index lookup
+        assert_eq!(select("SELECT id FROM {} WHERE name = concat('B', 'ob')", 0), vec![Data::INT(3)]);
+
+        let result = query("SELECT upper(trim(name)), length(name), round(price, 1), coalesce(note, name), sqrt(id) FROM {} ORDER BY id").unwrap();
+        let data = result.data.unwrap();
+        let column_types: Vec<DataType> = data.columns.iter().map(|column| column.data_type).collect();
+        assert_eq!(
+            column_types,
+            vec![
+                DataType::STRING { size: 10 },
+                DataType::INT,
+                DataType::DECIMAL { precision: 6, scale: 2 },
+                DataType::TEXT,
+                DataType::FLOAT,
+            ]
+        );
+        assert_eq!(data.columns[0].name, "upper(trim(name))");
+        assert_eq!(
+            data.rows[0].values,
+            vec![
+                Data::STRING("ANN".into()),
+                Data::INT(5),
+                Data::DECIMAL(Decimal::new(103, 1)),
+                Data::STRING(" Ann ".into()),
+                Data::FLOAT(1.0),
+            ]
+        );
+        assert_eq!(data.rows[2].values[2], Data::NULL);
+
+        assert_eq!(
+            select("SELECT count(*), round(sum(ifnull(price, 0)), 0) FROM {}", 1),
+            vec![Data::DECIMAL(Decimal::new(14, 0))]
+        );
+
+        assert!(query("UPDATE {} SET name = trim(name), note = replace(note, 'x', 'y') WHERE id < 3").is_ok());
+        assert_eq!(
+            select("SELECT name, note FROM {} ORDER BY id", 0),
+            vec![Data::STRING("Ann".into()), Data::STRING("bob".into()), Data::STRING("Bob".into())]
+        );
+        assert_eq!(select("SELECT note FROM {} WHERE id = 2", 0), vec![Data::STRING("y".into())]);
+        assert!(matches!(
+            query("SELECT sqrt(-id) FROM {}"),
+            Err(QueryError::ParseError(ParseError::InvalidArgument(..)))
+        ));
+        assert!(matches!(
+            query("SELECT nullif(id) FROM {}"),
+            Err(QueryError::ParseError(ParseError::InvalidArgumentCount(..)))
+        ));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_case_when() {
+        let table_name = "test_case_when";
+        let sync_guard = sync_guard();
+        let query = |query: &str| process_query(query.replace("{}", table_name).as_str(), sync_guard.clone());
+        assert!(query("CREATE TABLE {} (id int, age int, status int, price decimal(4,1))").is_ok());
+        assert!(query("INSERT INTO {} VALUES (1, 12, 1, '2.5'), (2, 40, 2, NULL), (3, 70, 9, '10.0'), (4, NULL, NULL, '1.0')").is_ok());
+
+        // the values of the sum are promoted to the type of its argument
+        assert_eq!(
+            query("SELECT sum(CASE WHEN id = 1 THEN price ELSE 5 END), sum(CASE WHEN id = 1 THEN age ELSE 3000000000 END) FROM {}")
+                .unwrap()
+                .data
+                .unwrap()
+                .rows,
+            vec![Row {
+                values: vec![Data::DECIMAL(Decimal::parse("17.5").unwrap()), Data::BIGINT(9000000012)]
+            }]
+        );
+        let result = query("SELECT sum(decimal '999999999999999999' * decimal '999999999999999999' * 100) FROM {}");
+        assert!(matches!(result, Err(QueryError::ValueOutOfRange(_, data_type)) if data_type == "DECIMAL"));
+
+        let result = query(
+            "SELECT CASE WHEN age < 18 THEN 'minor' WHEN age < 65 THEN 'adult' ELSE 'senior' END AS bucket, \
+             CASE status WHEN 1 THEN 'new' WHEN 2 THEN 'paid' END, \
+             CASE WHEN price > 5 THEN 100 WHEN price IS NULL THEN 0 ELSE price END \
+             FROM {} ORDER BY id",
+        )
+        .unwrap();
+        let data = result.data.unwrap();
+        let column_types: Vec<DataType> = data.columns.iter().map(|column| column.data_type).collect();
+        assert_eq!(
+            column_types,
+            vec![
+                DataType::STRING { size: 6 },
+                DataType::STRING { size: 4 },
+                DataType::DECIMAL { precision: 4, scale: 1 },
+            ]
+        );
+        assert_eq!(data.columns[0].name, "bucket");
+        let column = |index: usize| -> Vec<Data> { data.rows.iter().map(|row| row.values[index].clone()).collect() };
+        let strings = |values: &[&str]| -> Vec<Data> { values.iter().map(|value| Data::STRING(value.to_string())).collect() };
+        assert_eq!(column(0), strings(&["minor", "adult", "senior", "senior"]));
+        assert_eq!(
+            column(1),
+            vec![Data::STRING("new".into()), Data::STRING("paid".into()), Data::NULL, Data::NULL]
+        );
+        // the INT results are converted to the DECIMAL of the column
+        assert_eq!(
+            column(2),
+            vec![
+                Data::DECIMAL(Decimal::new(25, 1)),
+                Data::DECIMAL(Decimal::from_int(0)),
+                Data::DECIMAL(Decimal::from_int(100)),
+                Data::DECIMAL(Decimal::new(10, 1)),
+            ]
+        );
+
+        let select = |query: &str, column| select_column(query.replace("{}", table_name), sync_guard.clone(), column);
+        assert_eq!(
+            select("SELECT id FROM {} WHERE CASE status WHEN 9 THEN true ELSE age < 18 END ORDER BY id", 0),
+            vec![Data::INT(1), Data::INT(3)]
+        );
+        assert_eq!(
+            select("SELECT sum(CASE WHEN age >= 18 THEN 1 ELSE 0 END) FROM {}", 0),
+            vec![Data::INT(2)]
+        );
+        assert!(query("UPDATE {} SET status = CASE WHEN status IS NULL THEN 0 ELSE status + 1 END").is_ok());
+        assert_eq!(
+            select("SELECT status FROM {} ORDER BY id", 0),
+            vec![Data::INT(2), Data::INT(3), Data::INT(10), Data::INT(0)]
+        );
+
+        let result = query("SELECT CASE WHEN age < 18 THEN 'minor' ELSE age END FROM {}");
+        assert!(matches!(&result, Err(QueryError::MismatchedTypes(expression, ..)) if expression == "CASE"));
+        assert_eq!(result.unwrap_err().to_string(), "CASE types VARCHAR(5) and INT cannot be matched");
+        assert!(matches!(
+            query("SELECT coalesce(price, 'none') FROM {}"),
+            Err(QueryError::MismatchedTypes(expression, ..)) if expression == "COALESCE"
+        ));
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_insert_multiple_rows() {
+        let table_name = "test_insert_multiple_rows";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        assert!(
+            process_query(
+                format!("CREATE INDEX x ON {}", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Index not created"
+        );
+
+        let result = process_query(
+            format!(
+                "INSERT INTO {} (y, x) VALUES ('e', 5), ('f', 2), ('g', NULL)",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert_eq!(
+            result.unwrap().message,
+            Some("3 rows were succesfully inserted".to_string())
+        );
+        assert_eq!(
+            select_column(
+                format!("SELECT y FROM {} WHERE x = 2", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![
+                Data::STRING("b".to_string()),
+                Data::STRING("a".to_string()),
+                Data::STRING("f".to_string())
+            ]
+        );
+
+        // nothing is inserted when any of the rows is invalid
+        let result = process_query(
+            format!(
+                "INSERT INTO {} VALUES (6, 'h', 1.0), (7, 8, 1.0)",
+                table_name
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::InvalidDataType(..))));
+        let result = process_query(
+            format!("INSERT INTO {} VALUES (6, 'h', 1.0), (7, 'i')", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::IncorrectNumberOfValues(3, 2))));
+        assert_eq!(
+            select_column(
+                format!("SELECT count(*) FROM {}", table_name),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(7)]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_insert_select() {
+        let (source, target) = ("test_insert_select_source", "test_insert_select_target");
+        let sync_guard = sync_guard();
+        create_order_by_table(source, sync_guard.clone());
+        assert!(
+            process_query(
+                format!("CREATE TABLE {} y varchar, x int", target).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok(),
+            "Table not created"
+        );
+
+        let result = process_query(
+            format!(
+                "INSERT INTO {} (x, y) SELECT x * 10, y FROM {} WHERE x > 1 ORDER BY y",
+                target, source
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Insert failed");
+        // the table can be inserted into itself
+        let result = process_query(
+            format!("INSERT INTO {} SELECT y, x + 1 FROM {} LIMIT 1", target, target).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(result.is_ok(), "Insert failed");
+
+        let result = process_query(format!("SELECT * FROM {}", target).as_str(), sync_guard.clone());
+        assert_eq!(
+            result.unwrap().data.unwrap().rows,
+            vec![
+                Row {
+                    values: vec![Data::STRING("a".to_string()), Data::INT(20)]
+                },
+                Row {
+                    values: vec![Data::STRING("b".to_string()), Data::INT(20)]
+                },
+                Row {
+                    values: vec![Data::STRING("d".to_string()), Data::INT(30)]
+                },
+                Row {
+                    values: vec![Data::STRING("a".to_string()), Data::INT(21)]
+                },
+            ]
+        );
+
+        let result = process_query(
+            format!("INSERT INTO {} SELECT y FROM {}", target, source).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::IncorrectNumberOfValues(2, 1))));
+        let result = process_query(
+            format!("INSERT INTO {} SELECT x, y FROM {}", target, source).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::InvalidDataType(..))));
+
+        drop_table(source);
+        drop_table(target);
+    }
+
+    #[test]
+    fn test_alter_table() {
+        let (table_name, new_name) = ("test_alter_table", "test_alter_table_renamed");
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        let alter = |query: &str| {
+            process_query(
+                format!("ALTER TABLE {} {}", table_name, query).as_str(),
+                sync_guard.clone(),
+            )
+        };
+        assert!(alter("ADD COLUMN y int").is_err());
+        assert!(process_query(
+            format!("CREATE INDEX x ON {}", table_name).as_str(),
+            sync_guard.clone()
+        )
+        .is_ok());
+
+        assert!(alter("ADD COLUMN b boolean DEFAULT true").is_ok());
+        assert!(alter("DROP COLUMN y").is_ok());
+        assert!(alter("RENAME COLUMN x TO id").is_ok());
+        assert!(alter(format!("RENAME TO {}", new_name).as_str()).is_ok());
+        assert!(Table::load(table_name.to_string()).is_err());
+        assert_eq!(Table::load(new_name.to_string()).unwrap().indexes, vec![hash_index("id")]);
+
+        // the index is kept under the new names
+        let result = process_query(
+            format!("SELECT * FROM {} WHERE id = 2", new_name).as_str(),
+            sync_guard.clone(),
+        );
+        let data = result.unwrap().data.unwrap();
+        assert_eq!(
+            data.columns,
+            vec![
+                Column {
+                    name: "id".to_string(),
+                    data_type: DataType::INT,
+                    constraints: ColumnConstraints::default(),
+                },
+                Column {
+                    name: "f".to_string(),
+                    data_type: DataType::FLOAT,
+                    constraints: ColumnConstraints::default(),
+                },
+                Column {
+                    name: "b".to_string(),
+                    data_type: DataType::BOOLEAN,
+                    constraints: ColumnConstraints {
+                        default: Some("true".to_string()),
+                        ..ColumnConstraints::default()
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            data.rows,
+            vec![
+                Row {
+                    values: vec![Data::INT(2), Data::FLOAT(1.5), Data::BOOLEAN(true)]
+                },
+                Row {
+                    values: vec![Data::INT(2), Data::FLOAT(0.25), Data::BOOLEAN(true)]
+                },
+            ]
+        );
+
+        assert_eq!(
+            select_column(
+                format!(
+                    "SELECT columns_count FROM all_tables WHERE table_name = '{}' OR table_name = '{}'",
+                    table_name, new_name
+                ),
+                sync_guard.clone(),
+                0
+            ),
+            vec![Data::INT(3)]
+        );
+
+        // the default is kept for the rows inserted later
+        let alter = |query: &str| {
+            process_query(
+                format!("ALTER TABLE {} {}", new_name, query).as_str(),
+                sync_guard.clone(),
+            )
+        };
+        assert!(alter("ADD COLUMN n int DEFAULT -4 + 1").is_ok());
+        assert!(process_query(
+            format!("INSERT INTO {} (id, f) VALUES (4, 1.0)", new_name).as_str(),
+            sync_guard.clone()
+        )
+        .is_ok());
+        assert_eq!(
+            select_column(
+                format!("SELECT b, n FROM {} WHERE id > 2", new_name),
+                sync_guard.clone(),
+                1
+            ),
+            vec![Data::INT(-3), Data::INT(-3)]
+        );
+        assert_eq!(
+            select_column(format!("SELECT b FROM {} WHERE id = 4", new_name), sync_guard.clone(), 0),
+            vec![Data::BOOLEAN(true)]
+        );
+
+        drop_table(new_name);
+    }
+
+    #[test]
+    fn test_alter_table_errors() {
+        let (table_name, other) = ("test_alter_table_errors", "test_alter_table_errors_other");
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        assert!(process_query(
+            format!("CREATE TABLE {} z int", other).as_str(),
+            sync_guard.clone()
+        )
+        .is_ok());
+        let alter = |table_name: &str, query: &str| {
+            process_query(
+                format!("ALTER TABLE {} {}", table_name, query).as_str(),
+                sync_guard.clone(),
+            )
+        };
+
+        assert!(matches!(
+            alter(table_name, "ADD COLUMN y int"),
+            Err(QueryError::DuplicateColumn(column)) if column == "y"
+        ));
+        assert!(matches!(
+            alter(table_name, "ADD COLUMN z int DEFAULT 'a'"),
+            Err(QueryError::InvalidDataType(..))
+        ));
+        assert!(alter(table_name, "ADD COLUMN z int DEFAULT x + 1").is_err());
+        assert!(matches!(
+            alter(table_name, "DROP COLUMN z"),
+            Err(QueryError::ColumnNotExists(..))
+        ));
+        assert!(matches!(
+            alter(table_name, "RENAME COLUMN x TO y"),
+            Err(QueryError::DuplicateColumn(column)) if column == "y"
+        ));
+        assert!(matches!(
+            alter(table_name, format!("RENAME TO {}", other).as_str()),
+            Err(QueryError::TableAlreadyExists(_))
+        ));
+        assert!(matches!(
+            alter(other, "DROP COLUMN z"),
+            Err(QueryError::CannotDropLastColumn(..))
+        ));
+
+        drop_table(table_name);
+        drop_table(other);
+    }
+
+    fn count_rows(table_name: &str, sync_guard: AcidSync) -> usize {
+        let result = process_query(
+            format!("SELECT * FROM {}", table_name).as_str(),
+            sync_guard,
+        );
+        result.unwrap().data.unwrap().rows.len()
+    }
+
+    #[test]
+    fn test_transaction_rollback() {
+        let (table_name, created) = ("test_transaction_rollback", "test_transaction_rollback_new");
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        let mut session = Session::new();
+        let mut run = |query: &str| session.process_query(query, sync_guard.clone());
+
+        assert!(run("BEGIN").is_ok());
+        assert!(run(format!("INSERT INTO {} VALUES 5, 'e', 0.5", table_name).as_str()).is_ok());
+        assert!(run(format!("DELETE FROM {} WHERE x = 2", table_name).as_str()).is_ok());
+        assert!(run(format!("ALTER TABLE {} DROP COLUMN y", table_name).as_str()).is_ok());
+        assert!(run(format!("CREATE TABLE {} a int", created).as_str()).is_ok());
+        assert!(run(format!("INSERT INTO {} VALUES 1", created).as_str()).is_ok());
+        let result = run(format!("SELECT * FROM {}", created).as_str());
+        assert_eq!(result.unwrap().data.unwrap().rows.len(), 1);
+        let result = run("ROLLBACK");
+        assert_eq!(
+            result.unwrap().message,
+            Some("Transaction rolled back.".to_string())
+        );
+
+        assert!(Table::load(created.to_string()).is_err());
+        assert!(!Path::new(&format!("{}#backup", table_name)).exists());
+        let table = Table::load(table_name.to_string()).unwrap();
+        assert_eq!(table.columns.len(), 3);
+        assert_eq!(count_rows(table_name, sync_guard.clone()), 4);
+
+        // the catalog is restored as well
+        let result = process_query(
+            format!(
+                "SELECT columns_count FROM all_tables WHERE table_name = '{}' OR table_name = '{}'",
+                table_name, created
+            )
+            .as_str(),
+            sync_guard.clone(),
+        );
+        assert_eq!(
+            result.unwrap().data.unwrap().rows,
+            vec![Row {
+                values: vec![Data::INT(3)]
+            }]
+        );
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        let (table_name, other) = ("test_transaction_commit", "test_transaction_commit_dropped");
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        create_order_by_table(other, sync_guard.clone());
+        let mut session = Session::new();
+
+        assert!(session.process_query("BEGIN TRANSACTION", sync_guard.clone()).is_ok());
+        assert!(session.is_in_transaction());
+        assert!(session
+            .process_query(
+                format!("INSERT INTO {} VALUES 5, 'e', 0.5", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok());
+        assert!(session
+            .process_query(format!("DROP TABLE {}", other).as_str(), sync_guard.clone())
+            .is_ok());
+        assert!(session.process_query("COMMIT", sync_guard.clone()).is_ok());
+        assert!(!session.is_in_transaction());
+
+        assert!(Table::load(other.to_string()).is_err());
+        assert!(!Path::new(&format!("{}#backup", other)).exists());
+        assert_eq!(count_rows(table_name, sync_guard.clone()), 5);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_transaction_isolation() {
+        let table_name = "test_transaction_isolation";
+        let sync_guard = sync_guard();
+        create_order_by_table(table_name, sync_guard.clone());
+        let mut session = Session::new();
+        let mut other_session = Session::new();
+
+        assert!(session.process_query("BEGIN", sync_guard.clone()).is_ok());
+        assert!(session
+            .process_query(
+                format!("DELETE FROM {} WHERE x = 1", table_name).as_str(),
+                sync_guard.clone()
+            )
+            .is_ok());
+
+        let query = format!("SELECT * FROM {}", table_name);
+        let result = other_session.process_query(query.as_str(), sync_guard.clone());
+        assert!(matches!(result, Err(QueryError::TableInTransaction(name)) if name == table_name));
+        assert!(other_session.process_query("BEGIN", sync_guard.clone()).is_ok());
+        let result = other_session.process_query(
+            format!("INSERT INTO {} VALUES 5, 'e', 0.5", table_name).as_str(),
+            sync_guard.clone(),
+        );
+        assert!(matches!(result, Err(QueryError::TableInTransaction(name)) if name == table_name));
+        assert!(other_session.process_query("ROLLBACK", sync_guard.clone()).is_ok());
+        // the query outside of the sessions is kept away as well
+        let result = process_query(query.as_str(), sync_guard.clone());
+        assert!(matches!(result, Err(QueryError::TableInTransaction(name)) if name == table_name));
+
+        assert!(session.process_query("COMMIT", sync_guard.clone()).is_ok());
+        let result = other_session.process_query(query.as_str(), sync_guard.clone());
+        assert_eq!(result.unwrap().data.unwrap().rows.len(), 3);
+
+        drop_table(table_name);
+    }
+
+    #[test]
+    fn test_transaction_waits_for_statements() {
+        let sync_guard = sync_guard();
+        let table_names = vec!["test_transaction_waits_for_statements".to_string()];
+        let statement_ended = Arc::new(AtomicBool::new(false));
+        sync_guard.begin_statement(1, &table_names).unwrap();
+
+        let claim = thread::spawn({
+            let sync_guard = sync_guard.clone();
+            let table_names = table_names.clone();
+            let statement_ended = statement_ended.clone();
+            move || {
+                sync_guard.claim_tables(2, &table_names).unwrap();
+                statement_ended.load(Ordering::SeqCst)
+            }
+        });
+        thread::sleep(Duration::from_millis(50));
+        statement_ended.store(true, Ordering::SeqCst);
+        sync_guard.end_statement(1, &table_names);
+        assert!(claim.join().unwrap());
+
+        assert_eq!(
+            sync_guard.begin_statement(1, &table_names),
+            Err(table_names[0].clone())
+        );
+        sync_guard.release_tables(2);
+        assert!(sync_guard.begin_statement(1, &table_names).is_ok());
+    }
+
+    #[test]
+    fn test_transaction_errors() {
+        let sync_guard = sync_guard();
+        let mut session = Session::new();
+
+        assert!(matches!(
+            session.process_query("COMMIT", sync_guard.clone()),
+            Err(QueryError::NoTransaction)
+        ));
+        assert!(matches!(
+            session.process_query("ROLLBACK", sync_guard.clone()),
+            Err(QueryError::NoTransaction)
+        ));
+        assert!(session.process_query("BEGIN", sync_guard.clone()).is_ok());
+        assert!(matches!(
+            session.process_query("BEGIN", sync_guard.clone()),
+            Err(QueryError::TransactionAlreadyStarted)
+        ));
+        assert!(session.process_query("ROLLBACK", sync_guard.clone()).is_ok());
+
+        assert!(matches!(
+            process_query("BEGIN", sync_guard.clone()),
+            Err(QueryError::TransactionWithoutSession)
+        ));
+    }
 }